@@ -0,0 +1,112 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compares `EscapeTime::evaluate_batch()` to evaluating one point at a time with
+//! `EscapeTime::evaluate()`, over a 480x270 view at 5000 iterations. Most of the default views
+//! escape within a few iterations, which leaves little for batching to speed up, so the seahorse
+//! valley of the Mandelbrot set is included as a view where iteration dominates. Run it with
+//! `cargo bench -p fractal-lib`. The speedups it has measured are summarized in the docs of the
+//! `escapetime::batch` module.
+
+use fractal_lib::escapetime::burningship::BurningShip;
+use fractal_lib::escapetime::julia::JuliaSet;
+use fractal_lib::escapetime::mandelbrot::Mandelbrot;
+use fractal_lib::escapetime::{EscapeResult, EscapeTime};
+use num::complex::Complex64;
+use std::time::{Duration, Instant};
+
+const WIDTH: u32 = 480;
+const HEIGHT: u32 = 270;
+const MAX_ITERATIONS: u64 = 5000;
+/// Each way of evaluating the points is timed this many times, and the fastest time counts.
+const RUNS: u32 = 5;
+
+/// The points of one column of the view at a time, the way the renderers evaluate them.
+fn columns(view: [Complex64; 2]) -> Vec<Vec<Complex64>> {
+    let [a, b] = view;
+    (0..WIDTH)
+        .map(|x| {
+            (0..HEIGHT)
+                .map(|y| {
+                    Complex64::new(
+                        a.re + (b.re - a.re) * f64::from(x) / f64::from(WIDTH - 1),
+                        a.im + (b.im - a.im) * f64::from(y) / f64::from(HEIGHT - 1),
+                    )
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn time<F: FnMut()>(mut run: F) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn bench<E: EscapeTime>(name: &str, etsystem: &E, view: [Complex64; 2]) {
+    let columns = columns(view);
+    let blank = EscapeResult::attracted(Complex64::new(0.0, 0.0));
+    let mut scalar = vec![vec![blank; HEIGHT as usize]; WIDTH as usize];
+    let mut batched = scalar.clone();
+
+    let scalar_time = time(|| {
+        for (points, results) in columns.iter().zip(scalar.iter_mut()) {
+            for (point, result) in points.iter().zip(results.iter_mut()) {
+                *result = etsystem.evaluate(*point);
+            }
+        }
+    });
+    let batched_time = time(|| {
+        for (points, results) in columns.iter().zip(batched.iter_mut()) {
+            etsystem.evaluate_batch(points, results);
+        }
+    });
+    assert!(scalar == batched, "{}: the results differ", name);
+
+    println!(
+        "{:<12} evaluate: {:>8.1}ms  evaluate_batch: {:>8.1}ms  speedup: {:.2}x",
+        name,
+        scalar_time.as_secs_f64() * 1000.0,
+        batched_time.as_secs_f64() * 1000.0,
+        scalar_time.as_secs_f64() / batched_time.as_secs_f64()
+    );
+}
+
+fn bench_default_view<E: EscapeTime>(name: &str, etsystem: &E) {
+    bench(name, etsystem, etsystem.default_view_area());
+}
+
+fn main() {
+    let mandelbrot = Mandelbrot::new(MAX_ITERATIONS, 2);
+    bench_default_view("mandelbrot", &mandelbrot);
+    bench(
+        "seahorses",
+        &mandelbrot,
+        [Complex64::new(-0.755, 0.1), Complex64::new(-0.735, 0.11)],
+    );
+    bench_default_view("burningship", &BurningShip::new(MAX_ITERATIONS, 2));
+    bench_default_view(
+        "julia",
+        &JuliaSet::new(
+            Mandelbrot::new(MAX_ITERATIONS, 2),
+            Complex64::new(-0.8, 0.156),
+        ),
+    );
+}
@@ -0,0 +1,560 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Software arbitrary-precision floating point numbers.
+//!
+//! `f64` only has 53 bits of precision, so it cannot tell apart points on the complex plane that
+//! are closer together than about 1e-16 times their magnitude. Deep zooms into escape time
+//! fractals need far more precision than that for at least a few points, so this module provides
+//! a simple binary floating point type whose precision is chosen at runtime.
+//!
+//! A `BigFloat` is stored as an arbitrary-precision integer mantissa and a binary exponent:
+//!
+//! ```text
+//! value = mantissa * 2^exponent
+//! ```
+//!
+//! After every operation, the mantissa is truncated (towards zero) to at most `precision` bits.
+
+use num::bigint::{BigInt, Sign};
+use num::complex::Complex64;
+use num::{Float, Signed, ToPrimitive, Zero};
+use std::cmp::{self, Ordering};
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+use std::str::FromStr;
+
+/// The default number of bits of precision, which is a bit more than an `f64` has.
+pub const DEFAULT_PRECISION: u32 = 64;
+
+/// An arbitrary-precision binary floating point number.
+#[derive(Clone, Debug)]
+pub struct BigFloat {
+    mantissa: BigInt,
+    exponent: i64,
+    precision: u32,
+}
+
+/// Multiplies `value` by 2^`exponent`, without overflowing the intermediate power of 2.
+fn ldexp(mut value: f64, mut exponent: i64) -> f64 {
+    while exponent > 1000 {
+        value *= 2f64.powi(1000);
+        exponent -= 1000;
+        if value.is_infinite() {
+            return value;
+        }
+    }
+    while exponent < -1000 {
+        value *= 2f64.powi(-1000);
+        exponent += 1000;
+        if value == 0.0 {
+            return value;
+        }
+    }
+    value * 2f64.powi(exponent as i32)
+}
+
+/// Shifts `value` right by `shift` bits, truncating towards zero.
+fn shr_truncate(value: BigInt, shift: usize) -> BigInt {
+    if value.is_negative() {
+        -((-value) >> shift)
+    } else {
+        value >> shift
+    }
+}
+
+impl BigFloat {
+    /// Constructs a BigFloat from its parts, truncating the mantissa to `precision` bits.
+    fn normalized(mantissa: BigInt, exponent: i64, precision: u32) -> BigFloat {
+        if mantissa.is_zero() {
+            return BigFloat::zero(precision);
+        }
+        let bits = mantissa.bits();
+        if bits > precision as usize {
+            let shift = bits - precision as usize;
+            BigFloat {
+                mantissa: shr_truncate(mantissa, shift),
+                exponent: exponent + shift as i64,
+                precision,
+            }
+        } else {
+            BigFloat {
+                mantissa,
+                exponent,
+                precision,
+            }
+        }
+    }
+
+    /// Zero, with the given precision.
+    pub fn zero(precision: u32) -> BigFloat {
+        BigFloat {
+            mantissa: BigInt::zero(),
+            exponent: 0,
+            precision,
+        }
+    }
+
+    /// Converts an `f64` exactly (as long as `precision` is at least 53 bits).
+    ///
+    /// Panics if `value` is not finite.
+    pub fn from_f64(value: f64, precision: u32) -> BigFloat {
+        if !value.is_finite() {
+            panic!("Can not convert {} to a BigFloat", value);
+        }
+        if value == 0.0 {
+            return BigFloat::zero(precision);
+        }
+        let (mantissa, exponent, sign) = value.integer_decode();
+        let mantissa = if sign < 0 {
+            -BigInt::from(mantissa)
+        } else {
+            BigInt::from(mantissa)
+        };
+        BigFloat::normalized(mantissa, i64::from(exponent), precision)
+    }
+
+    /// Parses a decimal number such as `-0.75`, `1.25e-30`, or
+    /// `-1.7400623825793399052208441670658256382966417204361718668798624`, rounding it to
+    /// `precision` bits.
+    pub fn parse(text: &str, precision: u32) -> Result<BigFloat, String> {
+        let text = text.trim();
+        let (negative, unsigned) = match text.chars().next() {
+            Some('-') => (true, &text[1..]),
+            Some('+') => (false, &text[1..]),
+            _ => (false, text),
+        };
+        let (number, decimal_exponent) = match unsigned.find(['e', 'E']) {
+            Some(index) => (
+                &unsigned[..index],
+                unsigned[index + 1..]
+                    .parse::<i64>()
+                    .map_err(|e| format!("Invalid exponent in {}: {}", text, e))?,
+            ),
+            None => (unsigned, 0),
+        };
+        let (whole, fraction) = match number.find('.') {
+            Some(index) => (&number[..index], &number[index + 1..]),
+            None => (number, ""),
+        };
+        if whole.is_empty() && fraction.is_empty()
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(format!("Invalid number: {}", text));
+        }
+
+        let digits = BigInt::from_str(&format!("0{}{}", whole, fraction))
+            .map_err(|e| format!("Invalid number {}: {}", text, e))?;
+        let digits = if negative { -digits } else { digits };
+        let decimal_exponent = decimal_exponent - fraction.len() as i64;
+
+        let ten = BigInt::from(10);
+        if decimal_exponent >= 0 {
+            let scale = num::pow(ten, decimal_exponent as usize);
+            Ok(BigFloat::normalized(digits * scale, 0, precision))
+        } else {
+            // Divide by 10^-decimal_exponent, after shifting in enough bits that the quotient
+            // still has `precision` bits of precision.
+            let divisor = num::pow(ten, (-decimal_exponent) as usize);
+            let shift = precision as usize + divisor.bits() + 2;
+            let quotient = (digits << shift) / divisor;
+            Ok(BigFloat::normalized(quotient, -(shift as i64), precision))
+        }
+    }
+
+    /// The number of bits of precision of the mantissa.
+    pub fn precision(&self) -> u32 {
+        self.precision
+    }
+
+    /// Returns the same value, rounded or extended to the new precision.
+    pub fn with_precision(&self, precision: u32) -> BigFloat {
+        BigFloat::normalized(self.mantissa.clone(), self.exponent, precision)
+    }
+
+    /// Converts to the nearest `f64` (truncating any bits that do not fit).
+    pub fn to_f64(&self) -> f64 {
+        if self.mantissa.is_zero() {
+            return 0.0;
+        }
+        let bits = self.mantissa.bits();
+        let (top, exponent) = if bits > 64 {
+            let shift = bits - 64;
+            (
+                shr_truncate(self.mantissa.clone(), shift),
+                self.exponent + shift as i64,
+            )
+        } else {
+            (self.mantissa.clone(), self.exponent)
+        };
+        ldexp(top.to_f64().unwrap_or(0.0), exponent)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    /// The absolute value.
+    pub fn abs(&self) -> BigFloat {
+        BigFloat {
+            mantissa: self.mantissa.abs(),
+            exponent: self.exponent,
+            precision: self.precision,
+        }
+    }
+
+    /// Multiplies by 2^`exponent`, which is exact.
+    pub fn mul_pow2(&self, exponent: i64) -> BigFloat {
+        BigFloat {
+            mantissa: self.mantissa.clone(),
+            exponent: self.exponent + exponent,
+            precision: self.precision,
+        }
+    }
+
+    /// log2 of the magnitude, rounded up, or None for 0. Useful for comparing magnitudes
+    /// without converting to an `f64`, which may underflow.
+    fn magnitude_exponent(&self) -> Option<i64> {
+        if self.mantissa.is_zero() {
+            None
+        } else {
+            Some(self.exponent + self.mantissa.bits() as i64)
+        }
+    }
+
+    fn add_impl(&self, other: &BigFloat) -> BigFloat {
+        let precision = cmp::max(self.precision, other.precision);
+        let (high, low) = match (self.magnitude_exponent(), other.magnitude_exponent()) {
+            (None, _) => return other.with_precision(precision),
+            (_, None) => return self.with_precision(precision),
+            (Some(a), Some(b)) => {
+                // If one value is too small to affect the bits of the other, skip the shifting.
+                if a - b > i64::from(precision) + 2 {
+                    return self.with_precision(precision);
+                } else if b - a > i64::from(precision) + 2 {
+                    return other.with_precision(precision);
+                }
+                if self.exponent >= other.exponent {
+                    (self, other)
+                } else {
+                    (other, self)
+                }
+            }
+        };
+        let shift = (high.exponent - low.exponent) as usize;
+        BigFloat::normalized(
+            (&high.mantissa << shift) + &low.mantissa,
+            low.exponent,
+            precision,
+        )
+    }
+
+    fn mul_impl(&self, other: &BigFloat) -> BigFloat {
+        BigFloat::normalized(
+            &self.mantissa * &other.mantissa,
+            self.exponent + other.exponent,
+            cmp::max(self.precision, other.precision),
+        )
+    }
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &BigFloat) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BigFloat {}
+
+impl PartialOrd for BigFloat {
+    fn partial_cmp(&self, other: &BigFloat) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigFloat {
+    fn cmp(&self, other: &BigFloat) -> Ordering {
+        match (self - other).mantissa.sign() {
+            Sign::Minus => Ordering::Less,
+            Sign::NoSign => Ordering::Equal,
+            Sign::Plus => Ordering::Greater,
+        }
+    }
+}
+
+impl<'a> Add<&'a BigFloat> for &'a BigFloat {
+    type Output = BigFloat;
+
+    fn add(self, other: &BigFloat) -> BigFloat {
+        self.add_impl(other)
+    }
+}
+
+impl Add for BigFloat {
+    type Output = BigFloat;
+
+    fn add(self, other: BigFloat) -> BigFloat {
+        self.add_impl(&other)
+    }
+}
+
+impl<'a> Sub<&'a BigFloat> for &'a BigFloat {
+    type Output = BigFloat;
+
+    fn sub(self, other: &BigFloat) -> BigFloat {
+        self.add_impl(&-other)
+    }
+}
+
+impl Sub for BigFloat {
+    type Output = BigFloat;
+
+    fn sub(self, other: BigFloat) -> BigFloat {
+        self.add_impl(&-other)
+    }
+}
+
+impl<'a> Mul<&'a BigFloat> for &'a BigFloat {
+    type Output = BigFloat;
+
+    fn mul(self, other: &BigFloat) -> BigFloat {
+        self.mul_impl(other)
+    }
+}
+
+impl Mul for BigFloat {
+    type Output = BigFloat;
+
+    fn mul(self, other: BigFloat) -> BigFloat {
+        self.mul_impl(&other)
+    }
+}
+
+impl Neg for &BigFloat {
+    type Output = BigFloat;
+
+    fn neg(self) -> BigFloat {
+        BigFloat {
+            mantissa: -&self.mantissa,
+            exponent: self.exponent,
+            precision: self.precision,
+        }
+    }
+}
+
+impl Neg for BigFloat {
+    type Output = BigFloat;
+
+    fn neg(self) -> BigFloat {
+        -&self
+    }
+}
+
+impl fmt::Display for BigFloat {
+    /// Writes the exact decimal expansion of the value (every binary fraction has a finite
+    /// decimal expansion), so that it can be parsed again without losing precision.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sign = if self.mantissa.is_negative() { "-" } else { "" };
+        let magnitude = self.mantissa.abs();
+        if self.exponent >= 0 {
+            return write!(f, "{}{}", sign, magnitude << self.exponent as usize);
+        }
+        let fraction_bits = (-self.exponent) as usize;
+        // 2^-n has exactly n decimal digits after the decimal point
+        let scaled = magnitude * num::pow(BigInt::from(5), fraction_bits);
+        let digits = format!("{:0>width$}", scaled, width = fraction_bits + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - fraction_bits);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}{}", sign, whole)
+        } else {
+            write!(f, "{}{}.{}", sign, whole, fraction)
+        }
+    }
+}
+
+/// A complex number made of `BigFloat`s.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BigComplex {
+    pub re: BigFloat,
+    pub im: BigFloat,
+}
+
+impl BigComplex {
+    pub fn new(re: BigFloat, im: BigFloat) -> BigComplex {
+        BigComplex { re, im }
+    }
+
+    /// Converts a `Complex64` exactly (as long as `precision` is at least 53 bits).
+    pub fn from_complex64(c: Complex64, precision: u32) -> BigComplex {
+        BigComplex {
+            re: BigFloat::from_f64(c.re, precision),
+            im: BigFloat::from_f64(c.im, precision),
+        }
+    }
+
+    /// Converts to the nearest `Complex64`.
+    pub fn to_complex64(&self) -> Complex64 {
+        Complex64::new(self.re.to_f64(), self.im.to_f64())
+    }
+
+    /// The precision of the least precise component.
+    pub fn precision(&self) -> u32 {
+        cmp::min(self.re.precision(), self.im.precision())
+    }
+
+    /// Returns the same value, rounded or extended to the new precision.
+    pub fn with_precision(&self, precision: u32) -> BigComplex {
+        BigComplex {
+            re: self.re.with_precision(precision),
+            im: self.im.with_precision(precision),
+        }
+    }
+
+    /// Computes `self * self`, which is slightly cheaper than a general multiplication.
+    pub fn square(&self) -> BigComplex {
+        BigComplex {
+            re: &(&self.re * &self.re) - &(&self.im * &self.im),
+            im: (&self.re * &self.im).mul_pow2(1),
+        }
+    }
+
+    /// The squared norm of the value, as an `f64`.
+    pub fn norm_sqr_f64(&self) -> f64 {
+        self.to_complex64().norm_sqr()
+    }
+}
+
+impl<'a> Add<&'a BigComplex> for &'a BigComplex {
+    type Output = BigComplex;
+
+    fn add(self, other: &BigComplex) -> BigComplex {
+        BigComplex {
+            re: &self.re + &other.re,
+            im: &self.im + &other.im,
+        }
+    }
+}
+
+impl<'a> Mul<&'a BigComplex> for &'a BigComplex {
+    type Output = BigComplex;
+
+    fn mul(self, other: &BigComplex) -> BigComplex {
+        BigComplex {
+            re: &(&self.re * &other.re) - &(&self.im * &other.im),
+            im: &(&self.re * &other.im) + &(&self.im * &other.re),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_f64_round_trip() {
+        for &value in &[
+            0.0,
+            1.0,
+            -1.0,
+            0.1,
+            -2.75,
+            1e-300,
+            123_456_789.123,
+            -3.5e200,
+        ] {
+            assert_eq!(BigFloat::from_f64(value, 64).to_f64(), value);
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = BigFloat::from_f64(1.5, 64);
+        let b = BigFloat::from_f64(-0.25, 64);
+        assert_eq!((&a + &b).to_f64(), 1.25);
+        assert_eq!((&a - &b).to_f64(), 1.75);
+        assert_eq!((&a * &b).to_f64(), -0.375);
+        assert_eq!((-&a).to_f64(), -1.5);
+        assert_eq!(b.abs().to_f64(), 0.25);
+        assert_eq!(a.mul_pow2(-3).to_f64(), 0.1875);
+        assert!(b < a);
+        assert_eq!(&a + &BigFloat::zero(64), a);
+    }
+
+    #[test]
+    fn test_precision_beyond_f64() {
+        // 1 + 2^-100 can not be represented by an f64, but subtracting 1 again should recover
+        // 2^-100 exactly when there is enough precision.
+        let one = BigFloat::from_f64(1.0, 128);
+        let tiny = BigFloat::from_f64(2f64.powi(-100), 128);
+        let sum = &one + &tiny;
+        assert_eq!((&sum - &one).to_f64(), 2f64.powi(-100));
+        assert!(sum > one);
+
+        // But not with only 64 bits.
+        let one = BigFloat::from_f64(1.0, 64);
+        let sum = &one + &tiny.with_precision(64);
+        assert_eq!((&sum - &one).to_f64(), 0.0);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(BigFloat::parse("-0.75", 64).unwrap().to_f64(), -0.75);
+        assert_eq!(BigFloat::parse("12", 64).unwrap().to_f64(), 12.0);
+        assert_eq!(BigFloat::parse("1.5e3", 64).unwrap().to_f64(), 1500.0);
+        assert_approx_eq!(BigFloat::parse("0.1", 64).unwrap().to_f64(), 0.1, 1e-17);
+        assert_approx_eq!(
+            BigFloat::parse("2.5E-30", 128).unwrap().to_f64(),
+            2.5e-30,
+            1e-45
+        );
+        assert!(BigFloat::parse("abc", 64).is_err());
+        assert!(BigFloat::parse("1.2.3", 64).is_err());
+        assert!(BigFloat::parse("-", 64).is_err());
+
+        // digits beyond the precision of an f64 are kept
+        let a = BigFloat::parse("1.000000000000000000000000000001", 128).unwrap();
+        let one = BigFloat::from_f64(1.0, 128);
+        assert_approx_eq!((&a - &one).to_f64(), 1e-30, 1e-37);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        assert_eq!(format!("{}", BigFloat::from_f64(-2.5, 64)), "-2.5");
+        assert_eq!(format!("{}", BigFloat::from_f64(1024.0, 64)), "1024");
+        assert_eq!(format!("{}", BigFloat::from_f64(0.0, 64)), "0");
+        let a = BigFloat::parse("-0.743643887037158704752191506114774", 160).unwrap();
+        assert_eq!(BigFloat::parse(&format!("{}", a), 160).unwrap(), a);
+    }
+
+    #[test]
+    fn test_complex() {
+        let a = BigComplex::from_complex64(Complex64::new(1.0, 2.0), 64);
+        let b = BigComplex::from_complex64(Complex64::new(-0.5, 0.25), 64);
+        assert_eq!(
+            (&a * &b).to_complex64(),
+            Complex64::new(1.0, 2.0) * Complex64::new(-0.5, 0.25)
+        );
+        assert_eq!((&a + &b).to_complex64(), Complex64::new(0.5, 2.25));
+        assert_eq!(a.square().to_complex64(), Complex64::new(-3.0, 4.0));
+        assert_eq!(a.norm_sqr_f64(), 5.0);
+    }
+}
@@ -26,6 +26,8 @@ pub struct ColorU8(pub [u8; 4]);
 pub const BLACK_F32: ColorF32 = ColorF32([0.0, 0.0, 0.0, 1.0]);
 /// Grey for use with `graphics`' functions
 pub const GREY_F32: ColorF32 = ColorF32([0.5, 0.5, 0.5, 1.0]);
+/// Red for use with `graphics`' functions
+pub const RED_F32: ColorF32 = ColorF32([1.0, 0.0, 0.0, 1.0]);
 /// White for use with `graphics`' functions
 pub const WHITE_F32: ColorF32 = ColorF32([1.0, 1.0, 1.0, 1.0]);
 
@@ -96,6 +98,421 @@ pub fn color_range_linear(first: ColorU8, last: ColorU8, count: usize) -> Vec<Co
         .collect()
 }
 
+/// Linearly interpolates between two colors. `t` is clamped to `[0.0, 1.0]`, where 0.0 results in
+/// `first` and 1.0 results in `last`.
+///
+/// ```
+/// use fractal_lib::color::{ColorU8, lerp};
+///
+/// let black = ColorU8([0,0,0,255]);
+/// let white = ColorU8([255,255,255,255]);
+///
+/// assert_eq!(lerp(black, white, 0.0), black);
+/// assert_eq!(lerp(black, white, 1.0), white);
+/// assert_eq!(lerp(black, white, 0.5), ColorU8([128,128,128,255]));
+/// ```
+pub fn lerp(first: ColorU8, last: ColorU8, t: f64) -> ColorU8 {
+    let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+    let mut mixed = [0u8; 4];
+    for (i, channel) in mixed.iter_mut().enumerate() {
+        let a = f64::from(first.0[i]);
+        let b = f64::from(last.0[i]);
+        *channel = (a + (b - a) * t).round() as u8;
+    }
+    ColorU8(mixed)
+}
+
+/// Looks up a fractional `index` in a palette, interpolating between the two nearest colors.
+///
+/// This is meant to be used with a continuous value such as a smoothed escape time iteration
+/// count, in order to avoid visible steps between the colors of the palette. Like using
+/// `std::cmp::min` with an integer index, indices past the end of the palette saturate at the
+/// last color, and negative indices use the first color.
+///
+/// ```
+/// use fractal_lib::color::{ColorU8, color_range_linear, palette_lookup_smooth};
+///
+/// let black = ColorU8([0,0,0,255]);
+/// let white = ColorU8([255,255,255,255]);
+/// let range = color_range_linear(black, white, 3);
+///
+/// assert_eq!(palette_lookup_smooth(&range, 0.0), black);
+/// assert_eq!(palette_lookup_smooth(&range, 0.5), ColorU8([64,64,64,255]));
+/// assert_eq!(palette_lookup_smooth(&range, 2.0), white);
+/// assert_eq!(palette_lookup_smooth(&range, 300.0), white);
+/// ```
+pub fn palette_lookup_smooth(palette: &[ColorU8], index: f64) -> ColorU8 {
+    let last = palette.len() - 1;
+    if index.is_nan() || index <= 0.0 {
+        return palette[0];
+    }
+    if index >= last as f64 {
+        return palette[last];
+    }
+    let whole = index.floor();
+    let lower = whole as usize;
+    lerp(palette[lower], palette[lower + 1], index - whole)
+}
+
+/// How the smoothed iteration counts of the points that escaped are spread across a palette.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorScale {
+    /// Each iteration moves one color along the palette, so every count past the end of the
+    /// palette gets its last color.
+    Fixed,
+    /// Histogram equalization: each count is placed by the fraction of the frame's escaped
+    /// points that escaped no later than it, so that each color covers about as many pixels.
+    Histogram,
+    /// The counts are rescaled linearly from the lowest to the highest one in the frame.
+    Range,
+}
+
+impl ColorScale {
+    /// The names that `ColorScale::from_name()` accepts.
+    pub const NAMES: [&'static str; 3] = ["fixed", "histogram", "range"];
+
+    /// Looks up a scale by one of `ColorScale::NAMES`.
+    pub fn from_name(name: &str) -> Result<ColorScale, String> {
+        match name {
+            "fixed" => Ok(ColorScale::Fixed),
+            "histogram" => Ok(ColorScale::Histogram),
+            "range" => Ok(ColorScale::Range),
+            _ => Err(format!("Unknown color scale: {}", name)),
+        }
+    }
+
+    /// Whether the color of a point depends on the other points of the frame, in which case the
+    /// whole frame has to be evaluated before it can be colored.
+    pub fn is_adaptive(self) -> bool {
+        self != ColorScale::Fixed
+    }
+
+    /// Builds the mapping for a frame where the points that escaped have the smoothed iteration
+    /// counts in `counts`.
+    pub fn mapping(self, counts: &[f64]) -> IterationMapping {
+        match self {
+            ColorScale::Fixed => IterationMapping::Fixed,
+            ColorScale::Histogram => IterationMapping::histogram(counts),
+            ColorScale::Range => IterationMapping::range(counts),
+        }
+    }
+}
+
+/// Maps the smoothed iteration count of a point that escaped to an index into a palette, for use
+/// with `palette_lookup_smooth`, according to a `ColorScale`.
+///
+/// ```
+/// use fractal_lib::color::{ColorScale, IterationMapping};
+///
+/// let counts = [10.5, 11.5, 12.5, 40.5];
+/// assert_eq!(ColorScale::Fixed.mapping(&counts).palette_index(40.5, 5), 40.5);
+///
+/// let range = ColorScale::Range.mapping(&counts);
+/// assert_eq!(range, IterationMapping::Range { min: 10.5, max: 40.5 });
+/// assert_eq!(range.palette_index(10.5, 5), 0.0);
+/// assert_eq!(range.palette_index(25.5, 5), 2.0);
+/// assert_eq!(range.palette_index(40.5, 5), 4.0);
+///
+/// // Three quarters of the points escaped by iteration 12, so the gap up to 40 gets only the
+/// // last quarter of the palette
+/// let histogram = ColorScale::Histogram.mapping(&counts);
+/// assert_eq!(histogram.palette_index(12.0, 5), 2.0);
+/// assert_eq!(histogram.palette_index(12.5, 5), 2.5);
+/// assert_eq!(histogram.palette_index(40.5, 5), 3.5);
+/// assert_eq!(histogram.palette_index(41.0, 5), 4.0);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum IterationMapping {
+    /// Uses the count itself as the index.
+    Fixed,
+    /// Uses the cumulative distribution of the counts.
+    Histogram {
+        /// The lowest whole iteration count.
+        first: u64,
+        /// For each whole iteration count starting at `first`, the fraction of the counts whose
+        /// whole part is no more than it.
+        cumulative: Vec<f64>,
+    },
+    /// Rescales the counts from `min` to `max`.
+    Range { min: f64, max: f64 },
+}
+
+impl IterationMapping {
+    /// Histogram equalization for `counts`.
+    pub fn histogram(counts: &[f64]) -> IterationMapping {
+        let wholes = counts
+            .iter()
+            .map(|count| count.floor().max(0.0) as u64)
+            .collect::<Vec<u64>>();
+        let (first, last) = match (wholes.iter().min(), wholes.iter().max()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => {
+                return IterationMapping::Histogram {
+                    first: 0,
+                    cumulative: Vec::new(),
+                }
+            }
+        };
+        let mut cumulative = vec![0.0; (last - first + 1) as usize];
+        for whole in wholes {
+            cumulative[(whole - first) as usize] += 1.0;
+        }
+        let mut total = 0.0;
+        for bin in cumulative.iter_mut() {
+            total += *bin;
+            *bin = total / counts.len() as f64;
+        }
+        IterationMapping::Histogram { first, cumulative }
+    }
+
+    /// Rescales from the lowest to the highest of `counts`.
+    pub fn range(counts: &[f64]) -> IterationMapping {
+        let min = counts.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max = counts.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        if min > max {
+            return IterationMapping::Range { min: 0.0, max: 0.0 };
+        }
+        IterationMapping::Range { min, max }
+    }
+
+    /// The index in a palette with `palette_size` colors for a point that escaped with the
+    /// smoothed iteration count `count`.
+    pub fn palette_index(&self, count: f64, palette_size: usize) -> f64 {
+        let last = palette_size.saturating_sub(1) as f64;
+        match self {
+            IterationMapping::Fixed => count,
+            IterationMapping::Histogram { first, cumulative } => {
+                let whole = count.floor().max(0.0);
+                if cumulative.is_empty() || (whole as u64) < *first {
+                    return 0.0;
+                }
+                let bin = (whole as u64 - first) as usize;
+                if bin >= cumulative.len() {
+                    return last;
+                }
+                let below = if bin == 0 { 0.0 } else { cumulative[bin - 1] };
+                let fraction = below + (cumulative[bin] - below) * (count - whole);
+                fraction * last
+            }
+            IterationMapping::Range { min, max } => {
+                if max > min {
+                    ((count - min) / (max - min)).clamp(0.0, 1.0) * last
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// Converts a channel of an sRGB color to linear light, from 0.0 to 1.0.
+///
+/// sRGB values are gamma encoded, so averaging them directly (for example to blend the samples
+/// of a pixel) comes out too dark. Averaging in linear light gives the physically correct mix.
+///
+/// ```
+/// use fractal_lib::color::srgb_to_linear;
+///
+/// assert_eq!(srgb_to_linear(0), 0.0);
+/// assert_eq!(srgb_to_linear(255), 1.0);
+/// assert!((srgb_to_linear(188) - 0.5).abs() < 0.01);
+/// ```
+pub fn srgb_to_linear(channel: u8) -> f64 {
+    let value = f64::from(channel) / 255.0;
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a value in linear light (clamped to `[0.0, 1.0]`) back to a channel of an sRGB
+/// color. This is the inverse of `srgb_to_linear()`.
+///
+/// ```
+/// use fractal_lib::color::{linear_to_srgb, srgb_to_linear};
+///
+/// assert_eq!(linear_to_srgb(0.5), 188);
+/// for channel in 0..=255 {
+///     assert_eq!(linear_to_srgb(srgb_to_linear(channel)), channel);
+/// }
+/// ```
+pub fn linear_to_srgb(value: f64) -> u8 {
+    let value = if value.is_nan() {
+        0.0
+    } else {
+        value.clamp(0.0, 1.0)
+    };
+    let encoded = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0).round() as u8
+}
+
+/// Averages `colors` in linear light, such as the colors of the samples that make up a pixel.
+/// The alpha channel is not gamma encoded, so it is averaged directly.
+///
+/// ```
+/// use fractal_lib::color::{average_linear, ColorU8};
+///
+/// let black = ColorU8([0,0,0,255]);
+/// let white = ColorU8([255,255,255,255]);
+///
+/// assert_eq!(average_linear(&[black, white]), ColorU8([188,188,188,255]));
+/// assert_eq!(average_linear(&[white]), white);
+/// ```
+pub fn average_linear(colors: &[ColorU8]) -> ColorU8 {
+    let count = colors.len().max(1) as f64;
+    let mut mixed = [0u8; 4];
+    for (i, channel) in mixed.iter_mut().enumerate() {
+        if i == 3 {
+            let sum: f64 = colors.iter().map(|color| f64::from(color.0[i])).sum();
+            *channel = (sum / count).round() as u8;
+        } else {
+            let sum: f64 = colors.iter().map(|color| srgb_to_linear(color.0[i])).sum();
+            *channel = linear_to_srgb(sum / count);
+        }
+    }
+    ColorU8(mixed)
+}
+
+/// Converts a color from the HSV color model to RGBA, for use with `image` functions.
+///
+/// `hue` is in degrees and wraps around (so 360.0 is red again), while `saturation` and `value`
+/// are clamped to `[0.0, 1.0]`.
+///
+/// ```
+/// use fractal_lib::color::{ColorU8, hsv_to_rgb};
+///
+/// assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), ColorU8([255,0,0,255]));
+/// assert_eq!(hsv_to_rgb(120.0, 1.0, 1.0), ColorU8([0,255,0,255]));
+/// assert_eq!(hsv_to_rgb(600.0, 1.0, 1.0), ColorU8([0,0,255,255]));
+/// assert_eq!(hsv_to_rgb(42.0, 0.0, 0.5), ColorU8([128,128,128,255]));
+/// ```
+pub fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> ColorU8 {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let saturation = saturation.clamp(0.0, 1.0);
+    let value = value.clamp(0.0, 1.0);
+
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    ColorU8([
+        ((r + m) * 255.0).round() as u8,
+        ((g + m) * 255.0).round() as u8,
+        ((b + m) * 255.0).round() as u8,
+        255,
+    ])
+}
+
+/// Picks the color for a point in the interior of an escape time fractal.
+///
+/// If the length of the cycle that the point's orbit settled into is known, then each period gets
+/// its own dark hue (spaced by the golden angle so that nearby periods contrast), which makes the
+/// bulbs of the fractal stand out from each other. Otherwise it uses `AEBLUE_U8`.
+pub fn interior_color(period: Option<u64>) -> ColorU8 {
+    match period {
+        None => AEBLUE_U8,
+        Some(period) => hsv_to_rgb(240.0 + 137.5 * (period - 1) as f64, 0.8, 0.35),
+    }
+}
+
+/// Picks the color for a point outside of an escape time fractal from its estimated distance to the
+/// fractal's set (see `escapetime::distance`), where `pixel_size` is the width of a pixel in the
+/// same units.
+///
+/// Points within a pixel or two of the set are shaded from black to white, and everything
+/// further away is white, which draws the boundary (including filaments that are much thinner
+/// than a pixel) as crisp dark lines.
+///
+/// ```
+/// use fractal_lib::color::{ColorU8, distance_color};
+///
+/// assert_eq!(distance_color(0.0, 0.01), ColorU8([0,0,0,255]));
+/// assert_eq!(distance_color(0.005, 0.01), ColorU8([128,128,128,255]));
+/// assert_eq!(distance_color(0.02, 0.01), ColorU8([255,255,255,255]));
+/// assert_eq!(distance_color(std::f64::INFINITY, 0.01), ColorU8([255,255,255,255]));
+/// ```
+pub fn distance_color(distance: f64, pixel_size: f64) -> ColorU8 {
+    lerp(BLACK_U8, WHITE_U8, (distance / (2.0 * pixel_size)).sqrt())
+}
+
+/// Picks the color for a point from the closest that its orbit came to an orbit trap (see
+/// `escapetime::orbittrap`).
+///
+/// Orbits that pass right through the trap are bright, and they fade to dark as the distance
+/// approaches `falloff`. Points outside of the fractal's set fade from orange to dark blue, while
+/// points in its interior fade from pale cyan to dark purple, so that the two can be told apart.
+///
+/// ```
+/// use fractal_lib::color::{ColorU8, trap_color};
+///
+/// assert_eq!(trap_color(0.0, 0.5, true), ColorU8([255,136,77,255]));
+/// assert_eq!(trap_color(0.5, 0.5, true), ColorU8([13,13,38,255]));
+/// assert_eq!(trap_color(std::f64::INFINITY, 0.5, true), ColorU8([13,13,38,255]));
+/// assert_eq!(trap_color(0.0, 0.5, false), ColorU8([153,238,255,255]));
+/// ```
+pub fn trap_color(distance: f64, falloff: f64, escaped: bool) -> ColorU8 {
+    let t = (distance / falloff).clamp(0.0, 1.0).sqrt();
+    if escaped {
+        hsv_to_rgb(20.0 + 220.0 * t, 0.7 - 0.04 * t, 1.0 - 0.85 * t)
+    } else {
+        hsv_to_rgb(190.0 + 90.0 * t, 0.4 + 0.2 * t, 1.0 - 0.85 * t)
+    }
+}
+
+/// Picks the color for a point outside of an escape time fractal from the average of a statistic
+/// over its orbit (see `escapetime::averaging`), which is between 0.0 and 1.0.
+///
+/// The colors run from dark blue through pale blue and orange to black, which gives the subtle
+/// differences between the averages of neighboring points plenty of contrast.
+///
+/// ```
+/// use fractal_lib::color::{ColorU8, average_color, AEBLUE_U8, BLACK_U8};
+///
+/// assert_eq!(average_color(0.0), AEBLUE_U8);
+/// assert_eq!(average_color(0.5), ColorU8([237,255,255,255]));
+/// assert_eq!(average_color(1.0), BLACK_U8);
+/// assert_eq!(average_color(7.0), BLACK_U8);
+/// ```
+pub fn average_color(average: f64) -> ColorU8 {
+    let gradient = [
+        AEBLUE_U8,
+        ColorU8([32, 107, 203, 255]),
+        ColorU8([237, 255, 255, 255]),
+        ColorU8([255, 170, 0, 255]),
+        BLACK_U8,
+    ];
+    palette_lookup_smooth(&gradient, average * (gradient.len() - 1) as f64)
+}
+
+/// Picks the color for a point that converged to the attractor `index` out of `count` attractors.
+///
+/// Each attractor gets its own hue, spread evenly around the color wheel, and `shade` (from 0.0 to
+/// 1.0) darkens the color, so that points that took longer to converge can be drawn darker.
+///
+/// ```
+/// use fractal_lib::color::{ColorU8, attractor_color};
+///
+/// assert_eq!(attractor_color(0, 3, 0.0), ColorU8([255,64,64,255]));
+/// assert_eq!(attractor_color(1, 3, 0.0), ColorU8([64,255,64,255]));
+/// assert_eq!(attractor_color(1, 3, 1.0), ColorU8([13,51,13,255]));
+/// ```
+pub fn attractor_color(index: usize, count: usize, shade: f64) -> ColorU8 {
+    let hue = 360.0 * index as f64 / count.max(1) as f64;
+    hsv_to_rgb(hue, 0.75, 1.0 - 0.8 * shade.clamp(0.0, 1.0))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -125,4 +542,36 @@ mod test {
         assert_eq!(black, range[0]);
         assert_eq!(white, range[1]);
     }
+
+    #[test]
+    fn test_lerp_clamps() {
+        let black = ColorU8([0, 0, 0, 255]);
+        let white = ColorU8([255, 255, 255, 255]);
+        assert_eq!(lerp(black, white, -1.0), black);
+        assert_eq!(lerp(black, white, 2.0), white);
+        assert_eq!(lerp(black, white, f64::NAN), black);
+    }
+
+    #[test]
+    fn test_palette_lookup_smooth_between_entries() {
+        let black = ColorU8([0, 0, 0, 255]);
+        let white = ColorU8([255, 255, 255, 255]);
+        let range = color_range_linear(black, white, 4);
+        assert_eq!(palette_lookup_smooth(&range, 1.0), range[1]);
+        assert_eq!(
+            palette_lookup_smooth(&range, 1.25),
+            lerp(range[1], range[2], 0.25)
+        );
+        assert_eq!(palette_lookup_smooth(&range, -3.0), black);
+    }
+
+    #[test]
+    fn test_interior_color_by_period() {
+        assert_eq!(interior_color(None), AEBLUE_U8);
+        assert_ne!(interior_color(Some(1)), interior_color(Some(2)));
+        assert_ne!(interior_color(Some(2)), interior_color(Some(3)));
+        // Period 1 is a dark blue, like the color used when the period is unknown
+        let ColorU8([r, g, b, _]) = interior_color(Some(1));
+        assert!(b > r && b > g);
+    }
 }
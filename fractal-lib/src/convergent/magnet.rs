@@ -0,0 +1,164 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Magnet fractals, which come from models of magnetic materials in physics (the renormalization
+//! of the Potts model), and are given by:
+//!
+//! ```text
+//! Type I:  z_{n+1} = ((z_n^2 + c - 1) / (2z_n + c - 2))^2
+//! Type II: z_{n+1} = ((z_n^3 + 3(c - 1)z_n + (c - 1)(c - 2))
+//!                     / (3z_n^2 + 3(c - 2)z_n + (c - 1)(c - 2) + 1))^2
+//! ```
+//!
+//! Like the Mandelbrot set, the point being tested is used as `c`, and iteration starts from 0.
+//! Orbits either converge to the fixed point `z = 1` (which is a fixed point for every `c`),
+//! converge elsewhere, or diverge.
+
+use super::*;
+
+/// The norm beyond which the value being iterated is considered to have diverged.
+pub const MAGNET_DIVERGENCE_RADIUS: f64 = 100.0;
+
+/// The fixed point shared by every magnet fractal.
+const ONE: Complex64 = Complex64 { re: 1.0, im: 0.0 };
+
+/// The type I magnet fractal.
+pub struct MagnetTypeI {
+    max_iters: u64,
+}
+
+impl MagnetTypeI {
+    pub fn new(max_iterations: u64) -> MagnetTypeI {
+        MagnetTypeI {
+            max_iters: max_iterations,
+        }
+    }
+}
+
+impl ConvergenceTime for MagnetTypeI {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-1.5, 2.5), Complex64::new(3.5, -2.5)]
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        let q = (z * z + c - 1.0) / (2.0 * z + c - 2.0);
+        q * q
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        (point, Complex64::new(0.0, 0.0))
+    }
+
+    fn attractors(&self) -> &[Complex64] {
+        &[ONE]
+    }
+
+    fn divergence_radius(&self) -> Option<f64> {
+        Some(MAGNET_DIVERGENCE_RADIUS)
+    }
+}
+
+/// The type II magnet fractal.
+pub struct MagnetTypeII {
+    max_iters: u64,
+}
+
+impl MagnetTypeII {
+    pub fn new(max_iterations: u64) -> MagnetTypeII {
+        MagnetTypeII {
+            max_iters: max_iterations,
+        }
+    }
+}
+
+impl ConvergenceTime for MagnetTypeII {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-1.0, 2.0), Complex64::new(3.0, -2.0)]
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        let c1 = c - 1.0;
+        let c2 = c - 2.0;
+        let q = (z * z * z + 3.0 * c1 * z + c1 * c2) / (3.0 * z * z + 3.0 * c2 * z + c1 * c2 + 1.0);
+        q * q
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        (point, Complex64::new(0.0, 0.0))
+    }
+
+    fn attractors(&self) -> &[Complex64] {
+        &[ONE]
+    }
+
+    fn divergence_radius(&self) -> Option<f64> {
+        Some(MAGNET_DIVERGENCE_RADIUS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_one_is_fixed() {
+        for &c in &[Complex64::new(0.5, 0.5), Complex64::new(-1.0, 2.0)] {
+            assert_eq!(MagnetTypeI::new(10).iterate(c, ONE), ONE);
+            assert!((MagnetTypeII::new(10).iterate(c, ONE) - ONE).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_magnet_type_i() {
+        let magnet = MagnetTypeI::new(200);
+        // c = 4: z_1 = (3 / 2)^2 = 9/4, which is then attracted to 1
+        assert_eq!(
+            magnet.evaluate(Complex64::new(4.0, 0.0)).convergence,
+            Convergence::Attractor(0)
+        );
+        match magnet.evaluate(Complex64::new(1.2, 0.5)).convergence {
+            Convergence::FixedPoint(z) => assert!((z - ONE).norm() > 1.0),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            magnet.evaluate(Complex64::new(2.0, 0.1)).convergence,
+            Convergence::Diverged
+        );
+    }
+
+    #[test]
+    fn test_magnet_type_ii() {
+        let magnet = MagnetTypeII::new(200);
+        assert_eq!(
+            magnet.evaluate(Complex64::new(3.0, 0.0)).convergence,
+            Convergence::Attractor(0)
+        );
+        match magnet.evaluate(Complex64::new(2.0, 0.1)).convergence {
+            Convergence::FixedPoint(z) => assert!(z.norm() < 0.1),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(
+            magnet.evaluate(Complex64::new(1.5, 1.0)).convergence,
+            Convergence::Diverged
+        );
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fractals that classify points by what their orbits converge to, rather than by when they
+//! escape.
+//!
+//! Where an `EscapeTime` fractal colors a point by how quickly it diverges, a convergent fractal
+//! iterates a function that pulls most points towards one of several attractors (such as the
+//! roots of a polynomial for Newton's method), and colors each point by which attractor it ends
+//! up at and how many iterations that took.
+
+pub mod magnet;
+pub mod newton;
+pub mod polynomial;
+
+use super::color::{self, ColorU8};
+pub use num::complex::Complex64;
+
+/// The default distance from an attractor that a value has to reach before it is considered to
+/// have converged to it.
+pub const DEFAULT_TOLERANCE: f64 = 1e-6;
+
+/// What the orbit of a point ended up doing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Convergence {
+    /// Converged to the attractor with this index in `ConvergenceTime::attractors()`.
+    Attractor(usize),
+    /// Settled down to a value that is not one of the known attractors.
+    FixedPoint(Complex64),
+    /// Grew beyond `ConvergenceTime::divergence_radius()`.
+    Diverged,
+    /// Did not settle down within the maximum number of iterations (or it hit a point where the
+    /// function is undefined).
+    Undecided,
+}
+
+/// The result of testing a single complex number with `ConvergenceTime::evaluate()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConvergenceResult {
+    pub convergence: Convergence,
+    /// The iteration during which the orbit converged (or diverged), or 0 if it did neither.
+    pub iterations: u64,
+    /// The final value of `z`.
+    pub z: Complex64,
+    /// A continuous version of `iterations`, which interpolates (on a log scale) how far into its
+    /// last iteration the orbit crossed the tolerance. It falls within `[iterations,
+    /// iterations + 1]`, and is 0 for undecided points.
+    pub smooth: f64,
+}
+
+impl ConvergenceResult {
+    /// Constructs the result for an orbit that reached `z` during iteration `iterations`, where
+    /// `previous_distance` and `distance` measure how far the last two values were from where the
+    /// orbit is headed, and `tolerance` is the distance that counts as having arrived.
+    fn settled(
+        convergence: Convergence,
+        iterations: u64,
+        z: Complex64,
+        previous_distance: f64,
+        distance: f64,
+        tolerance: f64,
+    ) -> ConvergenceResult {
+        let fraction =
+            if previous_distance.is_finite() && previous_distance > tolerance && distance > 0.0 {
+                ((previous_distance.ln() - tolerance.ln())
+                    / (previous_distance.ln() - distance.ln()))
+                .clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+        ConvergenceResult {
+            convergence,
+            iterations,
+            z,
+            smooth: iterations as f64 + fraction,
+        }
+    }
+
+    fn undecided(z: Complex64) -> ConvergenceResult {
+        ConvergenceResult {
+            convergence: Convergence::Undecided,
+            iterations: 0,
+            z,
+            smooth: 0.0,
+        }
+    }
+
+    /// Picks a color for the point.
+    ///
+    /// Each of the `attractor_count` attractors gets its own hue, and points are darkened the
+    /// longer they took to converge, bottoming out at `shading_iterations`. Points that settled
+    /// at other fixed points are colored by the angle of the fixed point, diverging points are
+    /// shaded in grey, and undecided points are black.
+    pub fn color(&self, attractor_count: usize, shading_iterations: f64) -> ColorU8 {
+        let shade = self.smooth / shading_iterations.max(1.0);
+        match self.convergence {
+            Convergence::Attractor(index) => color::attractor_color(index, attractor_count, shade),
+            Convergence::FixedPoint(z) => {
+                color::hsv_to_rgb(z.arg().to_degrees(), 0.75, 1.0 - 0.8 * shade.min(1.0))
+            }
+            Convergence::Diverged => color::lerp(color::BLACK_U8, color::WHITE_U8, shade),
+            Convergence::Undecided => color::BLACK_U8,
+        }
+    }
+}
+
+pub trait ConvergenceTime {
+    /// The maximum number of iterations to perform before giving up on the value settling down.
+    fn max_iterations(&self) -> u64;
+
+    /// The default view area of the complex number plane, specified as 2 complex numbers.
+    fn default_view_area(&self) -> [Complex64; 2];
+
+    /// A single iteration of the function that defines this particular fractal.
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64;
+
+    /// Maps the complex number being tested to the constant `c` and the initial value of `z` that
+    /// are used to start iterating.
+    ///
+    /// The default implementation starts iterating from the point being tested (like Newton's
+    /// method), and uses 0 for `c`.
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        (Complex64::new(0.0, 0.0), point)
+    }
+
+    /// The attractors that points are classified by, such as the roots of a polynomial.
+    ///
+    /// The default implementation does not know of any, in which case points can only settle at
+    /// a `Convergence::FixedPoint`.
+    fn attractors(&self) -> &[Complex64] {
+        &[]
+    }
+
+    /// How close a value has to get to an attractor (or to the previous value) to have converged.
+    fn tolerance(&self) -> f64 {
+        DEFAULT_TOLERANCE
+    }
+
+    /// The norm beyond which a value is considered to have diverged, or None if the fractal does
+    /// not diverge.
+    fn divergence_radius(&self) -> Option<f64> {
+        None
+    }
+
+    /// Iterates the given complex number until it settles down near an attractor or a fixed
+    /// point, diverges, or runs out of iterations.
+    ///
+    /// The default implementation uses ConvergenceTime::initial_values() to decide where to start,
+    /// and checks the known ConvergenceTime::attractors() before checking whether successive
+    /// values have come within ConvergenceTime::tolerance() of each other.
+    fn evaluate(&self, point: Complex64) -> ConvergenceResult {
+        let tolerance = self.tolerance();
+        let attractors = self.attractors();
+        let (c, mut z) = self.initial_values(point);
+        let mut previous_step = f64::INFINITY;
+        for i in 0..self.max_iterations() {
+            let next = self.iterate(c, z);
+            if !next.re.is_finite() || !next.im.is_finite() {
+                return ConvergenceResult::undecided(z);
+            }
+            if let Some(radius) = self.divergence_radius() {
+                if next.norm() > radius {
+                    return ConvergenceResult {
+                        convergence: Convergence::Diverged,
+                        iterations: i,
+                        z: next,
+                        smooth: i as f64,
+                    };
+                }
+            }
+            for (index, attractor) in attractors.iter().enumerate() {
+                let distance = (next - attractor).norm();
+                if distance < tolerance {
+                    return ConvergenceResult::settled(
+                        Convergence::Attractor(index),
+                        i,
+                        next,
+                        (z - attractor).norm(),
+                        distance,
+                        tolerance,
+                    );
+                }
+            }
+            let step = (next - z).norm();
+            if step < tolerance {
+                return ConvergenceResult::settled(
+                    Convergence::FixedPoint(next),
+                    i,
+                    next,
+                    previous_step,
+                    step,
+                    tolerance,
+                );
+            }
+            previous_step = step;
+            z = next;
+        }
+        ConvergenceResult::undecided(z)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Halves the distance to 0 each iteration.
+    struct Halving;
+
+    impl ConvergenceTime for Halving {
+        fn max_iterations(&self) -> u64 {
+            100
+        }
+
+        fn default_view_area(&self) -> [Complex64; 2] {
+            [Complex64::new(-1.0, 1.0), Complex64::new(1.0, -1.0)]
+        }
+
+        fn iterate(&self, _: Complex64, z: Complex64) -> Complex64 {
+            z / 2.0
+        }
+    }
+
+    #[test]
+    fn test_fixed_point() {
+        let result = Halving.evaluate(Complex64::new(1.0, 0.0));
+        match result.convergence {
+            Convergence::FixedPoint(z) => assert!(z.norm() < 1e-5),
+            other => panic!("unexpected {:?}", other),
+        }
+        // The steps are 2^-(i+1), so the step drops below 1e-6 during iteration 19
+        assert_eq!(result.iterations, 19);
+        assert!(result.smooth >= 19.0 && result.smooth <= 20.0);
+    }
+
+    #[test]
+    fn test_smooth_is_continuous() {
+        // Starting further out takes longer, and the smooth count should grow steadily rather
+        // than in whole steps.
+        let near = Halving.evaluate(Complex64::new(1.0, 0.0)).smooth;
+        let middle = Halving.evaluate(Complex64::new(1.4, 0.0)).smooth;
+        let far = Halving.evaluate(Complex64::new(2.0, 0.0)).smooth;
+        assert!(near < middle && middle < far);
+        assert_approx_eq!(far - near, 1.0, 1e-9);
+    }
+
+    #[test]
+    fn test_undecided_color() {
+        let result = ConvergenceResult::undecided(Complex64::new(0.0, 0.0));
+        assert_eq!(result.color(3, 50.0), color::BLACK_U8);
+    }
+}
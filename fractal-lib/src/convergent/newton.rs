@@ -0,0 +1,207 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Newton fractals and their Nova relatives.
+//!
+//! Newton's method finds a root of a polynomial `p` by repeatedly moving towards it:
+//!
+//! ```text
+//! z_{n+1} = z_n - a * p(z_n) / p'(z_n)
+//! ```
+//!
+//! where `a` is a relaxation factor (1 for the classic method). Starting from each point on the
+//! plane, the method usually converges to one of the roots, and the boundaries between the basins
+//! of attraction of the roots form a fractal.
+//!
+//! Nova fractals add a constant to each step, and use the point being tested as that constant
+//! (the same way the Mandelbrot set does), starting from a root of the polynomial:
+//!
+//! ```text
+//! z_{n+1} = z_n - a * p(z_n) / p'(z_n) + c
+//! ```
+
+use super::polynomial::Polynomial;
+use super::*;
+
+/// The Newton fractal for a polynomial.
+pub struct NewtonFractal {
+    max_iters: u64,
+    polynomial: Polynomial,
+    roots: Vec<Complex64>,
+    relaxation: Complex64,
+}
+
+impl NewtonFractal {
+    /// Creates the Newton fractal for `polynomial`, with the classic relaxation factor of 1.
+    ///
+    /// The roots of the polynomial are found numerically, and every point is classified by which
+    /// of them it converges to.
+    pub fn new(max_iterations: u64, polynomial: Polynomial) -> NewtonFractal {
+        NewtonFractal::with_relaxation(max_iterations, polynomial, Complex64::new(1.0, 0.0))
+    }
+
+    /// Like `NewtonFractal::new()`, but `relaxation` scales each step. Values other than 1
+    /// converge more slowly (or not at all), which changes the shapes of the basins.
+    pub fn with_relaxation(
+        max_iterations: u64,
+        polynomial: Polynomial,
+        relaxation: Complex64,
+    ) -> NewtonFractal {
+        let roots = polynomial.roots();
+        NewtonFractal {
+            max_iters: max_iterations,
+            polynomial,
+            roots,
+            relaxation,
+        }
+    }
+
+    /// The polynomial whose roots the fractal is attracted to.
+    pub fn polynomial(&self) -> &Polynomial {
+        &self.polynomial
+    }
+}
+
+impl ConvergenceTime for NewtonFractal {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.0, 1.5), Complex64::new(2.0, -1.5)]
+    }
+
+    fn iterate(&self, _: Complex64, z: Complex64) -> Complex64 {
+        let (value, derivative) = self.polynomial.evaluate_with_derivative(z);
+        z - self.relaxation * value / derivative
+    }
+
+    fn attractors(&self) -> &[Complex64] {
+        &self.roots
+    }
+}
+
+/// The Nova fractal for a polynomial, which is the Mandelbrot-style counterpart to
+/// `NewtonFractal`.
+pub struct NovaFractal {
+    max_iters: u64,
+    polynomial: Polynomial,
+    start: Complex64,
+    relaxation: Complex64,
+}
+
+impl NovaFractal {
+    /// Creates the Nova fractal for `polynomial` with the given relaxation factor. Iteration
+    /// starts from one of the roots of the polynomial (the one with the largest real part), which
+    /// is a fixed point of Newton's method.
+    pub fn new(max_iterations: u64, polynomial: Polynomial, relaxation: Complex64) -> NovaFractal {
+        let start = polynomial
+            .roots()
+            .into_iter()
+            .fold(None, |best: Option<Complex64>, root| match best {
+                Some(best) if best.re >= root.re => Some(best),
+                _ => Some(root),
+            })
+            .unwrap();
+        NovaFractal {
+            max_iters: max_iterations,
+            polynomial,
+            start,
+            relaxation,
+        }
+    }
+}
+
+impl ConvergenceTime for NovaFractal {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.5, 1.5), Complex64::new(1.5, -1.5)]
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        let (value, derivative) = self.polynomial.evaluate_with_derivative(z);
+        z - self.relaxation * value / derivative + c
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        (point, self.start)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn cubic() -> Polynomial {
+        // z^3 - 1
+        Polynomial::from_roots(&[
+            Complex64::new(1.0, 0.0),
+            Complex64::from_polar(&1.0, &(2.0 * std::f64::consts::PI / 3.0)),
+            Complex64::from_polar(&1.0, &(-2.0 * std::f64::consts::PI / 3.0)),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_newton_finds_each_root() {
+        let newton = NewtonFractal::new(50, cubic());
+        assert_eq!(newton.attractors().len(), 3);
+        // Points close to a root converge to it
+        for (index, root) in newton.attractors().iter().enumerate() {
+            let result = newton.evaluate(root * 1.1);
+            assert_eq!(result.convergence, Convergence::Attractor(index));
+            assert!(result.iterations < 10);
+        }
+    }
+
+    #[test]
+    fn test_newton_derivative_zero() {
+        // p'(0) = 0, so Newton's method is undefined at the origin
+        let newton = NewtonFractal::new(50, cubic());
+        assert_eq!(
+            newton.evaluate(Complex64::new(0.0, 0.0)).convergence,
+            Convergence::Undecided
+        );
+    }
+
+    #[test]
+    fn test_newton_relaxation_slows_convergence() {
+        let point = Complex64::new(2.0, 0.5);
+        let classic = NewtonFractal::new(200, cubic()).evaluate(point);
+        let relaxed =
+            NewtonFractal::with_relaxation(200, cubic(), Complex64::new(0.5, 0.0)).evaluate(point);
+        assert_eq!(classic.convergence, relaxed.convergence);
+        assert!(relaxed.iterations > classic.iterations);
+    }
+
+    #[test]
+    fn test_nova() {
+        let nova = NovaFractal::new(100, cubic(), Complex64::new(1.0, 0.0));
+        // With c = 0, iteration starts at (and stays on) the root 1
+        let result = nova.evaluate(Complex64::new(0.0, 0.0));
+        assert_eq!(result.iterations, 0);
+        match result.convergence {
+            Convergence::FixedPoint(z) => assert!((z - 1.0).norm() < 1e-6),
+            other => panic!("unexpected {:?}", other),
+        }
+        // A small c moves the fixed point slightly
+        match nova.evaluate(Complex64::new(-0.1, 0.05)).convergence {
+            Convergence::FixedPoint(z) => assert!((z - 1.0).norm() > 1e-3),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Polynomials with complex coefficients, for Newton's method and its relatives.
+
+use num::complex::Complex64;
+
+/// The number of Durand-Kerner iterations to try before giving up on refining the roots.
+const MAX_ROOT_ITERATIONS: u32 = 1000;
+
+/// A polynomial with complex coefficients, stored from the highest degree term down to the
+/// constant term.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polynomial {
+    coefficients: Vec<Complex64>,
+}
+
+impl Polynomial {
+    /// Creates a polynomial from its coefficients, starting with the highest degree term. For
+    /// example, `z^3 - 1` is `[1, 0, 0, -1]`.
+    ///
+    /// Leading zeros are dropped. Returns an error if the polynomial is not at least linear, since
+    /// it would not have any roots.
+    pub fn from_coefficients(coefficients: &[Complex64]) -> Result<Polynomial, String> {
+        let first = coefficients
+            .iter()
+            .position(|c| c.norm_sqr() != 0.0)
+            .unwrap_or(coefficients.len());
+        let coefficients = coefficients[first..].to_vec();
+        if coefficients.len() < 2 {
+            return Err("A polynomial needs a degree of at least 1".to_string());
+        }
+        Ok(Polynomial { coefficients })
+    }
+
+    /// Creates the monic polynomial `(z - roots[0]) * (z - roots[1]) * ...`.
+    pub fn from_roots(roots: &[Complex64]) -> Result<Polynomial, String> {
+        if roots.is_empty() {
+            return Err("A polynomial needs at least one root".to_string());
+        }
+        let mut coefficients = vec![Complex64::new(1.0, 0.0)];
+        for root in roots {
+            // multiply by (z - root)
+            coefficients.push(Complex64::new(0.0, 0.0));
+            for i in (1..coefficients.len()).rev() {
+                let previous = coefficients[i - 1];
+                coefficients[i] -= previous * root;
+            }
+        }
+        Ok(Polynomial { coefficients })
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len() - 1
+    }
+
+    /// The coefficients, starting with the highest degree term.
+    pub fn coefficients(&self) -> &[Complex64] {
+        &self.coefficients
+    }
+
+    /// Evaluates the polynomial at `z`.
+    pub fn evaluate(&self, z: Complex64) -> Complex64 {
+        self.coefficients
+            .iter()
+            .fold(Complex64::new(0.0, 0.0), |accum, c| accum * z + c)
+    }
+
+    /// Evaluates the polynomial and its derivative at `z`, in a single pass.
+    pub fn evaluate_with_derivative(&self, z: Complex64) -> (Complex64, Complex64) {
+        let mut value = Complex64::new(0.0, 0.0);
+        let mut derivative = Complex64::new(0.0, 0.0);
+        for c in &self.coefficients {
+            derivative = derivative * z + value;
+            value = value * z + c;
+        }
+        (value, derivative)
+    }
+
+    /// Approximates all of the roots (with multiplicity) using the Durand-Kerner method.
+    pub fn roots(&self) -> Vec<Complex64> {
+        let leading = self.coefficients[0];
+        let monic = Polynomial {
+            coefficients: self.coefficients.iter().map(|c| c / leading).collect(),
+        };
+        // The usual starting guesses: powers of a number that is neither real nor a root of unity
+        let seed = Complex64::new(0.4, 0.9);
+        let mut roots = (0..self.degree())
+            .map(|i| seed.powu(i as u32))
+            .collect::<Vec<Complex64>>();
+        for _ in 0..MAX_ROOT_ITERATIONS {
+            let mut largest_change: f64 = 0.0;
+            for i in 0..roots.len() {
+                let denominator = roots
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .fold(Complex64::new(1.0, 0.0), |accum, (_, other)| {
+                        accum * (roots[i] - other)
+                    });
+                if denominator.norm_sqr() == 0.0 {
+                    continue;
+                }
+                let change = monic.evaluate(roots[i]) / denominator;
+                roots[i] -= change;
+                largest_change = largest_change.max(change.norm());
+            }
+            if largest_change < 1e-15 {
+                break;
+            }
+        }
+        roots
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn c(re: f64, im: f64) -> Complex64 {
+        Complex64::new(re, im)
+    }
+
+    #[test]
+    fn test_from_coefficients() {
+        let p =
+            Polynomial::from_coefficients(&[c(0.0, 0.0), c(1.0, 0.0), c(0.0, 0.0), c(-1.0, 0.0)])
+                .unwrap();
+        assert_eq!(p.degree(), 2);
+        assert_eq!(p.coefficients(), &[c(1.0, 0.0), c(0.0, 0.0), c(-1.0, 0.0)]);
+        assert!(Polynomial::from_coefficients(&[c(0.0, 0.0), c(5.0, 0.0)]).is_err());
+        assert!(Polynomial::from_coefficients(&[]).is_err());
+    }
+
+    #[test]
+    fn test_from_roots() {
+        // (z - 1)(z + 1) = z^2 - 1
+        let p = Polynomial::from_roots(&[c(1.0, 0.0), c(-1.0, 0.0)]).unwrap();
+        assert_eq!(p.coefficients(), &[c(1.0, 0.0), c(0.0, 0.0), c(-1.0, 0.0)]);
+        // (z - i)(z + i) = z^2 + 1
+        let p = Polynomial::from_roots(&[c(0.0, 1.0), c(0.0, -1.0)]).unwrap();
+        assert_eq!(p.coefficients(), &[c(1.0, 0.0), c(0.0, 0.0), c(1.0, 0.0)]);
+        assert!(Polynomial::from_roots(&[]).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_with_derivative() {
+        // z^3 - 2z + 1, derivative 3z^2 - 2
+        let p =
+            Polynomial::from_coefficients(&[c(1.0, 0.0), c(0.0, 0.0), c(-2.0, 0.0), c(1.0, 0.0)])
+                .unwrap();
+        let z = c(1.5, -0.5);
+        let (value, derivative) = p.evaluate_with_derivative(z);
+        assert_eq!(value, p.evaluate(z));
+        assert_eq!(value, z * z * z - 2.0 * z + 1.0);
+        assert_eq!(derivative, 3.0 * z * z - 2.0);
+    }
+
+    #[test]
+    fn test_roots() {
+        let p =
+            Polynomial::from_coefficients(&[c(1.0, 0.0), c(0.0, 0.0), c(0.0, 0.0), c(-1.0, 0.0)])
+                .unwrap();
+        let roots = p.roots();
+        assert_eq!(roots.len(), 3);
+        for root in &roots {
+            assert!(p.evaluate(*root).norm() < 1e-12);
+        }
+        // the roots of unity are all found, not the same one three times
+        for k in 0..3 {
+            let expected =
+                Complex64::from_polar(&1.0, &(2.0 * std::f64::consts::PI * f64::from(k) / 3.0));
+            assert!(roots.iter().any(|root| (root - expected).norm() < 1e-12));
+        }
+    }
+}
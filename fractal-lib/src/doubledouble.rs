@@ -0,0 +1,256 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Double-double arithmetic, which represents a number as the unevaluated sum of two `f64`s.
+//!
+//! The high part holds the value rounded to an `f64`, and the low part holds the rounding error.
+//! This gives about 106 bits of precision (roughly 32 decimal digits) while staying much faster
+//! than a `BigFloat`, since every operation is just a handful of `f64` operations. The range is
+//! the same as an `f64`.
+//!
+//! The algorithms follow "Library for Double-Double and Quad-Double Arithmetic" by Hida, Li, and
+//! Bailey.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A number with roughly twice the precision of an `f64`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct DoubleDouble {
+    hi: f64,
+    lo: f64,
+}
+
+/// Computes `a + b` and the rounding error of that sum.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    let b_virtual = sum - a;
+    let error = (a - (sum - b_virtual)) + (b - b_virtual);
+    (sum, error)
+}
+
+/// Like `two_sum()`, but requires that `|a| >= |b|`.
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let sum = a + b;
+    (sum, b - (sum - a))
+}
+
+/// Computes `a * b` and the rounding error of that product.
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let product = a * b;
+    (product, a.mul_add(b, -product))
+}
+
+impl DoubleDouble {
+    pub fn new(hi: f64, lo: f64) -> DoubleDouble {
+        let (hi, lo) = two_sum(hi, lo);
+        DoubleDouble { hi, lo }
+    }
+
+    pub fn from_f64(value: f64) -> DoubleDouble {
+        DoubleDouble { hi: value, lo: 0.0 }
+    }
+
+    /// Rounds to the nearest `f64`.
+    pub fn to_f64(self) -> f64 {
+        self.hi + self.lo
+    }
+
+    /// The high (`f64` rounded) part.
+    pub fn hi(self) -> f64 {
+        self.hi
+    }
+
+    /// The low part, which is the rounding error of `hi()`.
+    pub fn lo(self) -> f64 {
+        self.lo
+    }
+
+    pub fn abs(self) -> DoubleDouble {
+        if self.hi < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    /// Computes `self^exponent` by repeated squaring.
+    pub fn powi(self, mut exponent: u32) -> DoubleDouble {
+        let mut base = self;
+        let mut result = DoubleDouble::from_f64(1.0);
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result * base;
+            }
+            base = base * base;
+            exponent >>= 1;
+        }
+        result
+    }
+
+    /// Parses a decimal number such as `-1.25`, `3`, or `6.02e23`.
+    pub fn parse(text: &str) -> Result<DoubleDouble, String> {
+        let trimmed = text.trim();
+        let (negative, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (number, exponent) = match unsigned.find(['e', 'E']) {
+            Some(index) => {
+                let exponent = unsigned[index + 1..]
+                    .parse::<i32>()
+                    .map_err(|_| format!("Invalid number: {}", text))?;
+                (&unsigned[..index], exponent)
+            }
+            None => (unsigned, 0),
+        };
+        let (whole, fraction) = match number.find('.') {
+            Some(index) => (&number[..index], &number[index + 1..]),
+            None => (number, ""),
+        };
+        if whole.is_empty() && fraction.is_empty()
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(format!("Invalid number: {}", text));
+        }
+
+        let ten = DoubleDouble::from_f64(10.0);
+        let mut mantissa = DoubleDouble::default();
+        for digit in whole.chars().chain(fraction.chars()) {
+            let digit = f64::from(digit.to_digit(10).unwrap());
+            mantissa = mantissa * ten + DoubleDouble::from_f64(digit);
+        }
+        let exponent = exponent - fraction.len() as i32;
+        let scale = ten.powi(exponent.unsigned_abs());
+        let value = if exponent < 0 {
+            mantissa / scale
+        } else {
+            mantissa * scale
+        };
+        Ok(if negative { -value } else { value })
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn add(self, other: DoubleDouble) -> DoubleDouble {
+        let (sum, error) = two_sum(self.hi, other.hi);
+        let (low_sum, low_error) = two_sum(self.lo, other.lo);
+        let (sum, error) = quick_two_sum(sum, error + low_sum);
+        let (hi, lo) = quick_two_sum(sum, error + low_error);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn sub(self, other: DoubleDouble) -> DoubleDouble {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn mul(self, other: DoubleDouble) -> DoubleDouble {
+        let (product, error) = two_prod(self.hi, other.hi);
+        let error = error + (self.hi * other.lo + self.lo * other.hi);
+        let (hi, lo) = quick_two_sum(product, error);
+        DoubleDouble { hi, lo }
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn div(self, other: DoubleDouble) -> DoubleDouble {
+        // Long division, one f64's worth of quotient at a time.
+        let q1 = self.hi / other.hi;
+        let remainder = self - other * DoubleDouble::from_f64(q1);
+        let q2 = remainder.hi / other.hi;
+        let remainder = remainder - other * DoubleDouble::from_f64(q2);
+        let q3 = remainder.hi / other.hi;
+        let (hi, lo) = quick_two_sum(q1, q2);
+        DoubleDouble { hi, lo } + DoubleDouble::from_f64(q3)
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = DoubleDouble;
+
+    fn neg(self) -> DoubleDouble {
+        DoubleDouble {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_add_keeps_small_parts() {
+        let sum = DoubleDouble::from_f64(1.0) + DoubleDouble::from_f64(1e-20);
+        assert_eq!(sum.hi(), 1.0);
+        assert_eq!(sum.lo(), 1e-20);
+        assert_eq!((sum - DoubleDouble::from_f64(1.0)).to_f64(), 1e-20);
+    }
+
+    #[test]
+    fn test_mul() {
+        // (1 + 2^-40)^2 = 1 + 2^-39 + 2^-80, which needs more than 53 bits
+        let x = DoubleDouble::from_f64(1.0) + DoubleDouble::from_f64(2f64.powi(-40));
+        let square = x * x;
+        assert_eq!(square.hi(), 1.0 + 2f64.powi(-39));
+        assert_eq!(square.lo(), 2f64.powi(-80));
+    }
+
+    #[test]
+    fn test_div() {
+        let third = DoubleDouble::from_f64(1.0) / DoubleDouble::from_f64(3.0);
+        let error = third * DoubleDouble::from_f64(3.0) - DoubleDouble::from_f64(1.0);
+        assert!(error.abs().to_f64() < 1e-30);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(DoubleDouble::parse("-2.5").unwrap().to_f64(), -2.5);
+        assert_eq!(DoubleDouble::parse("6.25e2").unwrap().to_f64(), 625.0);
+        let tenth = DoubleDouble::parse("0.1").unwrap();
+        assert_eq!(tenth.hi(), 0.1);
+        let error = tenth * DoubleDouble::from_f64(10.0) - DoubleDouble::from_f64(1.0);
+        assert!(error.abs().to_f64() < 1e-30);
+        // digits beyond the precision of an f64 are kept
+        let x = DoubleDouble::parse("1.00000000000000000001").unwrap();
+        assert_approx_eq!((x - DoubleDouble::from_f64(1.0)).to_f64(), 1e-20, 1e-34);
+        assert!(DoubleDouble::parse("1.2.3").is_err());
+        assert!(DoubleDouble::parse("").is_err());
+    }
+
+    #[test]
+    fn test_ordering() {
+        let one = DoubleDouble::from_f64(1.0);
+        let tiny = DoubleDouble::from_f64(1e-25);
+        assert!(one + tiny > one);
+        assert!(one - tiny < one);
+        assert!(-one < one);
+        assert_eq!((-one).abs(), one);
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Average colorings for the points outside of an escape time fractal's set.
+//!
+//! These colorings accumulate a statistic over each term of a point's orbit and color the point
+//! by its average, which brings out structure that the escape time alone does not show:
+//!
+//! * The stripe average averages `sin(density * arg(z))`, which draws stripes that follow the
+//!   fractal's filaments.
+//! * The triangle inequality average measures where `|z|` falls between the smallest and largest
+//!   values that the triangle inequality allows for `|z_prev^d + c|`.
+//! * Curvature estimation averages how sharply the orbit turns at each term.
+//!
+//! Since the number of terms changes by one between neighboring escape time bands, the averages
+//! with and without the last term are interpolated using the fractional part of the smooth
+//! iteration count (see `EscapeResult::smooth`), which hides the bands. The interpolation only
+//! works well with a large bailout radius (eg, 100 or more). The term for the value that escaped
+//! is left out, since it is huge enough that rounding errors dominate the triangle inequality
+//! average.
+
+use super::super::color::{self, ColorU8};
+use super::orbit::OrbitObserver;
+use super::{EscapeResult, EscapeTime};
+use num::complex::Complex64;
+use std::f64::consts::PI;
+
+/// The default number of stripes per turn around the origin for `Averaging::Stripe`.
+pub const DEFAULT_STRIPE_DENSITY: f64 = 5.0;
+
+/// The statistic that is averaged over each orbit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Averaging {
+    /// The stripe average, with the number of stripes per turn around the origin.
+    Stripe(f64),
+    /// The triangle inequality average.
+    TriangleInequality,
+    /// Curvature estimation.
+    Curvature,
+}
+
+impl Averaging {
+    /// The names of the averages that Averaging::from_name() accepts.
+    pub const NAMES: [&'static str; 3] = ["stripe", "triangle", "curvature"];
+
+    /// Constructs an average from its name. `stripe_density` is only used by the stripe average.
+    pub fn from_name(name: &str, stripe_density: f64) -> Result<Averaging, String> {
+        match name {
+            "stripe" => Ok(Averaging::Stripe(stripe_density)),
+            "triangle" => Ok(Averaging::TriangleInequality),
+            "curvature" => Ok(Averaging::Curvature),
+            _ => Err(format!("Unknown averaging coloring: {}", name)),
+        }
+    }
+
+    /// Follows the orbit of `point` (see EscapeTime::evaluate_observed()), averaging the
+    /// statistic over its terms.
+    pub fn evaluate<E>(&self, etsystem: &E, point: Complex64) -> AverageResult
+    where
+        E: EscapeTime + ?Sized,
+    {
+        let (c, z) = etsystem.initial_values(point);
+        let mut accumulator = AverageAccumulator {
+            averaging: *self,
+            c_norm: c.norm(),
+            degree: etsystem.degree(),
+            previous: [z, z],
+            values: 1,
+            sum: 0.0,
+            count: 0,
+            last: 0.0,
+            pending: None,
+        };
+        let result = etsystem.evaluate_observed(point, &mut accumulator);
+        let average = if result.escaped && accumulator.count > 0 {
+            let count = accumulator.count as f64;
+            let average = accumulator.sum / count;
+            let without_last = if accumulator.count > 1 {
+                (accumulator.sum - accumulator.last) / (count - 1.0)
+            } else {
+                average
+            };
+            let fraction = result.smooth - result.iterations as f64;
+            Some(without_last + (average - without_last) * fraction)
+        } else {
+            None
+        };
+        AverageResult { result, average }
+    }
+
+    /// Picks the color for a point from its average (see `color::average_color()`), or with
+    /// `color::interior_color()` if it did not escape.
+    pub fn color(&self, averaged: &AverageResult) -> ColorU8 {
+        match averaged.average {
+            Some(average) => color::average_color(average),
+            None if averaged.result.escaped => color::average_color(0.0),
+            None => color::interior_color(averaged.result.period),
+        }
+    }
+}
+
+/// What an `Averaging` found out about a point's orbit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AverageResult {
+    /// The result of iterating the point.
+    pub result: EscapeResult,
+    /// The interpolated average of the statistic, between 0.0 and 1.0, or None if the point did
+    /// not escape (or its orbit was too short to have any terms).
+    pub average: Option<f64>,
+}
+
+/// Accumulates the sum of an average's terms, as an orbit is iterated.
+struct AverageAccumulator {
+    averaging: Averaging,
+    /// `|c|`, for the triangle inequality average.
+    c_norm: f64,
+    degree: f64,
+    /// The two values before the one being observed, most recent first.
+    previous: [Complex64; 2],
+    /// How many values of the orbit have been seen, including the initial value.
+    values: usize,
+    sum: f64,
+    count: usize,
+    /// The most recent term in `sum`.
+    last: f64,
+    /// The term for the most recent value, which is only added to `sum` once the orbit continues
+    /// past it, so that the term for the value that escaped is left out.
+    pending: Option<f64>,
+}
+
+impl AverageAccumulator {
+    /// The term for `z`, if it has one.
+    fn term(&self, z: Complex64) -> Option<f64> {
+        match self.averaging {
+            Averaging::Stripe(density) => Some(0.5 * (density * z.arg()).sin() + 0.5),
+            Averaging::TriangleInequality => {
+                let previous = self.previous[0].norm().powf(self.degree);
+                let low = (previous - self.c_norm).abs();
+                let high = previous + self.c_norm;
+                if high - low > 0.0 {
+                    Some((z.norm() - low) / (high - low))
+                } else {
+                    None
+                }
+            }
+            Averaging::Curvature => {
+                let before = self.previous[0] - self.previous[1];
+                if self.values < 2 || before.norm_sqr() == 0.0 {
+                    None
+                } else {
+                    Some(((z - self.previous[0]) / before).arg().abs() / PI)
+                }
+            }
+        }
+    }
+}
+
+impl OrbitObserver for AverageAccumulator {
+    fn observe(&mut self, z: Complex64) {
+        if let Some(term) = self.pending.take() {
+            self.sum += term;
+            self.count += 1;
+            self.last = term;
+        }
+        self.pending = self.term(z).filter(|term| term.is_finite());
+        self.previous = [z, self.previous[0]];
+        self.values += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::mandelbrot::Mandelbrot;
+    use super::super::EscapeCriterion;
+    use super::*;
+
+    #[test]
+    fn test_averages_are_in_range() {
+        let mandelbrot = Mandelbrot::with_bailout(200, 2, EscapeCriterion::Norm(1000.0));
+        for &averaging in &[
+            Averaging::Stripe(DEFAULT_STRIPE_DENSITY),
+            Averaging::TriangleInequality,
+            Averaging::Curvature,
+        ] {
+            for &point in &[
+                Complex64::new(0.3, 0.6),
+                Complex64::new(-0.8, 0.35),
+                Complex64::new(-1.9, 0.01),
+            ] {
+                let averaged = averaging.evaluate(&mandelbrot, point);
+                assert!(averaged.result.escaped);
+                let average = averaged.average.unwrap();
+                assert!(
+                    (0.0..=1.0).contains(&average),
+                    "{:?}: {}",
+                    averaging,
+                    average
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_interior_has_no_average() {
+        let mandelbrot = Mandelbrot::new(100, 2);
+        let averaged = Averaging::Curvature.evaluate(&mandelbrot, Complex64::new(-1.0, 0.0));
+        assert!(!averaged.result.escaped);
+        assert_eq!(averaged.average, None);
+        assert_eq!(
+            Averaging::Curvature.color(&averaged),
+            color::interior_color(Some(2))
+        );
+    }
+
+    #[test]
+    fn test_stripe_average() {
+        // The orbit of 1 is 0, 1, 2, 5, which all have an argument of 0, so every term is 0.5
+        let mandelbrot = Mandelbrot::with_bailout(100, 2, EscapeCriterion::Norm(4.0));
+        let averaged = Averaging::Stripe(3.0).evaluate(&mandelbrot, Complex64::new(1.0, 0.0));
+        assert_approx_eq!(averaged.average.unwrap(), 0.5, 1e-12);
+    }
+
+    #[test]
+    fn test_average_is_continuous() {
+        // Walk across several escape time bands, and make sure the average never jumps by much
+        // between neighbors even though the number of terms does.
+        let mandelbrot = Mandelbrot::with_bailout(1000, 2, EscapeCriterion::Norm(1e10));
+        let averaging = Averaging::TriangleInequality;
+        let mut previous = averaging
+            .evaluate(&mandelbrot, Complex64::new(0.5, 0.2))
+            .average
+            .unwrap();
+        for i in 1..200 {
+            let point = Complex64::new(0.5 + 0.0025 * f64::from(i), 0.2);
+            let average = averaging.evaluate(&mandelbrot, point).average.unwrap();
+            assert!(
+                (average - previous).abs() < 0.01,
+                "{} vs {}",
+                average,
+                previous
+            );
+            previous = average;
+        }
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(
+            Averaging::from_name("stripe", 2.0),
+            Ok(Averaging::Stripe(2.0))
+        );
+        assert_eq!(
+            Averaging::from_name("curvature", 2.0),
+            Ok(Averaging::Curvature)
+        );
+        assert!(Averaging::from_name("bogus", 2.0).is_err());
+    }
+}
@@ -0,0 +1,410 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Evaluates escape time fractals for many points at once.
+//!
+//! `EscapeTime::evaluate()` follows one orbit at a time, so each step waits for the one before it
+//! to finish, and every step goes through a virtual call when the fractal is a trait object. The
+//! batch API instead iterates `LANES` orbits side by side in `ComplexLanes`, which store the real
+//! and imaginary parts in separate arrays. Fractals implement `EscapeTime::iterate_lanes()` with
+//! plain loops over those arrays, which the compiler can turn into SIMD instructions (and even
+//! without SIMD, the independent orbits keep the CPU's pipelines full).
+//!
+//! The lanes take `CHUNK_STEPS` steps at a time before checking which orbits have escaped or
+//! settled, and those lanes are refilled with the next points, so a few slow points do not hold
+//! up the rest of the batch.
+//!
+//! How much this helps depends on how many iterations the points take. On x86-64 without any
+//! target-specific features (so SIMD instructions only work on two lanes at a time),
+//! `benches/batch.rs` has measured `evaluate_batch()` at about 2.5 to 3.5 times the speed of
+//! `EscapeTime::evaluate()` in the seahorse valley of the Mandelbrot set and over the Burning
+//! Ship's default view, but only about 1 to 2 times its speed over the default views of the
+//! Mandelbrot set and a Julia set. Most of the points in those views escape within a few
+//! iterations (or are known to be in the interior), and each lane still takes at least
+//! `CHUNK_STEPS` steps for them, so there is little iteration left to speed up.
+
+use super::*;
+
+/// The number of orbits that are iterated side by side.
+pub const LANES: usize = 8;
+
+/// The number of steps that the lanes take between checks for the orbits that have finished.
+pub const CHUNK_STEPS: u64 = 16;
+
+/// `LANES` complex numbers, with the real and imaginary parts stored in separate arrays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComplexLanes {
+    pub re: [f64; LANES],
+    pub im: [f64; LANES],
+}
+
+impl ComplexLanes {
+    /// Every lane set to `z`.
+    pub fn splat(z: Complex64) -> ComplexLanes {
+        ComplexLanes {
+            re: [z.re; LANES],
+            im: [z.im; LANES],
+        }
+    }
+
+    pub fn get(&self, lane: usize) -> Complex64 {
+        Complex64::new(self.re[lane], self.im[lane])
+    }
+
+    pub fn set(&mut self, lane: usize, z: Complex64) {
+        self.re[lane] = z.re;
+        self.im[lane] = z.im;
+    }
+
+    /// Replaces each lane's real and imaginary parts with the result of `f`.
+    #[inline(always)]
+    pub fn map<F>(&mut self, f: F)
+    where
+        F: Fn(f64, f64) -> (f64, f64),
+    {
+        for lane in 0..LANES {
+            let (re, im) = f(self.re[lane], self.im[lane]);
+            self.re[lane] = re;
+            self.im[lane] = im;
+        }
+    }
+
+    /// Adds `other` to each lane.
+    #[inline(always)]
+    pub fn add(&mut self, other: &ComplexLanes) {
+        for lane in 0..LANES {
+            self.re[lane] += other.re[lane];
+            self.im[lane] += other.im[lane];
+        }
+    }
+
+    /// Raises each lane to the power of `exponent`, giving exactly the same result as
+    /// `geometry::cpow()`.
+    #[inline(always)]
+    pub fn powu(&mut self, exponent: u64) {
+        match exponent {
+            0 => *self = ComplexLanes::splat(Complex64::new(1.0, 0.0)),
+            1 => {}
+            2 => self.map(|re, im| (re * re - im * im, re * im + im * re)),
+            _ => {
+                let base = *self;
+                for _ in 1..exponent {
+                    for lane in 0..LANES {
+                        let (re, im) = (self.re[lane], self.im[lane]);
+                        self.re[lane] = re * base.re[lane] - im * base.im[lane];
+                        self.im[lane] = re * base.im[lane] + im * base.re[lane];
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Like `EscapeTime::iterate_with_previous()`, one lane at a time. This is the default
+/// implementation of `EscapeTime::iterate_lanes()`, and fractals can fall back to it for the
+/// cases that they do not specialize.
+pub fn iterate_each_lane<E>(
+    etsystem: &E,
+    c: &ComplexLanes,
+    z: &mut ComplexLanes,
+    previous: &ComplexLanes,
+) where
+    E: EscapeTime + ?Sized,
+{
+    for lane in 0..LANES {
+        let next = etsystem.iterate_with_previous(c.get(lane), z.get(lane), previous.get(lane));
+        z.set(lane, next);
+    }
+}
+
+/// Evaluates each of `points` into the same position in `results`, using
+/// `EscapeTime::iterate_lanes()` to iterate `LANES` orbits at a time. This is the default
+/// implementation of `EscapeTime::evaluate_batch()`.
+///
+/// The lanes take `CHUNK_STEPS` steps at a time without stopping, remembering every value along
+/// the way, and only keeping track of how far each lane strayed from 0 and how close it came to
+/// the value that periodicity checking compares against. Afterwards, the lanes that might have
+/// escaped or settled into a cycle (or that reach the maximum number of iterations) go back over
+/// the values of the chunk to find out exactly where they finished: the same way that
+/// `EscapeTime::evaluate()` does if they came close enough to the compared value, and otherwise
+/// by just looking for the first value that escaped. This keeps the loop over the chunk free of
+/// branches, so that it can be vectorized.
+///
+/// The results are exactly the same as the ones `EscapeTime::evaluate()` computes (including
+/// known interior points and periodicity checking), as long as `iterate_lanes()` exactly matches
+/// `iterate_with_previous()`.
+///
+/// Panics if `points` and `results` have different lengths.
+pub fn evaluate_batch<E>(etsystem: &E, points: &[Complex64], results: &mut [EscapeResult])
+where
+    E: EscapeTime + ?Sized,
+{
+    assert_eq!(points.len(), results.len());
+    // Each criterion gets its own copy of the loop, so that measuring how far the lanes have
+    // strayed does not branch on the criterion at every step. Values that escaped measure at
+    // least the bound, although some that did not escape might too.
+    match etsystem.bailout() {
+        EscapeCriterion::Norm(r) => {
+            evaluate_chunks(etsystem, points, results, r * r, |re, im| re * re + im * im)
+        }
+        EscapeCriterion::Real(r) => evaluate_chunks(etsystem, points, results, r, |re, _| re.abs()),
+        EscapeCriterion::Imaginary(r) => {
+            evaluate_chunks(etsystem, points, results, r, |_, im| im.abs())
+        }
+        EscapeCriterion::Manhattan(r) => {
+            evaluate_chunks(etsystem, points, results, r, |re, im| re.abs() + im.abs())
+        }
+    }
+}
+
+/// Implements `evaluate_batch()`, where `measure` is at least `escape_bound` for every value that
+/// has escaped.
+#[inline(always)]
+fn evaluate_chunks<E, M>(
+    etsystem: &E,
+    points: &[Complex64],
+    results: &mut [EscapeResult],
+    escape_bound: f64,
+    measure: M,
+) where
+    E: EscapeTime + ?Sized,
+    M: Fn(f64, f64) -> f64,
+{
+    let settings = OrbitSettings::new(etsystem);
+    // Nothing is closer than -∞, so this disables the periodicity test without a branch
+    let cycle_tolerance_sqr = settings.tolerance_sqr.unwrap_or(f64::NEG_INFINITY);
+
+    // Idle lanes follow the orbit of 0, which stays at 0 instead of overflowing
+    let zero = Complex64::new(0.0, 0.0);
+    let idle = OrbitState::new(zero, zero);
+    // The orbit each lane is following as of the start of the chunk, and the point (and result)
+    // that it belongs to
+    let mut orbits = [idle; LANES];
+    let mut active = [false; LANES];
+    let mut index = [0; LANES];
+    let mut next_point = 0;
+    // Every value of the current chunk
+    let mut values = [ComplexLanes::splat(zero); CHUNK_STEPS as usize];
+    loop {
+        // Give every idle lane a new point, skipping the points that need no iteration
+        for lane in 0..LANES {
+            while !active[lane] && next_point < points.len() {
+                let point = next_point;
+                next_point += 1;
+                let (c, z) = etsystem.initial_values(points[point]);
+                if let Some(result) = etsystem.known_interior(c, z) {
+                    results[point] = result;
+                } else if settings.max_iterations == 0 {
+                    results[point] = EscapeResult::attracted(z);
+                } else {
+                    orbits[lane] = OrbitState::new(c, z);
+                    active[lane] = true;
+                    index[lane] = point;
+                }
+            }
+        }
+        if !active.iter().any(|&active| active) {
+            return;
+        }
+
+        let mut c = ComplexLanes::splat(zero);
+        let mut z = ComplexLanes::splat(zero);
+        let mut previous = ComplexLanes::splat(zero);
+        let mut saved = ComplexLanes::splat(zero);
+        for (lane, orbit) in orbits.iter().enumerate() {
+            c.set(lane, orbit.c);
+            z.set(lane, orbit.z);
+            previous.set(lane, orbit.previous);
+            saved.set(lane, orbit.saved.0);
+        }
+        // The saved values only change at the end of a window, and every window ends at the end
+        // of a chunk, so they stay the same throughout the chunk
+        let mut farthest = [f64::NEG_INFINITY; LANES];
+        let mut closest = [f64::INFINITY; LANES];
+        for value in values.iter_mut() {
+            let before = z;
+            etsystem.iterate_lanes(&c, &mut z, &previous);
+            previous = before;
+            *value = z;
+            for lane in 0..LANES {
+                let (re, im) = (z.re[lane], z.im[lane]);
+                let strayed = measure(re, im);
+                farthest[lane] = if strayed > farthest[lane] {
+                    strayed
+                } else {
+                    farthest[lane]
+                };
+                let (dre, dim) = (re - saved.re[lane], im - saved.im[lane]);
+                let distance_sqr = dre * dre + dim * dim;
+                closest[lane] = if distance_sqr < closest[lane] {
+                    distance_sqr
+                } else {
+                    closest[lane]
+                };
+            }
+        }
+
+        for lane in 0..LANES {
+            if !active[lane] {
+                continue;
+            }
+            let orbit = &mut orbits[lane];
+            let exhausted = orbit.iteration + CHUNK_STEPS >= settings.max_iterations;
+            if closest[lane] < cycle_tolerance_sqr {
+                // Go over the values of the chunk one at a time, to find out exactly how the
+                // orbit finished (if it did)
+                for value in values.iter() {
+                    if let Some(result) = orbit.advance(value.get(lane), &settings) {
+                        results[index[lane]] = result;
+                        *orbit = idle;
+                        active[lane] = false;
+                        break;
+                    }
+                }
+            } else if farthest[lane] >= escape_bound || exhausted {
+                // Periodicity checking can not have stopped the orbit during this chunk, so it
+                // finished with the first value that escaped, or when it ran out of iterations
+                let remaining = settings.max_iterations - orbit.iteration;
+                let steps = (CHUNK_STEPS.min(remaining)) as usize;
+                let finished = values[..steps]
+                    .iter()
+                    .map(|value| value.get(lane))
+                    .enumerate()
+                    .find(|&(_, value)| settings.bailout.has_escaped(value));
+                let result = match finished {
+                    Some((step, value)) => Some(EscapeResult::escaped(
+                        orbit.iteration + step as u64,
+                        value,
+                        settings.degree,
+                        settings.bailout.radius(),
+                    )),
+                    None if exhausted => Some(EscapeResult::attracted(values[steps - 1].get(lane))),
+                    None => None,
+                };
+                if let Some(result) = result {
+                    results[index[lane]] = result;
+                    *orbit = idle;
+                    active[lane] = false;
+                } else {
+                    orbit.skip_chunk(z.get(lane), previous.get(lane), &settings);
+                }
+            } else {
+                orbit.skip_chunk(z.get(lane), previous.get(lane), &settings);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::geometry::{self, Exponent};
+    use super::super::burningship::{
+        Buffalo, BurningMandel, BurningShip, Celtic, RoadRunner, Tricorn,
+    };
+    use super::super::formula::FormulaFractal;
+    use super::super::julia::JuliaSet;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::super::phoenix::Phoenix;
+    use super::*;
+
+    /// Checks that `evaluate_batch()` gives exactly the same results as `evaluate()` over a grid
+    /// that covers the default view area.
+    fn check_matches_evaluate<E: EscapeTime>(etsystem: &E) {
+        let [a, b] = etsystem.default_view_area();
+        // An odd number of points, so that the last batch does not fill every lane
+        let (width, height) = (37, 23);
+        let points = (0..width * height)
+            .map(|i| {
+                let (x, y) = (f64::from(i % width), f64::from(i / width));
+                Complex64::new(
+                    a.re + (b.re - a.re) * x / f64::from(width - 1),
+                    a.im + (b.im - a.im) * y / f64::from(height - 1),
+                )
+            })
+            .collect::<Vec<Complex64>>();
+        let mut results = vec![EscapeResult::attracted(Complex64::new(0.0, 0.0)); points.len()];
+        etsystem.evaluate_batch(&points, &mut results);
+        for (point, result) in points.iter().zip(results.iter()) {
+            // Compared as text, since NaN != NaN
+            assert_eq!(
+                format!("{:?}", result),
+                format!("{:?}", etsystem.evaluate(*point)),
+                "at {}",
+                point
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_evaluate() {
+        check_matches_evaluate(&Mandelbrot::new(200, 2));
+        check_matches_evaluate(&Mandelbrot::new(200, 3));
+        check_matches_evaluate(&Mandelbrot::new(200, 0));
+        check_matches_evaluate(&Mandelbrot::with_exponent(
+            200,
+            Exponent::Real(2.5),
+            EscapeCriterion::default(),
+        ));
+        check_matches_evaluate(&Mandelbrot::with_bailout(
+            200,
+            2,
+            EscapeCriterion::Manhattan(4.0),
+        ));
+        check_matches_evaluate(&BurningShip::new(200, 2));
+        check_matches_evaluate(&BurningMandel::new(200, 2));
+        check_matches_evaluate(&RoadRunner::new(200, 3));
+        check_matches_evaluate(&Tricorn::new(200, 2));
+        check_matches_evaluate(&Celtic::new(200, 2));
+        check_matches_evaluate(&Buffalo::with_bailout(
+            200,
+            2,
+            EscapeCriterion::Imaginary(2.0),
+        ));
+        check_matches_evaluate(&JuliaSet::new(
+            Mandelbrot::new(200, 2),
+            Complex64::new(-0.8, 0.156),
+        ));
+        check_matches_evaluate(&Phoenix::new(200, 2));
+        check_matches_evaluate(&FormulaFractal::new(200, "z^2 + c").unwrap());
+    }
+
+    #[test]
+    fn test_empty_and_zero_iterations() {
+        let mb = Mandelbrot::new(0, 2);
+        mb.evaluate_batch(&[], &mut []);
+        let point = Complex64::new(1.0, 0.0);
+        let mut results = [EscapeResult::attracted(point); 1];
+        mb.evaluate_batch(&[point], &mut results);
+        assert_eq!(results[0], mb.evaluate(point));
+    }
+
+    #[test]
+    fn test_powu_matches_cpow() {
+        let values = [
+            Complex64::new(0.3, -1.7),
+            Complex64::new(-2.5, 0.25),
+            Complex64::new(1e-3, 4.0),
+        ];
+        for exponent in 0..6 {
+            let mut lanes = ComplexLanes::splat(Complex64::new(0.0, 0.0));
+            for (lane, value) in values.iter().enumerate() {
+                lanes.set(lane, *value);
+            }
+            lanes.powu(exponent);
+            for (lane, value) in values.iter().enumerate() {
+                assert_eq!(lanes.get(lane), geometry::cpow(*value, exponent));
+            }
+        }
+    }
+}
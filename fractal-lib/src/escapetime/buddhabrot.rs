@@ -0,0 +1,488 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Renders escape time fractals by how often the orbits of points pass through each pixel, rather
+//! than by how quickly each pixel's point escapes.
+//!
+//! The Buddhabrot samples many random points, and for every point whose orbit escapes, it records
+//! each value of the orbit in a `DensityMap`. The anti-Buddhabrot records the orbits that never
+//! escape instead. The Nebulabrot renders the Buddhabrot three times with different iteration
+//! limits, and uses each one as the red, green, or blue channel of the image.
+//!
+//! Sampling is embarrassingly parallel: each thread can fill in its own `DensityMap` and then
+//! `DensityMap::merge()` it into a shared one.
+
+use super::{Complex64, EscapeTime};
+use crate::color::ColorU8;
+use crate::geometry::{Point, ViewAreaTransformer};
+use rand::distributions::{Distribution, Uniform};
+use rand::Rng;
+
+/// Which orbits contribute to a `DensityMap`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrbitSelection {
+    /// Orbits that escape within the iteration limit (the Buddhabrot).
+    Escaping,
+    /// Orbits that do not escape within the iteration limit (the anti-Buddhabrot).
+    Bounded,
+}
+
+impl OrbitSelection {
+    /// The names accepted by `OrbitSelection::from_name()`.
+    pub const NAMES: [&'static str; 2] = ["buddhabrot", "anti-buddhabrot"];
+
+    /// Looks up a selection by its name (one of `OrbitSelection::NAMES`).
+    pub fn from_name(name: &str) -> Result<OrbitSelection, String> {
+        match name {
+            "buddhabrot" => Ok(OrbitSelection::Escaping),
+            "anti-buddhabrot" => Ok(OrbitSelection::Bounded),
+            _ => Err(format!("Unknown orbit selection: {}", name)),
+        }
+    }
+}
+
+/// Counts how many times orbits have passed through each pixel of an image, with a separate count
+/// for each channel.
+pub struct DensityMap {
+    width: u32,
+    height: u32,
+    vat: ViewAreaTransformer,
+    channels: Vec<Vec<u64>>,
+}
+
+impl DensityMap {
+    /// Creates an empty map that is `width` by `height` pixels, showing `view_area` of the complex
+    /// plane (the same way `ViewAreaTransformer` would), with `channel_count` channels.
+    pub fn new(
+        width: u32,
+        height: u32,
+        view_area: [Complex64; 2],
+        channel_count: usize,
+    ) -> DensityMap {
+        DensityMap {
+            width,
+            height,
+            vat: ViewAreaTransformer::new(
+                [f64::from(width), f64::from(height)],
+                Point::from(view_area[0]),
+                Point::from(view_area[1]),
+            ),
+            channels: vec![vec![0; (width * height) as usize]; channel_count],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn channel_count(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// The index into a channel for the pixel that `z` falls within, if it is within the map.
+    fn index_of(&self, z: Complex64) -> Option<usize> {
+        let pixel = self.vat.map_point_to_pixel(Point::from(z));
+        let (x, y) = (pixel[0].floor(), pixel[1].floor());
+        if x >= 0.0 && y >= 0.0 && x < f64::from(self.width) && y < f64::from(self.height) {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Records that an orbit passed through `z` in `channel`. Values outside of the map are
+    /// ignored.
+    pub fn record(&mut self, channel: usize, z: Complex64) {
+        if let Some(index) = self.index_of(z) {
+            self.channels[channel][index] += 1;
+        }
+    }
+
+    /// The number of times orbits passed through pixel `(x, y)` in `channel`.
+    pub fn get(&self, channel: usize, x: u32, y: u32) -> u64 {
+        self.channels[channel][(y * self.width + x) as usize]
+    }
+
+    /// The largest count in each channel.
+    pub fn maxima(&self) -> Vec<u64> {
+        self.channels
+            .iter()
+            .map(|counts| counts.iter().cloned().max().unwrap_or(0))
+            .collect()
+    }
+
+    /// Resets every count to zero.
+    pub fn clear(&mut self) {
+        for counts in &mut self.channels {
+            for count in counts.iter_mut() {
+                *count = 0;
+            }
+        }
+    }
+
+    /// Adds the counts from `other` into this map, so that threads can sample separately and
+    /// combine their results. The maps must have the same dimensions and channels.
+    pub fn merge(&mut self, other: &DensityMap) -> Result<(), String> {
+        if self.width != other.width
+            || self.height != other.height
+            || self.channels.len() != other.channels.len()
+        {
+            return Err(format!(
+                "Cannot merge a {}x{}x{} density map into a {}x{}x{} density map",
+                other.width,
+                other.height,
+                other.channels.len(),
+                self.width,
+                self.height,
+                self.channels.len()
+            ));
+        }
+        for (counts, other_counts) in self.channels.iter_mut().zip(other.channels.iter()) {
+            for (count, other_count) in counts.iter_mut().zip(other_counts.iter()) {
+                *count += other_count;
+            }
+        }
+        Ok(())
+    }
+
+    /// Picks the color of pixel `(x, y)`, where `maxima` is the result of `DensityMap::maxima()`.
+    ///
+    /// Each count is scaled by the square root of its fraction of the channel's maximum, which
+    /// brings out the faint orbits without washing out the dense ones. A single channel is drawn
+    /// in grayscale, and otherwise the first three channels are used as red, green, and blue.
+    pub fn color(&self, x: u32, y: u32, maxima: &[u64]) -> ColorU8 {
+        let intensity = |channel: usize| {
+            let max = maxima[channel];
+            if max == 0 {
+                0
+            } else {
+                let fraction = self.get(channel, x, y) as f64 / max as f64;
+                (255.0 * fraction.sqrt()).round().clamp(0.0, 255.0) as u8
+            }
+        };
+        match self.channels.len() {
+            0 => ColorU8([0, 0, 0, 255]),
+            1 => {
+                let value = intensity(0);
+                ColorU8([value, value, value, 255])
+            }
+            2 => ColorU8([intensity(0), intensity(1), 0, 255]),
+            _ => ColorU8([intensity(0), intensity(1), intensity(2), 255]),
+        }
+    }
+}
+
+/// Accumulates the orbits of an escape time fractal into a `DensityMap`.
+///
+/// Each channel of the density map has its own iteration limit: a point that escapes within the
+/// limit counts as an escaping orbit for that channel, and any other point counts as a bounded
+/// orbit. Every orbit is only iterated once, up to the largest limit.
+pub struct Buddhabrot<E>
+where
+    E: EscapeTime,
+{
+    etsystem: E,
+    selection: OrbitSelection,
+    iteration_limits: Vec<u64>,
+}
+
+impl<E> Buddhabrot<E>
+where
+    E: EscapeTime,
+{
+    /// Renders a single channel of `selection` orbits, using the fractal's maximum iterations as
+    /// the iteration limit.
+    pub fn new(etsystem: E, selection: OrbitSelection) -> Buddhabrot<E> {
+        let iteration_limits = vec![etsystem.max_iterations()];
+        Buddhabrot {
+            etsystem,
+            selection,
+            iteration_limits,
+        }
+    }
+
+    /// Renders the Nebulabrot, where the red, green, and blue channels each have their own
+    /// iteration limit. The fractal's own maximum iterations are ignored.
+    pub fn nebulabrot(
+        etsystem: E,
+        selection: OrbitSelection,
+        iteration_limits: [u64; 3],
+    ) -> Buddhabrot<E> {
+        Buddhabrot {
+            etsystem,
+            selection,
+            iteration_limits: iteration_limits.to_vec(),
+        }
+    }
+
+    pub fn etsystem(&self) -> &E {
+        &self.etsystem
+    }
+
+    pub fn selection(&self) -> OrbitSelection {
+        self.selection
+    }
+
+    pub fn iteration_limits(&self) -> &[u64] {
+        &self.iteration_limits
+    }
+
+    /// The number of channels that a `DensityMap` needs for this renderer.
+    pub fn channel_count(&self) -> usize {
+        self.iteration_limits.len()
+    }
+
+    /// Creates an empty `DensityMap` for this renderer.
+    pub fn density_map(&self, width: u32, height: u32, view_area: [Complex64; 2]) -> DensityMap {
+        DensityMap::new(width, height, view_area, self.channel_count())
+    }
+
+    /// Iterates `point` up to the largest iteration limit, replacing the contents of `orbit` with
+    /// every value of `z` before it escaped.
+    ///
+    /// Returns the iteration during which the orbit escaped (counted the same way as
+    /// `EscapeResult::iterations`), or None if it did not escape.
+    pub fn orbit(&self, point: Complex64, orbit: &mut Vec<Complex64>) -> Option<u64> {
+        orbit.clear();
+        let bailout = self.etsystem.bailout();
+        let limit = self.iteration_limits.iter().cloned().max().unwrap_or(0);
+        let (c, mut z) = self.etsystem.initial_values(point);
+        let mut previous = Complex64::new(0.0, 0.0);
+        for i in 0..limit {
+            let next = self.etsystem.iterate_with_previous(c, z, previous);
+            previous = z;
+            z = next;
+            if bailout.has_escaped(z) {
+                return Some(i);
+            }
+            orbit.push(z);
+        }
+        None
+    }
+
+    /// Adds the orbit of `point` to each channel of `density` whose iteration limit selects it.
+    ///
+    /// `orbit` is scratch space, so that it can be reused between calls.
+    pub fn accumulate(
+        &self,
+        point: Complex64,
+        density: &mut DensityMap,
+        orbit: &mut Vec<Complex64>,
+    ) {
+        // Escaping orbits never start in the fractal's interior, so there is no need to iterate
+        // points that are known to be there.
+        if self.selection == OrbitSelection::Escaping {
+            let (c, z) = self.etsystem.initial_values(point);
+            if self.etsystem.known_interior(c, z).is_some() {
+                return;
+            }
+        }
+
+        let escaped_at = self.orbit(point, orbit);
+        for (channel, &limit) in self.iteration_limits.iter().enumerate() {
+            let escaped = matches!(escaped_at, Some(i) if i < limit);
+            let recorded = match self.selection {
+                OrbitSelection::Escaping if escaped => orbit.len(),
+                OrbitSelection::Bounded if !escaped => limit as usize,
+                _ => 0,
+            };
+            for &z in &orbit[..recorded] {
+                density.record(channel, z);
+            }
+        }
+    }
+
+    /// Accumulates the orbits of `samples` points picked uniformly at random from `sample_area`
+    /// into `density`.
+    ///
+    /// `sample_area` usually needs to cover the whole fractal (eg, its default view area) even
+    /// when `density` only shows part of it, because orbits that start outside of the visible
+    /// area can still pass through it.
+    pub fn sample<R: Rng>(
+        &self,
+        rng: &mut R,
+        samples: u64,
+        sample_area: [Complex64; 2],
+        density: &mut DensityMap,
+    ) {
+        let re_range = Uniform::new_inclusive(
+            sample_area[0].re.min(sample_area[1].re),
+            sample_area[0].re.max(sample_area[1].re),
+        );
+        let im_range = Uniform::new_inclusive(
+            sample_area[0].im.min(sample_area[1].im),
+            sample_area[0].im.max(sample_area[1].im),
+        );
+        let mut orbit = Vec::new();
+        for _ in 0..samples {
+            let point = Complex64::new(re_range.sample(rng), im_range.sample(rng));
+            self.accumulate(point, density, &mut orbit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn view_area() -> [Complex64; 2] {
+        [Complex64::new(-2.0, 2.0), Complex64::new(2.0, -2.0)]
+    }
+
+    fn total(density: &DensityMap, channel: usize) -> u64 {
+        let mut total = 0;
+        for y in 0..density.height() {
+            for x in 0..density.width() {
+                total += density.get(channel, x, y);
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn test_density_map_record() {
+        let mut density = DensityMap::new(4, 4, view_area(), 1);
+        density.record(0, Complex64::new(-1.5, 1.5));
+        density.record(0, Complex64::new(-1.9, 1.1));
+        density.record(0, Complex64::new(0.5, -0.5));
+        density.record(0, Complex64::new(2.5, 0.0));
+        assert_eq!(density.get(0, 0, 0), 2);
+        assert_eq!(density.get(0, 2, 2), 1);
+        assert_eq!(total(&density, 0), 3);
+        assert_eq!(density.maxima(), vec![2]);
+    }
+
+    #[test]
+    fn test_density_map_merge() {
+        let mut first = DensityMap::new(4, 4, view_area(), 2);
+        let mut second = DensityMap::new(4, 4, view_area(), 2);
+        first.record(0, Complex64::new(0.5, 0.5));
+        second.record(0, Complex64::new(0.5, 0.5));
+        second.record(1, Complex64::new(-0.5, -0.5));
+        first.merge(&second).unwrap();
+        assert_eq!(first.get(0, 2, 1), 2);
+        assert_eq!(first.get(1, 1, 2), 1);
+        assert!(first.merge(&DensityMap::new(4, 4, view_area(), 3)).is_err());
+        assert!(first.merge(&DensityMap::new(8, 4, view_area(), 2)).is_err());
+        first.clear();
+        assert_eq!(first.maxima(), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_density_map_color() {
+        let mut density = DensityMap::new(
+            2,
+            1,
+            [Complex64::new(0.0, 1.0), Complex64::new(2.0, 0.0)],
+            1,
+        );
+        for _ in 0..4 {
+            density.record(0, Complex64::new(0.5, 0.5));
+        }
+        density.record(0, Complex64::new(1.5, 0.5));
+        let maxima = density.maxima();
+        assert_eq!(density.color(0, 0, &maxima), ColorU8([255, 255, 255, 255]));
+        assert_eq!(density.color(1, 0, &maxima), ColorU8([128, 128, 128, 255]));
+    }
+
+    #[test]
+    fn test_orbit() {
+        let bb = Buddhabrot::new(Mandelbrot::new(100, 2), OrbitSelection::Escaping);
+        let mut orbit = Vec::new();
+        // 1 -> 2 -> 5, which escapes
+        assert_eq!(bb.orbit(Complex64::new(1.0, 0.0), &mut orbit), Some(2));
+        assert_eq!(
+            orbit,
+            vec![Complex64::new(1.0, 0.0), Complex64::new(2.0, 0.0)]
+        );
+        // -1 -> 0 -> -1 -> ... never escapes
+        assert_eq!(bb.orbit(Complex64::new(-1.0, 0.0), &mut orbit), None);
+        assert_eq!(orbit.len(), 100);
+        assert_eq!(orbit[1], Complex64::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn test_accumulate_selection() {
+        let escaping = Buddhabrot::new(Mandelbrot::new(100, 2), OrbitSelection::Escaping);
+        let bounded = Buddhabrot::new(Mandelbrot::new(100, 2), OrbitSelection::Bounded);
+        let mut orbit = Vec::new();
+
+        let mut density = escaping.density_map(40, 40, view_area());
+        escaping.accumulate(Complex64::new(-1.0, 0.0), &mut density, &mut orbit);
+        assert_eq!(total(&density, 0), 0);
+        escaping.accumulate(Complex64::new(0.5, 0.5), &mut density, &mut orbit);
+        assert!(total(&density, 0) > 0);
+
+        let mut density = bounded.density_map(40, 40, view_area());
+        bounded.accumulate(Complex64::new(0.5, 0.5), &mut density, &mut orbit);
+        assert_eq!(total(&density, 0), 0);
+        bounded.accumulate(Complex64::new(-1.0, 0.0), &mut density, &mut orbit);
+        assert_eq!(total(&density, 0), 100);
+    }
+
+    #[test]
+    fn test_nebulabrot_channels() {
+        let nb = Buddhabrot::nebulabrot(
+            Mandelbrot::new(1, 2),
+            OrbitSelection::Escaping,
+            [5, 50, 500],
+        );
+        // Large enough to contain every value before the orbit escapes
+        let everything = [Complex64::new(-3.0, 3.0), Complex64::new(3.0, -3.0)];
+        let mut density = nb.density_map(200, 200, everything);
+        let mut orbit = Vec::new();
+        // Escapes during iteration 12, so only the channels with larger limits record it
+        let point = Complex64::new(0.3, 0.0);
+        assert_eq!(nb.orbit(point, &mut orbit), Some(12));
+        nb.accumulate(point, &mut density, &mut orbit);
+        assert_eq!(total(&density, 0), 0);
+        assert_eq!(total(&density, 1), 12);
+        assert_eq!(total(&density, 2), 12);
+
+        let bounded =
+            Buddhabrot::nebulabrot(Mandelbrot::new(1, 2), OrbitSelection::Bounded, [5, 50, 500]);
+        let mut density = bounded.density_map(200, 200, everything);
+        bounded.accumulate(point, &mut density, &mut orbit);
+        assert_eq!(total(&density, 0), 5);
+        assert_eq!(total(&density, 1), 0);
+        assert_eq!(total(&density, 2), 0);
+    }
+
+    #[test]
+    fn test_sample_is_symmetric() {
+        // The Mandelbrot set is symmetric across the real axis, and so is its Buddhabrot
+        let bb = Buddhabrot::new(Mandelbrot::new(50, 2), OrbitSelection::Escaping);
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut density = bb.density_map(16, 16, view_area());
+        bb.sample(&mut rng, 20_000, view_area(), &mut density);
+        let top: u64 = (0..8)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .map(|(x, y)| density.get(0, x, y))
+            .sum();
+        let bottom: u64 = (8..16)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .map(|(x, y)| density.get(0, x, y))
+            .sum();
+        assert!(top > 0);
+        let ratio = top as f64 / bottom as f64;
+        assert!(ratio > 0.9 && ratio < 1.1, "ratio {}", ratio);
+    }
+}
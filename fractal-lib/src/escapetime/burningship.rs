@@ -26,14 +26,20 @@
 //!
 //! This module also contains a few other variations of the burning ship fractal that differ by
 //! only taking the absolute value of one of the components of z (taking no absolute value would be
-//! the mandelbrot set)
+//! the mandelbrot set), by taking the absolute value of the components of z^2 instead of z (the
+//! Celtic and Buffalo fractals), or by conjugating z (the Tricorn).
 
-use super::super::geometry;
+use super::super::geometry::{self, Exponent};
+use super::batch::{self, ComplexLanes};
+use super::generic::{
+    generic_power, multibrot_initial_z_generic, GenericEscapeTime, Real, RealComplex,
+};
 use super::*;
 
 pub struct BurningShip {
     max_iters: u64,
-    power: u64,
+    power: Exponent,
+    bailout: EscapeCriterion,
 }
 
 impl BurningShip {
@@ -47,9 +53,26 @@ impl BurningShip {
     /// these related fractals. See <https://theory.org/fracdyn/burningship/symmetry.html> for
     /// examples of what these may look like.
     pub fn new(max_iterations: u64, power: u64) -> BurningShip {
+        BurningShip::with_bailout(max_iterations, power, EscapeCriterion::default())
+    }
+
+    /// Like `BurningShip::new()`, but `bailout` specifies the test (and radius) that decides when a
+    /// value has escaped.
+    pub fn with_bailout(max_iterations: u64, power: u64, bailout: EscapeCriterion) -> BurningShip {
+        BurningShip::with_exponent(max_iterations, Exponent::Natural(power), bailout)
+    }
+
+    /// Like `BurningShip::with_bailout()`, but `exponent` may also be negative, fractional, or
+    /// complex (see `escapetime::multibrot_initial_values()`).
+    pub fn with_exponent(
+        max_iterations: u64,
+        exponent: Exponent,
+        bailout: EscapeCriterion,
+    ) -> BurningShip {
         BurningShip {
             max_iters: max_iterations,
-            power,
+            power: exponent,
+            bailout,
         }
     }
 }
@@ -65,7 +88,45 @@ impl EscapeTime for BurningShip {
 
     fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
         let absz = Complex64::new(z.re.abs(), -z.im.abs());
-        geometry::cpow(absz, self.power) + c
+        geometry::cpow_general(absz, self.power) + c
+    }
+
+    #[inline(always)]
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        match self.power.as_natural() {
+            Some(n) => {
+                z.map(|re, im| (re.abs(), -im.abs()));
+                z.powu(n);
+                z.add(c);
+            }
+            None => batch::iterate_each_lane(self, c, z, previous),
+        }
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn degree(&self) -> f64 {
+        self.power.real_part()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_values(self.power, point)
+    }
+}
+
+impl<T> GenericEscapeTime<T> for BurningShip
+where
+    T: Real,
+{
+    fn iterate_generic(&self, c: &RealComplex<T>, z: &RealComplex<T>) -> RealComplex<T> {
+        let absz = RealComplex::new(z.re.abs(), -z.im.abs());
+        generic_power(&absz, self.power) + c.clone()
+    }
+
+    fn initial_z_generic(&self, point: &RealComplex<T>) -> RealComplex<T> {
+        multibrot_initial_z_generic(self.power, point)
     }
 }
 
@@ -84,10 +145,12 @@ impl EscapeTime for BurningShip {
 /// f(z) = (abs(Re(z)) - i*Im(z))^2 + c
 /// ```
 ///
-/// Where only the Real part of z is converted to its absolute value.
+/// Where only the Real part of z is converted to its absolute value. It is also known as the
+/// perpendicular Mandelbrot.
 pub struct BurningMandel {
     max_iters: u64,
-    power: u64,
+    power: Exponent,
+    bailout: EscapeCriterion,
 }
 
 impl BurningMandel {
@@ -101,9 +164,30 @@ impl BurningMandel {
     /// these related fractals. See <https://theory.org/fracdyn/burningship/symmetry.html> for
     /// examples of what these may look like.
     pub fn new(max_iterations: u64, power: u64) -> BurningMandel {
+        BurningMandel::with_bailout(max_iterations, power, EscapeCriterion::default())
+    }
+
+    /// Like `BurningMandel::new()`, but `bailout` specifies the test (and radius) that decides when a
+    /// value has escaped.
+    pub fn with_bailout(
+        max_iterations: u64,
+        power: u64,
+        bailout: EscapeCriterion,
+    ) -> BurningMandel {
+        BurningMandel::with_exponent(max_iterations, Exponent::Natural(power), bailout)
+    }
+
+    /// Like `BurningMandel::with_bailout()`, but `exponent` may also be negative, fractional, or
+    /// complex (see `escapetime::multibrot_initial_values()`).
+    pub fn with_exponent(
+        max_iterations: u64,
+        exponent: Exponent,
+        bailout: EscapeCriterion,
+    ) -> BurningMandel {
         BurningMandel {
             max_iters: max_iterations,
-            power,
+            power: exponent,
+            bailout,
         }
     }
 }
@@ -119,7 +203,45 @@ impl EscapeTime for BurningMandel {
 
     fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
         let absz = Complex64::new(z.re.abs(), -z.im);
-        geometry::cpow(absz, self.power) + c
+        geometry::cpow_general(absz, self.power) + c
+    }
+
+    #[inline(always)]
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        match self.power.as_natural() {
+            Some(n) => {
+                z.map(|re, im| (re.abs(), -im));
+                z.powu(n);
+                z.add(c);
+            }
+            None => batch::iterate_each_lane(self, c, z, previous),
+        }
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn degree(&self) -> f64 {
+        self.power.real_part()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_values(self.power, point)
+    }
+}
+
+impl<T> GenericEscapeTime<T> for BurningMandel
+where
+    T: Real,
+{
+    fn iterate_generic(&self, c: &RealComplex<T>, z: &RealComplex<T>) -> RealComplex<T> {
+        let absz = RealComplex::new(z.re.abs(), -z.im.clone());
+        generic_power(&absz, self.power) + c.clone()
+    }
+
+    fn initial_z_generic(&self, point: &RealComplex<T>) -> RealComplex<T> {
+        multibrot_initial_z_generic(self.power, point)
     }
 }
 
@@ -138,10 +260,12 @@ impl EscapeTime for BurningMandel {
 /// f(z) = (Re(z) - i*abs(Im(z)))^2 + c
 /// ```
 ///
-/// Where only the Imaginary part of z is converted to its absolute value.
+/// Where only the Imaginary part of z is converted to its absolute value. It is better known as
+/// the perpendicular burning ship (see `PerpendicularBurningShip`).
 pub struct RoadRunner {
     max_iters: u64,
-    power: u64,
+    power: Exponent,
+    bailout: EscapeCriterion,
 }
 
 impl RoadRunner {
@@ -155,9 +279,26 @@ impl RoadRunner {
     /// these related fractals. See <https://theory.org/fracdyn/burningship/symmetry.html> for
     /// examples of what these may look like.
     pub fn new(max_iterations: u64, power: u64) -> RoadRunner {
+        RoadRunner::with_bailout(max_iterations, power, EscapeCriterion::default())
+    }
+
+    /// Like `RoadRunner::new()`, but `bailout` specifies the test (and radius) that decides when a
+    /// value has escaped.
+    pub fn with_bailout(max_iterations: u64, power: u64, bailout: EscapeCriterion) -> RoadRunner {
+        RoadRunner::with_exponent(max_iterations, Exponent::Natural(power), bailout)
+    }
+
+    /// Like `RoadRunner::with_bailout()`, but `exponent` may also be negative, fractional, or
+    /// complex (see `escapetime::multibrot_initial_values()`).
+    pub fn with_exponent(
+        max_iterations: u64,
+        exponent: Exponent,
+        bailout: EscapeCriterion,
+    ) -> RoadRunner {
         RoadRunner {
             max_iters: max_iterations,
-            power,
+            power: exponent,
+            bailout,
         }
     }
 }
@@ -173,6 +314,374 @@ impl EscapeTime for RoadRunner {
 
     fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
         let absz = Complex64::new(z.re, -z.im.abs());
-        geometry::cpow(absz, self.power) + c
+        geometry::cpow_general(absz, self.power) + c
+    }
+
+    #[inline(always)]
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        match self.power.as_natural() {
+            Some(n) => {
+                z.map(|re, im| (re, -im.abs()));
+                z.powu(n);
+                z.add(c);
+            }
+            None => batch::iterate_each_lane(self, c, z, previous),
+        }
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn degree(&self) -> f64 {
+        self.power.real_part()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_values(self.power, point)
+    }
+}
+
+impl<T> GenericEscapeTime<T> for RoadRunner
+where
+    T: Real,
+{
+    fn iterate_generic(&self, c: &RealComplex<T>, z: &RealComplex<T>) -> RealComplex<T> {
+        let absz = RealComplex::new(z.re.clone(), -z.im.abs());
+        generic_power(&absz, self.power) + c.clone()
+    }
+
+    fn initial_z_generic(&self, point: &RealComplex<T>) -> RealComplex<T> {
+        multibrot_initial_z_generic(self.power, point)
+    }
+}
+
+/// The perpendicular burning ship, which is the same fractal as the `RoadRunner`.
+pub type PerpendicularBurningShip = RoadRunner;
+
+/// The Tricorn, or Mandelbar, fractal is defined as:
+///
+/// ```text
+/// f(z) = conj(z)^2 + c
+/// ```
+///
+/// Where the imaginary part of z is negated before squaring it, which gives the fractal its
+/// three-cornered shape.
+pub struct Tricorn {
+    max_iters: u64,
+    power: Exponent,
+    bailout: EscapeCriterion,
+}
+
+impl Tricorn {
+    /// Creates a new escape time specification for the tricorn fractal.
+    ///
+    /// `max_iterations` specifies the cutoff iteration for deciding whether a complex number
+    /// escapes or has converged.
+    ///
+    /// `power` specifies the exponent that is used instead of 2 in the equation above.
+    pub fn new(max_iterations: u64, power: u64) -> Tricorn {
+        Tricorn::with_bailout(max_iterations, power, EscapeCriterion::default())
+    }
+
+    /// Like `Tricorn::new()`, but `bailout` specifies the test (and radius) that decides when a
+    /// value has escaped.
+    pub fn with_bailout(max_iterations: u64, power: u64, bailout: EscapeCriterion) -> Tricorn {
+        Tricorn::with_exponent(max_iterations, Exponent::Natural(power), bailout)
+    }
+
+    /// Like `Tricorn::with_bailout()`, but `exponent` may also be negative, fractional, or
+    /// complex (see `escapetime::multibrot_initial_values()`).
+    pub fn with_exponent(
+        max_iterations: u64,
+        exponent: Exponent,
+        bailout: EscapeCriterion,
+    ) -> Tricorn {
+        Tricorn {
+            max_iters: max_iterations,
+            power: exponent,
+            bailout,
+        }
+    }
+}
+
+impl EscapeTime for Tricorn {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.5, 1.75), Complex64::new(1.5, -1.75)]
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        geometry::cpow_general(z.conj(), self.power) + c
+    }
+
+    #[inline(always)]
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        match self.power.as_natural() {
+            Some(n) => {
+                z.map(|re, im| (re, -im));
+                z.powu(n);
+                z.add(c);
+            }
+            None => batch::iterate_each_lane(self, c, z, previous),
+        }
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn degree(&self) -> f64 {
+        self.power.real_part()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_values(self.power, point)
+    }
+}
+
+impl<T> GenericEscapeTime<T> for Tricorn
+where
+    T: Real,
+{
+    fn iterate_generic(&self, c: &RealComplex<T>, z: &RealComplex<T>) -> RealComplex<T> {
+        let conjz = RealComplex::new(z.re.clone(), -z.im.clone());
+        generic_power(&conjz, self.power) + c.clone()
+    }
+
+    fn initial_z_generic(&self, point: &RealComplex<T>) -> RealComplex<T> {
+        multibrot_initial_z_generic(self.power, point)
+    }
+}
+
+/// The Celtic fractal is defined as:
+///
+/// ```text
+/// f(z) = abs(Re(z^2)) + i*Im(z^2) + c
+/// ```
+///
+/// Where the absolute value is taken after squaring z, and only of the real part.
+pub struct Celtic {
+    max_iters: u64,
+    power: Exponent,
+    bailout: EscapeCriterion,
+}
+
+impl Celtic {
+    /// Creates a new escape time specification for the Celtic fractal.
+    ///
+    /// `max_iterations` specifies the cutoff iteration for deciding whether a complex number
+    /// escapes or has converged.
+    ///
+    /// `power` specifies the exponent that is used instead of 2 in the equation above.
+    pub fn new(max_iterations: u64, power: u64) -> Celtic {
+        Celtic::with_bailout(max_iterations, power, EscapeCriterion::default())
+    }
+
+    /// Like `Celtic::new()`, but `bailout` specifies the test (and radius) that decides when a
+    /// value has escaped.
+    pub fn with_bailout(max_iterations: u64, power: u64, bailout: EscapeCriterion) -> Celtic {
+        Celtic::with_exponent(max_iterations, Exponent::Natural(power), bailout)
+    }
+
+    /// Like `Celtic::with_bailout()`, but `exponent` may also be negative, fractional, or
+    /// complex (see `escapetime::multibrot_initial_values()`).
+    pub fn with_exponent(
+        max_iterations: u64,
+        exponent: Exponent,
+        bailout: EscapeCriterion,
+    ) -> Celtic {
+        Celtic {
+            max_iters: max_iterations,
+            power: exponent,
+            bailout,
+        }
+    }
+}
+
+impl EscapeTime for Celtic {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.5, 1.5), Complex64::new(1.5, -1.5)]
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        let w = geometry::cpow_general(z, self.power);
+        Complex64::new(w.re.abs(), w.im) + c
+    }
+
+    #[inline(always)]
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        match self.power.as_natural() {
+            Some(n) => {
+                z.powu(n);
+                z.map(|re, im| (re.abs(), im));
+                z.add(c);
+            }
+            None => batch::iterate_each_lane(self, c, z, previous),
+        }
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn degree(&self) -> f64 {
+        self.power.real_part()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_values(self.power, point)
+    }
+}
+
+impl<T> GenericEscapeTime<T> for Celtic
+where
+    T: Real,
+{
+    fn iterate_generic(&self, c: &RealComplex<T>, z: &RealComplex<T>) -> RealComplex<T> {
+        let w = generic_power(z, self.power);
+        RealComplex::new(w.re.abs(), w.im) + c.clone()
+    }
+
+    fn initial_z_generic(&self, point: &RealComplex<T>) -> RealComplex<T> {
+        multibrot_initial_z_generic(self.power, point)
+    }
+}
+
+/// The Buffalo fractal is defined as:
+///
+/// ```text
+/// f(z) = abs(Re(z^2)) - i*abs(Im(z^2)) + c
+/// ```
+///
+/// Where, like the `Celtic` fractal, the absolute values are taken after squaring z. Like the
+/// `BurningShip`, the imaginary part is subtracted.
+pub struct Buffalo {
+    max_iters: u64,
+    power: Exponent,
+    bailout: EscapeCriterion,
+}
+
+impl Buffalo {
+    /// Creates a new escape time specification for the buffalo fractal.
+    ///
+    /// `max_iterations` specifies the cutoff iteration for deciding whether a complex number
+    /// escapes or has converged.
+    ///
+    /// `power` specifies the exponent that is used instead of 2 in the equation above.
+    pub fn new(max_iterations: u64, power: u64) -> Buffalo {
+        Buffalo::with_bailout(max_iterations, power, EscapeCriterion::default())
+    }
+
+    /// Like `Buffalo::new()`, but `bailout` specifies the test (and radius) that decides when a
+    /// value has escaped.
+    pub fn with_bailout(max_iterations: u64, power: u64, bailout: EscapeCriterion) -> Buffalo {
+        Buffalo::with_exponent(max_iterations, Exponent::Natural(power), bailout)
+    }
+
+    /// Like `Buffalo::with_bailout()`, but `exponent` may also be negative, fractional, or
+    /// complex (see `escapetime::multibrot_initial_values()`).
+    pub fn with_exponent(
+        max_iterations: u64,
+        exponent: Exponent,
+        bailout: EscapeCriterion,
+    ) -> Buffalo {
+        Buffalo {
+            max_iters: max_iterations,
+            power: exponent,
+            bailout,
+        }
+    }
+}
+
+impl EscapeTime for Buffalo {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.5, 1.5), Complex64::new(1.5, -1.5)]
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        let w = geometry::cpow_general(z, self.power);
+        Complex64::new(w.re.abs(), -w.im.abs()) + c
+    }
+
+    #[inline(always)]
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        match self.power.as_natural() {
+            Some(n) => {
+                z.powu(n);
+                z.map(|re, im| (re.abs(), -im.abs()));
+                z.add(c);
+            }
+            None => batch::iterate_each_lane(self, c, z, previous),
+        }
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn degree(&self) -> f64 {
+        self.power.real_part()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_values(self.power, point)
+    }
+}
+
+impl<T> GenericEscapeTime<T> for Buffalo
+where
+    T: Real,
+{
+    fn iterate_generic(&self, c: &RealComplex<T>, z: &RealComplex<T>) -> RealComplex<T> {
+        let w = generic_power(z, self.power);
+        RealComplex::new(w.re.abs(), -w.im.abs()) + c.clone()
+    }
+
+    fn initial_z_generic(&self, point: &RealComplex<T>) -> RealComplex<T> {
+        multibrot_initial_z_generic(self.power, point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_iterate_variations() {
+        // z^2 = -7 - 24i for z = 3 - 4i, and conj(z)^2 = -7 + 24i
+        let z = Complex64::new(3.0, -4.0);
+        let c = Complex64::new(0.5, 0.25);
+        assert_eq!(
+            Tricorn::new(10, 2).iterate(c, z),
+            Complex64::new(-6.5, 24.25)
+        );
+        assert_eq!(
+            Celtic::new(10, 2).iterate(c, z),
+            Complex64::new(7.5, -23.75)
+        );
+        assert_eq!(
+            Buffalo::new(10, 2).iterate(c, z),
+            Complex64::new(7.5, -23.75)
+        );
+        assert_eq!(
+            Buffalo::new(10, 2).iterate(c, z.conj()),
+            Complex64::new(7.5, -23.75)
+        );
+        assert_eq!(
+            PerpendicularBurningShip::new(10, 2).iterate(c, z),
+            Complex64::new(-6.5, -23.75)
+        );
     }
 }
@@ -0,0 +1,190 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Exterior distance estimation for escape time fractals.
+//!
+//! Alongside the orbit `z_n` of a point, a `DistanceEstimator` tracks the derivative `dz_n` of the
+//! orbit with respect to the point being tested. Once the orbit escapes, the distance from the
+//! point to the fractal's set can be estimated as:
+//!
+//! ```text
+//! distance = |z_n| ln|z_n| / |dz_n|
+//! ```
+//!
+//! For points close to the Mandelbrot set, the Koebe 1/4 theorem guarantees that the true distance
+//! is between half and twice this estimate (given a large bailout radius). Unlike the escape
+//! time, the distance can be compared against the size of a pixel, which makes it possible to
+//! draw filaments of the boundary that are much thinner than a pixel, or to decide where a view
+//! needs more samples.
+
+use super::{Complex64, EscapeResult, EscapeTime};
+
+/// The result of testing a single complex number with `DistanceEstimator::estimate_distance()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceEstimate {
+    /// How the point escaped (or did not).
+    pub result: EscapeResult,
+    /// The derivative of the final value of `z` with respect to the point being tested.
+    pub derivative: Complex64,
+    /// The estimated distance from the point to the fractal's set, or None if the point did not
+    /// escape.
+    pub distance: Option<f64>,
+}
+
+impl DistanceEstimate {
+    /// Constructs the estimate for a point whose orbit escaped, where `derivative` is the
+    /// derivative of `result.z`.
+    pub fn escaped(result: EscapeResult, derivative: Complex64) -> DistanceEstimate {
+        let norm = result.z.norm();
+        DistanceEstimate {
+            result,
+            derivative,
+            distance: Some(norm * norm.ln() / derivative.norm()),
+        }
+    }
+
+    /// Constructs the estimate for a point whose orbit did not escape.
+    pub fn interior(result: EscapeResult, derivative: Complex64) -> DistanceEstimate {
+        DistanceEstimate {
+            result,
+            derivative,
+            distance: None,
+        }
+    }
+}
+
+/// An `EscapeTime` fractal whose iterated function is differentiable, so that it can estimate
+/// the distance from a point to its set.
+pub trait DistanceEstimator: EscapeTime {
+    /// The derivatives of the constant `c` and the initial value of `z` from
+    /// `EscapeTime::initial_values()` with respect to the point being tested.
+    ///
+    /// The default implementation implements the mandelbrot convention, where `c` is the point
+    /// (a derivative of 1) and `z` starts at a constant (a derivative of 0).
+    fn initial_derivatives(&self, _point: Complex64) -> (Complex64, Complex64) {
+        (Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0))
+    }
+
+    /// Applies the chain rule to `EscapeTime::iterate()`: given `c` and `z` along with their
+    /// derivatives `dc` and `dz` with respect to the point being tested, computes the derivative
+    /// of `iterate(c, z)`.
+    ///
+    /// For example, `z^2 + c` has a derivative of `2 * z * dz + dc`.
+    fn iterate_derivative(
+        &self,
+        c: Complex64,
+        z: Complex64,
+        dc: Complex64,
+        dz: Complex64,
+    ) -> Complex64;
+
+    /// Tests whether a given complex number is in the fractal's set, and if it is not, estimates
+    /// how far away from the set it is.
+    ///
+    /// Like `EscapeTime::evaluate()`, it checks `EscapeTime::known_interior()` before iterating,
+    /// but it does not use periodicity checking, so other interior points are iterated for all
+    /// of `EscapeTime::max_iterations()`.
+    fn estimate_distance(&self, point: Complex64) -> DistanceEstimate {
+        let bailout = self.bailout();
+        let (c, mut z) = self.initial_values(point);
+        let (dc, mut dz) = self.initial_derivatives(point);
+        if let Some(result) = self.known_interior(c, z) {
+            return DistanceEstimate::interior(result, dz);
+        }
+
+        for i in 0..self.max_iterations() {
+            dz = self.iterate_derivative(c, z, dc, dz);
+            z = self.iterate(c, z);
+            if bailout.has_escaped(z) {
+                let result = EscapeResult::escaped(i, z, self.degree(), bailout.radius());
+                return DistanceEstimate::escaped(result, dz);
+            }
+        }
+        DistanceEstimate::interior(EscapeResult::attracted(z), dz)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::julia::JuliaSet;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::super::EscapeCriterion;
+    use super::*;
+
+    #[test]
+    fn test_estimate_distance_matches_evaluate() {
+        let mb = Mandelbrot::new(100, 2);
+        for &point in &[
+            Complex64::new(1.0, 0.0),
+            Complex64::new(-0.8, 0.35),
+            Complex64::new(-1.0, 0.0),
+            Complex64::new(0.1, 0.1),
+        ] {
+            let estimate = mb.estimate_distance(point);
+            let result = mb.evaluate(point);
+            assert_eq!(estimate.result.escaped, result.escaped);
+            assert_eq!(estimate.result.iterations, result.iterations);
+            assert_eq!(estimate.distance.is_some(), result.escaped);
+        }
+    }
+
+    #[test]
+    fn test_estimate_distance_mandelbrot() {
+        // The mandelbrot set meets the negative real axis at -2. Close to the tip, the estimate
+        // comes out at about twice the true distance.
+        let mb = Mandelbrot::with_bailout(10_000, 2, EscapeCriterion::Norm(1e10));
+        for &x in &[-2.001, -2.01, -2.05] {
+            let distance = mb
+                .estimate_distance(Complex64::new(x, 0.0))
+                .distance
+                .unwrap();
+            let actual = -2.0 - x;
+            assert!(distance > actual / 4.0, "{} at {}", distance, x);
+            assert!(distance < actual * 4.0, "{} at {}", distance, x);
+        }
+    }
+
+    #[test]
+    fn test_estimate_distance_julia() {
+        // With c = 0, the Julia set is the unit disk, so the distance from r is r - 1. Since
+        // z_n = r^(2^n), the estimate works out to r * ln(r), which approaches r - 1 as r gets
+        // closer to the disk.
+        let js = JuliaSet::new(
+            Mandelbrot::with_bailout(100, 2, EscapeCriterion::Norm(1e10)),
+            Complex64::new(0.0, 0.0),
+        );
+        for &r in &[1.1, 1.5, 3.0] {
+            let distance = js
+                .estimate_distance(Complex64::new(0.0, r))
+                .distance
+                .unwrap();
+            assert_approx_eq!(distance, r * r.ln(), 1e-9);
+            assert!(distance > (r - 1.0) / 2.0 && distance < (r - 1.0) * 2.0);
+        }
+    }
+
+    #[test]
+    fn test_estimate_distance_shrinks_towards_boundary() {
+        let mb = Mandelbrot::with_bailout(10_000, 2, EscapeCriterion::Norm(1e10));
+        let mut previous = f64::INFINITY;
+        for &x in &[1.0, 0.5, 0.3, 0.26, 0.251] {
+            let distance = mb
+                .estimate_distance(Complex64::new(x, 0.0))
+                .distance
+                .unwrap();
+            assert!(distance < previous);
+            previous = distance;
+        }
+    }
+}
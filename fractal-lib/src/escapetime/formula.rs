@@ -0,0 +1,199 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Escape time fractals whose iterated function is a formula that is parsed at runtime (see the
+//! `formula` module for the language). For example, the mandelbrot set is `z^2 + c`, and the
+//! burning ship is `(abs(re(z)) - i*abs(im(z)))^2 + c`.
+//!
+//! Like the built in fractals, the point being tested is used as `c`, and iteration starts from
+//! `z = 0`. Wrap the fractal in a `julia::JuliaSet` to use the Julia set convention instead.
+
+use super::super::formula::{Formula, Variables};
+use super::*;
+
+/// The magnitude of `z` used to estimate the degree of a formula.
+const DEGREE_ESTIMATE_RADIUS: f64 = 1e8;
+
+pub struct FormulaFractal {
+    max_iters: u64,
+    formula: Formula,
+    parameters: [Complex64; 4],
+    bailout: EscapeCriterion,
+    degree: f64,
+}
+
+impl FormulaFractal {
+    /// Creates a fractal that iterates `formula`, which is parsed using `Formula::parse()`.
+    ///
+    /// `max_iterations` specifies the cutoff iteration for deciding whether a complex number
+    /// escapes or has converged.
+    pub fn new(max_iterations: u64, formula: &str) -> Result<FormulaFractal, String> {
+        FormulaFractal::with_parameters(
+            max_iterations,
+            formula,
+            [Complex64::new(0.0, 0.0); 4],
+            EscapeCriterion::default(),
+        )
+    }
+
+    /// Like `FormulaFractal::new()`, but `parameters` gives the values of `p1` through `p4`, and
+    /// `bailout` specifies the test (and radius) that decides when a value has escaped.
+    pub fn with_parameters(
+        max_iterations: u64,
+        formula: &str,
+        parameters: [Complex64; 4],
+        bailout: EscapeCriterion,
+    ) -> Result<FormulaFractal, String> {
+        let formula = Formula::parse(formula)?;
+        let degree = estimate_degree(&formula, parameters);
+        Ok(FormulaFractal {
+            max_iters: max_iterations,
+            formula,
+            parameters,
+            bailout,
+            degree,
+        })
+    }
+
+    pub fn formula(&self) -> &Formula {
+        &self.formula
+    }
+
+    pub fn parameters(&self) -> [Complex64; 4] {
+        self.parameters
+    }
+}
+
+/// Estimates how quickly a formula grows for large values of `z`, by comparing `ln|f(z)|` to
+/// `ln|z|` at a few points far from the origin. Formulas that do not grow polynomially (such as
+/// ones using `exp`) get a degree of 1, which disables smoothing.
+fn estimate_degree(formula: &Formula, parameters: [Complex64; 4]) -> f64 {
+    let angles = [0.3, 1.9, 3.4, 5.1];
+    let mut total = 0.0;
+    for angle in angles.iter() {
+        let variables = Variables {
+            z: Complex64::from_polar(&DEGREE_ESTIMATE_RADIUS, angle),
+            c: Complex64::new(0.25, 0.25),
+            parameters,
+        };
+        total += formula.evaluate(&variables).norm().ln() / DEGREE_ESTIMATE_RADIUS.ln();
+    }
+    let degree = total / angles.len() as f64;
+    if degree.is_finite() && degree > 1.0 {
+        // integer degrees are by far the most common, so avoid tiny errors in them
+        if (degree - degree.round()).abs() < 1e-3 {
+            degree.round()
+        } else {
+            degree
+        }
+    } else {
+        1.0
+    }
+}
+
+impl EscapeTime for FormulaFractal {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.5, 1.5), Complex64::new(2.5, -1.5)]
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        self.formula.evaluate(&Variables {
+            z,
+            c,
+            parameters: self.parameters,
+        })
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn degree(&self) -> f64 {
+        self.degree
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::burningship::BurningShip;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    /// Checks that `formula` escapes at the same time and place as `expected` does.
+    fn assert_matches<E: EscapeTime>(formula: &FormulaFractal, expected: &E) {
+        for point in &[
+            Complex64::new(0.3, 0.0),
+            Complex64::new(-0.75, 0.1),
+            Complex64::new(0.0, 1.0),
+            Complex64::new(-1.5, 0.5),
+            Complex64::new(-1.75, -0.03),
+        ] {
+            let expected = expected.evaluate(*point);
+            let result = formula.evaluate(*point);
+            assert_eq!(result.escaped, expected.escaped);
+            if expected.escaped {
+                assert_eq!(result.iterations, expected.iterations);
+                assert_eq!(result.z, expected.z);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_mandelbrot() {
+        let formula = FormulaFractal::new(100, "z^2 + c").unwrap();
+        assert_eq!(formula.degree(), 2.0);
+        assert_matches(&formula, &Mandelbrot::new(100, 2));
+    }
+
+    #[test]
+    fn test_matches_burning_ship() {
+        let formula = FormulaFractal::new(100, "(abs(re(z)) - i*abs(im(z)))^2 + c").unwrap();
+        assert_eq!(formula.degree(), 2.0);
+        assert_matches(&formula, &BurningShip::new(100, 2));
+    }
+
+    #[test]
+    fn test_parameters() {
+        let mut parameters = [Complex64::new(0.0, 0.0); 4];
+        parameters[1] = Complex64::new(3.0, 0.0);
+        let fractal =
+            FormulaFractal::with_parameters(10, "z^p2 + c", parameters, EscapeCriterion::default())
+                .unwrap();
+        assert_eq!(fractal.degree(), 3.0);
+        assert_eq!(
+            fractal.iterate(Complex64::new(1.0, 0.0), Complex64::new(2.0, 0.0)),
+            Complex64::new(9.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn test_degree_estimate() {
+        let degree = |formula| FormulaFractal::new(10, formula).unwrap().degree();
+        assert_eq!(degree("z^4 + c"), 4.0);
+        assert_eq!(degree("z^2 - z + c"), 2.0);
+        assert!((degree("z^2.5 + c") - 2.5).abs() < 1e-9);
+        assert_eq!(degree("exp(z) + c"), 1.0);
+        assert_eq!(degree("z^-2 + c"), 1.0);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = FormulaFractal::new(10, "z^2 + ").err().unwrap();
+        assert!(error.contains("column 7"), "{}", error);
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Escape time fractals that can be computed with any `numeric::Real` type.
+//!
+//! `EscapeTime` always works with `Complex64`s, which is the fastest option, but the points that
+//! neighboring pixels map to become indistinguishable once a view is narrower than about 1e-13.
+//! `GenericEscapeTime` iterates the same function with a more precise number type instead (or a
+//! less precise one, like `f32`), which is much slower but accurate at depth. This makes it
+//! useful for rendering reference images to check faster approaches, such as perturbation,
+//! against.
+//!
+//! The piston frontend's `reference` subcommand renders such images with a chosen `Real` type.
+
+use super::super::geometry::{self, Exponent};
+pub use super::super::numeric::{Real, RealComplex, ViewArea};
+use super::*;
+
+/// Raises `z` to `exponent`.
+///
+/// Natural exponents are computed exactly with `T`s, but other exponents are computed with
+/// `geometry::cpow_general()` on the nearest `Complex64`, which loses any extra precision.
+pub fn generic_power<T>(z: &RealComplex<T>, exponent: Exponent) -> RealComplex<T>
+where
+    T: Real,
+{
+    match exponent.as_natural() {
+        Some(n) => z.powu(n),
+        None => RealComplex::from_complex64(
+            geometry::cpow_general(z.to_complex64(), exponent),
+            z.precision(),
+        ),
+    }
+}
+
+/// Like `multibrot_initial_values()`, returns the starting value of `z` for `point`.
+pub fn multibrot_initial_z_generic<T>(exponent: Exponent, point: &RealComplex<T>) -> RealComplex<T>
+where
+    T: Real,
+{
+    if has_pole_at_zero(exponent) {
+        point.clone()
+    } else {
+        RealComplex::from_complex64(Complex64::new(0.0, 0.0), point.precision())
+    }
+}
+
+/// An `EscapeTime` fractal whose iteration function can also be computed with the number type
+/// `T`.
+///
+/// The maximum number of iterations, bailout test, and degree are shared with the `EscapeTime`
+/// implementation.
+pub trait GenericEscapeTime<T>: EscapeTime
+where
+    T: Real,
+{
+    /// Like `EscapeTime::iterate()`, with `T`s for the parts of the complex numbers.
+    fn iterate_generic(&self, c: &RealComplex<T>, z: &RealComplex<T>) -> RealComplex<T>;
+
+    /// The value of `z` that iteration starts from when testing `point`, which is used as `c`.
+    ///
+    /// The default implementation starts from 0, like `EscapeTime::initial_values()`.
+    fn initial_z_generic(&self, point: &RealComplex<T>) -> RealComplex<T> {
+        RealComplex::from_complex64(Complex64::new(0.0, 0.0), point.precision())
+    }
+
+    /// Like `EscapeTime::evaluate()`, but every step is computed with `T`s.
+    ///
+    /// Only the bailout test and the values in the result are rounded to `f64`. The shortcuts for
+    /// interior points (bulb checks and periodicity detection) are skipped, since they work with
+    /// `f64` precision.
+    fn evaluate_generic(&self, point: &RealComplex<T>) -> EscapeResult {
+        let bailout = self.bailout();
+        let mut z = self.initial_z_generic(point);
+        let mut approx = z.to_complex64();
+        for i in 0..self.max_iterations() {
+            z = self.iterate_generic(point, &z);
+            approx = z.to_complex64();
+            if bailout.has_escaped(approx) {
+                return EscapeResult::escaped(i, approx, self.degree(), bailout.radius());
+            }
+        }
+        EscapeResult::attracted(approx)
+    }
+
+    /// The default view area, at the given precision.
+    fn default_view_area_generic(&self, precision: T::Precision) -> ViewArea<T> {
+        ViewArea::from_complex64(self.default_view_area(), precision)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::bigfloat::BigFloat;
+    use super::super::super::doubledouble::DoubleDouble;
+    use super::super::burningship::{
+        Buffalo, BurningMandel, BurningShip, Celtic, RoadRunner, Tricorn,
+    };
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    /// Checks that the generic implementations give the same escape times as the `Complex64` one
+    /// over the default view area.
+    fn check_matches_complex64<E>(fractal: &E)
+    where
+        E: GenericEscapeTime<f64>
+            + GenericEscapeTime<f32>
+            + GenericEscapeTime<DoubleDouble>
+            + GenericEscapeTime<BigFloat>,
+    {
+        let screen = [24.0, 16.0];
+        let view_f64 = GenericEscapeTime::<f64>::default_view_area_generic(fractal, ());
+        let view_dd = GenericEscapeTime::<DoubleDouble>::default_view_area_generic(fractal, ());
+        let view_big = GenericEscapeTime::<BigFloat>::default_view_area_generic(fractal, 80);
+        let view_f32 = GenericEscapeTime::<f32>::default_view_area_generic(fractal, ());
+        let mut f32_mismatches = 0;
+        for x in 0..24 {
+            for y in 0..16 {
+                let pixel = [f64::from(x), f64::from(y)];
+                let point = view_f64.map_pixel_to_point(screen, pixel);
+                // Without the interior shortcuts, the escape times should match exactly
+                let mut expected = fractal.evaluate(point.to_complex64());
+                if !expected.escaped {
+                    expected = EscapeResult::attracted(expected.z);
+                }
+                let actual = fractal.evaluate_generic(&point);
+                assert_eq!(expected.escaped, actual.escaped);
+                assert_eq!(expected.iterations, actual.iterations);
+
+                for actual in &[
+                    fractal.evaluate_generic(&view_dd.map_pixel_to_point(screen, pixel)),
+                    fractal.evaluate_generic(&view_big.map_pixel_to_point(screen, pixel)),
+                ] {
+                    assert_eq!(expected.escaped, actual.escaped);
+                    assert_eq!(expected.iterations, actual.iterations);
+                }
+
+                let actual = fractal.evaluate_generic(&view_f32.map_pixel_to_point(screen, pixel));
+                if expected.escaped != actual.escaped || expected.iterations != actual.iterations {
+                    f32_mismatches += 1;
+                }
+            }
+        }
+        // f32 rounding may change the escape time of a few boundary points
+        assert!(
+            f32_mismatches < 24 * 16 / 10,
+            "{} mismatches",
+            f32_mismatches
+        );
+    }
+
+    #[test]
+    fn test_matches_complex64() {
+        check_matches_complex64(&Mandelbrot::new(40, 2));
+        check_matches_complex64(&Mandelbrot::new(40, 3));
+        check_matches_complex64(&BurningShip::new(40, 2));
+        check_matches_complex64(&BurningMandel::new(40, 2));
+        check_matches_complex64(&RoadRunner::new(40, 3));
+        check_matches_complex64(&Tricorn::new(40, 2));
+        check_matches_complex64(&Celtic::new(40, 2));
+        check_matches_complex64(&Buffalo::new(40, 2));
+    }
+
+    #[test]
+    fn test_precision_at_depth() {
+        // Two points that are 1e-20 apart round to the same Complex64, but not the same
+        // DoubleDouble
+        let mb = Mandelbrot::new(500, 2);
+        let view = ViewArea::new(
+            RealComplex::new(
+                DoubleDouble::parse("-1.7400623825793399052").unwrap(),
+                DoubleDouble::from_f64(1e-20),
+            ),
+            RealComplex::new(
+                DoubleDouble::parse("-1.7400623825793399051").unwrap(),
+                DoubleDouble::from_f64(0.0),
+            ),
+        );
+        let screen = [32.0, 32.0];
+        let points = (0..32)
+            .map(|x| view.map_pixel_to_point(screen, [f64::from(x), 16.0]))
+            .collect::<Vec<_>>();
+        assert_eq!(points[0].to_complex64(), points[31].to_complex64());
+        let distinct = points
+            .iter()
+            .map(|point| mb.evaluate_generic(point).iterations)
+            .collect::<std::collections::HashSet<_>>();
+        assert!(distinct.len() > 1);
+    }
+}
@@ -0,0 +1,480 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Julia sets over four dimensional hypercomplex numbers, drawn as two dimensional slices.
+//!
+//! Quaternions and bicomplex numbers both extend the complex numbers with two more imaginary
+//! units, `j` and `k`, so that each number has four components along the axes `1`, `i`, `j`, and
+//! `k`. They only differ in how the units multiply: for quaternions `j^2 = k^2 = -1` and
+//! multiplication does not commute, while for bicomplex numbers `j^2 = -1`, `k = ij = ji`, and so
+//! `k^2 = 1`. Either way, iterating
+//!
+//! ```text
+//! q_0 = point
+//! q_{n+1} = q_n^2 + c
+//! ```
+//!
+//! for a constant `c` draws a four dimensional Julia set.
+//!
+//! To draw it on the plane, a `Slice` picks the two axes that the real and imaginary parts of
+//! each point on the screen map to, and fixes the other two components to an offset. Moving the
+//! offset along the last of those axes (its "depth") sweeps the slice through the fourth
+//! dimension.
+
+use super::*;
+use std::ops::Add;
+
+/// The names of the axes of a hypercomplex number, in the order of its components.
+pub const AXIS_NAMES: [&str; 4] = ["1", "i", "j", "k"];
+
+/// A four dimensional number, with components along `1`, `i`, `j`, and `k`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hypercomplex(pub [f64; 4]);
+
+impl Hypercomplex {
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Hypercomplex {
+        Hypercomplex([w, x, y, z])
+    }
+
+    /// The square of the (Euclidean) length of the number.
+    pub fn norm_sqr(self) -> f64 {
+        self.0.iter().map(|v| v * v).sum()
+    }
+}
+
+impl Add for Hypercomplex {
+    type Output = Hypercomplex;
+
+    fn add(self, other: Hypercomplex) -> Hypercomplex {
+        let [w, x, y, z] = self.0;
+        let [ow, ox, oy, oz] = other.0;
+        Hypercomplex([w + ow, x + ox, y + oy, z + oz])
+    }
+}
+
+/// How the imaginary units of a `Hypercomplex` number multiply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Algebra {
+    /// `i^2 = j^2 = k^2 = ijk = -1`.
+    Quaternion,
+    /// `i^2 = j^2 = -1` and `k = ij = ji`, so that `k^2 = 1`.
+    Bicomplex,
+}
+
+impl Algebra {
+    /// The names accepted by `Algebra::from_name()`.
+    pub const NAMES: [&'static str; 2] = ["quaternion", "bicomplex"];
+
+    pub fn from_name(name: &str) -> Result<Algebra, String> {
+        match name {
+            "quaternion" => Ok(Algebra::Quaternion),
+            "bicomplex" => Ok(Algebra::Bicomplex),
+            _ => Err(format!("Unknown algebra: {}", name)),
+        }
+    }
+
+    /// Squares `q`.
+    ///
+    /// The imaginary parts of a quaternion all square the same way, since `q^2` only mixes
+    /// each of them with the real part. A bicomplex number `z1 + z2 j` (where `z1` and `z2` are
+    /// complex numbers over `1` and `i`) squares to `(z1^2 - z2^2) + 2 z1 z2 j`.
+    pub fn square(self, q: Hypercomplex) -> Hypercomplex {
+        let [a, b, c, d] = q.0;
+        match self {
+            Algebra::Quaternion => Hypercomplex([
+                a * a - b * b - c * c - d * d,
+                2.0 * a * b,
+                2.0 * a * c,
+                2.0 * a * d,
+            ]),
+            Algebra::Bicomplex => Hypercomplex([
+                a * a - b * b - c * c + d * d,
+                2.0 * (a * b - c * d),
+                2.0 * (a * c - b * d),
+                2.0 * (a * d + b * c),
+            ]),
+        }
+    }
+}
+
+/// A plane through four dimensional space, which maps the complex plane onto hypercomplex
+/// numbers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slice {
+    axes: [usize; 2],
+    offset: Hypercomplex,
+}
+
+impl Slice {
+    /// The plane where the real and imaginary parts of a complex number become the components
+    /// along `axes` (indices into `AXIS_NAMES`), and the other components are taken from
+    /// `offset`. The components of `offset` along `axes` are ignored.
+    pub fn new(axes: [usize; 2], offset: Hypercomplex) -> Result<Slice, String> {
+        if axes[0] >= AXIS_NAMES.len() || axes[1] >= AXIS_NAMES.len() {
+            return Err(format!("Unknown axes: {:?}", axes));
+        }
+        if axes[0] == axes[1] {
+            return Err("A slice needs two different axes".to_string());
+        }
+        Ok(Slice { axes, offset })
+    }
+
+    /// Like `Slice::new()`, but the axes are given by their names, such as `1,i` or `j,k`.
+    pub fn from_names(plane: &str, offset: Hypercomplex) -> Result<Slice, String> {
+        let axes = plane
+            .split(',')
+            .map(|name| {
+                AXIS_NAMES
+                    .iter()
+                    .position(|&axis| axis == name.trim())
+                    .ok_or_else(|| format!("Unknown axis {}, expected one of 1, i, j, or k", name))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+        if axes.len() != 2 {
+            return Err(format!(
+                "Expected a plane of the form AXIS,AXIS (eg, 1,i), not {}",
+                plane
+            ));
+        }
+        Slice::new([axes[0], axes[1]], offset)
+    }
+
+    /// The axes that the real and imaginary parts of a complex number map to.
+    pub fn axes(&self) -> [usize; 2] {
+        self.axes
+    }
+
+    /// The last axis that is not part of the plane, which the slice can be swept along.
+    pub fn depth_axis(&self) -> usize {
+        (0..AXIS_NAMES.len())
+            .rev()
+            .find(|axis| !self.axes.contains(axis))
+            .unwrap()
+    }
+
+    /// How far along `Slice::depth_axis()` the plane is.
+    pub fn depth(&self) -> f64 {
+        self.offset.0[self.depth_axis()]
+    }
+
+    /// The same plane, moved to `depth` along `Slice::depth_axis()`.
+    pub fn with_depth(mut self, depth: f64) -> Slice {
+        self.offset.0[self.depth_axis()] = depth;
+        self
+    }
+
+    /// The hypercomplex number that `z` maps to.
+    pub fn point(&self, z: Complex64) -> Hypercomplex {
+        let mut point = self.offset;
+        point.0[self.axes[0]] = z.re;
+        point.0[self.axes[1]] = z.im;
+        point
+    }
+
+    /// The point on the complex plane that `q` is projected onto, by dropping its components
+    /// that are not along the plane.
+    pub fn project(&self, q: Hypercomplex) -> Complex64 {
+        Complex64::new(q.0[self.axes[0]], q.0[self.axes[1]])
+    }
+}
+
+/// The Julia set of `q^2 + c` for a hypercomplex `c`, drawn through a `Slice`.
+///
+/// Since the values being iterated do not fit on the complex plane, this fractal evaluates
+/// points itself instead of relying on EscapeTime::iterate(). The values that it reports (the
+/// final `z` of each `EscapeResult`, and each value passed to an `OrbitObserver`) are projected
+/// onto the slice, while the norm of each result is the norm of the full hypercomplex value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HypercomplexJulia {
+    algebra: Algebra,
+    max_iterations: u64,
+    c: Hypercomplex,
+    slice: Slice,
+    bailout: f64,
+}
+
+impl HypercomplexJulia {
+    pub fn new(
+        algebra: Algebra,
+        max_iterations: u64,
+        c: Hypercomplex,
+        slice: Slice,
+    ) -> HypercomplexJulia {
+        HypercomplexJulia {
+            algebra,
+            max_iterations,
+            c,
+            slice,
+            bailout: DEFAULT_BAILOUT,
+        }
+    }
+
+    /// Values escape once their norm reaches `radius`.
+    pub fn with_bailout(mut self, radius: f64) -> HypercomplexJulia {
+        self.bailout = radius;
+        self
+    }
+
+    /// The same fractal, drawn through `slice` instead.
+    pub fn with_slice(mut self, slice: Slice) -> HypercomplexJulia {
+        self.slice = slice;
+        self
+    }
+
+    pub fn algebra(&self) -> Algebra {
+        self.algebra
+    }
+
+    /// The constant that is added to every value of every orbit.
+    pub fn c(&self) -> Hypercomplex {
+        self.c
+    }
+
+    pub fn slice(&self) -> Slice {
+        self.slice
+    }
+
+    /// Iterates the hypercomplex number that `point` maps to, passing the projection of each new
+    /// value to `observer`.
+    ///
+    /// Cycles are detected in four dimensions, by comparing each value to a saved one that is
+    /// replaced after windows of doubling length (Brent's algorithm). Unlike the cycle detection
+    /// of EscapeTime::evaluate(), the first window is 1 step long rather than
+    /// `batch::CHUNK_STEPS`, since these orbits are never iterated in chunks, so the period that
+    /// is found for a cycle may differ from the one EscapeTime::evaluate() would find.
+    fn iterate_orbit<O>(&self, point: Complex64, observer: &mut O) -> EscapeResult
+    where
+        O: OrbitObserver + ?Sized,
+    {
+        let bailout_sqr = self.bailout * self.bailout;
+        let tolerance_sqr = self.periodicity_tolerance().map(|t| t * t);
+        let mut q = self.slice.point(point);
+        let mut saved = q;
+        let mut window = 1;
+        let mut steps = 0;
+        for i in 0..self.max_iterations {
+            q = self.algebra.square(q) + self.c;
+            observer.observe(self.slice.project(q));
+            let norm_sqr = q.norm_sqr();
+            if norm_sqr >= bailout_sqr {
+                // Smooth the iteration count with the full norm, then report the projection
+                let result = EscapeResult::escaped(
+                    i,
+                    Complex64::new(norm_sqr.sqrt(), 0.0),
+                    self.degree(),
+                    self.bailout,
+                );
+                return EscapeResult {
+                    z: self.slice.project(q),
+                    ..result
+                };
+            }
+            if let Some(tolerance_sqr) = tolerance_sqr {
+                steps += 1;
+                let distance_sqr: f64 =
+                    q.0.iter()
+                        .zip(&saved.0)
+                        .map(|(a, b)| (a - b) * (a - b))
+                        .sum();
+                if distance_sqr < tolerance_sqr {
+                    return EscapeResult {
+                        norm: q.norm_sqr().sqrt(),
+                        ..EscapeResult::periodic(self.slice.project(q), steps)
+                    };
+                }
+                if steps == window {
+                    saved = q;
+                    window *= 2;
+                    steps = 0;
+                }
+            }
+        }
+        EscapeResult {
+            norm: q.norm_sqr().sqrt(),
+            ..EscapeResult::attracted(self.slice.project(q))
+        }
+    }
+}
+
+impl EscapeTime for HypercomplexJulia {
+    fn max_iterations(&self) -> u64 {
+        self.max_iterations
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.0, 1.5), Complex64::new(2.0, -1.5)]
+    }
+
+    /// Iterates the point of the slice that `z` maps to, and projects the result back onto the
+    /// slice. This loses the components of the result that leave the plane, so it is only exact
+    /// for slices that iteration keeps values within (such as the `1,i` plane when `c` lies in
+    /// it).
+    fn iterate(&self, _c: Complex64, z: Complex64) -> Complex64 {
+        self.slice
+            .project(self.algebra.square(self.slice.point(z)) + self.c)
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        EscapeCriterion::Norm(self.bailout)
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        (self.slice.project(self.c), point)
+    }
+
+    fn evaluate(&self, point: Complex64) -> EscapeResult {
+        self.iterate_orbit(point, &mut ())
+    }
+
+    fn evaluate_observed(
+        &self,
+        point: Complex64,
+        observer: &mut dyn OrbitObserver,
+    ) -> EscapeResult {
+        self.iterate_orbit(point, observer)
+    }
+
+    fn evaluate_batch(&self, points: &[Complex64], results: &mut [EscapeResult]) {
+        assert_eq!(points.len(), results.len());
+        for (&point, result) in points.iter().zip(results.iter_mut()) {
+            *result = self.evaluate(point);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::julia::JuliaSet;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let i = Hypercomplex::new(0.0, 1.0, 0.0, 0.0);
+        let j = Hypercomplex::new(0.0, 0.0, 1.0, 0.0);
+        let k = Hypercomplex::new(0.0, 0.0, 0.0, 1.0);
+        let minus_one = Hypercomplex::new(-1.0, 0.0, 0.0, 0.0);
+        for &unit in &[i, j, k] {
+            assert_eq!(Algebra::Quaternion.square(unit), minus_one);
+        }
+        assert_eq!(Algebra::Bicomplex.square(i), minus_one);
+        assert_eq!(Algebra::Bicomplex.square(j), minus_one);
+        assert_eq!(
+            Algebra::Bicomplex.square(k),
+            Hypercomplex::new(1.0, 0.0, 0.0, 0.0)
+        );
+        // (1 + j)^2 = 2j for both
+        let q = Hypercomplex::new(1.0, 0.0, 1.0, 0.0);
+        let expected = Hypercomplex::new(0.0, 0.0, 2.0, 0.0);
+        assert_eq!(Algebra::Quaternion.square(q), expected);
+        assert_eq!(Algebra::Bicomplex.square(q), expected);
+    }
+
+    #[test]
+    fn test_slice() {
+        let offset = Hypercomplex::new(0.1, 0.2, 0.3, 0.4);
+        let slice = Slice::from_names("j, 1", offset).unwrap();
+        assert_eq!(slice.axes(), [2, 0]);
+        let point = slice.point(Complex64::new(-1.0, 2.0));
+        assert_eq!(point, Hypercomplex::new(2.0, 0.2, -1.0, 0.4));
+        assert_eq!(slice.project(point), Complex64::new(-1.0, 2.0));
+
+        assert_eq!(slice.depth_axis(), 3);
+        assert_eq!(slice.depth(), 0.4);
+        let moved = slice.with_depth(-0.5);
+        assert_eq!(moved.point(Complex64::new(0.0, 0.0)).0[3], -0.5);
+        assert_eq!(Slice::from_names("i,k", offset).unwrap().depth_axis(), 2);
+
+        assert!(Slice::from_names("i,i", offset).is_err());
+        assert!(Slice::from_names("i,x", offset).is_err());
+        assert!(Slice::from_names("i", offset).is_err());
+        assert!(Slice::from_names("1,i,j", offset).is_err());
+    }
+
+    #[test]
+    fn test_complex_plane() {
+        // Through the 1,i plane, a constant on that plane draws the ordinary Julia set
+        let c = Complex64::new(-0.8, 0.156);
+        let julia = JuliaSet::new(Mandelbrot::new(100, 2), c);
+        let slice = Slice::from_names("1,i", Hypercomplex::new(0.0, 0.0, 0.0, 0.0)).unwrap();
+        for &algebra in &[Algebra::Quaternion, Algebra::Bicomplex] {
+            let hyper = HypercomplexJulia::new(
+                algebra,
+                100,
+                Hypercomplex::new(c.re, c.im, 0.0, 0.0),
+                slice,
+            );
+            for &point in &[
+                Complex64::new(0.0, 0.0),
+                Complex64::new(0.3, 0.4),
+                Complex64::new(1.2, -0.1),
+                Complex64::new(-0.5, 0.9),
+            ] {
+                let expected = julia.evaluate(point);
+                let result = hyper.evaluate(point);
+                assert_eq!(result.escaped, expected.escaped, "{}", point);
+                assert_eq!(result.iterations, expected.iterations, "{}", point);
+                assert_approx_eq!(result.smooth, expected.smooth, 1e-9);
+                assert_eq!(hyper.iterate(c, point), julia.iterate(c, point));
+            }
+        }
+    }
+
+    #[test]
+    fn test_depth() {
+        // With c = 0, the quaternion Julia set is the unit ball, so the slice at depth 0.8 is a
+        // disk of radius 0.6
+        let slice = Slice::from_names("1,i", Hypercomplex::new(0.0, 0.0, 0.0, 0.0))
+            .unwrap()
+            .with_depth(0.8);
+        let ball = HypercomplexJulia::new(
+            Algebra::Quaternion,
+            100,
+            Hypercomplex::new(0.0, 0.0, 0.0, 0.0),
+            slice,
+        );
+        assert!(ball.test_point(Complex64::new(0.5, 0.0)).0);
+        assert!(ball.test_point(Complex64::new(0.0, -0.55)).0);
+        assert!(!ball.test_point(Complex64::new(0.7, 0.0)).0);
+        assert!(
+            !ball
+                .with_slice(slice.with_depth(1.1))
+                .test_point(Complex64::new(0.0, 0.0))
+                .0
+        );
+    }
+
+    #[test]
+    fn test_orbit() {
+        let slice = Slice::from_names("1,j", Hypercomplex::new(0.0, 0.5, 0.0, 0.0)).unwrap();
+        let julia = HypercomplexJulia::new(
+            Algebra::Quaternion,
+            10,
+            Hypercomplex::new(0.0, 0.0, 1.0, 0.0),
+            slice,
+        );
+        // (1 + 0.5i) -> 0.75 + i + j -> ...
+        let orbit = julia.orbit(Complex64::new(1.0, 0.0));
+        assert_eq!(orbit.values[0], Complex64::new(1.0, 0.0));
+        assert_eq!(orbit.values[1], Complex64::new(0.75, 1.0));
+        assert!(orbit.result.escaped);
+        assert_eq!(orbit.values.len() as u64, orbit.result.iterations + 2);
+
+        let mut results = vec![EscapeResult::attracted(Complex64::new(0.0, 0.0)); 2];
+        let points = [Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0)];
+        julia.evaluate_batch(&points, &mut results);
+        assert_eq!(results[0], julia.evaluate(points[0]));
+        assert_eq!(results[1], julia.evaluate(points[1]));
+    }
+}
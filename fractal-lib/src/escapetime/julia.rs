@@ -0,0 +1,153 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Julia (and Fatou) sets are the counterparts to the Mandelbrot family of escape time fractals.
+//! Where the Mandelbrot set uses the point being tested as the constant `c` and starts iterating
+//! from 0, a Julia set fixes `c` to a constant for the whole plane and starts iterating from the
+//! point being tested:
+//!
+//! ```text
+//! z_0 = point
+//! z_{n+1} = f(z_n) + c
+//! ```
+//!
+//! Every choice of `c` yields a different Julia set. The Julia sets for values of `c` inside the
+//! Mandelbrot set are connected, while those for values of `c` outside of it are not.
+//!
+//! `JuliaSet` wraps any other `EscapeTime` fractal and reuses its `iterate()` function, so every
+//! Mandelbrot-style fractal gets a Julia counterpart for free.
+
+use super::distance::DistanceEstimator;
+use super::*;
+
+pub struct JuliaSet<E>
+where
+    E: EscapeTime,
+{
+    fractal: E,
+    c: Complex64,
+}
+
+impl<E> JuliaSet<E>
+where
+    E: EscapeTime,
+{
+    /// Creates the Julia set that corresponds to `fractal` for the constant `c`.
+    ///
+    /// `fractal` provides the iteration function and the maximum number of iterations.
+    pub fn new(fractal: E, c: Complex64) -> JuliaSet<E> {
+        JuliaSet { fractal, c }
+    }
+
+    /// The constant that is used for `c` for every point on the plane.
+    pub fn c(&self) -> Complex64 {
+        self.c
+    }
+}
+
+impl<E> EscapeTime for JuliaSet<E>
+where
+    E: EscapeTime,
+{
+    fn max_iterations(&self) -> u64 {
+        self.fractal.max_iterations()
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.0, 1.5), Complex64::new(2.0, -1.5)]
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        self.fractal.iterate(c, z)
+    }
+
+    fn iterate_with_previous(&self, c: Complex64, z: Complex64, previous: Complex64) -> Complex64 {
+        self.fractal.iterate_with_previous(c, z, previous)
+    }
+
+    #[inline(always)]
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        self.fractal.iterate_lanes(c, z, previous)
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.fractal.bailout()
+    }
+
+    fn allows_subdivision(&self) -> bool {
+        self.fractal.allows_subdivision()
+    }
+
+    fn degree(&self) -> f64 {
+        self.fractal.degree()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        (self.c, point)
+    }
+}
+
+/// The distance estimate of a Julia set uses the derivative with respect to the starting value of
+/// `z` instead of `c`, since `c` is the same for every point.
+impl<E> DistanceEstimator for JuliaSet<E>
+where
+    E: DistanceEstimator,
+{
+    fn initial_derivatives(&self, _point: Complex64) -> (Complex64, Complex64) {
+        (Complex64::new(0.0, 0.0), Complex64::new(1.0, 0.0))
+    }
+
+    fn iterate_derivative(
+        &self,
+        c: Complex64,
+        z: Complex64,
+        dc: Complex64,
+        dz: Complex64,
+    ) -> Complex64 {
+        self.fractal.iterate_derivative(c, z, dc, dz)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    #[test]
+    fn test_test_point_unit_disk() {
+        // With c = 0, z^2 + c just squares z, so the Julia set is the closed unit disk.
+        let js = JuliaSet::new(Mandelbrot::new(100, 2), Complex64::new(0.0, 0.0));
+        assert!(js.test_point(Complex64::new(0.0, 0.0)).0);
+        assert!(js.test_point(Complex64::new(0.5, 0.5)).0);
+        assert!(js.test_point(Complex64::new(0.0, -0.9)).0);
+        assert!(!js.test_point(Complex64::new(1.5, 0.0)).0);
+        assert!(!js.test_point(Complex64::new(-1.1, 0.3)).0);
+    }
+
+    #[test]
+    fn test_test_point_basilica() {
+        // c = -1 has a period 2 orbit: 0 -> -1 -> 0
+        let js = JuliaSet::new(Mandelbrot::new(100, 2), Complex64::new(-1.0, 0.0));
+        assert!(js.test_point(Complex64::new(0.0, 0.0)).0);
+        assert!(js.test_point(Complex64::new(-1.0, 0.0)).0);
+        assert!(!js.test_point(Complex64::new(1.7, 0.0)).0);
+    }
+
+    #[test]
+    fn test_forwards_max_iterations() {
+        let js = JuliaSet::new(Mandelbrot::new(42, 2), Complex64::new(0.3, 0.5));
+        assert_eq!(js.max_iterations(), 42);
+        assert_eq!(js.c(), Complex64::new(0.3, 0.5));
+    }
+}
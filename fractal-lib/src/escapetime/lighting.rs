@@ -0,0 +1,403 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Slope shading, which lights an escape time fractal as if it were a surface.
+//!
+//! The height of the surface comes either from the smoothed iteration count of each pixel, or
+//! from the potential that the distance estimate is derived from. Either way it rises towards the
+//! fractal's set. A `ReliefRaster` holds the palette color and the slope of the surface at every
+//! pixel of an image, and `Lighting` shades those colors with the Blinn-Phong model for a light
+//! shining from a given direction. Since the slopes are precomputed, the light can be moved
+//! without iterating any orbits again.
+
+use super::super::color::{self, ColorU8};
+use super::raster::{Colorizer, EscapeTimeRaster};
+use num::complex::Complex64;
+
+/// The default direction the light comes from, in degrees counterclockwise from the right of the
+/// image (so it shines from the top right).
+pub const DEFAULT_AZIMUTH: f64 = 45.0;
+
+/// The default angle of the light above the image, in degrees.
+pub const DEFAULT_ELEVATION: f64 = 45.0;
+
+/// The default factor that slopes are exaggerated by.
+pub const DEFAULT_HEIGHT_SCALE: f64 = 1.0;
+
+/// The default share of each color that is replaced by its lit version.
+pub const DEFAULT_STRENGTH: f64 = 0.75;
+
+/// A light shining on an image, and how the image reflects it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lighting {
+    /// The direction the light comes from, in degrees counterclockwise from the right of the
+    /// image.
+    pub azimuth: f64,
+    /// The angle of the light above the image, in degrees from 0 (grazing the image) to 90
+    /// (shining straight down onto it).
+    pub elevation: f64,
+    /// How much the slopes are exaggerated by.
+    pub height_scale: f64,
+    /// How much of each palette color is replaced by its lit version, from 0 to 1.
+    pub strength: f64,
+    /// The light that every pixel reflects, regardless of its slope.
+    pub ambient: f64,
+    /// How much light is scattered by a pixel that faces the light.
+    pub diffuse: f64,
+    /// How bright the highlights are.
+    pub specular: f64,
+    /// How tight the highlights are.
+    pub shininess: f64,
+}
+
+impl Lighting {
+    /// A light shining from `azimuth` degrees counterclockwise from the right of the image, and
+    /// `elevation` degrees above it.
+    pub fn new(azimuth: f64, elevation: f64) -> Lighting {
+        Lighting {
+            azimuth,
+            elevation: elevation.clamp(0.0, 90.0),
+            height_scale: DEFAULT_HEIGHT_SCALE,
+            strength: DEFAULT_STRENGTH,
+            ambient: 0.2,
+            diffuse: 0.8,
+            specular: 0.3,
+            shininess: 20.0,
+        }
+    }
+
+    pub fn with_height_scale(mut self, height_scale: f64) -> Lighting {
+        self.height_scale = height_scale;
+        self
+    }
+
+    pub fn with_strength(mut self, strength: f64) -> Lighting {
+        self.strength = strength.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Turns the light by `azimuth` degrees counterclockwise, and raises it by `elevation`
+    /// degrees (without going past straight down or below the image).
+    pub fn move_by(&mut self, azimuth: f64, elevation: f64) {
+        self.azimuth = (self.azimuth + azimuth).rem_euclid(360.0);
+        self.elevation = (self.elevation + elevation).clamp(0.0, 90.0);
+    }
+
+    /// The unit vector pointing towards the light, in pixel coordinates (where y points down,
+    /// and z points out of the image).
+    fn direction(&self) -> [f64; 3] {
+        let (azimuth, elevation) = (self.azimuth.to_radians(), self.elevation.to_radians());
+        [
+            elevation.cos() * azimuth.cos(),
+            -elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+        ]
+    }
+
+    /// The diffuse and specular intensities of the light reflected by a pixel where the surface
+    /// rises by `slope` per pixel (along x and y).
+    pub fn intensity(&self, slope: [f64; 2]) -> (f64, f64) {
+        let normal = normalize([
+            -self.height_scale * slope[0],
+            -self.height_scale * slope[1],
+            1.0,
+        ]);
+        let light = self.direction();
+        // The image is viewed from straight above
+        let halfway = normalize([light[0], light[1], light[2] + 1.0]);
+        let diffuse = dot(normal, light).max(0.0);
+        let specular = if diffuse > 0.0 {
+            dot(normal, halfway).max(0.0).powf(self.shininess)
+        } else {
+            0.0
+        };
+        (diffuse, specular)
+    }
+
+    /// Lights `color` at a pixel with the given slope, or leaves it alone if the pixel has no
+    /// slope (such as the points in the interior of the set).
+    pub fn shade(&self, color: ColorU8, slope: Option<[f64; 2]>) -> ColorU8 {
+        let slope = match slope {
+            Some(slope) => slope,
+            None => return color,
+        };
+        let (diffuse, specular) = self.intensity(slope);
+        let reflected = self.ambient + self.diffuse * diffuse;
+        let highlight = self.specular * specular;
+        let mut shaded = color;
+        for channel in shaded.0.iter_mut().take(3) {
+            let value = color::srgb_to_linear(*channel);
+            let lit = value * reflected + highlight;
+            *channel = color::linear_to_srgb(value + self.strength * (lit - value));
+        }
+        shaded
+    }
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: [f64; 3]) -> [f64; 3] {
+    let length = dot(v, v).sqrt();
+    [v[0] / length, v[1] / length, v[2] / length]
+}
+
+/// The palette color and the slope of the surface at each pixel of an image, stored row by row.
+///
+/// The slopes are in pixel coordinates, where y points down. Pixels that are not part of the
+/// surface (the points that did not escape) have no slope.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReliefRaster {
+    width: u32,
+    height: u32,
+    colors: Vec<ColorU8>,
+    slopes: Vec<Option<[f64; 2]>>,
+}
+
+impl ReliefRaster {
+    /// Colors `raster` with `colorizer`, and takes the height of the surface from the smoothed
+    /// iteration counts, so that the slopes are measured in iterations per pixel.
+    ///
+    /// The slopes are estimated from the neighboring pixels, so they are only as smooth as the
+    /// counts: use a large bailout radius to avoid seams between the iteration bands.
+    pub fn from_smooth_iterations<C>(raster: &EscapeTimeRaster, colorizer: &C) -> ReliefRaster
+    where
+        C: Colorizer + ?Sized,
+    {
+        let (width, height) = (raster.width(), raster.height());
+        let count = |x: i64, y: i64| {
+            if x < 0 || y < 0 || x >= i64::from(width) || y >= i64::from(height) {
+                return None;
+            }
+            let result = raster.get(x as u32, y as u32);
+            if result.escaped {
+                Some(result.smooth)
+            } else {
+                None
+            }
+        };
+        // The central difference where both neighbors escaped, or the difference to whichever
+        // one did
+        let difference = |before: Option<f64>, here: f64, after: Option<f64>| match (before, after)
+        {
+            (Some(before), Some(after)) => (after - before) / 2.0,
+            (Some(before), None) => here - before,
+            (None, Some(after)) => after - here,
+            (None, None) => 0.0,
+        };
+        let mut slopes = Vec::with_capacity(raster.results().len());
+        for y in 0..i64::from(height) {
+            for x in 0..i64::from(width) {
+                slopes.push(count(x, y).map(|here| {
+                    [
+                        difference(count(x - 1, y), here, count(x + 1, y)),
+                        difference(count(x, y - 1), here, count(x, y + 1)),
+                    ]
+                }));
+            }
+        }
+        ReliefRaster::with_slopes(raster, colorizer, slopes)
+    }
+
+    /// Colors `raster` with `colorizer`, and takes the slope of the surface from the derivative
+    /// of each pixel's final value of `z` with respect to its point (such as
+    /// `DistanceEstimate::derivative`), given row by row in `derivatives`.
+    ///
+    /// The potential that the distance estimate comes from falls fastest in the direction of
+    /// `z / dz`, so the surface rises in the opposite direction. Only that direction is used, so
+    /// every slope is 1 unit per pixel, and the relief shows the shape of the set evenly at any
+    /// zoom.
+    pub fn from_derivatives<C>(
+        raster: &EscapeTimeRaster,
+        derivatives: &[Complex64],
+        colorizer: &C,
+    ) -> ReliefRaster
+    where
+        C: Colorizer + ?Sized,
+    {
+        assert_eq!(
+            derivatives.len(),
+            raster.results().len(),
+            "every pixel needs a derivative"
+        );
+        let slopes = raster
+            .results()
+            .iter()
+            .zip(derivatives)
+            .map(|(result, &derivative)| {
+                if !result.escaped {
+                    return None;
+                }
+                let direction = result.z / derivative;
+                let norm = direction.norm();
+                if norm.is_finite() && norm > 0.0 {
+                    // Pixel rows go down while the imaginary axis goes up
+                    Some([-direction.re / norm, direction.im / norm])
+                } else {
+                    Some([0.0, 0.0])
+                }
+            })
+            .collect();
+        ReliefRaster::with_slopes(raster, colorizer, slopes)
+    }
+
+    fn with_slopes<C>(
+        raster: &EscapeTimeRaster,
+        colorizer: &C,
+        slopes: Vec<Option<[f64; 2]>>,
+    ) -> ReliefRaster
+    where
+        C: Colorizer + ?Sized,
+    {
+        ReliefRaster {
+            width: raster.width(),
+            height: raster.height(),
+            colors: raster
+                .results()
+                .iter()
+                .map(|result| colorizer.color(result))
+                .collect(),
+            slopes,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The palette color of the pixel at `(x, y)`, before it is lit.
+    pub fn color(&self, x: u32, y: u32) -> ColorU8 {
+        self.colors[y as usize * self.width as usize + x as usize]
+    }
+
+    /// The slope of the surface at the pixel at `(x, y)`, if it is part of the surface.
+    pub fn slope(&self, x: u32, y: u32) -> Option<[f64; 2]> {
+        self.slopes[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Lights every pixel with `lighting`, returning the RGBA components of each pixel row by
+    /// row (like `Colorizer::colorize()`).
+    pub fn shade(&self, lighting: &Lighting) -> Vec<u8> {
+        self.colors
+            .iter()
+            .zip(&self.slopes)
+            .flat_map(|(&color, &slope)| lighting.shade(color, slope).0.to_vec())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::geometry::{Point, ViewAreaTransformer};
+    use super::super::distance::DistanceEstimator;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::super::raster::PaletteColorizer;
+    use super::super::{EscapeCriterion, EscapeResult, EscapeTime};
+    use super::*;
+
+    #[test]
+    fn test_shade() {
+        let lighting = Lighting::new(DEFAULT_AZIMUTH, 90.0).with_strength(1.0);
+        let gray = ColorU8([128, 128, 128, 255]);
+        // A flat pixel lit from straight above gets all of the light and the brightest highlight
+        assert_eq!(lighting.intensity([0.0, 0.0]), (1.0, 1.0));
+        let shaded = lighting.shade(gray, Some([0.0, 0.0]));
+        assert!(shaded.0[0] > 128);
+        assert_eq!(shaded.0[3], 255);
+        // Pixels that are not part of the surface are left alone, and so is everything without
+        // any strength
+        assert_eq!(lighting.shade(gray, None), gray);
+        assert_eq!(
+            lighting.with_strength(0.0).shade(gray, Some([1.0, -1.0])),
+            gray
+        );
+    }
+
+    #[test]
+    fn test_slopes_facing_the_light() {
+        // Light from the right, just above the horizon
+        let lighting = Lighting::new(0.0, 10.0);
+        let (facing, _) = lighting.intensity([-1.0, 0.0]);
+        let (flat, _) = lighting.intensity([0.0, 0.0]);
+        let (away, _) = lighting.intensity([1.0, 0.0]);
+        assert!(facing > flat && flat > away, "{} {} {}", facing, flat, away);
+        assert_eq!(away, 0.0);
+        // Turning the light to the top makes the slopes that rise to the right look the same
+        let lighting = Lighting::new(90.0, 10.0);
+        assert_approx_eq!(
+            lighting.intensity([-1.0, 0.0]).0,
+            lighting.intensity([1.0, 0.0]).0,
+            1e-12
+        );
+        // Pixel rows go down, so a surface that rises downwards faces the top
+        assert!(lighting.intensity([0.0, 1.0]).0 > lighting.intensity([0.0, -1.0]).0);
+    }
+
+    #[test]
+    fn test_move_by() {
+        let mut lighting = Lighting::new(350.0, 80.0);
+        lighting.move_by(20.0, 15.0);
+        assert_approx_eq!(lighting.azimuth, 10.0, 1e-9);
+        assert_eq!(lighting.elevation, 90.0);
+        lighting.move_by(-30.0, -100.0);
+        assert_approx_eq!(lighting.azimuth, 340.0, 1e-9);
+        assert_eq!(lighting.elevation, 0.0);
+    }
+
+    #[test]
+    fn test_relief_rises_towards_the_set() {
+        // A strip along the real axis to the left of the mandelbrot set, whose tip at -2 is to the
+        // right of the strip
+        let mandelbrot = Mandelbrot::with_bailout(100, 2, EscapeCriterion::Norm(1e10));
+        let vat = ViewAreaTransformer::new(
+            [40.0, 10.0],
+            Point { x: -2.45, y: 0.05 },
+            Point { x: -2.05, y: -0.05 },
+        );
+        let raster = EscapeTimeRaster::evaluate(&mandelbrot, &vat, 40, 10);
+        let colorizer = PaletteColorizer::new(mandelbrot.max_iterations());
+        let derivatives = (0..10)
+            .flat_map(|y| (0..40).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let point = vat.map_pixel_to_point([f64::from(x), f64::from(y)]).into();
+                mandelbrot.estimate_distance(point).derivative
+            })
+            .collect::<Vec<Complex64>>();
+        let by_iterations = ReliefRaster::from_smooth_iterations(&raster, &colorizer);
+        let by_distance = ReliefRaster::from_derivatives(&raster, &derivatives, &colorizer);
+        for relief in &[&by_iterations, &by_distance] {
+            assert_eq!(relief.color(3, 4), colorizer.color(raster.get(3, 4)));
+            for x in 1..39 {
+                let slope = relief.slope(x, 5).unwrap();
+                assert!(slope[0] > 0.0, "{:?} at {}", slope, x);
+            }
+            // The pixels above the axis rise downwards, and the ones below rise upwards
+            assert!(relief.slope(20, 1).unwrap()[1] > 0.0);
+            assert!(relief.slope(20, 8).unwrap()[1] < 0.0);
+            assert_eq!(relief.shade(&Lighting::new(0.0, 45.0)).len(), 40 * 10 * 4);
+        }
+
+        // The interior has no slope
+        let mut interior = raster.clone();
+        interior.set(7, 3, EscapeResult::periodic(Complex64::new(0.0, 0.0), 1));
+        let relief = ReliefRaster::from_smooth_iterations(&interior, &colorizer);
+        assert_eq!(relief.slope(7, 3), None);
+        assert!(relief.slope(8, 3).is_some());
+    }
+}
@@ -20,12 +20,18 @@
 //! converges. That is, f(0), f(f(0)), f(f(f(0))), ... converges. Alternately, if it diverges
 //! (trends towards a value of ∞ ), then a point `c` is not in the Mandelbrot set.
 
-use super::super::geometry;
+use super::super::geometry::{self, Exponent};
+use super::batch::{self, ComplexLanes};
+use super::distance::DistanceEstimator;
+use super::generic::{
+    generic_power, multibrot_initial_z_generic, GenericEscapeTime, Real, RealComplex,
+};
 use super::*;
 
 pub struct Mandelbrot {
     max_iters: u64,
-    power: u64,
+    power: Exponent,
+    bailout: EscapeCriterion,
 }
 
 impl Mandelbrot {
@@ -39,9 +45,26 @@ impl Mandelbrot {
     /// fractal has an exponent of 2, but this allows for the exploration of fractals with an
     /// exponent of 3, 4, etc.
     pub fn new(max_iterations: u64, power: u64) -> Mandelbrot {
+        Mandelbrot::with_bailout(max_iterations, power, EscapeCriterion::default())
+    }
+
+    /// Like `Mandelbrot::new()`, but `bailout` specifies the test (and radius) that decides when a
+    /// value has escaped.
+    pub fn with_bailout(max_iterations: u64, power: u64, bailout: EscapeCriterion) -> Mandelbrot {
+        Mandelbrot::with_exponent(max_iterations, Exponent::Natural(power), bailout)
+    }
+
+    /// Like `Mandelbrot::with_bailout()`, but `exponent` may also be negative, fractional, or
+    /// complex (see `escapetime::multibrot_initial_values()`).
+    pub fn with_exponent(
+        max_iterations: u64,
+        exponent: Exponent,
+        bailout: EscapeCriterion,
+    ) -> Mandelbrot {
         Mandelbrot {
             max_iters: max_iterations,
-            power,
+            power: exponent,
+            bailout,
         }
     }
 }
@@ -56,7 +79,85 @@ impl EscapeTime for Mandelbrot {
     }
 
     fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
-        geometry::cpow(z, self.power) + c
+        geometry::cpow_general(z, self.power) + c
+    }
+
+    #[inline(always)]
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        match self.power.as_natural() {
+            Some(n) => {
+                z.powu(n);
+                z.add(c);
+            }
+            None => batch::iterate_each_lane(self, c, z, previous),
+        }
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn allows_subdivision(&self) -> bool {
+        self.power.as_natural().is_some() && matches!(self.bailout, EscapeCriterion::Norm(_))
+    }
+
+    fn degree(&self) -> f64 {
+        self.power.real_part()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_values(self.power, point)
+    }
+
+    /// For the traditional mandelbrot set (power 2), recognizes points inside the main cardioid
+    /// (period 1) and the period 2 bulb to its left, which make up most of the set's area.
+    fn known_interior(&self, c: Complex64, z: Complex64) -> Option<EscapeResult> {
+        if self.power.as_natural() != Some(2) || z != Complex64::new(0.0, 0.0) {
+            return None;
+        }
+        let y2 = c.im * c.im;
+        let q = (c.re - 0.25) * (c.re - 0.25) + y2;
+        if q * (q + (c.re - 0.25)) <= y2 / 4.0 {
+            // attracted to the fixed point z = z^2 + c
+            let fixed =
+                (Complex64::new(1.0, 0.0) - (Complex64::new(1.0, 0.0) - 4.0 * c).sqrt()) / 2.0;
+            return Some(EscapeResult::periodic(fixed, 1));
+        }
+        if (c.re + 1.0) * (c.re + 1.0) + y2 <= 1.0 / 16.0 {
+            // attracted to the 2-cycle whose points are the roots of z^2 + z + c + 1
+            let cycle = (Complex64::new(-1.0, 0.0) + (-3.0 - 4.0 * c).sqrt()) / 2.0;
+            return Some(EscapeResult::periodic(cycle, 2));
+        }
+        None
+    }
+}
+
+impl DistanceEstimator for Mandelbrot {
+    fn initial_derivatives(&self, _point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_derivatives(self.power)
+    }
+
+    fn iterate_derivative(
+        &self,
+        _c: Complex64,
+        z: Complex64,
+        dc: Complex64,
+        dz: Complex64,
+    ) -> Complex64 {
+        geometry::cpow_general_derivative(z, self.power) * dz + dc
+    }
+}
+
+impl<T> GenericEscapeTime<T> for Mandelbrot
+where
+    T: Real,
+{
+    fn iterate_generic(&self, c: &RealComplex<T>, z: &RealComplex<T>) -> RealComplex<T> {
+        generic_power(z, self.power) + c.clone()
+    }
+
+    fn initial_z_generic(&self, point: &RealComplex<T>) -> RealComplex<T> {
+        multibrot_initial_z_generic(self.power, point)
     }
 }
 
@@ -72,4 +173,124 @@ mod test {
         assert!(!mb.test_point(Complex64::new(1.0, 0.0)).0);
         assert!(!mb.test_point(Complex64::new(-0.8, 0.35)).0);
     }
+
+    #[test]
+    fn test_known_interior() {
+        let mb = Mandelbrot::new(100, 2);
+        let zero = Complex64::new(0.0, 0.0);
+        assert_eq!(mb.known_interior(zero, zero).unwrap().period, Some(1));
+        assert_eq!(
+            mb.known_interior(Complex64::new(0.2, 0.5), zero)
+                .unwrap()
+                .period,
+            Some(1)
+        );
+        assert_eq!(
+            mb.known_interior(Complex64::new(-1.1, 0.1), zero)
+                .unwrap()
+                .period,
+            Some(2)
+        );
+        // period 3 bulb, outside, and other powers are not recognized
+        assert!(mb
+            .known_interior(Complex64::new(-0.12, 0.75), zero)
+            .is_none());
+        assert!(mb.known_interior(Complex64::new(0.3, 0.0), zero).is_none());
+        assert!(Mandelbrot::new(100, 3).known_interior(zero, zero).is_none());
+    }
+
+    #[test]
+    fn test_known_interior_agrees_with_iteration() {
+        let mb = Mandelbrot::new(500, 2);
+        let zero = Complex64::new(0.0, 0.0);
+        for step in 0..200 {
+            let c = Complex64::new(-1.5 + f64::from(step) * 0.0105, 0.13);
+            if let Some(known) = mb.known_interior(c, zero) {
+                let (mut z, mut previous) = (zero, zero);
+                for _ in 0..10_000 {
+                    previous = z;
+                    z = mb.iterate(c, z);
+                }
+                // the computed cycle point should be (one of) the points the orbit settled into
+                assert_complex_approx_in!(&[z, previous], known.z, 0.000_001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_exponent() {
+        // A real exponent that happens to be a natural number gives the same fractal
+        let natural = Mandelbrot::new(100, 2);
+        let real = Mandelbrot::with_exponent(100, Exponent::Real(2.0), EscapeCriterion::default());
+        for &point in &[
+            Complex64::new(-0.8, 0.35),
+            Complex64::new(0.3, 0.0),
+            Complex64::new(-1.0, 0.0),
+        ] {
+            assert_eq!(natural.test_point(point), real.test_point(point));
+        }
+        let zero = Complex64::new(0.0, 0.0);
+        assert!(real.known_interior(zero, zero).is_some());
+
+        let fractional =
+            Mandelbrot::with_exponent(100, Exponent::Real(2.5), EscapeCriterion::default());
+        assert_eq!(fractional.degree(), 2.5);
+        assert!(fractional.test_point(Complex64::new(0.0, 0.0)).0);
+        assert!(!fractional.test_point(Complex64::new(1.0, 0.0)).0);
+    }
+
+    #[test]
+    fn test_negative_exponent() {
+        let mb = Mandelbrot::with_exponent(100, Exponent::Integer(-2), EscapeCriterion::default());
+        // Iteration starts from c, since 0^-2 is infinite
+        assert_eq!(
+            mb.initial_values(Complex64::new(1.0, 0.0)),
+            (Complex64::new(1.0, 0.0), Complex64::new(1.0, 0.0))
+        );
+        // c = 1 settles down near 1.466, but c = 0.5 jumps to 4.5
+        assert!(mb.test_point(Complex64::new(1.0, 0.0)).0);
+        assert_eq!(mb.test_point(Complex64::new(0.5, 0.0)), (false, 0));
+        assert_eq!(mb.test_point(Complex64::new(0.0, 0.0)), (false, 0));
+    }
+
+    #[test]
+    fn test_complex_exponent() {
+        let exponent = Exponent::Complex(Complex64::new(2.0, 0.1));
+        let mb = Mandelbrot::with_exponent(100, exponent, EscapeCriterion::default());
+        assert!(mb.test_point(Complex64::new(-0.1, 0.1)).0);
+        assert!(!mb.test_point(Complex64::new(1.0, 1.0)).0);
+    }
+
+    #[test]
+    fn test_iterate_derivative() {
+        // Compare the derivative after a few iterations against a finite difference
+        let orbit = |mb: &Mandelbrot, point: Complex64| {
+            let (c, mut z) = mb.initial_values(point);
+            let (dc, mut dz) = mb.initial_derivatives(point);
+            for _ in 0..4 {
+                dz = mb.iterate_derivative(c, z, dc, dz);
+                z = mb.iterate(c, z);
+            }
+            (z, dz)
+        };
+        let point = Complex64::new(-0.3, 0.4);
+        let h = Complex64::new(1e-7, 0.0);
+        for &exponent in &[
+            Exponent::Natural(2),
+            Exponent::Natural(3),
+            Exponent::Integer(-2),
+            Exponent::Real(2.5),
+        ] {
+            let mb = Mandelbrot::with_exponent(100, exponent, EscapeCriterion::default());
+            let numeric = (orbit(&mb, point + h).0 - orbit(&mb, point - h).0) / (2.0 * h);
+            let (_, derivative) = orbit(&mb, point);
+            assert!(
+                (derivative - numeric).norm() < 1e-5 * derivative.norm().max(1.0),
+                "{} vs {} for {}",
+                derivative,
+                numeric,
+                exponent
+            );
+        }
+    }
 }
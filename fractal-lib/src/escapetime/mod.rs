@@ -12,11 +12,217 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod averaging;
+pub mod batch;
+pub mod buddhabrot;
 pub mod burningship;
+pub mod distance;
+pub mod formula;
+pub mod generic;
+pub mod hypercomplex;
+pub mod julia;
+pub mod lighting;
 pub mod mandelbrot;
+pub mod orbit;
+pub mod orbittrap;
+pub mod perturbation;
+pub mod phoenix;
+pub mod progressive;
+pub mod raster;
+pub mod supersample;
+pub mod viewcache;
 
+use self::batch::ComplexLanes;
+use self::orbit::{Orbit, OrbitObserver};
+use super::geometry::Exponent;
 pub use num::complex::Complex64;
 
+/// The default bailout radius that a value being iterated has to reach before it is considered to
+/// have escaped.
+pub const DEFAULT_BAILOUT: f64 = 3.0;
+
+/// The default tolerance used to decide that an orbit has settled into a cycle: if a value comes
+/// within this distance of a value from earlier in the orbit, the orbit is treated as periodic.
+pub const DEFAULT_PERIODICITY_TOLERANCE: f64 = 1e-13;
+
+/// The test that decides when a value being iterated has escaped, along with its bailout radius.
+///
+/// The traditional test checks whether `|z|` has grown beyond the bailout radius, but the other
+/// criteria change the shapes of the bands between escape times, and they are also cheaper to
+/// compute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EscapeCriterion {
+    /// Escaped once `|z| >= R`.
+    Norm(f64),
+    /// Escaped once `|Re(z)| > R`.
+    Real(f64),
+    /// Escaped once `|Im(z)| > R`.
+    Imaginary(f64),
+    /// Escaped once `|Re(z)| + |Im(z)| >= R`.
+    Manhattan(f64),
+}
+
+impl EscapeCriterion {
+    /// The names accepted by `EscapeCriterion::from_name()`.
+    pub const NAMES: [&'static str; 4] = ["norm", "real", "imaginary", "manhattan"];
+
+    /// Constructs a criterion from its name (one of `EscapeCriterion::NAMES`) and a bailout
+    /// radius.
+    pub fn from_name(name: &str, radius: f64) -> Result<EscapeCriterion, String> {
+        if radius <= 0.0 || !radius.is_finite() {
+            return Err(format!("Bailout radius must be positive: {}", radius));
+        }
+        match name {
+            "norm" => Ok(EscapeCriterion::Norm(radius)),
+            "real" => Ok(EscapeCriterion::Real(radius)),
+            "imaginary" => Ok(EscapeCriterion::Imaginary(radius)),
+            "manhattan" => Ok(EscapeCriterion::Manhattan(radius)),
+            _ => Err(format!("Unknown escape criterion: {}", name)),
+        }
+    }
+
+    /// The bailout radius.
+    pub fn radius(self) -> f64 {
+        match self {
+            EscapeCriterion::Norm(r)
+            | EscapeCriterion::Real(r)
+            | EscapeCriterion::Imaginary(r)
+            | EscapeCriterion::Manhattan(r) => r,
+        }
+    }
+
+    /// Whether `z` has escaped.
+    pub fn has_escaped(self, z: Complex64) -> bool {
+        match self {
+            EscapeCriterion::Norm(r) => z.norm_sqr() >= r * r,
+            EscapeCriterion::Real(r) => z.re.abs() > r,
+            EscapeCriterion::Imaginary(r) => z.im.abs() > r,
+            EscapeCriterion::Manhattan(r) => z.re.abs() + z.im.abs() >= r,
+        }
+    }
+}
+
+impl Default for EscapeCriterion {
+    /// `|z| >= DEFAULT_BAILOUT`
+    fn default() -> EscapeCriterion {
+        EscapeCriterion::Norm(DEFAULT_BAILOUT)
+    }
+}
+
+/// The result of testing a single complex number with `EscapeTime::evaluate()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EscapeResult {
+    /// Whether the value being iterated escaped (and is not a member of the fractal's set).
+    pub escaped: bool,
+    /// The iteration during which the value escaped, or 0 if it did not escape.
+    pub iterations: u64,
+    /// The final value of `z`: the first value beyond the escape radius if it escaped, or the
+    /// value after `max_iterations` if it did not.
+    pub z: Complex64,
+    /// The norm (modulus) of `z`.
+    pub norm: f64,
+    /// A continuous version of `iterations` that uses how far `z` overshot the escape radius to
+    /// interpolate between whole iterations, which avoids color banding when it is used to pick
+    /// colors. It falls within `[iterations, iterations + 1]` for points that escaped (exactly
+    /// `iterations` when the overshoot can not be measured), and is 0 for points that did not.
+    pub smooth: f64,
+    /// For values that did not escape, the length of the cycle that the orbit settled into, if one
+    /// was detected (either by periodicity checking or because the fractal knows analytically that
+    /// the point is in its interior).
+    pub period: Option<u64>,
+}
+
+impl EscapeResult {
+    /// Constructs the result for a value that escaped during iteration `iterations` with the
+    /// final value of `z`, for a fractal whose iterated function has the given `degree` and that
+    /// uses a bailout radius of `radius`.
+    ///
+    /// Computes the smoothed iteration count using the normalized iteration count algorithm:
+    ///
+    /// ```text
+    /// smooth = iterations + 1 - log_degree(ln(|z|) / ln(radius))
+    /// ```
+    ///
+    /// Each iteration raises `|z|` to roughly the `degree`th power, so the double logarithm
+    /// measures how far into its last iteration `z` was when it escaped. If the degree is 1 or
+    /// less, there is no such growth to measure, and it falls back to the whole iteration count.
+    /// Larger radii result in more accurate smoothing.
+    pub fn escaped(iterations: u64, z: Complex64, degree: f64, radius: f64) -> EscapeResult {
+        let norm = z.norm();
+        let smooth = if degree > 1.0 && radius > 1.0 && norm > radius {
+            let overshoot = (norm.ln() / radius.ln()).ln() / degree.ln();
+            (iterations as f64 + 1.0 - overshoot)
+                .max(iterations as f64)
+                .min(iterations as f64 + 1.0)
+        } else {
+            iterations as f64
+        };
+        EscapeResult {
+            escaped: true,
+            iterations,
+            z,
+            norm,
+            smooth,
+            period: None,
+        }
+    }
+
+    /// Constructs the result for a value that did not escape, where `z` is the final value.
+    pub fn attracted(z: Complex64) -> EscapeResult {
+        EscapeResult {
+            escaped: false,
+            iterations: 0,
+            z,
+            norm: z.norm(),
+            smooth: 0.0,
+            period: None,
+        }
+    }
+
+    /// Constructs the result for a value that did not escape because its orbit settled into a
+    /// cycle of length `period`, where `z` is the final value.
+    pub fn periodic(z: Complex64, period: u64) -> EscapeResult {
+        EscapeResult {
+            period: Some(period),
+            ..EscapeResult::attracted(z)
+        }
+    }
+}
+
+/// Whether `0^exponent` is infinite, which happens when the real part of the exponent is not
+/// positive (except for `z^0`, which is always 1).
+fn has_pole_at_zero(exponent: Exponent) -> bool {
+    exponent.as_natural().is_none() && exponent.real_part() <= 0.0
+}
+
+/// Computes `EscapeTime::initial_values()` for a Mandelbrot-style fractal whose iterated function
+/// raises (some variation of) `z` to `exponent`.
+///
+/// The fractals that take an `Exponent` (through their `with_exponent()` constructors) all follow
+/// the same rules. Exponents that are not natural numbers (negative, fractional, or complex ones)
+/// raise `z` with `geometry::cpow_general()`, using its branch cut. Usually iteration starts from
+/// 0 with `c` set to the point being tested. When the real part of the exponent is not positive
+/// (other than for `z^0`), `0^exponent` is infinite, so iteration starts from `c` instead, which
+/// is where 0 would be mapped to if the power were positive.
+pub fn multibrot_initial_values(exponent: Exponent, point: Complex64) -> (Complex64, Complex64) {
+    if has_pole_at_zero(exponent) {
+        (point, point)
+    } else {
+        (point, Complex64::new(0.0, 0.0))
+    }
+}
+
+/// Computes `distance::DistanceEstimator::initial_derivatives()` for a Mandelbrot-style fractal
+/// whose iterated function raises (some variation of) `z` to `exponent`, to match
+/// `multibrot_initial_values()`.
+pub fn multibrot_initial_derivatives(exponent: Exponent) -> (Complex64, Complex64) {
+    if has_pole_at_zero(exponent) {
+        (Complex64::new(1.0, 0.0), Complex64::new(1.0, 0.0))
+    } else {
+        (Complex64::new(1.0, 0.0), Complex64::new(0.0, 0.0))
+    }
+}
+
 pub trait EscapeTime {
     /// The maximum number of iterations to perform before accepting that the value being
     /// iterated will not diverge.
@@ -30,21 +236,441 @@ pub trait EscapeTime {
     /// A single iteration of the function that defines this particular fractal.
     fn iterate(&self, c: Complex64, z: Complex64) -> Complex64;
 
-    /// Tests whether a given complex number is in the fractal's set or if it diverges.
+    /// Like EscapeTime::iterate(), but also given the value of the orbit before `z` (0 for the
+    /// first iteration), for fractals whose next value depends on more than just `z`, such as
+    /// `phoenix::Phoenix`.
+    ///
+    /// The default implementation ignores `previous` and uses EscapeTime::iterate(). Code that
+    /// follows an orbit should call this instead of EscapeTime::iterate().
+    fn iterate_with_previous(&self, c: Complex64, z: Complex64, _previous: Complex64) -> Complex64 {
+        self.iterate(c, z)
+    }
+
+    /// Like EscapeTime::iterate_with_previous(), for `batch::LANES` values at once: replaces each
+    /// lane of `z` with the next value of its orbit.
     ///
-    /// The default implementation implements the mandelbrot test, which uses the complex number
-    /// being tested as the constant `c`, and starts iteration with an input of 0+0i
-    /// (Julia/Fatou sets would have to reimplement this).
+    /// The default implementation calls EscapeTime::iterate_with_previous() for one lane at a
+    /// time. Fractals can override it with plain arithmetic on the lanes' arrays, which the
+    /// compiler can vectorize, but the results must be exactly the same.
+    fn iterate_lanes(&self, c: &ComplexLanes, z: &mut ComplexLanes, previous: &ComplexLanes) {
+        batch::iterate_each_lane(self, c, z, previous)
+    }
+
+    /// The test used to decide whether a value has escaped, including its bailout radius.
+    fn bailout(&self) -> EscapeCriterion {
+        EscapeCriterion::default()
+    }
+
+    /// The degree of the iterated function (eg, 2 for `z^2 + c`), which describes how quickly
+    /// values grow once they get large. It is used to compute smooth iteration counts.
+    fn degree(&self) -> f64 {
+        2.0
+    }
+
+    /// Maps the complex number being tested to the constant `c` and the initial value of `z` that
+    /// are used to start iterating.
     ///
-    /// The default implementation uses EscapeTime::max_iterations() and EscapeTime::iterate().
+    /// The default implementation implements the mandelbrot convention, which uses the complex
+    /// number being tested as the constant `c`, and starts iteration with an input of 0+0i. See
+    /// `julia::JuliaSet` for the Julia/Fatou set convention.
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        (point, Complex64::new(0.0, 0.0))
+    }
+
+    /// Optionally lets a fractal recognize, without iterating, that the starting values `c` and
+    /// `z` (see EscapeTime::initial_values()) belong to its interior. If they do, it returns the
+    /// result for the point, including the period of the cycle its orbit is attracted to.
+    ///
+    /// The default implementation does not know about any such regions.
+    fn known_interior(&self, _c: Complex64, _z: Complex64) -> Option<EscapeResult> {
+        None
+    }
+
+    /// The tolerance used for periodicity checking, or None to disable periodicity checking.
+    ///
+    /// Points in the interior of the set never escape, so they would normally run for all of
+    /// `max_iterations`. Most of their orbits settle into a cycle well before then though, and
+    /// periodicity checking notices when the orbit returns to within this distance of an earlier
+    /// value so that it can stop early.
+    fn periodicity_tolerance(&self) -> Option<f64> {
+        Some(DEFAULT_PERIODICITY_TOLERANCE)
+    }
+
+    /// Whether the points that do not escape (within any number of iterations) form regions
+    /// without holes, so that a rectangle whose border lies entirely within one such region can
+    /// be filled in without evaluating its inside. Subdivision (see
+    /// `raster::EscapeTimeRaster::evaluate_subdivided()`) only skips pixels for fractals that
+    /// allow it.
+    ///
+    /// This holds for polynomials in `z` (such as the Mandelbrot set and its Julia sets) with a
+    /// round bailout region, but not for most fractals that fold the plane with absolute values,
+    /// so the default implementation does not allow it.
+    fn allows_subdivision(&self) -> bool {
+        false
+    }
+
+    /// Tests whether a given complex number is in the fractal's set or if it diverges, and
+    /// returns the details about how it escaped.
+    ///
+    /// The default implementation uses EscapeTime::initial_values() to decide where to start, and
+    /// then EscapeTime::max_iterations(), EscapeTime::iterate_with_previous(), and
+    /// EscapeTime::bailout(). It checks EscapeTime::known_interior() before iterating, and it uses
+    /// Brent's cycle detection algorithm with EscapeTime::periodicity_tolerance() to classify
+    /// orbits that have settled into a cycle as interior points early: it remembers one value of
+    /// the orbit, compares it to every following value, and replaces the remembered value each
+    /// time the number of values compared against it reaches the length of the window, which
+    /// starts at `batch::CHUNK_STEPS` and doubles every time. This finds
+    /// cycles of any length, and the number of steps between the remembered value and the
+    /// matching value is the cycle's period. Since the next value may depend on the previous one
+    /// too, both have to match.
+    fn evaluate(&self, point: Complex64) -> EscapeResult {
+        let (c, z) = self.initial_values(point);
+        if let Some(result) = self.known_interior(c, z) {
+            return result;
+        }
+        iterate_orbit(self, c, z, &mut ())
+    }
+
+    /// Like EscapeTime::evaluate(), but passes every value of `z` that iteration produces
+    /// (everything after the initial value) to `observer`, so that it can accumulate information
+    /// about the orbit (see `orbittrap::OrbitTrap`).
+    ///
+    /// Unlike EscapeTime::evaluate(), this does not stop early for points that the fractal knows
+    /// are in its interior, since their orbits are just as interesting to observers.
+    fn evaluate_observed(
+        &self,
+        point: Complex64,
+        observer: &mut dyn OrbitObserver,
+    ) -> EscapeResult {
+        let (c, z) = self.initial_values(point);
+        iterate_orbit(self, c, z, observer)
+    }
+
+    /// Like EscapeTime::evaluate(), for each of `points`, storing the result for each point in
+    /// the same position in `results`. Panics if they have different lengths.
+    ///
+    /// The default implementation iterates many points side by side with
+    /// EscapeTime::iterate_lanes() (see `batch::evaluate_batch()`). For fractals that specialize
+    /// it, this is faster than evaluating the points one at a time, but only by about 1 to 2
+    /// times when most points escape within a few iterations, and by up to about 3.5 times when
+    /// they take many (see the `batch` module). Fractals that override EscapeTime::evaluate()
+    /// need to override this as well.
+    fn evaluate_batch(&self, points: &[Complex64], results: &mut [EscapeResult]) {
+        batch::evaluate_batch(self, points, results)
+    }
+
+    /// Follows the orbit of `point`, returning every value of `z` along with the result of
+    /// EscapeTime::evaluate_observed(), for inspecting how the fractal treats a single point. See
+    /// `orbit::trace()`.
+    fn orbit(&self, point: Complex64) -> Orbit {
+        orbit::trace(self, point)
+    }
+
+    /// Tests whether a given complex number is in the fractal's set or if it diverges.
+    ///
+    /// Returns whether it is in the set, and if it is not, the iteration during which it escaped.
+    /// Uses EscapeTime::evaluate().
     fn test_point(&self, point: Complex64) -> (bool, u64) {
-        let mut zp = Complex64::new(0.0, 0.0);
-        for i in 0..self.max_iterations() {
-            zp = self.iterate(point, zp);
-            if zp.norm() >= 3.0 {
-                return (false, i);
+        let result = self.evaluate(point);
+        if result.escaped {
+            (false, result.iterations)
+        } else {
+            (true, 0)
+        }
+    }
+}
+
+/// The settings of a fractal that following its orbits depends on, looked up once for many
+/// orbits.
+#[derive(Debug, Clone, Copy)]
+struct OrbitSettings {
+    max_iterations: u64,
+    bailout: EscapeCriterion,
+    degree: f64,
+    /// The square of EscapeTime::periodicity_tolerance().
+    tolerance_sqr: Option<f64>,
+}
+
+impl OrbitSettings {
+    fn new<E: EscapeTime + ?Sized>(etsystem: &E) -> OrbitSettings {
+        OrbitSettings {
+            max_iterations: etsystem.max_iterations(),
+            bailout: etsystem.bailout(),
+            degree: etsystem.degree(),
+            tolerance_sqr: etsystem.periodicity_tolerance().map(|t| t * t),
+        }
+    }
+}
+
+/// An orbit that is partway through being followed by EscapeTime::evaluate() or
+/// `batch::evaluate_batch()`, including the state of its cycle detection. Both of them take their
+/// steps with OrbitState::step(), so that they reach exactly the same results.
+#[derive(Debug, Clone, Copy)]
+struct OrbitState {
+    c: Complex64,
+    z: Complex64,
+    previous: Complex64,
+    /// How many iterations have been taken so far.
+    iteration: u64,
+    /// The values of `z` and `previous` that later values are compared to.
+    saved: (Complex64, Complex64),
+    /// How many steps are compared to `saved` before it is replaced.
+    window: u64,
+    /// How many steps have been compared to `saved` so far.
+    steps: u64,
+}
+
+impl OrbitState {
+    /// Starts following the orbit from `c` and `z`.
+    ///
+    /// The first window of the cycle detection is `batch::CHUNK_STEPS` long rather than 1, so that
+    /// every window ends at the end of one of the chunks that `batch::evaluate_batch()` iterates.
+    fn new(c: Complex64, z: Complex64) -> OrbitState {
+        let previous = Complex64::new(0.0, 0.0);
+        OrbitState {
+            c,
+            z,
+            previous,
+            iteration: 0,
+            saved: (z, previous),
+            window: batch::CHUNK_STEPS,
+            steps: 0,
+        }
+    }
+
+    /// Takes one step of the orbit, passing the new value of `z` to `observer`. Returns the
+    /// result once the orbit escapes, settles into a cycle, or reaches the maximum number of
+    /// iterations (which must not be 0).
+    #[inline(always)]
+    fn step<E, O>(
+        &mut self,
+        etsystem: &E,
+        settings: &OrbitSettings,
+        observer: &mut O,
+    ) -> Option<EscapeResult>
+    where
+        E: EscapeTime + ?Sized,
+        O: OrbitObserver + ?Sized,
+    {
+        let next = etsystem.iterate_with_previous(self.c, self.z, self.previous);
+        observer.observe(next);
+        self.advance(next, settings)
+    }
+
+    /// Moves the orbit on to `next`, which must be the next value of `z`, and checks whether it
+    /// has finished like OrbitState::step().
+    #[inline(always)]
+    fn advance(&mut self, next: Complex64, settings: &OrbitSettings) -> Option<EscapeResult> {
+        self.previous = self.z;
+        self.z = next;
+        let iteration = self.iteration;
+        self.iteration += 1;
+        if settings.bailout.has_escaped(next) {
+            let radius = settings.bailout.radius();
+            return Some(EscapeResult::escaped(
+                iteration,
+                next,
+                settings.degree,
+                radius,
+            ));
+        }
+        if let Some(tolerance_sqr) = settings.tolerance_sqr {
+            self.steps += 1;
+            if (next - self.saved.0).norm_sqr() < tolerance_sqr
+                && (self.previous - self.saved.1).norm_sqr() < tolerance_sqr
+            {
+                return Some(EscapeResult::periodic(next, self.steps));
             }
+            self.end_window();
+        }
+        if self.iteration == settings.max_iterations {
+            return Some(EscapeResult::attracted(next));
+        }
+        None
+    }
+
+    /// Moves the orbit on to `z` and `previous`, which must be the values after another
+    /// `batch::CHUNK_STEPS` steps, none of which finished the orbit.
+    fn skip_chunk(&mut self, z: Complex64, previous: Complex64, settings: &OrbitSettings) {
+        self.z = z;
+        self.previous = previous;
+        self.iteration += batch::CHUNK_STEPS;
+        if settings.tolerance_sqr.is_some() {
+            self.steps += batch::CHUNK_STEPS;
+            self.end_window();
+        }
+    }
+
+    /// Replaces the saved values once the current window has been compared against them.
+    #[inline(always)]
+    fn end_window(&mut self) {
+        if self.steps == self.window {
+            self.saved = (self.z, self.previous);
+            self.window *= 2;
+            self.steps = 0;
+        }
+    }
+}
+
+/// Iterates from `c` and `z` for EscapeTime::evaluate() and EscapeTime::evaluate_observed(),
+/// passing each new value of `z` to `observer`.
+///
+/// This is generic over the observer so that EscapeTime::evaluate() does not pay for observing
+/// anything.
+fn iterate_orbit<E, O>(etsystem: &E, c: Complex64, z: Complex64, observer: &mut O) -> EscapeResult
+where
+    E: EscapeTime + ?Sized,
+    O: OrbitObserver + ?Sized,
+{
+    let settings = OrbitSettings::new(etsystem);
+    if settings.max_iterations == 0 {
+        return EscapeResult::attracted(z);
+    }
+    let mut orbit = OrbitState::new(c, z);
+    loop {
+        if let Some(result) = orbit.step(etsystem, &settings, observer) {
+            return result;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    #[test]
+    fn test_evaluate_smooth_within_iteration() {
+        let mb = Mandelbrot::new(100, 2);
+        for &point in &[
+            Complex64::new(1.0, 0.0),
+            Complex64::new(-0.8, 0.35),
+            Complex64::new(0.3, 0.6),
+            Complex64::new(-2.5, 1.0),
+        ] {
+            let result = mb.evaluate(point);
+            assert!(result.escaped);
+            assert!(result.smooth > result.iterations as f64);
+            assert!(result.smooth <= result.iterations as f64 + 1.0);
+            assert_approx_eq!(result.norm, result.z.norm(), 0.000_000_1);
+        }
+    }
+
+    #[test]
+    fn test_evaluate_smooth_is_continuous() {
+        // Walk along the real axis past 0.25, where the escape iterations change frequently, and
+        // make sure the smooth value never jumps by much between neighbors even though the
+        // integer iteration count does.
+        let mb = Mandelbrot::new(1000, 2);
+        let mut previous = mb.evaluate(Complex64::new(0.3, 0.0)).smooth;
+        let mut iteration_changes = 0;
+        let mut previous_iterations = mb.evaluate(Complex64::new(0.3, 0.0)).iterations;
+        for step in 1..1000 {
+            let result = mb.evaluate(Complex64::new(0.3 + f64::from(step) * 0.0005, 0.0));
+            assert!((result.smooth - previous).abs() < 0.5);
+            if result.iterations != previous_iterations {
+                iteration_changes += 1;
+            }
+            previous = result.smooth;
+            previous_iterations = result.iterations;
+        }
+        assert!(iteration_changes > 5);
+    }
+
+    #[test]
+    fn test_evaluate_larger_bailout_escapes_later() {
+        let small = Mandelbrot::new(100, 2);
+        let large = Mandelbrot::with_bailout(100, 2, EscapeCriterion::Norm(1000.0));
+        let point = Complex64::new(-0.8, 0.35);
+        let small_result = small.evaluate(point);
+        let large_result = large.evaluate(point);
+        assert!(large_result.iterations > small_result.iterations);
+        assert!(large_result.norm >= 1000.0);
+        // Apart from a constant offset that depends on the radii, the smooth values agree
+        let offset = (1000.0f64.ln() / DEFAULT_BAILOUT.ln()).log2();
+        assert_approx_eq!(large_result.smooth - small_result.smooth, offset, 0.1);
+    }
+
+    #[test]
+    fn test_escape_criteria() {
+        let z = Complex64::new(2.0, -1.5);
+        assert!(!EscapeCriterion::Norm(3.0).has_escaped(z));
+        assert!(EscapeCriterion::Norm(2.5).has_escaped(z));
+        assert!(EscapeCriterion::Real(1.9).has_escaped(z));
+        assert!(!EscapeCriterion::Real(2.0).has_escaped(z));
+        assert!(EscapeCriterion::Imaginary(1.0).has_escaped(z));
+        assert!(!EscapeCriterion::Imaginary(1.5).has_escaped(z));
+        assert!(EscapeCriterion::Manhattan(3.5).has_escaped(z));
+        assert!(!EscapeCriterion::Manhattan(3.6).has_escaped(z));
+    }
+
+    #[test]
+    fn test_escape_criterion_from_name() {
+        assert_eq!(
+            EscapeCriterion::from_name("manhattan", 4.0),
+            Ok(EscapeCriterion::Manhattan(4.0))
+        );
+        assert_eq!(
+            EscapeCriterion::from_name("norm", 2.0).unwrap().radius(),
+            2.0
+        );
+        assert!(EscapeCriterion::from_name("taxicab", 4.0).is_err());
+        assert!(EscapeCriterion::from_name("norm", -1.0).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_attracted() {
+        let mb = Mandelbrot::new(100, 2);
+        let result = mb.evaluate(Complex64::new(-1.0, 0.0));
+        assert!(!result.escaped);
+        assert_eq!(result.iterations, 0);
+        assert_eq!(result.smooth, 0.0);
+        assert_eq!(mb.test_point(Complex64::new(-1.0, 0.0)), (true, 0));
+    }
+
+    /// Only overrides the required methods, so that the default evaluate() has to iterate.
+    struct PlainMandelbrot;
+
+    impl EscapeTime for PlainMandelbrot {
+        fn max_iterations(&self) -> u64 {
+            10_000
+        }
+
+        fn default_view_area(&self) -> [Complex64; 2] {
+            [Complex64::new(-2.0, 1.0), Complex64::new(1.0, -1.0)]
+        }
+
+        fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+            z * z + c
+        }
+    }
+
+    #[test]
+    fn test_periodicity_detection() {
+        // (c, period of the attracting cycle)
+        for &(c, period) in &[
+            (Complex64::new(0.1, 0.1), 1),
+            (Complex64::new(-1.0, 0.05), 2),
+            (Complex64::new(-0.12, 0.75), 3),
+            (Complex64::new(-1.31, 0.0), 4),
+            (Complex64::new(0.28, 0.53), 4),
+        ] {
+            let result = PlainMandelbrot.evaluate(c);
+            assert!(!result.escaped);
+            assert_eq!(result.period, Some(period), "period of {}", c);
+        }
+    }
+
+    #[test]
+    fn test_periodicity_detection_does_not_affect_escapes() {
+        for &point in &[
+            Complex64::new(0.26, 0.0),
+            Complex64::new(-0.75, 0.05),
+            Complex64::new(-2.1, 0.0),
+        ] {
+            let result = PlainMandelbrot.evaluate(point);
+            assert!(result.escaped);
+            assert_eq!(result.period, None);
         }
-        (true, 0)
     }
 }
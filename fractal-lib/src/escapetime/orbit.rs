@@ -0,0 +1,109 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Follows the orbit of a single point, for inspecting how an escape time fractal treats it.
+
+use super::{EscapeResult, EscapeTime};
+use num::complex::Complex64;
+
+/// The orbit of a single point under an escape time fractal.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Orbit {
+    /// Every value of `z`, starting with the initial one. It ends with the first value beyond the
+    /// escape radius, once periodicity checking detects a cycle, or after `max_iterations`
+    /// iterations.
+    pub values: Vec<Complex64>,
+    /// The result of the iteration that produced `values`, which has the iteration during which
+    /// it escaped or the period of the cycle that it settled into. It comes from
+    /// EscapeTime::evaluate_observed(), so unlike EscapeTime::evaluate() it always describes the
+    /// traced orbit, even for points that the fractal knows are in its interior.
+    pub result: EscapeResult,
+}
+
+/// Receives every value of `z` that iteration produces, see EscapeTime::evaluate_observed().
+pub trait OrbitObserver {
+    fn observe(&mut self, z: Complex64);
+}
+
+/// Ignores the orbit.
+impl OrbitObserver for () {
+    fn observe(&mut self, _z: Complex64) {}
+}
+
+/// Records every value of the orbit.
+impl OrbitObserver for Vec<Complex64> {
+    fn observe(&mut self, z: Complex64) {
+        self.push(z);
+    }
+}
+
+/// Iterates `point` with EscapeTime::evaluate_observed(), recording every value of `z` along the
+/// way (starting with the initial value).
+///
+/// Like EscapeTime::evaluate_observed(), this does not stop early for points that the fractal
+/// knows are in its interior, since the point of an orbit is to see how it gets wherever it goes.
+pub fn trace<E: EscapeTime + ?Sized>(etsystem: &E, point: Complex64) -> Orbit {
+    let (_, z) = etsystem.initial_values(point);
+    let mut values = vec![z];
+    let result = etsystem.evaluate_observed(point, &mut values);
+    Orbit { values, result }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::julia::JuliaSet;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    #[test]
+    fn test_escaping_orbit() {
+        // 0, 1, 2, 5
+        let orbit = Mandelbrot::new(100, 2).orbit(Complex64::new(1.0, 0.0));
+        assert!(orbit.result.escaped);
+        assert_eq!(orbit.result.iterations, 2);
+        assert_eq!(
+            orbit.values,
+            vec![
+                Complex64::new(0.0, 0.0),
+                Complex64::new(1.0, 0.0),
+                Complex64::new(2.0, 0.0),
+                Complex64::new(5.0, 0.0),
+            ]
+        );
+        assert_eq!(*orbit.values.last().unwrap(), orbit.result.z);
+    }
+
+    #[test]
+    fn test_periodic_orbit() {
+        // 0, -1, 0, -1, ... which the Mandelbrot set knows is in its period 2 bulb, but the orbit
+        // is traced anyway until the cycle is detected.
+        let orbit = Mandelbrot::new(100, 2).orbit(Complex64::new(-1.0, 0.0));
+        assert!(!orbit.result.escaped);
+        assert_eq!(orbit.result.period, Some(2));
+        assert!(orbit.values.len() > 2);
+        assert!(orbit.values.len() < 100);
+        let n = orbit.values.len();
+        assert_eq!(orbit.values[n - 1], orbit.values[n - 3]);
+        assert_eq!(*orbit.values.last().unwrap(), orbit.result.z);
+    }
+
+    #[test]
+    fn test_julia_orbit_starts_at_point() {
+        let julia = JuliaSet::new(Mandelbrot::new(50, 2), Complex64::new(-0.8, 0.156));
+        let point = Complex64::new(0.1, -0.2);
+        let orbit = julia.orbit(point);
+        assert_eq!(orbit.values[0], point);
+        assert!(orbit.values.len() <= 51);
+    }
+}
@@ -0,0 +1,309 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Orbit trap coloring for escape time fractals.
+//!
+//! Instead of coloring a point by how many iterations it took to escape, orbit trap coloring
+//! follows the point's orbit and remembers how close it came to a shape (the trap), such as a
+//! point, a line, or a circle. Coloring by that distance draws copies of the trap's shape all over
+//! the fractal, both inside and outside of its set. An image can be used as a trap as well, in
+//! which case the orbit picks up the color of the first opaque pixel of the image that it lands
+//! on.
+
+use super::super::color::{self, ColorU8};
+use super::orbit::OrbitObserver;
+use super::{EscapeResult, EscapeTime};
+use num::complex::Complex64;
+
+/// The default distance from the trap beyond which every point gets the same color.
+pub const DEFAULT_FALLOFF: f64 = 0.5;
+
+/// An image placed on the complex plane, for use as a trap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageTrap {
+    width: u32,
+    height: u32,
+    /// Row by row
+    pixels: Vec<ColorU8>,
+    top_left: Complex64,
+    /// The width (and height) of a pixel of the image on the complex plane.
+    pixel_size: f64,
+}
+
+impl ImageTrap {
+    /// Places an image that is `width` by `height` pixels (given row by row in `pixels`) on the
+    /// complex plane, centered on `center` and `size` wide.
+    pub fn new(
+        width: u32,
+        height: u32,
+        pixels: Vec<ColorU8>,
+        center: Complex64,
+        size: f64,
+    ) -> Result<ImageTrap, String> {
+        if width == 0 || height == 0 || pixels.len() != width as usize * height as usize {
+            return Err(format!(
+                "Expected {} pixels for a {}x{} trap image, but got {}",
+                width as usize * height as usize,
+                width,
+                height,
+                pixels.len()
+            ));
+        }
+        if !size.is_finite() || size <= 0.0 {
+            return Err("The size of a trap image must be greater than 0".to_string());
+        }
+        let pixel_size = size / f64::from(width);
+        let top_left = center + Complex64::new(-size / 2.0, pixel_size * f64::from(height) / 2.0);
+        Ok(ImageTrap {
+            width,
+            height,
+            pixels,
+            top_left,
+            pixel_size,
+        })
+    }
+
+    /// The color of the image at `z`, or None if `z` is outside of the image or lands on a
+    /// transparent pixel.
+    pub fn sample(&self, z: Complex64) -> Option<ColorU8> {
+        let x = (z.re - self.top_left.re) / self.pixel_size;
+        let y = (self.top_left.im - z.im) / self.pixel_size;
+        let (width, height) = (f64::from(self.width), f64::from(self.height));
+        if !(0.0..width).contains(&x) || !(0.0..height).contains(&y) {
+            return None;
+        }
+        let color = self.pixels[y as usize * self.width as usize + x as usize];
+        if color.0[3] == 0 {
+            None
+        } else {
+            Some(color)
+        }
+    }
+}
+
+/// The shape that orbits are measured against.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TrapShape {
+    /// A single point.
+    Point(Complex64),
+    /// The line through a point, at an angle (in radians, counterclockwise from the real axis).
+    Line(Complex64, f64),
+    /// The horizontal and vertical lines through a point.
+    Cross(Complex64),
+    /// The circle around a point, with a radius.
+    Circle(Complex64, f64),
+    /// An image, which colors orbits that land on it instead of measuring their distance.
+    Image(ImageTrap),
+}
+
+impl TrapShape {
+    /// The names of the shapes that TrapShape::from_name() can construct.
+    pub const NAMES: [&'static str; 4] = ["point", "line", "cross", "circle"];
+
+    /// Constructs a trap shape from its name, centered on `center`. `radius` is only used by
+    /// circles, and `angle` (in degrees) is only used by lines.
+    pub fn from_name(
+        name: &str,
+        center: Complex64,
+        radius: f64,
+        angle: f64,
+    ) -> Result<TrapShape, String> {
+        match name {
+            "point" => Ok(TrapShape::Point(center)),
+            "line" => Ok(TrapShape::Line(center, angle.to_radians())),
+            "cross" => Ok(TrapShape::Cross(center)),
+            "circle" => Ok(TrapShape::Circle(center, radius)),
+            _ => Err(format!("Unknown trap shape: {}", name)),
+        }
+    }
+
+    /// The distance from `z` to the shape. Images are 0 away wherever they have an opaque
+    /// pixel, and infinitely far away everywhere else.
+    pub fn distance(&self, z: Complex64) -> f64 {
+        match self {
+            TrapShape::Point(point) => (z - point).norm(),
+            TrapShape::Line(point, angle) => {
+                // Rotate the line onto the real axis
+                ((z - point) * Complex64::from_polar(&1.0, &-angle))
+                    .im
+                    .abs()
+            }
+            TrapShape::Cross(point) => {
+                let offset = z - point;
+                offset.re.abs().min(offset.im.abs())
+            }
+            TrapShape::Circle(center, radius) => ((z - center).norm() - radius).abs(),
+            TrapShape::Image(image) => match image.sample(z) {
+                Some(_) => 0.0,
+                None => f64::INFINITY,
+            },
+        }
+    }
+}
+
+/// What an orbit trap found out about a point's orbit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TrapResult {
+    /// The result of iterating the point.
+    pub result: EscapeResult,
+    /// The closest that the orbit came to the trap (after its initial value).
+    pub distance: f64,
+    /// For image traps, the color of the first opaque pixel that the orbit landed on.
+    pub image_color: Option<ColorU8>,
+}
+
+/// Accumulates the closest approach of an orbit to a `TrapShape`, as it is iterated.
+struct TrapAccumulator<'a> {
+    shape: &'a TrapShape,
+    distance: f64,
+    image_color: Option<ColorU8>,
+}
+
+impl<'a> OrbitObserver for TrapAccumulator<'a> {
+    fn observe(&mut self, z: Complex64) {
+        if let TrapShape::Image(image) = self.shape {
+            if self.image_color.is_none() {
+                self.image_color = image.sample(z);
+                if self.image_color.is_some() {
+                    self.distance = 0.0;
+                }
+            }
+        } else {
+            self.distance = self.distance.min(self.shape.distance(z));
+        }
+    }
+}
+
+/// Colors escape time fractals by how close each point's orbit comes to a trap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrbitTrap {
+    shape: TrapShape,
+    falloff: f64,
+}
+
+impl OrbitTrap {
+    /// Creates an orbit trap with the default falloff (see OrbitTrap::with_falloff()).
+    pub fn new(shape: TrapShape) -> OrbitTrap {
+        OrbitTrap::with_falloff(shape, DEFAULT_FALLOFF)
+    }
+
+    /// Creates an orbit trap where orbits that come no closer than `falloff` to the trap all get
+    /// the same color.
+    pub fn with_falloff(shape: TrapShape, falloff: f64) -> OrbitTrap {
+        OrbitTrap { shape, falloff }
+    }
+
+    pub fn shape(&self) -> &TrapShape {
+        &self.shape
+    }
+
+    /// Follows the orbit of `point` (see EscapeTime::evaluate_observed()), measuring how close
+    /// it comes to the trap.
+    pub fn evaluate<E>(&self, etsystem: &E, point: Complex64) -> TrapResult
+    where
+        E: EscapeTime + ?Sized,
+    {
+        let mut accumulator = TrapAccumulator {
+            shape: &self.shape,
+            distance: f64::INFINITY,
+            image_color: None,
+        };
+        let result = etsystem.evaluate_observed(point, &mut accumulator);
+        TrapResult {
+            result,
+            distance: accumulator.distance,
+            image_color: accumulator.image_color,
+        }
+    }
+
+    /// Picks the color for a point from what the trap found out about its orbit (see
+    /// `color::trap_color()`). Orbits that landed on an image trap take the image's color, and
+    /// the rest are black if they escaped, or use `color::interior_color()` if they did not.
+    pub fn color(&self, trapped: &TrapResult) -> ColorU8 {
+        if let TrapShape::Image(_) = self.shape {
+            return match trapped.image_color {
+                Some(color) => color,
+                None if trapped.result.escaped => color::BLACK_U8,
+                None => color::interior_color(trapped.result.period),
+            };
+        }
+        color::trap_color(trapped.distance, self.falloff, trapped.result.escaped)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        let origin = Complex64::new(0.0, 0.0);
+        let z = Complex64::new(3.0, 4.0);
+        assert_approx_eq!(TrapShape::Point(origin).distance(z), 5.0, 1e-12);
+        assert_approx_eq!(TrapShape::Line(origin, 0.0).distance(z), 4.0, 1e-12);
+        assert_approx_eq!(
+            TrapShape::Line(origin, std::f64::consts::FRAC_PI_2).distance(z),
+            3.0,
+            1e-12
+        );
+        assert_approx_eq!(TrapShape::Cross(origin).distance(z), 3.0, 1e-12);
+        assert_approx_eq!(TrapShape::Circle(origin, 2.0).distance(z), 3.0, 1e-12);
+        assert_approx_eq!(TrapShape::Circle(origin, 7.0).distance(z), 2.0, 1e-12);
+    }
+
+    #[test]
+    fn test_evaluate_minimum_distance() {
+        // The orbit of 1 is 0, 1, 2, 5, and the initial value is not counted
+        let trap = OrbitTrap::new(TrapShape::Point(Complex64::new(2.5, 0.0)));
+        let trapped = trap.evaluate(&Mandelbrot::new(100, 2), Complex64::new(1.0, 0.0));
+        assert!(trapped.result.escaped);
+        assert_approx_eq!(trapped.distance, 0.5, 1e-12);
+        assert_eq!(trapped.image_color, None);
+    }
+
+    #[test]
+    fn test_evaluate_interior() {
+        // The orbit of -1 cycles through 0 and -1, even though the Mandelbrot set knows that it
+        // is in the interior without iterating
+        let trap = OrbitTrap::new(TrapShape::Point(Complex64::new(0.0, 0.5)));
+        let trapped = trap.evaluate(&Mandelbrot::new(100, 2), Complex64::new(-1.0, 0.0));
+        assert!(!trapped.result.escaped);
+        assert_eq!(trapped.result.period, Some(2));
+        assert_approx_eq!(trapped.distance, 0.5, 1e-12);
+    }
+
+    #[test]
+    fn test_image_trap() {
+        let red = ColorU8([255, 0, 0, 255]);
+        let clear = ColorU8([0, 0, 0, 0]);
+        // 2x1 pixels over [2.5, 3.5] x [-0.25, 0.25], where only the right pixel is opaque
+        let image = ImageTrap::new(2, 1, vec![clear, red], Complex64::new(3.0, 0.0), 1.0).unwrap();
+        assert_eq!(image.sample(Complex64::new(2.75, 0.0)), None);
+        assert_eq!(image.sample(Complex64::new(3.25, 0.1)), Some(red));
+        assert_eq!(image.sample(Complex64::new(3.25, 0.3)), None);
+        assert!(ImageTrap::new(2, 2, vec![red], Complex64::new(0.0, 0.0), 1.0).is_err());
+
+        // The orbit of 1 is 0, 1, 2, 5
+        let trap = OrbitTrap::new(TrapShape::Image(image));
+        let mandelbrot = Mandelbrot::new(100, 2);
+        let trapped = trap.evaluate(&mandelbrot, Complex64::new(1.0, 0.0));
+        assert_eq!(trapped.image_color, None);
+        assert_eq!(trap.color(&trapped), color::BLACK_U8);
+
+        let trapped = trap.evaluate(&mandelbrot, Complex64::new(3.25, 0.0));
+        assert_eq!(trapped.image_color, Some(red));
+        assert_eq!(trap.color(&trapped), red);
+    }
+}
@@ -0,0 +1,300 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deep zooms into the Mandelbrot set using perturbation theory.
+//!
+//! Once the view area is narrower than about 1e-13, `Complex64` can no longer tell apart the
+//! points that neighboring pixels map to. Iterating every pixel with a `BigFloat` would work, but
+//! it would be far too slow. Instead, perturbation theory iterates just one reference point `C`
+//! (the center of the view) with high precision, and then describes every other point
+//! `c = C + δc` by how far its orbit `z_n = Z_n + δ_n` strays from the reference orbit `Z_n`:
+//!
+//! ```text
+//! z_{n+1} = z_n^p + c
+//! Z_{n+1} + δ_{n+1} = (Z_n + δ_n)^p + C + δc
+//! δ_{n+1} = (Z_n + δ_n)^p - Z_n^p + δc
+//!         = Σ_{k=1..p} binomial(p, k) * Z_n^(p-k) * δ_n^k + δc
+//! ```
+//!
+//! The deltas are tiny compared to `Z_n`, but they are stored as differences, so an `f64` keeps
+//! all of their significant bits. Only the reference orbit needs the extra precision, and it is
+//! rounded to `Complex64` once it has been computed.
+//!
+//! When a pixel's orbit passes close to 0 while the reference orbit does not, the delta becomes
+//! larger than the value it describes, and the `f64` math loses the precision that the delta was
+//! supposed to preserve. These are the "glitches" that perturbation renderers are known for. This
+//! module detects them by checking whether `|Z_n + δ_n| < |δ_n|`, and then rebases the pixel onto
+//! the start of the reference orbit: since `Z_0 = 0`, the full value `Z_n + δ_n` becomes the new
+//! delta, and iteration continues from there. Pixels that outlive the reference orbit (because
+//! the reference escaped first) are rebased the same way.
+
+use super::super::bigfloat::BigComplex;
+use super::super::geometry;
+use super::*;
+
+/// The number of bits of precision beyond what is needed to tell the pixels in the view apart.
+const PRECISION_MARGIN: u32 = 64;
+
+/// Chooses how many bits of precision the reference orbit needs for a view that is `view_size`
+/// wide.
+pub fn precision_for_view_size(view_size: f64) -> u32 {
+    let needed = -view_size.abs().log2();
+    if needed.is_finite() && needed > 0.0 {
+        needed.ceil() as u32 + PRECISION_MARGIN
+    } else {
+        PRECISION_MARGIN
+    }
+}
+
+/// Computes `binomial(n, k)` for every `k` in `0..=n`.
+fn binomial_coefficients(n: u64) -> Vec<f64> {
+    let mut coefficients = vec![1.0];
+    for k in 1..=n {
+        let previous = coefficients[(k - 1) as usize];
+        coefficients.push(previous * (n - k + 1) as f64 / k as f64);
+    }
+    coefficients
+}
+
+/// A multibrot set (see `mandelbrot::Mandelbrot`) that is rendered relative to a high precision
+/// center point using perturbation theory.
+///
+/// The complex numbers passed to `PerturbedMandelbrot::evaluate()` are offsets from the center
+/// (`δc`), so that they stay representable as `Complex64`s no matter how deep the zoom is.
+pub struct PerturbedMandelbrot {
+    center: BigComplex,
+    max_iters: u64,
+    power: u64,
+    bailout: EscapeCriterion,
+    binomials: Vec<f64>,
+    /// `Z_0` through `Z_n` for the center, rounded to Complex64. It ends early if the center
+    /// escapes.
+    reference: Vec<Complex64>,
+}
+
+impl PerturbedMandelbrot {
+    /// Computes the reference orbit for `center`, using the precision of `center`.
+    ///
+    /// `max_iterations`, `power`, and `bailout` have the same meaning as they do for
+    /// `Mandelbrot::with_bailout()`.
+    pub fn new(
+        center: BigComplex,
+        max_iterations: u64,
+        power: u64,
+        bailout: EscapeCriterion,
+    ) -> PerturbedMandelbrot {
+        let precision = center.precision();
+        let mut reference = Vec::with_capacity(max_iterations as usize + 1);
+        let mut z = BigComplex::from_complex64(Complex64::new(0.0, 0.0), precision);
+        reference.push(z.to_complex64());
+        for _ in 0..max_iterations {
+            let mut zp = z.clone();
+            for _ in 1..power {
+                zp = &zp * &z;
+            }
+            z = &zp + &center;
+            let approx = z.to_complex64();
+            reference.push(approx);
+            if bailout.has_escaped(approx) {
+                break;
+            }
+        }
+        log::debug!(
+            "Computed reference orbit of length {} with {} bits of precision",
+            reference.len(),
+            precision
+        );
+
+        PerturbedMandelbrot {
+            center,
+            max_iters: max_iterations,
+            power,
+            bailout,
+            binomials: binomial_coefficients(power),
+            reference,
+        }
+    }
+
+    /// The high precision center that all points are relative to.
+    pub fn center(&self) -> &BigComplex {
+        &self.center
+    }
+
+    pub fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    /// The number of points in the reference orbit, including `Z_0`.
+    pub fn reference_len(&self) -> usize {
+        self.reference.len()
+    }
+
+    /// Creates a new `PerturbedMandelbrot` for the same fractal that is centered on `offset`
+    /// (relative to the current center), with enough precision for a view that is `view_size`
+    /// wide.
+    pub fn recenter(&self, offset: Complex64, view_size: f64) -> PerturbedMandelbrot {
+        let precision = precision_for_view_size(view_size);
+        let center = self.center.with_precision(precision);
+        let offset = BigComplex::from_complex64(offset, precision);
+        PerturbedMandelbrot::new(&center + &offset, self.max_iters, self.power, self.bailout)
+    }
+
+    /// Computes `(Z + δ)^p - Z^p` without cancellation, using the binomial expansion.
+    fn perturb(&self, reference: Complex64, delta: Complex64) -> Complex64 {
+        if self.power == 2 {
+            return delta * (reference * 2.0 + delta);
+        }
+        let mut sum = Complex64::new(0.0, 0.0);
+        let mut delta_k = Complex64::new(1.0, 0.0);
+        for k in 1..=self.power {
+            delta_k *= delta;
+            sum += delta_k * geometry::cpow(reference, self.power - k) * self.binomials[k as usize];
+        }
+        sum
+    }
+
+    /// Tests the point `center + delta_c`.
+    ///
+    /// The result describes the full value of `z` (`Z_n + δ_n`), so it can be colored the same
+    /// way as an `EscapeTime::evaluate()` result.
+    pub fn evaluate(&self, delta_c: Complex64) -> EscapeResult {
+        let degree = self.power as f64;
+        let last = self.reference.len() - 1;
+        let mut delta = Complex64::new(0.0, 0.0);
+        let mut m = 0;
+        let mut z = self.reference[0];
+        for i in 0..self.max_iters {
+            delta = self.perturb(self.reference[m], delta) + delta_c;
+            m += 1;
+            z = self.reference[m] + delta;
+            if self.bailout.has_escaped(z) {
+                return EscapeResult::escaped(i, z, degree, self.bailout.radius());
+            }
+            // Glitch detection: rebase onto Z_0 = 0 once the delta dominates, or once the
+            // reference orbit runs out.
+            if m == last || z.norm_sqr() < delta.norm_sqr() {
+                delta = z;
+                m = 0;
+            }
+        }
+        EscapeResult::attracted(z)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::bigfloat::BigFloat;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    /// Iterates `c` with BigFloats, which is slow but accurate.
+    fn escape_iteration(c: &BigComplex, max_iterations: u64) -> Option<u64> {
+        let mut z = BigComplex::from_complex64(Complex64::new(0.0, 0.0), c.precision());
+        for i in 0..max_iterations {
+            z = &z.square() + c;
+            if EscapeCriterion::default().has_escaped(z.to_complex64()) {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_precision_for_view_size() {
+        assert_eq!(precision_for_view_size(3.0), PRECISION_MARGIN);
+        assert_eq!(precision_for_view_size(1.0 / 1024.0), 10 + PRECISION_MARGIN);
+        assert!(precision_for_view_size(1e-100) > 332 + PRECISION_MARGIN);
+    }
+
+    #[test]
+    fn test_binomial_coefficients() {
+        assert_eq!(binomial_coefficients(2), vec![1.0, 2.0, 1.0]);
+        assert_eq!(binomial_coefficients(4), vec![1.0, 4.0, 6.0, 4.0, 1.0]);
+    }
+
+    #[test]
+    fn test_matches_mandelbrot_at_shallow_zoom() {
+        for &power in &[2, 3] {
+            let center = Complex64::new(-0.75, 0.1);
+            let mb = Mandelbrot::new(300, power);
+            let pb = PerturbedMandelbrot::new(
+                BigComplex::from_complex64(center, 64),
+                300,
+                power,
+                EscapeCriterion::default(),
+            );
+            let mut mismatches = 0;
+            for x in 0..20 {
+                for y in 0..20 {
+                    let delta = Complex64::new(f64::from(x) * 0.01 - 0.1, f64::from(y) * 0.01);
+                    let expected = mb.evaluate(center + delta);
+                    let actual = pb.evaluate(delta);
+                    if expected.escaped != actual.escaped
+                        || expected.iterations != actual.iterations
+                    {
+                        mismatches += 1;
+                    }
+                }
+            }
+            // rounding differences may flip a rare boundary point
+            assert!(
+                mismatches <= 4,
+                "{} mismatches for power {}",
+                mismatches,
+                power
+            );
+        }
+    }
+
+    #[test]
+    fn test_deep_zoom_matches_bigfloat_iteration() {
+        // A point near the boundary, viewed at a scale that Complex64 can not resolve
+        let precision = precision_for_view_size(1e-30);
+        let center = BigComplex::new(
+            BigFloat::parse("-1.74006238257933990522084416706", precision).unwrap(),
+            BigFloat::parse("0.0000000000000000000000000000001", precision).unwrap(),
+        );
+        let pb = PerturbedMandelbrot::new(center.clone(), 2000, 2, EscapeCriterion::default());
+        let mut distinct = std::collections::HashSet::new();
+        for step in 0..8 {
+            let delta = Complex64::new(f64::from(step) * 3e-31, f64::from(step) * 1e-31);
+            let expected = escape_iteration(
+                &(&center + &BigComplex::from_complex64(delta, precision)),
+                2000,
+            );
+            let actual = pb.evaluate(delta);
+            assert_eq!(expected.is_some(), actual.escaped);
+            if let Some(iterations) = expected {
+                assert_eq!(iterations, actual.iterations);
+            }
+            distinct.insert(actual.iterations);
+        }
+        // Neighboring points should not all be lumped together
+        assert!(distinct.len() > 1);
+    }
+
+    #[test]
+    fn test_recenter() {
+        let pb = PerturbedMandelbrot::new(
+            BigComplex::from_complex64(Complex64::new(-0.5, 0.0), 64),
+            100,
+            2,
+            EscapeCriterion::default(),
+        );
+        let moved = pb.recenter(Complex64::new(-0.25, 0.5), 1e-40);
+        assert_eq!(moved.center().to_complex64(), Complex64::new(-0.75, 0.5));
+        assert_eq!(moved.center().precision(), precision_for_view_size(1e-40));
+        assert_eq!(moved.max_iterations(), 100);
+    }
+}
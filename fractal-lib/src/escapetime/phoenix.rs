@@ -0,0 +1,208 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The Phoenix fractal, discovered by Shigehiro Ushiki, is an escape time fractal whose next value
+//! depends on the last two values of the orbit:
+//!
+//! ```text
+//! z[n+1] = z[n]^2 + c + p*z[n-1]
+//! ```
+//!
+//! Where `p` weights how much of the previous value is kept, and is usually -0.5. Like the
+//! mandelbrot set, the point being tested is used as `c`, and iteration starts from 0 (with a
+//! previous value of 0). The well known image of the Phoenix is its Julia set for `c = 0.5667`.
+
+use super::super::geometry::{self, Exponent};
+use super::*;
+
+/// The weight of the previous value that Ushiki used.
+pub const DEFAULT_PREVIOUS_WEIGHT: f64 = -0.5;
+
+pub struct Phoenix {
+    max_iters: u64,
+    power: Exponent,
+    previous_weight: Complex64,
+    bailout: EscapeCriterion,
+}
+
+impl Phoenix {
+    /// Creates a new escape time specification for the Phoenix fractal, with a weight of
+    /// `DEFAULT_PREVIOUS_WEIGHT` for the previous value.
+    ///
+    /// `max_iterations` specifies the cutoff iteration for deciding whether a complex number
+    /// escapes or has converged.
+    ///
+    /// `power` specifies the exponent that is used instead of 2 in the equation above.
+    pub fn new(max_iterations: u64, power: u64) -> Phoenix {
+        Phoenix::with_bailout(max_iterations, power, EscapeCriterion::default())
+    }
+
+    /// Like `Phoenix::new()`, but `bailout` specifies the test (and radius) that decides when a
+    /// value has escaped.
+    pub fn with_bailout(max_iterations: u64, power: u64, bailout: EscapeCriterion) -> Phoenix {
+        Phoenix::with_exponent(max_iterations, Exponent::Natural(power), bailout)
+    }
+
+    /// Like `Phoenix::with_bailout()`, but `exponent` may also be negative, fractional, or
+    /// complex (see `escapetime::multibrot_initial_values()`).
+    pub fn with_exponent(
+        max_iterations: u64,
+        exponent: Exponent,
+        bailout: EscapeCriterion,
+    ) -> Phoenix {
+        Phoenix::with_previous_weight(
+            max_iterations,
+            exponent,
+            Complex64::new(DEFAULT_PREVIOUS_WEIGHT, 0.0),
+            bailout,
+        )
+    }
+
+    /// Like `Phoenix::with_exponent()`, but `previous_weight` specifies `p`, the weight of the
+    /// previous value. A weight of 0 gives the mandelbrot set.
+    pub fn with_previous_weight(
+        max_iterations: u64,
+        exponent: Exponent,
+        previous_weight: Complex64,
+        bailout: EscapeCriterion,
+    ) -> Phoenix {
+        Phoenix {
+            max_iters: max_iterations,
+            power: exponent,
+            previous_weight,
+            bailout,
+        }
+    }
+
+    pub fn previous_weight(&self) -> Complex64 {
+        self.previous_weight
+    }
+}
+
+impl EscapeTime for Phoenix {
+    fn max_iterations(&self) -> u64 {
+        self.max_iters
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        [Complex64::new(-2.0, 1.5), Complex64::new(1.5, -1.5)]
+    }
+
+    /// The first iteration, where there is no previous value yet.
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        self.iterate_with_previous(c, z, Complex64::new(0.0, 0.0))
+    }
+
+    fn iterate_with_previous(&self, c: Complex64, z: Complex64, previous: Complex64) -> Complex64 {
+        geometry::cpow_general(z, self.power) + c + self.previous_weight * previous
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.bailout
+    }
+
+    fn degree(&self) -> f64 {
+        self.power.real_part()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        multibrot_initial_values(self.power, point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::julia::JuliaSet;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    /// Follows the orbit of `point` by hand, and returns the iteration it escaped during.
+    fn escape_iteration(phoenix: &Phoenix, point: Complex64) -> Option<u64> {
+        let (c, mut z) = phoenix.initial_values(point);
+        let mut previous = Complex64::new(0.0, 0.0);
+        for i in 0..phoenix.max_iterations() {
+            let next = z * z + c + phoenix.previous_weight() * previous;
+            previous = z;
+            z = next;
+            if phoenix.bailout().has_escaped(z) {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_iterate_with_previous() {
+        let phoenix = Phoenix::new(10, 2);
+        let c = Complex64::new(0.5, 0.0);
+        let z = Complex64::new(1.0, 1.0);
+        let previous = Complex64::new(2.0, -2.0);
+        assert_eq!(
+            phoenix.iterate_with_previous(c, z, previous),
+            Complex64::new(-0.5, 3.0)
+        );
+        assert_eq!(phoenix.iterate(c, z), Complex64::new(0.5, 2.0));
+    }
+
+    #[test]
+    fn test_evaluate_uses_previous() {
+        let phoenix = Phoenix::new(100, 2);
+        for &point in &[
+            Complex64::new(0.6, 0.0),
+            Complex64::new(0.3, 0.5),
+            Complex64::new(-1.2, 0.8),
+            Complex64::new(-1.5, 0.0),
+        ] {
+            let expected = escape_iteration(&phoenix, point);
+            let result = phoenix.evaluate(point);
+            assert!(expected.is_some());
+            assert!(result.escaped);
+            assert_eq!(Some(result.iterations), expected);
+        }
+        // 0.4 is outside of the mandelbrot set, but the previous value keeps it bounded
+        assert!(
+            Mandelbrot::new(100, 2)
+                .evaluate(Complex64::new(0.4, 0.0))
+                .escaped
+        );
+        assert!(!phoenix.evaluate(Complex64::new(0.4, 0.0)).escaped);
+    }
+
+    #[test]
+    fn test_zero_weight_is_mandelbrot() {
+        let phoenix = Phoenix::with_previous_weight(
+            100,
+            Exponent::Natural(2),
+            Complex64::new(0.0, 0.0),
+            EscapeCriterion::default(),
+        );
+        let mandelbrot = Mandelbrot::new(100, 2);
+        for &point in &[Complex64::new(0.3, 0.0), Complex64::new(-1.5, 0.5)] {
+            assert_eq!(
+                phoenix.evaluate(point).iterations,
+                mandelbrot.evaluate(point).iterations
+            );
+        }
+    }
+
+    #[test]
+    fn test_julia_set_uses_previous() {
+        let julia = JuliaSet::new(Phoenix::new(100, 2), Complex64::new(0.5667, 0.0));
+        let plain = JuliaSet::new(Mandelbrot::new(100, 2), Complex64::new(0.5667, 0.0));
+        // c = 0.5667 is outside of the mandelbrot set, so almost every point escapes when
+        // iterating z^2 + c, but the Phoenix keeps some of them bounded
+        assert!(plain.evaluate(Complex64::new(0.0, 0.5)).escaped);
+        assert!(!julia.evaluate(Complex64::new(0.0, 0.5)).escaped);
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Renders an image in passes from coarse to fine, so that a rough version of it can be shown
+//! right away.
+//!
+//! The first pass samples one pixel out of every `initial_block_size` by `initial_block_size`
+//! block, and draws the whole block with that pixel's color. Each later pass halves the block
+//! size, and only samples the pixels that no earlier pass has, until the last pass (with blocks
+//! of one pixel) has sampled every pixel exactly once. This works for any renderer that picks
+//! the color of each pixel independently, such as escape time and convergent fractals.
+
+use std::cmp;
+use std::ops::Range;
+
+/// The default size of the blocks in the first pass.
+pub const DEFAULT_INITIAL_BLOCK_SIZE: u32 = 8;
+
+/// One pass of progressive rendering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pass {
+    block_size: u32,
+    first: bool,
+}
+
+impl Pass {
+    /// The passes that refine blocks of `initial_block_size` (rounded down to a power of two) by
+    /// `initial_block_size` pixels down to single pixels, in order.
+    pub fn passes(initial_block_size: u32) -> Vec<Pass> {
+        let mut block_size = cmp::max(initial_block_size, 1).next_power_of_two();
+        if block_size > initial_block_size.max(1) {
+            block_size /= 2;
+        }
+        let mut passes = vec![Pass {
+            block_size,
+            first: true,
+        }];
+        while block_size > 1 {
+            block_size /= 2;
+            passes.push(Pass {
+                block_size,
+                first: false,
+            });
+        }
+        passes
+    }
+
+    /// The width and height of the block that each sample of this pass is drawn over.
+    pub fn block_size(&self) -> u32 {
+        self.block_size
+    }
+
+    /// Whether this pass samples the pixel at `(x, y)`.
+    pub fn samples(&self, x: u32, y: u32) -> bool {
+        let coarser = self.block_size * 2;
+        is_aligned(x, self.block_size)
+            && is_aligned(y, self.block_size)
+            && (self.first || !is_aligned(x, coarser) || !is_aligned(y, coarser))
+    }
+
+    /// The columns of an image `width` pixels wide in which this pass samples any pixels.
+    pub fn columns(&self, width: u32) -> impl Iterator<Item = u32> {
+        (0..width).step_by(self.block_size as usize)
+    }
+
+    /// The rows in which this pass samples pixels in column `x` of an image `height` pixels
+    /// tall.
+    pub fn rows(&self, x: u32, height: u32) -> Vec<u32> {
+        let pass = *self;
+        (0..height)
+            .step_by(self.block_size as usize)
+            .filter(|&y| pass.samples(x, y))
+            .collect()
+    }
+
+    /// The columns and rows of the pixels that the sample at `(x, y)` is drawn over, clipped to
+    /// an image of the given size.
+    pub fn block(&self, x: u32, y: u32, width: u32, height: u32) -> (Range<u32>, Range<u32>) {
+        (
+            x..cmp::min(x + self.block_size, width),
+            y..cmp::min(y + self.block_size, height),
+        )
+    }
+}
+
+/// Whether `value` is a multiple of `size`, which must be a power of two.
+fn is_aligned(value: u32, size: u32) -> bool {
+    value & (size - 1) == 0
+}
+
+/// Tracks how finely each pixel of an image has been drawn, so that passes that run
+/// concurrently (such as on different threads) never draw the block of a coarse sample over
+/// pixels that a finer pass has already drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coverage {
+    width: u32,
+    height: u32,
+    /// The size of the block that each pixel was last drawn as part of, where 0 means that the
+    /// pixel itself was sampled.
+    block_sizes: Vec<u32>,
+}
+
+impl Coverage {
+    /// An image of the given size where nothing has been drawn yet.
+    pub fn new(width: u32, height: u32) -> Coverage {
+        Coverage {
+            width,
+            height,
+            block_sizes: vec![u32::MAX; width as usize * height as usize],
+        }
+    }
+
+    /// Records that `pass` sampled the pixel at `(x, y)`, and calls `draw` with each pixel of
+    /// its block that should be drawn with the sample's color: the ones that have not been drawn
+    /// by a finer pass.
+    pub fn draw_sample<F>(&mut self, pass: &Pass, x: u32, y: u32, mut draw: F)
+    where
+        F: FnMut(u32, u32),
+    {
+        let (columns, rows) = pass.block(x, y, self.width, self.height);
+        for py in rows {
+            for px in columns.clone() {
+                let block_size = if (px, py) == (x, y) {
+                    0
+                } else {
+                    pass.block_size
+                };
+                let index = py as usize * self.width as usize + px as usize;
+                if block_size < self.block_sizes[index] {
+                    self.block_sizes[index] = block_size;
+                    draw(px, py);
+                }
+            }
+        }
+    }
+
+    /// Whether every pixel has been sampled.
+    pub fn is_complete(&self) -> bool {
+        self.block_sizes.iter().all(|&block_size| block_size == 0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_passes() {
+        let sizes = |initial| {
+            Pass::passes(initial)
+                .iter()
+                .map(|pass| pass.block_size())
+                .collect::<Vec<u32>>()
+        };
+        assert_eq!(sizes(8), vec![8, 4, 2, 1]);
+        assert_eq!(sizes(6), vec![4, 2, 1]);
+        assert_eq!(sizes(1), vec![1]);
+        assert_eq!(sizes(0), vec![1]);
+    }
+
+    #[test]
+    fn test_every_pixel_sampled_once() {
+        let (width, height) = (21, 13);
+        let mut counts = vec![0; width * height];
+        for pass in Pass::passes(DEFAULT_INITIAL_BLOCK_SIZE) {
+            for x in pass.columns(width as u32) {
+                for y in pass.rows(x, height as u32) {
+                    assert!(pass.samples(x, y));
+                    counts[y as usize * width + x as usize] += 1;
+                }
+            }
+        }
+        assert!(counts.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn test_coverage() {
+        let passes = Pass::passes(4);
+        let mut coverage = Coverage::new(6, 5);
+        let mut drawn = Vec::new();
+
+        // A finer pass draws first, so the coarse block around it skips its pixels
+        coverage.draw_sample(&passes[1], 2, 0, |x, y| drawn.push((x, y)));
+        assert_eq!(drawn, vec![(2, 0), (3, 0), (2, 1), (3, 1)]);
+        drawn.clear();
+        coverage.draw_sample(&passes[0], 0, 0, |x, y| drawn.push((x, y)));
+        assert_eq!(
+            drawn,
+            vec![
+                (0, 0),
+                (1, 0),
+                (0, 1),
+                (1, 1),
+                (0, 2),
+                (1, 2),
+                (2, 2),
+                (3, 2),
+                (0, 3),
+                (1, 3),
+                (2, 3),
+                (3, 3)
+            ]
+        );
+        assert!(!coverage.is_complete());
+
+        for pass in &passes {
+            for x in pass.columns(6) {
+                for y in pass.rows(x, 5) {
+                    coverage.draw_sample(pass, x, y, |_, _| {});
+                }
+            }
+        }
+        assert!(coverage.is_complete());
+    }
+}
@@ -0,0 +1,506 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Separates computing an escape time fractal from coloring it.
+//!
+//! An `EscapeTimeRaster` holds the `EscapeResult` for every pixel of an image, and a `Colorizer`
+//! turns those results into colors. Keeping the results around means that an image can be
+//! recolored without iterating any orbits again, and sharing the `Colorizer` means that every
+//! frontend draws the same fractal with the same colors.
+
+use super::super::color::{self, ColorScale, ColorU8, IterationMapping};
+use super::super::geometry::ViewAreaTransformer;
+use super::*;
+
+/// The `EscapeResult` for each pixel of an image, stored row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EscapeTimeRaster {
+    width: u32,
+    height: u32,
+    results: Vec<EscapeResult>,
+}
+
+impl EscapeTimeRaster {
+    /// Creates a raster of the given size where no pixels have been evaluated yet. Until they
+    /// are, every pixel holds the result for a point that was attracted to 0.
+    pub fn new(width: u32, height: u32) -> EscapeTimeRaster {
+        EscapeTimeRaster {
+            width,
+            height,
+            results: vec![
+                EscapeResult::attracted(Complex64::new(0.0, 0.0));
+                width as usize * height as usize
+            ],
+        }
+    }
+
+    /// Creates a raster of the given size, and evaluates every pixel at the point that `vat`
+    /// maps it to.
+    pub fn evaluate<E>(
+        etsystem: &E,
+        vat: &ViewAreaTransformer,
+        width: u32,
+        height: u32,
+    ) -> EscapeTimeRaster
+    where
+        E: EscapeTime + ?Sized,
+    {
+        let mut raster = EscapeTimeRaster::new(width, height);
+        for y in 0..height {
+            raster.evaluate_row(etsystem, vat, y);
+        }
+        raster
+    }
+
+    /// Evaluates the pixels in row `y`, using `EscapeTime::evaluate_batch()`.
+    pub fn evaluate_row<E>(&mut self, etsystem: &E, vat: &ViewAreaTransformer, y: u32)
+    where
+        E: EscapeTime + ?Sized,
+    {
+        let points = (0..self.width)
+            .map(|x| vat.map_pixel_to_point([f64::from(x), f64::from(y)]).into())
+            .collect::<Vec<Complex64>>();
+        let start = y as usize * self.width as usize;
+        etsystem.evaluate_batch(&points, &mut self.results[start..start + points.len()]);
+    }
+
+    /// Evaluates the pixels in column `x`, using `EscapeTime::evaluate_batch()`.
+    pub fn evaluate_column<E>(&mut self, etsystem: &E, vat: &ViewAreaTransformer, x: u32)
+    where
+        E: EscapeTime + ?Sized,
+    {
+        let points = (0..self.height)
+            .map(|y| vat.map_pixel_to_point([f64::from(x), f64::from(y)]).into())
+            .collect::<Vec<Complex64>>();
+        let mut column = vec![EscapeResult::attracted(Complex64::new(0.0, 0.0)); points.len()];
+        etsystem.evaluate_batch(&points, &mut column);
+        for (y, result) in column.into_iter().enumerate() {
+            self.results[y * self.width as usize + x as usize] = result;
+        }
+    }
+
+    /// Like `EscapeTimeRaster::evaluate()`, but skips the pixels that Mariani–Silver
+    /// subdivision can fill in. Returns the raster along with the number of pixels that were
+    /// actually evaluated.
+    ///
+    /// Subdivision evaluates the border of a rectangle first. If none of the border escapes and
+    /// all of it settled into cycles of the same length, then (for fractals that
+    /// `EscapeTime::allows_subdivision()`) nothing inside the rectangle escapes either, and the
+    /// whole rectangle is filled with the same result. Otherwise the rectangle is split in half,
+    /// and each half is checked the same way. The filled pixels get a copy of the result of a
+    /// pixel on the border, so they have the right period but not their own final value of `z`.
+    ///
+    /// Regions outside of the set are always evaluated, because their smoothed iteration counts
+    /// keep changing even where their whole iteration counts do not. Fractals that do not allow
+    /// subdivision have every pixel evaluated.
+    pub fn evaluate_subdivided<E>(
+        etsystem: &E,
+        vat: &ViewAreaTransformer,
+        width: u32,
+        height: u32,
+    ) -> (EscapeTimeRaster, u64)
+    where
+        E: EscapeTime + ?Sized,
+    {
+        EscapeTimeRaster::evaluate_tile_subdivided(etsystem, vat, [0, 0], width, height)
+    }
+
+    /// Like `EscapeTimeRaster::evaluate_subdivided()`, for a `width` by `height` tile of a larger
+    /// image whose top left corner is at the pixel `origin` of `vat`. Pixel `(x, y)` of the
+    /// resulting raster is pixel `(origin[0] + x, origin[1] + y)` of the image.
+    pub fn evaluate_tile_subdivided<E>(
+        etsystem: &E,
+        vat: &ViewAreaTransformer,
+        origin: [u32; 2],
+        width: u32,
+        height: u32,
+    ) -> (EscapeTimeRaster, u64)
+    where
+        E: EscapeTime + ?Sized,
+    {
+        let mut subdivision = Subdivision {
+            etsystem,
+            vat,
+            origin,
+            raster: EscapeTimeRaster::new(width, height),
+            done: vec![false; width as usize * height as usize],
+            evaluated: 0,
+        };
+        if width > 0 && height > 0 {
+            if etsystem.allows_subdivision() {
+                subdivision.rectangle(0, 0, width - 1, height - 1);
+            } else {
+                let all = (0..height)
+                    .flat_map(|y| (0..width).map(move |x| (x, y)))
+                    .collect::<Vec<(u32, u32)>>();
+                subdivision.evaluate_pixels(&all);
+            }
+        }
+        (subdivision.raster, subdivision.evaluated)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The result for the pixel at `(x, y)`.
+    pub fn get(&self, x: u32, y: u32) -> &EscapeResult {
+        &self.results[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Replaces the result for the pixel at `(x, y)`.
+    pub fn set(&mut self, x: u32, y: u32, result: EscapeResult) {
+        self.results[y as usize * self.width as usize + x as usize] = result;
+    }
+
+    /// The results for every pixel, row by row.
+    pub fn results(&self) -> &[EscapeResult] {
+        &self.results
+    }
+}
+
+/// Rectangles with no more than this many pixels inside of their border are evaluated without
+/// subdividing them further, since checking their borders would save little.
+const MIN_SUBDIVISION_AREA: u32 = 16;
+
+/// The state of `EscapeTimeRaster::evaluate_tile_subdivided()`.
+struct Subdivision<'a, E: ?Sized> {
+    etsystem: &'a E,
+    vat: &'a ViewAreaTransformer,
+    origin: [u32; 2],
+    raster: EscapeTimeRaster,
+    /// Which pixels of the raster have been evaluated or filled in.
+    done: Vec<bool>,
+    /// How many pixels have been evaluated.
+    evaluated: u64,
+}
+
+impl<'a, E> Subdivision<'a, E>
+where
+    E: EscapeTime + ?Sized,
+{
+    /// Evaluates each of `pixels` that is not done yet.
+    fn evaluate_pixels(&mut self, pixels: &[(u32, u32)]) {
+        let width = self.raster.width as usize;
+        let pending = pixels
+            .iter()
+            .cloned()
+            .filter(|&(x, y)| !self.done[y as usize * width + x as usize])
+            .collect::<Vec<(u32, u32)>>();
+        let points = pending
+            .iter()
+            .map(|&(x, y)| {
+                let pixel = [f64::from(self.origin[0] + x), f64::from(self.origin[1] + y)];
+                self.vat.map_pixel_to_point(pixel).into()
+            })
+            .collect::<Vec<Complex64>>();
+        let mut results = vec![EscapeResult::attracted(Complex64::new(0.0, 0.0)); points.len()];
+        self.etsystem.evaluate_batch(&points, &mut results);
+        for (&(x, y), result) in pending.iter().zip(results) {
+            self.raster.set(x, y, result);
+            self.done[y as usize * width + x as usize] = true;
+        }
+        self.evaluated += pending.len() as u64;
+    }
+
+    /// Evaluates or fills in the rectangle from `(left, top)` to `(right, bottom)`, inclusive.
+    fn rectangle(&mut self, left: u32, top: u32, right: u32, bottom: u32) {
+        let mut border = Vec::new();
+        for x in left..=right {
+            border.push((x, top));
+            border.push((x, bottom));
+        }
+        for y in top + 1..bottom {
+            border.push((left, y));
+            border.push((right, y));
+        }
+        self.evaluate_pixels(&border);
+        if right - left < 2 || bottom - top < 2 {
+            // There is nothing inside of the border
+            return;
+        }
+
+        let first = *self.raster.get(left, top);
+        let uniform = border.iter().all(|&(x, y)| {
+            let result = self.raster.get(x, y);
+            !result.escaped && result.period == first.period
+        });
+        let inside = (top + 1..bottom).flat_map(|y| (left + 1..right).map(move |x| (x, y)));
+        if uniform {
+            let width = self.raster.width as usize;
+            for (x, y) in inside {
+                self.raster.set(x, y, first);
+                self.done[y as usize * width + x as usize] = true;
+            }
+        } else if (right - left - 1) * (bottom - top - 1) <= MIN_SUBDIVISION_AREA {
+            self.evaluate_pixels(&inside.collect::<Vec<(u32, u32)>>());
+        } else if right - left >= bottom - top {
+            let middle = left + (right - left) / 2;
+            self.rectangle(left, top, middle, bottom);
+            self.rectangle(middle, top, right, bottom);
+        } else {
+            let middle = top + (bottom - top) / 2;
+            self.rectangle(left, top, right, middle);
+            self.rectangle(left, middle, right, bottom);
+        }
+    }
+}
+
+/// Picks the colors for the results of an escape time fractal.
+pub trait Colorizer {
+    /// The color of the pixel for `result`.
+    fn color(&self, result: &EscapeResult) -> ColorU8;
+
+    /// Colors every pixel of `raster`, returning the RGBA components of each pixel row by row,
+    /// which is the layout that both `image::ImageBuffer` and canvas `ImageData` use.
+    fn colorize(&self, raster: &EscapeTimeRaster) -> Vec<u8> {
+        raster
+            .results()
+            .iter()
+            .flat_map(|result| self.color(result).0.to_vec())
+            .collect()
+    }
+}
+
+/// The number of colors in the default palette, which caps the number of iterations that get
+/// their own shade.
+pub const MAX_PALETTE_SIZE: u64 = 50;
+
+/// Colors the points that escaped by looking up their smoothed iteration count in a palette, and
+/// the points in the interior with `color::interior_color()`.
+///
+/// By default each iteration moves one color along the palette (`ColorScale::Fixed`), but the
+/// palette can also be spread over the iteration counts of a particular frame with
+/// `PaletteColorizer::scaled_to()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteColorizer {
+    palette: Vec<ColorU8>,
+    mapping: IterationMapping,
+}
+
+impl PaletteColorizer {
+    /// The default palette for a fractal that iterates up to `max_iterations` times: a linear
+    /// range from black to white that reaches white after at most `MAX_PALETTE_SIZE` iterations.
+    pub fn new(max_iterations: u64) -> PaletteColorizer {
+        PaletteColorizer::with_palette(color::color_range_linear(
+            color::BLACK_U8,
+            color::WHITE_U8,
+            max_iterations.clamp(2, MAX_PALETTE_SIZE) as usize,
+        ))
+    }
+
+    /// Uses `palette`, which must not be empty.
+    pub fn with_palette(palette: Vec<ColorU8>) -> PaletteColorizer {
+        assert!(!palette.is_empty(), "the palette must have colors");
+        PaletteColorizer {
+            palette,
+            mapping: IterationMapping::Fixed,
+        }
+    }
+
+    /// Spreads the palette over the smoothed iteration counts of the points that escaped in
+    /// `raster`, using `scale`.
+    pub fn scaled_to(mut self, scale: ColorScale, raster: &EscapeTimeRaster) -> PaletteColorizer {
+        let counts = raster
+            .results()
+            .iter()
+            .filter(|result| result.escaped)
+            .map(|result| result.smooth)
+            .collect::<Vec<f64>>();
+        self.mapping = scale.mapping(&counts);
+        self
+    }
+
+    pub fn palette(&self) -> &[ColorU8] {
+        &self.palette
+    }
+}
+
+impl Colorizer for PaletteColorizer {
+    fn color(&self, result: &EscapeResult) -> ColorU8 {
+        if result.escaped {
+            let index = self
+                .mapping
+                .palette_index(result.smooth, self.palette.len());
+            color::palette_lookup_smooth(&self.palette, index)
+        } else {
+            color::interior_color(result.period)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::super::geometry::Point;
+    use super::super::mandelbrot::Mandelbrot;
+    use super::*;
+
+    fn view_area_transformer(width: u32, height: u32) -> ViewAreaTransformer {
+        let mandelbrot = Mandelbrot::new(100, 2);
+        let [a, b] = mandelbrot.default_view_area();
+        ViewAreaTransformer::new(
+            [f64::from(width), f64::from(height)],
+            Point::from(a),
+            Point::from(b),
+        )
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mandelbrot = Mandelbrot::new(100, 2);
+        let vat = view_area_transformer(13, 7);
+        let raster = EscapeTimeRaster::evaluate(&mandelbrot, &vat, 13, 7);
+        assert_eq!(raster.width(), 13);
+        assert_eq!(raster.height(), 7);
+        assert_eq!(raster.results().len(), 13 * 7);
+        for y in 0..7 {
+            for x in 0..13 {
+                let point = vat.map_pixel_to_point([f64::from(x), f64::from(y)]).into();
+                assert_eq!(*raster.get(x, y), mandelbrot.evaluate(point));
+            }
+        }
+
+        let mut by_columns = EscapeTimeRaster::new(13, 7);
+        for x in 0..13 {
+            by_columns.evaluate_column(&mandelbrot, &vat, x);
+        }
+        assert_eq!(by_columns, raster);
+    }
+
+    #[test]
+    fn test_colorize() {
+        let mandelbrot = Mandelbrot::new(100, 2);
+        let vat = view_area_transformer(5, 3);
+        let raster = EscapeTimeRaster::evaluate(&mandelbrot, &vat, 5, 3);
+        let colorizer = PaletteColorizer::new(mandelbrot.max_iterations());
+        assert_eq!(colorizer.palette().len(), MAX_PALETTE_SIZE as usize);
+
+        let pixels = colorizer.colorize(&raster);
+        assert_eq!(pixels.len(), 5 * 3 * 4);
+        assert_eq!(pixels[4 * 6..4 * 7], colorizer.color(raster.get(1, 1)).0);
+
+        let interior = EscapeResult::periodic(Complex64::new(0.0, 0.0), 1);
+        assert_eq!(colorizer.color(&interior), color::interior_color(Some(1)));
+        let escaped = EscapeResult::escaped(500, Complex64::new(3.0, 0.0), 2.0, 2.0);
+        assert_eq!(colorizer.color(&escaped), color::WHITE_U8);
+    }
+
+    /// The parts of a result that subdivision preserves for the pixels that it fills in.
+    fn region(result: &EscapeResult) -> (bool, u64, Option<u64>) {
+        (result.escaped, result.iterations, result.period)
+    }
+
+    #[test]
+    fn test_evaluate_subdivided() {
+        let mandelbrot = Mandelbrot::new(100, 2);
+        let vat = view_area_transformer(120, 80);
+        let raster = EscapeTimeRaster::evaluate(&mandelbrot, &vat, 120, 80);
+        let (subdivided, evaluated) =
+            EscapeTimeRaster::evaluate_subdivided(&mandelbrot, &vat, 120, 80);
+        // Most of the set's interior is filled in
+        let interior = raster.results().iter().filter(|r| !r.escaped).count() as u64;
+        assert!(
+            evaluated < 120 * 80 - interior / 2,
+            "evaluated {} pixels, with {} in the interior",
+            evaluated,
+            interior
+        );
+        for y in 0..80 {
+            for x in 0..120 {
+                assert_eq!(
+                    region(subdivided.get(x, y)),
+                    region(raster.get(x, y)),
+                    "pixel {}, {}",
+                    x,
+                    y
+                );
+            }
+        }
+
+        // A tile matches the same part of the whole image
+        let (tile, _) =
+            EscapeTimeRaster::evaluate_tile_subdivided(&mandelbrot, &vat, [30, 20], 50, 40);
+        for y in 0..40 {
+            for x in 0..50 {
+                assert_eq!(region(tile.get(x, y)), region(raster.get(x + 30, y + 20)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_evaluate_subdivided_disallowed() {
+        let mandelbrot = Mandelbrot::with_bailout(100, 2, EscapeCriterion::Real(2.0));
+        assert!(!mandelbrot.allows_subdivision());
+        let vat = view_area_transformer(30, 20);
+        let (subdivided, evaluated) =
+            EscapeTimeRaster::evaluate_subdivided(&mandelbrot, &vat, 30, 20);
+        assert_eq!(evaluated, 30 * 20);
+        assert_eq!(
+            subdivided,
+            EscapeTimeRaster::evaluate(&mandelbrot, &vat, 30, 20)
+        );
+    }
+
+    #[test]
+    fn test_scaled_to() {
+        // Zoomed in near the boundary, where every point takes more than 50 iterations
+        let mandelbrot = Mandelbrot::new(1000, 2);
+        let vat = ViewAreaTransformer::new(
+            [20.0, 20.0],
+            Point {
+                x: -0.7436,
+                y: 0.1318,
+            },
+            Point {
+                x: -0.7432,
+                y: 0.1314,
+            },
+        );
+        let raster = EscapeTimeRaster::evaluate(&mandelbrot, &vat, 20, 20);
+        let escaped = raster
+            .results()
+            .iter()
+            .filter(|result| result.escaped)
+            .collect::<Vec<&EscapeResult>>();
+        assert!(escaped.iter().all(|result| result.iterations > 50));
+
+        let fixed = PaletteColorizer::new(mandelbrot.max_iterations());
+        assert!(escaped
+            .iter()
+            .all(|result| fixed.color(result) == color::WHITE_U8));
+
+        for &scale in &[ColorScale::Histogram, ColorScale::Range] {
+            let colorizer = fixed.clone().scaled_to(scale, &raster);
+            // The counts span (nearly) the whole palette
+            let shades = escaped
+                .iter()
+                .map(|result| colorizer.color(result).0[0])
+                .collect::<Vec<u8>>();
+            assert!(shades.iter().any(|&shade| shade < 16), "{:?}", scale);
+            assert!(shades.iter().any(|&shade| shade > 239), "{:?}", scale);
+        }
+        let colorizer = fixed.clone().scaled_to(ColorScale::Fixed, &raster);
+        assert_eq!(colorizer, fixed);
+    }
+
+    #[test]
+    fn test_small_palette() {
+        assert_eq!(PaletteColorizer::new(1).palette().len(), 2);
+        assert_eq!(PaletteColorizer::new(10).palette().len(), 10);
+    }
+}
@@ -0,0 +1,311 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Anti-aliasing by supersampling.
+//!
+//! Each pixel normally shows the color of the single point that it maps to, which aliases badly
+//! wherever the fractal has details that are smaller than a pixel, such as the filaments of the
+//! Mandelbrot set. Supersampling picks several points spread over the area of each pixel instead,
+//! and averages their colors in linear light (see `color::average_linear()`).
+
+use super::super::color::{self, ColorU8};
+use num::complex::Complex64;
+use rand::distributions::{Distribution, Uniform};
+
+/// How far apart (in any channel) the colors of neighboring pixels must be for
+/// `Supersampling::Adaptive` to supersample them.
+const ADAPTIVE_THRESHOLD: u8 = 8;
+
+/// How to pick the points that each pixel's color is averaged from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Supersampling {
+    /// Each pixel shows the color of the point that it maps to.
+    None,
+    /// Averages an N by N grid of evenly spaced points that cover the pixel.
+    Grid(u32),
+    /// Splits the pixel into an N by N grid of cells, and averages a random point from each cell,
+    /// which trades the moiré patterns that a regular grid can cause for noise.
+    Jittered(u32),
+    /// Uses the point that each pixel maps to, except for the pixels whose colors differ from
+    /// their neighbors', which average an N by N grid. Most of the extra work goes to the edges
+    /// where aliasing happens.
+    ///
+    /// A pixel's neighbors are above and below it as well as beside it, so this takes two steps:
+    /// `Supersampling::colors()` picks one sample per pixel, and once the whole image has been
+    /// drawn, `edges()` finds the pixels that need to be colored again with
+    /// `Supersampling::edge_sampling()`.
+    Adaptive(u32),
+}
+
+impl Supersampling {
+    /// The names of the modes that `Supersampling::from_name()` accepts.
+    pub const NAMES: [&'static str; 3] = ["grid", "jittered", "adaptive"];
+
+    /// Constructs supersampling from the name of a mode (one of `Supersampling::NAMES`) and the
+    /// number of samples along each side of a pixel. One sample per side results in
+    /// `Supersampling::None`.
+    pub fn from_name(name: &str, samples: u32) -> Result<Supersampling, String> {
+        let supersampling = match name {
+            "grid" => Supersampling::Grid(samples),
+            "jittered" => Supersampling::Jittered(samples),
+            "adaptive" => Supersampling::Adaptive(samples),
+            _ => return Err(format!("Unknown supersampling mode: {}", name)),
+        };
+        match samples {
+            0 => Err("The number of samples must be at least 1".to_string()),
+            1 => Ok(Supersampling::None),
+            _ => Ok(supersampling),
+        }
+    }
+
+    /// The number of samples along each side of a pixel (at most, for
+    /// `Supersampling::Adaptive`).
+    pub fn samples(self) -> u32 {
+        match self {
+            Supersampling::None => 1,
+            Supersampling::Grid(samples)
+            | Supersampling::Jittered(samples)
+            | Supersampling::Adaptive(samples) => samples,
+        }
+    }
+
+    /// Picks the colors of the pixels that `points` map to, where each pixel is `pixel_size`
+    /// wide on the complex plane.
+    ///
+    /// `point_colors` picks the colors of a list of points, and is called with all of the
+    /// samples at once so that it can evaluate them in a batch. `Supersampling::Adaptive` uses
+    /// one sample per pixel here, see `Supersampling::edge_sampling()`.
+    pub fn colors<F>(self, points: &[Complex64], pixel_size: f64, point_colors: F) -> Vec<ColorU8>
+    where
+        F: Fn(&[Complex64]) -> Vec<ColorU8>,
+    {
+        match self {
+            Supersampling::None | Supersampling::Adaptive(_) => point_colors(points),
+            Supersampling::Grid(samples) => {
+                averaged_colors(points, pixel_size, samples, false, &point_colors)
+            }
+            Supersampling::Jittered(samples) => {
+                averaged_colors(points, pixel_size, samples, true, &point_colors)
+            }
+        }
+    }
+
+    /// The supersampling that `Supersampling::Adaptive` colors the pixels that `edges()` finds
+    /// with, after `Supersampling::colors()` has picked one sample for every pixel. The other
+    /// modes are done after `Supersampling::colors()`, so they have none.
+    pub fn edge_sampling(self) -> Option<Supersampling> {
+        match self {
+            Supersampling::Adaptive(samples) => Some(Supersampling::Grid(samples)),
+            _ => None,
+        }
+    }
+}
+
+/// Finds the pixels of an image `width` by `height` pixels whose colors (from `color_at`) differ
+/// from the color of any of the pixels above, below, or beside them, which are the ones that
+/// `Supersampling::Adaptive` supersamples. They are grouped into columns, which are listed from
+/// left to right as the column's `x` and the rows of its edge pixels from top to bottom. Columns
+/// without any edge pixels are left out.
+pub fn edges<F>(width: u32, height: u32, color_at: F) -> Vec<(u32, Vec<u32>)>
+where
+    F: Fn(u32, u32) -> ColorU8,
+{
+    let index = |x: u32, y: u32| x as usize * height as usize + y as usize;
+    let mut is_edge = vec![false; width as usize * height as usize];
+    for x in 0..width {
+        for y in 0..height {
+            let color = color_at(x, y);
+            // Comparing each pair of neighbors once covers every pixel's neighbors
+            for &(nx, ny) in &[(x + 1, y), (x, y + 1)] {
+                if nx < width && ny < height && differ(color, color_at(nx, ny)) {
+                    is_edge[index(x, y)] = true;
+                    is_edge[index(nx, ny)] = true;
+                }
+            }
+        }
+    }
+    (0..width)
+        .map(|x| {
+            let rows = (0..height).filter(|&y| is_edge[index(x, y)]);
+            (x, rows.collect::<Vec<u32>>())
+        })
+        .filter(|(_, rows)| !rows.is_empty())
+        .collect()
+}
+
+/// Whether two colors are far enough apart to be supersampled by `Supersampling::Adaptive`.
+fn differ(a: ColorU8, b: ColorU8) -> bool {
+    a.0.iter()
+        .zip(b.0.iter())
+        .any(|(&a, &b)| (i16::from(a) - i16::from(b)).abs() > i16::from(ADAPTIVE_THRESHOLD))
+}
+
+/// Averages `samples` by `samples` points spread over each pixel, either at the middle of each
+/// cell of the grid or at a random position within it.
+fn averaged_colors<F>(
+    points: &[Complex64],
+    pixel_size: f64,
+    samples: u32,
+    jitter: bool,
+    point_colors: &F,
+) -> Vec<ColorU8>
+where
+    F: Fn(&[Complex64]) -> Vec<ColorU8>,
+{
+    let mut rng = rand::thread_rng();
+    let cell = Uniform::new(0.0, 1.0);
+    let samples = samples.max(1);
+    let mut offset = || {
+        if jitter {
+            cell.sample(&mut rng)
+        } else {
+            0.5
+        }
+    };
+    let mut sample_points = Vec::with_capacity(points.len() * (samples * samples) as usize);
+    for point in points {
+        for row in 0..samples {
+            for column in 0..samples {
+                let dx = (f64::from(column) + offset()) / f64::from(samples) - 0.5;
+                let dy = (f64::from(row) + offset()) / f64::from(samples) - 0.5;
+                // Pixels go down the screen as the imaginary part goes up
+                sample_points.push(point + Complex64::new(dx * pixel_size, -dy * pixel_size));
+            }
+        }
+    }
+    point_colors(&sample_points)
+        .chunks((samples * samples) as usize)
+        .map(color::average_linear)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    /// White to the right of the imaginary axis, and black to the left of it.
+    fn half_plane(points: &[Complex64]) -> Vec<ColorU8> {
+        points
+            .iter()
+            .map(|point| {
+                if point.re > 0.0 {
+                    color::WHITE_U8
+                } else {
+                    color::BLACK_U8
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(
+            Supersampling::from_name("grid", 3),
+            Ok(Supersampling::Grid(3))
+        );
+        assert_eq!(
+            Supersampling::from_name("jittered", 2),
+            Ok(Supersampling::Jittered(2))
+        );
+        assert_eq!(
+            Supersampling::from_name("adaptive", 4),
+            Ok(Supersampling::Adaptive(4))
+        );
+        assert_eq!(
+            Supersampling::from_name("adaptive", 1),
+            Ok(Supersampling::None)
+        );
+        assert!(Supersampling::from_name("grid", 0).is_err());
+        assert!(Supersampling::from_name("random", 2).is_err());
+        assert_eq!(Supersampling::Grid(3).samples(), 3);
+        assert_eq!(Supersampling::None.samples(), 1);
+    }
+
+    #[test]
+    fn test_grid() {
+        let points = [
+            Complex64::new(-3.0, 0.0),
+            Complex64::new(0.0, 0.0),
+            Complex64::new(3.0, 0.0),
+        ];
+        assert_eq!(
+            Supersampling::None.colors(&points, 1.0, half_plane),
+            vec![color::BLACK_U8, color::BLACK_U8, color::WHITE_U8]
+        );
+        let grey = color::average_linear(&[color::BLACK_U8, color::WHITE_U8]);
+        assert_eq!(
+            Supersampling::Grid(2).colors(&points, 1.0, half_plane),
+            vec![color::BLACK_U8, grey, color::WHITE_U8]
+        );
+    }
+
+    #[test]
+    fn test_jittered_stays_within_pixel() {
+        let points = [Complex64::new(10.0, -4.0)];
+        let colors = Supersampling::Jittered(4).colors(&points, 0.5, |samples| {
+            assert_eq!(samples.len(), 16);
+            for sample in samples {
+                assert!((sample.re - 10.0).abs() <= 0.25, "{}", sample);
+                assert!((sample.im + 4.0).abs() <= 0.25, "{}", sample);
+            }
+            half_plane(samples)
+        });
+        assert_eq!(colors, vec![color::WHITE_U8]);
+    }
+
+    #[test]
+    fn test_adaptive() {
+        let calls = Cell::new(0);
+        let counting = |samples: &[Complex64]| {
+            calls.set(calls.get() + 1);
+            assert_eq!(samples.len(), 2);
+            half_plane(samples)
+        };
+
+        // One sample per pixel, until the edges are known
+        let row = [Complex64::new(-0.5, 0.0), Complex64::new(0.5, 0.0)];
+        let colors = Supersampling::Adaptive(3).colors(&row, 1.0, counting);
+        assert_eq!(colors, vec![color::BLACK_U8, color::WHITE_U8]);
+        assert_eq!(calls.get(), 1);
+
+        assert_eq!(
+            Supersampling::Adaptive(3).edge_sampling(),
+            Some(Supersampling::Grid(3))
+        );
+        assert_eq!(Supersampling::Grid(3).edge_sampling(), None);
+        assert_eq!(Supersampling::None.edge_sampling(), None);
+    }
+
+    #[test]
+    fn test_edges() {
+        // Pixel (x, y) shows the point x - 2 + yi, so the imaginary axis is a vertical edge
+        // between the third and fourth columns
+        let color_at =
+            |x: u32, y: u32| half_plane(&[Complex64::new(f64::from(x) - 2.0, f64::from(y))])[0];
+        assert_eq!(
+            edges(6, 3, color_at),
+            vec![(2, vec![0, 1, 2]), (3, vec![0, 1, 2])]
+        );
+
+        // Turning it on its side makes it a horizontal edge
+        assert_eq!(
+            edges(3, 6, |x, y| color_at(y, x)),
+            vec![(0, vec![2, 3]), (1, vec![2, 3]), (2, vec![2, 3])]
+        );
+
+        assert_eq!(edges(4, 4, |_, _| color::WHITE_U8), vec![]);
+        assert_eq!(edges(0, 0, color_at), vec![]);
+    }
+}
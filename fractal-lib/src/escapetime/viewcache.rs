@@ -0,0 +1,317 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reuses the pixels of earlier renders when rendering a view area at the same scale, such as
+//! after panning, so that only the newly exposed pixels need to be computed.
+//!
+//! Two views line up when they have the same pixel size and the top-left pixel of one falls on a
+//! whole pixel of the other. The pixels where they overlap show the same points on the complex
+//! plane, so they can be copied instead of evaluated again.
+
+use super::super::geometry::ViewAreaTransformer;
+use std::cmp;
+use std::collections::VecDeque;
+
+/// The default number of earlier renders that a `ViewCache` keeps.
+pub const DEFAULT_CAPACITY: usize = 8;
+
+/// How far apart (in pixels) the pixels of two views may be and still be treated as the same.
+const PIXEL_TOLERANCE: f64 = 0.01;
+
+/// A rectangle of pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    pub fn new(left: u32, top: u32, width: u32, height: u32) -> Region {
+        Region {
+            left,
+            top,
+            width,
+            height,
+        }
+    }
+
+    /// The number of pixels in the region.
+    pub fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    /// Whether the region has no pixels.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+}
+
+/// Where the pixels of an earlier view appear in a new view that lines up with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    /// The position in the earlier view of the new view's top-left pixel.
+    offset: [i64; 2],
+    /// The width and height of the earlier view.
+    earlier_size: [u32; 2],
+    /// The width and height of the new view.
+    size: [u32; 2],
+}
+
+impl Alignment {
+    /// Checks whether the pixels of `vat` (a view of the given size) line up with the ones of
+    /// `earlier_vat`, and if so, where.
+    pub fn between(
+        earlier_vat: &ViewAreaTransformer,
+        earlier_size: [u32; 2],
+        vat: &ViewAreaTransformer,
+        size: [u32; 2],
+    ) -> Option<Alignment> {
+        // A difference in pixel size adds up across the screen
+        let span = f64::from(cmp::max(size[0], size[1]));
+        let scale_error = (vat.pixel_size() - earlier_vat.pixel_size()).abs() * span;
+        if scale_error > PIXEL_TOLERANCE * earlier_vat.pixel_size() {
+            return None;
+        }
+        let position = earlier_vat.map_point_to_pixel(vat.map_pixel_to_point([0.0, 0.0]));
+        let offset = [position[0].round(), position[1].round()];
+        if (position[0] - offset[0]).abs() > PIXEL_TOLERANCE
+            || (position[1] - offset[1]).abs() > PIXEL_TOLERANCE
+        {
+            return None;
+        }
+        Some(Alignment {
+            offset: [offset[0] as i64, offset[1] as i64],
+            earlier_size,
+            size,
+        })
+    }
+
+    /// The pixels of the new view that the earlier view also has.
+    pub fn overlap(&self) -> Region {
+        let span = |axis: usize| {
+            let start = cmp::max(0, -self.offset[axis]);
+            let end = cmp::min(
+                i64::from(self.size[axis]),
+                i64::from(self.earlier_size[axis]) - self.offset[axis],
+            );
+            (start, cmp::max(end - start, 0))
+        };
+        let (left, width) = span(0);
+        let (top, height) = span(1);
+        if width == 0 || height == 0 {
+            return Region::new(0, 0, 0, 0);
+        }
+        Region::new(left as u32, top as u32, width as u32, height as u32)
+    }
+
+    /// The regions of the new view that are not in the overlap, and still need to be rendered.
+    pub fn exposed(&self) -> Vec<Region> {
+        let [width, height] = self.size;
+        let overlap = self.overlap();
+        if overlap.is_empty() {
+            return vec![Region::new(0, 0, width, height)];
+        }
+        let right = overlap.left + overlap.width;
+        let bottom = overlap.top + overlap.height;
+        vec![
+            Region::new(0, 0, width, overlap.top),
+            Region::new(0, overlap.top, overlap.left, overlap.height),
+            Region::new(right, overlap.top, width - right, overlap.height),
+            Region::new(0, bottom, width, height - bottom),
+        ]
+        .into_iter()
+        .filter(|region| !region.is_empty())
+        .collect()
+    }
+
+    /// The position in the earlier view of the pixel at `(x, y)` in the overlap.
+    pub fn earlier_pixel(&self, x: u32, y: u32) -> (u32, u32) {
+        (
+            (i64::from(x) + self.offset[0]) as u32,
+            (i64::from(y) + self.offset[1]) as u32,
+        )
+    }
+}
+
+/// A finished render, and the view that it shows.
+struct CachedView<T> {
+    vat: ViewAreaTransformer,
+    size: [u32; 2],
+    image: T,
+}
+
+/// Keeps the most recent finished renders (of some image type `T`), so that new views that line
+/// up with one of them can start from its pixels.
+pub struct ViewCache<T> {
+    capacity: usize,
+    /// Most recently used first.
+    views: VecDeque<CachedView<T>>,
+}
+
+impl<T> ViewCache<T> {
+    /// A cache that keeps up to `capacity` renders.
+    pub fn new(capacity: usize) -> ViewCache<T> {
+        ViewCache {
+            capacity,
+            views: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// The number of renders in the cache.
+    pub fn len(&self) -> usize {
+        self.views.len()
+    }
+
+    /// Whether the cache has no renders.
+    pub fn is_empty(&self) -> bool {
+        self.views.is_empty()
+    }
+
+    /// Adds a finished render of `vat` (a view of the given size), replacing any earlier render
+    /// of the same view, and dropping the least recently used render if the cache is full.
+    pub fn insert(&mut self, vat: ViewAreaTransformer, size: [u32; 2], image: T) {
+        self.views.retain(|view| {
+            let alignment = Alignment::between(&view.vat, view.size, &vat, size);
+            !matches!(alignment, Some(alignment) if alignment.offset == [0, 0] && view.size == size)
+        });
+        self.views.push_front(CachedView { vat, size, image });
+        self.views.truncate(self.capacity);
+    }
+
+    /// Finds the render that has the most pixels in common with `vat` (a view of the given
+    /// size), and returns it along with how it lines up with the view.
+    pub fn best_match(
+        &mut self,
+        vat: &ViewAreaTransformer,
+        size: [u32; 2],
+    ) -> Option<(&T, Alignment)> {
+        let (index, alignment) = self
+            .views
+            .iter()
+            .enumerate()
+            .filter_map(|(index, view)| {
+                Alignment::between(&view.vat, view.size, vat, size)
+                    .map(|alignment| (index, alignment))
+            })
+            .filter(|(_, alignment)| !alignment.overlap().is_empty())
+            .max_by_key(|(_, alignment)| alignment.overlap().area())?;
+        let view = self.views.remove(index)?;
+        self.views.push_front(view);
+        Some((&self.views[0].image, alignment))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geometry::Point;
+
+    fn view(x: f64, y: f64) -> ViewAreaTransformer {
+        // 100 by 50 pixels, each 0.01 wide
+        ViewAreaTransformer::new(
+            [100.0, 50.0],
+            Point { x, y },
+            Point {
+                x: x + 1.0,
+                y: y - 0.5,
+            },
+        )
+    }
+
+    #[test]
+    fn test_alignment_after_panning() {
+        let alignment =
+            Alignment::between(&view(0.0, 0.0), [100, 50], &view(0.25, 0.0), [100, 50]).unwrap();
+        assert_eq!(alignment.overlap(), Region::new(0, 0, 75, 50));
+        assert_eq!(alignment.exposed(), vec![Region::new(75, 0, 25, 50)]);
+        assert_eq!(alignment.earlier_pixel(0, 10), (25, 10));
+
+        // Panning up moves the earlier pixels down
+        let alignment =
+            Alignment::between(&view(0.0, 0.0), [100, 50], &view(-0.1, 0.1), [100, 50]).unwrap();
+        assert_eq!(alignment.overlap(), Region::new(10, 10, 90, 40));
+        assert_eq!(
+            alignment.exposed(),
+            vec![Region::new(0, 0, 100, 10), Region::new(0, 10, 10, 40)]
+        );
+        assert_eq!(alignment.earlier_pixel(10, 10), (0, 0));
+    }
+
+    #[test]
+    fn test_alignment_mismatch() {
+        // Shifted by half of a pixel
+        assert_eq!(
+            Alignment::between(&view(0.0, 0.0), [100, 50], &view(0.005, 0.0), [100, 50]),
+            None
+        );
+        // Zoomed in
+        let zoomed = ViewAreaTransformer::new(
+            [100.0, 50.0],
+            Point { x: 0.0, y: 0.0 },
+            Point { x: 0.5, y: -0.25 },
+        );
+        assert_eq!(
+            Alignment::between(&view(0.0, 0.0), [100, 50], &zoomed, [100, 50]),
+            None
+        );
+        // No pixels in common
+        let alignment =
+            Alignment::between(&view(0.0, 0.0), [100, 50], &view(2.0, 0.0), [100, 50]).unwrap();
+        assert!(alignment.overlap().is_empty());
+        assert_eq!(alignment.exposed(), vec![Region::new(0, 0, 100, 50)]);
+    }
+
+    #[test]
+    fn test_exposed_covers_the_rest() {
+        let earlier = ViewAreaTransformer::new(
+            [40.0, 20.0],
+            Point { x: 0.3, y: -0.1 },
+            Point { x: 0.7, y: -0.3 },
+        );
+        let alignment = Alignment::between(&earlier, [40, 20], &view(0.0, 0.0), [100, 50]).unwrap();
+        assert_eq!(alignment.overlap(), Region::new(30, 10, 40, 20));
+        let mut covered = vec![0; 100 * 50];
+        for region in alignment.exposed().iter().chain(&[alignment.overlap()]) {
+            for y in region.top..region.top + region.height {
+                for x in region.left..region.left + region.width {
+                    covered[y as usize * 100 + x as usize] += 1;
+                }
+            }
+        }
+        assert!(covered.iter().all(|&count| count == 1));
+    }
+
+    #[test]
+    fn test_view_cache() {
+        let mut cache = ViewCache::new(2);
+        cache.insert(view(0.0, 0.0), [100, 50], "a");
+        cache.insert(view(3.0, 0.0), [100, 50], "b");
+        // Replaces the first render of the same view
+        cache.insert(view(0.0, 0.0), [100, 50], "c");
+        assert_eq!(cache.len(), 2);
+
+        let (image, alignment) = cache.best_match(&view(0.0, 0.0), [100, 50]).unwrap();
+        assert_eq!(*image, "c");
+        assert!(alignment.exposed().is_empty());
+        let (image, _) = cache.best_match(&view(2.8, 0.1), [100, 50]).unwrap();
+        assert_eq!(*image, "b");
+        assert!(cache.best_match(&view(10.0, 0.0), [100, 50]).is_none());
+
+        // "c" was used least recently, so it is dropped
+        cache.insert(view(5.0, 0.0), [100, 50], "d");
+        assert!(cache.best_match(&view(0.0, 0.0), [100, 50]).is_none());
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The Mandelbox iterates
+//! ```text
+//! f(v) = scale * sphere_fold(box_fold(v)) + c
+//! ```
+//! where the box fold reflects each component of `v` that is outside of `[-1, 1]` back inside,
+//! and the sphere fold inverts `v` through a sphere of radius 1 (and scales up the values that
+//! are very close to the origin by a constant factor instead). A scale of 2 draws a box full of
+//! intricate machinery, while negative scales (such as -1.5) draw rounder shapes.
+//!
+//! Each fold is a (piecewise) isometry or a uniform scaling, so the derivative of the iteration
+//! is just a running scale factor `dr`, and the distance to the set can be estimated as
+//! `|v| / |dr|`.
+
+use super::{DistanceEstimate3D, DistanceEstimator3D, Vector3};
+
+/// The scale of the original Mandelbox.
+pub const DEFAULT_SCALE: f64 = 2.0;
+
+/// Values closer to the origin than this (squared) are scaled up by the sphere fold by a
+/// constant factor.
+const MIN_RADIUS_SQUARED: f64 = 0.25;
+
+/// The radius (squared) of the sphere that the sphere fold inverts values through.
+const FIXED_RADIUS_SQUARED: f64 = 1.0;
+
+/// The radius that a value has to reach to escape.
+const BAILOUT: f64 = 1024.0;
+
+pub struct Mandelbox {
+    max_iterations: u64,
+    scale: f64,
+}
+
+impl Mandelbox {
+    /// The Mandelbox for `scale`, which must be greater than 1 or less than -1.
+    pub fn new(max_iterations: u64, scale: f64) -> Result<Mandelbox, String> {
+        if !scale.is_finite() || scale.abs() <= 1.0 {
+            return Err(
+                "The scale of a Mandelbox must be greater than 1 or less than -1".to_string(),
+            );
+        }
+        Ok(Mandelbox {
+            max_iterations,
+            scale,
+        })
+    }
+}
+
+/// Reflects a component that is outside of `[-1, 1]` back inside.
+fn box_fold(component: f64) -> f64 {
+    component.clamp(-1.0, 1.0) * 2.0 - component
+}
+
+impl DistanceEstimator3D for Mandelbox {
+    fn max_iterations(&self) -> u64 {
+        self.max_iterations
+    }
+
+    fn estimate_distance(&self, point: Vector3) -> DistanceEstimate3D {
+        let mut v = point;
+        let mut dr = 1.0;
+        let mut trap = v.length();
+        for _ in 0..self.max_iterations {
+            v = Vector3::new(box_fold(v.x), box_fold(v.y), box_fold(v.z));
+            let radius_squared = v.dot(v);
+            let fold = if radius_squared < MIN_RADIUS_SQUARED {
+                FIXED_RADIUS_SQUARED / MIN_RADIUS_SQUARED
+            } else if radius_squared < FIXED_RADIUS_SQUARED {
+                FIXED_RADIUS_SQUARED / radius_squared
+            } else {
+                1.0
+            };
+            v = v * (fold * self.scale) + point;
+            dr = dr * fold * self.scale.abs() + 1.0;
+            let radius = v.length();
+            trap = trap.min(radius);
+            if radius > BAILOUT {
+                break;
+            }
+        }
+        DistanceEstimate3D {
+            distance: v.length() / dr,
+            trap,
+        }
+    }
+
+    /// For positive scales, points farther from the origin than `2 (scale + 1) / (scale - 1)`
+    /// along any axis escape, since the folds never move a value more than 2 along any axis, and
+    /// the scale then pushes it farther out. Negative scales flip the value to the other side of
+    /// the origin on every iteration, which keeps the set within 2 of the origin along each axis.
+    fn bounding_radius(&self) -> f64 {
+        let half_width = if self.scale > 0.0 {
+            2.0 * (self.scale + 1.0) / (self.scale - 1.0)
+        } else {
+            2.0
+        };
+        half_width * 3f64.sqrt()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert!(Mandelbox::new(10, DEFAULT_SCALE).is_ok());
+        assert!(Mandelbox::new(10, -1.5).is_ok());
+        assert!(Mandelbox::new(10, 1.0).is_err());
+        assert!(Mandelbox::new(10, 0.5).is_err());
+    }
+
+    #[test]
+    fn test_box_fold() {
+        assert_eq!(box_fold(0.5), 0.5);
+        assert_eq!(box_fold(1.5), 0.5);
+        assert_eq!(box_fold(-3.0), 1.0);
+    }
+
+    #[test]
+    fn test_estimate_distance() {
+        for &scale in &[DEFAULT_SCALE, -1.5] {
+            let mandelbox = Mandelbox::new(30, scale).unwrap();
+            let radius = mandelbox.bounding_radius();
+            // Points on the edge of the bounding sphere are outside of the set, but not much
+            // farther from it than from the origin
+            for &direction in &[
+                Vector3::new(1.0, 0.0, 0.0),
+                Vector3::new(0.0, -1.0, 0.0),
+                Vector3::new(1.0, 1.0, 1.0).normalize(),
+            ] {
+                let point = direction * radius;
+                let estimate = mandelbox.estimate_distance(point);
+                assert!(estimate.distance > 0.0, "{:?} at {:?}", estimate, point);
+                assert!(estimate.distance < radius, "{:?} at {:?}", estimate, point);
+            }
+            // Points inside of the set never escape, and their distance shrinks towards 0
+            let estimate = mandelbox.estimate_distance(Vector3::new(0.0, 0.0, 0.0));
+            assert!(estimate.distance < 1e-3, "{:?} for {}", estimate, scale);
+        }
+        // The corners of the box are on the edge of the set
+        let corner = Vector3::new(1.0, 1.0, 1.0).normalize();
+        for &scale in &[DEFAULT_SCALE, -1.5] {
+            let mandelbox = Mandelbox::new(30, scale).unwrap();
+            let estimate =
+                mandelbox.estimate_distance(corner * (mandelbox.bounding_radius() * 0.99));
+            assert!(estimate.distance < 0.1, "{:?} for {}", estimate, scale);
+        }
+    }
+}
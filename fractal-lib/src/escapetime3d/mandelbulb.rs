@@ -0,0 +1,148 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The Mandelbulb extends the mandelbrot set to three dimensions by iterating
+//! ```text
+//! f(v) = v^n + c
+//! ```
+//! over "triplex" numbers, where `v^n` is defined in spherical coordinates: the radius of `v` is
+//! raised to the nth power, and both of its angles are multiplied by `n`. The traditional
+//! Mandelbulb uses a power of 8.
+//!
+//! Like the mandelbrot set, the distance to the Mandelbulb can be estimated from the running
+//! derivative `dr` of the radius:
+//! ```text
+//! distance = ln(r) r / 2 dr
+//! ```
+
+use super::{DistanceEstimate3D, DistanceEstimator3D, Vector3};
+
+/// The power of the traditional Mandelbulb.
+pub const DEFAULT_POWER: f64 = 8.0;
+
+/// The radius that a value has to reach to escape. A larger radius makes the distance estimate
+/// more accurate, at the cost of a few more iterations.
+const BAILOUT: f64 = 4.0;
+
+pub struct Mandelbulb {
+    max_iterations: u64,
+    power: f64,
+}
+
+impl Mandelbulb {
+    /// The Mandelbulb for `power`, which must be greater than 1.
+    pub fn new(max_iterations: u64, power: f64) -> Result<Mandelbulb, String> {
+        if !power.is_finite() || power <= 1.0 {
+            return Err("The power of a Mandelbulb must be greater than 1".to_string());
+        }
+        Ok(Mandelbulb {
+            max_iterations,
+            power,
+        })
+    }
+}
+
+impl DistanceEstimator3D for Mandelbulb {
+    fn max_iterations(&self) -> u64 {
+        self.max_iterations
+    }
+
+    fn estimate_distance(&self, point: Vector3) -> DistanceEstimate3D {
+        let mut v = point;
+        let mut dr = 1.0;
+        let mut r = v.length();
+        let mut trap = r;
+        for _ in 0..self.max_iterations {
+            if r > BAILOUT {
+                break;
+            }
+            dr = self.power * r.powf(self.power - 1.0) * dr + 1.0;
+            let theta = if r > 0.0 {
+                (v.z / r).acos() * self.power
+            } else {
+                0.0
+            };
+            let phi = v.y.atan2(v.x) * self.power;
+            let scaled = r.powf(self.power);
+            v = Vector3::new(
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            ) * scaled
+                + point;
+            r = v.length();
+            trap = trap.min(r);
+        }
+        DistanceEstimate3D {
+            // Points that never escaped have a radius of less than 1, for a negative distance
+            // (and the origin is its own orbit, with no logarithm at all)
+            distance: if r > 0.0 { 0.5 * r.ln() * r / dr } else { 0.0 },
+            trap,
+        }
+    }
+
+    /// Like the multibrot sets, every point farther than `2^(1 / (n - 1))` from the origin
+    /// escapes, since each iteration moves its value farther away.
+    fn bounding_radius(&self) -> f64 {
+        2f64.powf(1.0 / (self.power - 1.0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        assert!(Mandelbulb::new(10, DEFAULT_POWER).is_ok());
+        assert!(Mandelbulb::new(10, 1.0).is_err());
+        assert!(Mandelbulb::new(10, f64::NAN).is_err());
+    }
+
+    #[test]
+    fn test_estimate_distance() {
+        let bulb = Mandelbulb::new(20, DEFAULT_POWER).unwrap();
+        // The origin is a fixed point, deep inside of the set
+        assert!(bulb.estimate_distance(Vector3::new(0.0, 0.0, 0.0)).distance <= 0.0);
+        assert!(
+            bulb.estimate_distance(Vector3::new(0.1, 0.2, -0.1))
+                .distance
+                <= 0.0
+        );
+        // Points outside are at most as far from the surface as from the origin (which is inside
+        // of the set)
+        for &point in &[
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(0.0, -1.3, 0.4),
+            Vector3::new(0.9, 0.9, 0.9),
+            Vector3::new(0.0, 0.0, 3.0),
+        ] {
+            let estimate = bulb.estimate_distance(point);
+            assert!(estimate.distance > 0.0, "{:?} at {:?}", estimate, point);
+            assert!(estimate.distance < point.length(), "{:?}", point);
+            assert!(estimate.trap <= point.length());
+        }
+        // Moving away from the set increases the distance
+        let near = bulb.estimate_distance(Vector3::new(1.2, 0.0, 0.0)).distance;
+        let far = bulb.estimate_distance(Vector3::new(1.5, 0.0, 0.0)).distance;
+        assert!(near < far);
+    }
+
+    #[test]
+    fn test_bounding_radius() {
+        let bulb = Mandelbulb::new(50, DEFAULT_POWER).unwrap();
+        assert_approx_eq!(bulb.bounding_radius(), 1.104, 1e-3);
+        assert_eq!(Mandelbulb::new(50, 2.0).unwrap().bounding_radius(), 2.0);
+    }
+}
@@ -0,0 +1,143 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Escape time fractals in three dimensions, such as the Mandelbulb and the Mandelbox.
+//!
+//! These fractals iterate points in space instead of on the complex plane, and their sets are
+//! solids, so there is no plane to draw each point onto. Instead, each fractal estimates the
+//! distance from any point to the surface of its set, and `raymarch` uses those estimates to trace
+//! rays from a camera to the surface: a ray can always step as far as the estimate without
+//! passing through the surface.
+
+pub mod mandelbox;
+pub mod mandelbulb;
+pub mod raymarch;
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// A point or a direction in three dimensions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vector3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector3 {
+    pub fn new(x: f64, y: f64, z: f64) -> Vector3 {
+        Vector3 { x, y, z }
+    }
+
+    pub fn dot(self, other: Vector3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: Vector3) -> Vector3 {
+        Vector3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// The vector in the same direction with a length of 1. The zero vector has no direction, so
+    /// its components come out as NaN.
+    pub fn normalize(self) -> Vector3 {
+        self * (1.0 / self.length())
+    }
+}
+
+impl Add for Vector3 {
+    type Output = Vector3;
+
+    fn add(self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x + other.x, self.y + other.y, self.z + other.z)
+    }
+}
+
+impl Sub for Vector3 {
+    type Output = Vector3;
+
+    fn sub(self, other: Vector3) -> Vector3 {
+        Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z)
+    }
+}
+
+impl Mul<f64> for Vector3 {
+    type Output = Vector3;
+
+    fn mul(self, factor: f64) -> Vector3 {
+        Vector3::new(self.x * factor, self.y * factor, self.z * factor)
+    }
+}
+
+impl Neg for Vector3 {
+    type Output = Vector3;
+
+    fn neg(self) -> Vector3 {
+        Vector3::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// The result of testing a single point with `DistanceEstimator3D::estimate_distance()`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceEstimate3D {
+    /// The estimated distance from the point to the surface of the fractal's set, which is 0 or
+    /// less for points inside of the set. Like the distance estimates of `EscapeTime` fractals,
+    /// it is only accurate to within a small factor, so ray marching steps a bit short of it.
+    pub distance: f64,
+    /// The smallest distance from the origin that the point's orbit came to, for coloring the
+    /// surface.
+    pub trap: f64,
+}
+
+/// An escape time fractal in three dimensions, which can estimate how far any point is from the
+/// surface of its set.
+pub trait DistanceEstimator3D {
+    fn max_iterations(&self) -> u64;
+
+    /// Iterates `point`, and estimates the distance from it to the surface of the set.
+    fn estimate_distance(&self, point: Vector3) -> DistanceEstimate3D;
+
+    /// The radius of a sphere around the origin that contains the whole set. Rays that leave it
+    /// can not hit anything.
+    fn bounding_radius(&self) -> f64;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_vector3() {
+        let x = Vector3::new(1.0, 0.0, 0.0);
+        let y = Vector3::new(0.0, 1.0, 0.0);
+        assert_eq!(x.cross(y), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(y.cross(x), Vector3::new(0.0, 0.0, -1.0));
+        assert_eq!(x.dot(y), 0.0);
+
+        let v = Vector3::new(3.0, 0.0, -4.0);
+        assert_eq!(v.length(), 5.0);
+        let unit = v.normalize();
+        assert_approx_eq!(unit.x, 0.6, 1e-12);
+        assert_approx_eq!(unit.z, -0.8, 1e-12);
+        assert_eq!(v + x - y * 2.0, Vector3::new(4.0, -2.0, -4.0));
+        assert_eq!(-v, Vector3::new(-3.0, 0.0, 4.0));
+        assert!(Vector3::new(0.0, 0.0, 0.0).normalize().x.is_nan());
+    }
+}
@@ -0,0 +1,463 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Sphere tracing, which renders a `DistanceEstimator3D` as seen from a camera.
+//!
+//! Each pixel casts a ray from the camera, which steps forward by the distance estimate of the
+//! point it has reached until the estimate falls below the size of a pixel at that distance. The
+//! surface it hits is then shaded with the Blinn-Phong model, darkened in creases by ambient
+//! occlusion (sampling the distance estimates along the surface normal), and shadowed by
+//! marching a second ray towards the light, whose closest approach to the surface softens the
+//! edges of the shadows.
+
+use super::super::color::{self, ColorU8};
+use super::{DistanceEstimate3D, DistanceEstimator3D, Vector3};
+
+/// The default vertical field of view of a camera, in degrees.
+pub const DEFAULT_FIELD_OF_VIEW: f64 = 45.0;
+
+/// The default number of steps that a ray can take before it is assumed to have hit the surface.
+pub const DEFAULT_MAX_STEPS: u32 = 200;
+
+/// The default strength of ambient occlusion.
+pub const DEFAULT_AMBIENT_OCCLUSION: f64 = 1.0;
+
+/// The default hardness of the edges of shadows.
+pub const DEFAULT_SOFT_SHADOWS: f64 = 16.0;
+
+/// Rays step slightly less than the distance estimate, since it may be a bit too large.
+const STEP_FACTOR: f64 = 0.9;
+
+/// The closest that a ray needs to get to the surface to hit it, however close it is to the
+/// camera.
+const MIN_EPSILON: f64 = 1e-6;
+
+/// The number of samples taken along the normal for ambient occlusion.
+const OCCLUSION_SAMPLES: u32 = 5;
+
+/// The direction that the light comes from, unless it is changed.
+fn default_light() -> Vector3 {
+    Vector3::new(1.0, -1.0, 2.0).normalize()
+}
+
+/// A pinhole camera, with `z` pointing up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    position: Vector3,
+    forward: Vector3,
+    right: Vector3,
+    up: Vector3,
+    /// The tangent of half the vertical field of view.
+    tan_half_fov: f64,
+}
+
+impl Camera {
+    /// A camera at `position` looking at `target`, tilted so that `up` points towards the top of
+    /// the image. `field_of_view` is the vertical angle that the image spans, in degrees.
+    pub fn new(
+        position: Vector3,
+        target: Vector3,
+        up: Vector3,
+        field_of_view: f64,
+    ) -> Result<Camera, String> {
+        let forward = target - position;
+        if forward.length() == 0.0 {
+            return Err("The camera can not look at its own position".to_string());
+        }
+        let forward = forward.normalize();
+        let right = forward.cross(up);
+        if right.length() < 1e-9 {
+            return Err(
+                "The up direction of the camera can not be parallel to its view".to_string(),
+            );
+        }
+        let right = right.normalize();
+        if !field_of_view.is_finite() || field_of_view <= 0.0 || field_of_view >= 180.0 {
+            return Err("The field of view must be between 0 and 180 degrees".to_string());
+        }
+        Ok(Camera {
+            position,
+            forward,
+            right,
+            up: right.cross(forward),
+            tan_half_fov: (field_of_view.to_radians() / 2.0).tan(),
+        })
+    }
+
+    /// A camera that looks at the origin from above and in front, far enough away that a sphere
+    /// of `bounding_radius` fills most of the image.
+    pub fn overview(bounding_radius: f64) -> Camera {
+        let position = Vector3::new(1.0, -2.0, 1.0).normalize() * (2.7 * bounding_radius);
+        Camera::new(
+            position,
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            DEFAULT_FIELD_OF_VIEW,
+        )
+        .unwrap()
+    }
+
+    pub fn position(&self) -> Vector3 {
+        self.position
+    }
+
+    /// The direction of the ray through pixel `(x, y)` of an image of `width` by `height`
+    /// pixels, with `y` pointing down.
+    pub fn ray(&self, x: f64, y: f64, width: u32, height: u32) -> Vector3 {
+        let aspect = f64::from(width) / f64::from(height);
+        let u = (2.0 * (x + 0.5) / f64::from(width) - 1.0) * self.tan_half_fov * aspect;
+        let v = (1.0 - 2.0 * (y + 0.5) / f64::from(height)) * self.tan_half_fov;
+        (self.forward + self.right * u + self.up * v).normalize()
+    }
+
+    /// The angle that a single pixel spans in an image `height` pixels tall.
+    fn pixel_angle(&self, height: u32) -> f64 {
+        2.0 * self.tan_half_fov / f64::from(height)
+    }
+}
+
+/// Where a ray hit the surface.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub position: Vector3,
+    /// How far the ray travelled.
+    pub distance: f64,
+    /// The distance estimate at `position`.
+    pub estimate: DistanceEstimate3D,
+    /// The distance from the surface that counted as a hit.
+    pub epsilon: f64,
+}
+
+/// Renders a `DistanceEstimator3D` by sphere tracing.
+pub struct RayMarcher<F: DistanceEstimator3D> {
+    fractal: F,
+    camera: Camera,
+    light: Vector3,
+    ambient_occlusion: f64,
+    soft_shadows: f64,
+    max_steps: u32,
+    detail: f64,
+}
+
+impl<F: DistanceEstimator3D> RayMarcher<F> {
+    pub fn new(fractal: F, camera: Camera) -> RayMarcher<F> {
+        RayMarcher {
+            fractal,
+            camera,
+            light: default_light(),
+            ambient_occlusion: DEFAULT_AMBIENT_OCCLUSION,
+            soft_shadows: DEFAULT_SOFT_SHADOWS,
+            max_steps: DEFAULT_MAX_STEPS,
+            detail: 1.0,
+        }
+    }
+
+    /// Lights the fractal from `direction` (pointing towards the light).
+    pub fn with_light(mut self, direction: Vector3) -> Result<RayMarcher<F>, String> {
+        if !direction.length().is_finite() || direction.length() == 0.0 {
+            return Err("The light must come from a direction".to_string());
+        }
+        self.light = direction.normalize();
+        Ok(self)
+    }
+
+    /// How much creases are darkened by ambient occlusion, from 0 (not at all) to 1.
+    pub fn with_ambient_occlusion(mut self, strength: f64) -> RayMarcher<F> {
+        self.ambient_occlusion = strength.clamp(0.0, 1.0);
+        self
+    }
+
+    /// How hard the edges of shadows are. Larger values give sharper edges, and 0 turns off
+    /// shadows.
+    pub fn with_soft_shadows(mut self, hardness: f64) -> RayMarcher<F> {
+        self.soft_shadows = hardness.max(0.0);
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> RayMarcher<F> {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// Scales how close rays have to get to the surface to hit it. A detail of 2 resolves
+    /// features half the size of a pixel, at the cost of more steps.
+    pub fn with_detail(mut self, detail: f64) -> Result<RayMarcher<F>, String> {
+        if !detail.is_finite() || detail <= 0.0 {
+            return Err("The detail must be greater than 0".to_string());
+        }
+        self.detail = detail;
+        Ok(self)
+    }
+
+    pub fn fractal(&self) -> &F {
+        &self.fractal
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// The distances along the ray from `origin` in `direction` where it enters and leaves the
+    /// bounding sphere of the fractal, if it passes through it at all.
+    fn bounds(&self, origin: Vector3, direction: Vector3) -> Option<(f64, f64)> {
+        let radius = self.fractal.bounding_radius();
+        let b = origin.dot(direction);
+        let c = origin.dot(origin) - radius * radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        let exit = -b + root;
+        if exit < 0.0 {
+            return None;
+        }
+        Some(((-b - root).max(0.0), exit))
+    }
+
+    /// Marches the ray from `origin` in `direction` (which must have a length of 1) until it
+    /// hits the surface, where the surface counts as hit once the ray is within `pixel_angle`
+    /// of it as seen from `origin`.
+    pub fn march(&self, origin: Vector3, direction: Vector3, pixel_angle: f64) -> Option<Hit> {
+        let (mut distance, exit) = self.bounds(origin, direction)?;
+        for step in 0..self.max_steps {
+            let position = origin + direction * distance;
+            let estimate = self.fractal.estimate_distance(position);
+            let epsilon = (distance * pixel_angle / self.detail).max(MIN_EPSILON);
+            // Rays that run out of steps are crawling along the surface, so they count as hits
+            if estimate.distance < epsilon || step + 1 == self.max_steps {
+                return Some(Hit {
+                    position,
+                    distance,
+                    estimate,
+                    epsilon,
+                });
+            }
+            distance += estimate.distance * STEP_FACTOR;
+            if distance > exit {
+                return None;
+            }
+        }
+        None
+    }
+
+    /// The normal of the surface at `position`, from the gradient of the distance estimate
+    /// sampled `epsilon` apart.
+    pub fn normal(&self, position: Vector3, epsilon: f64) -> Vector3 {
+        let gradient = |offset: Vector3| {
+            self.fractal.estimate_distance(position + offset).distance
+                - self.fractal.estimate_distance(position - offset).distance
+        };
+        let normal = Vector3::new(
+            gradient(Vector3::new(epsilon, 0.0, 0.0)),
+            gradient(Vector3::new(0.0, epsilon, 0.0)),
+            gradient(Vector3::new(0.0, 0.0, epsilon)),
+        );
+        if normal.length() > 0.0 {
+            normal.normalize()
+        } else {
+            // Deep inside of the set the estimate is flat, so face the light
+            self.light
+        }
+    }
+
+    /// How much ambient light reaches `position` on the surface, from 0 (fully occluded) to 1.
+    ///
+    /// Points along the normal should be as far from the surface as from `position`, unless
+    /// other parts of the surface are nearby. The shortfall is summed over a few samples, with
+    /// the nearest ones weighted most.
+    pub fn ambient_occlusion(&self, position: Vector3, normal: Vector3, epsilon: f64) -> f64 {
+        if self.ambient_occlusion == 0.0 {
+            return 1.0;
+        }
+        let spacing = (0.02 * self.fractal.bounding_radius()).max(epsilon);
+        let mut occlusion = 0.0;
+        let mut total = 0.0;
+        let mut weight = 1.0;
+        for i in 1..=OCCLUSION_SAMPLES {
+            let offset = spacing * f64::from(i);
+            let estimate = self.fractal.estimate_distance(position + normal * offset);
+            occlusion += weight * (offset - estimate.distance.max(0.0)).clamp(0.0, offset);
+            total += weight * offset;
+            weight /= 2.0;
+        }
+        1.0 - self.ambient_occlusion * occlusion / total
+    }
+
+    /// How much of the light reaches `position` on the surface, from 0 (in full shadow) to 1.
+    /// Rays that pass close to the surface on their way to the light are partly shadowed.
+    pub fn soft_shadow(&self, position: Vector3, normal: Vector3, epsilon: f64) -> f64 {
+        if self.soft_shadows == 0.0 {
+            return 1.0;
+        }
+        let origin = position + normal * (2.0 * epsilon);
+        let exit = match self.bounds(origin, self.light) {
+            Some((_, exit)) => exit,
+            None => return 1.0,
+        };
+        let mut light: f64 = 1.0;
+        let mut distance = epsilon;
+        for _ in 0..self.max_steps {
+            let estimate = self
+                .fractal
+                .estimate_distance(origin + self.light * distance);
+            if estimate.distance < epsilon {
+                return 0.0;
+            }
+            light = light.min(self.soft_shadows * estimate.distance / distance);
+            distance += estimate.distance * STEP_FACTOR;
+            if distance > exit {
+                break;
+            }
+        }
+        light.clamp(0.0, 1.0)
+    }
+
+    /// The color of pixel `(x, y)` of an image of `width` by `height` pixels.
+    pub fn color(&self, x: u32, y: u32, width: u32, height: u32) -> ColorU8 {
+        let direction = self.camera.ray(f64::from(x), f64::from(y), width, height);
+        let hit = match self.march(
+            self.camera.position,
+            direction,
+            self.camera.pixel_angle(height),
+        ) {
+            Some(hit) => hit,
+            None => return background(direction),
+        };
+
+        let normal = self.normal(hit.position, hit.epsilon);
+        let occlusion = self.ambient_occlusion(hit.position, normal, hit.epsilon);
+        let shadow = self.soft_shadow(hit.position, normal, hit.epsilon);
+        let diffuse = normal.dot(self.light).max(0.0) * shadow;
+        let halfway = (self.light - direction).normalize();
+        let specular = normal.dot(halfway).max(0.0).powf(32.0) * shadow;
+
+        // Hue follows how close the orbit came to the origin, relative to the size of the set
+        let trap = hit.estimate.trap / self.fractal.bounding_radius();
+        let ColorU8(base) = color::hsv_to_rgb(200.0 + 160.0 * trap, 0.6, 1.0);
+        let mut lit = [0; 4];
+        for (lit, &channel) in lit.iter_mut().zip(base.iter()).take(3) {
+            let linear = color::srgb_to_linear(channel);
+            *lit = color::linear_to_srgb(
+                linear * (0.15 * occlusion + 0.85 * diffuse) + 0.3 * specular,
+            );
+        }
+        lit[3] = 255;
+        ColorU8(lit)
+    }
+
+    /// The colors of row `y` of an image of `width` by `height` pixels.
+    pub fn render_row(&self, y: u32, width: u32, height: u32) -> Vec<ColorU8> {
+        (0..width)
+            .map(|x| self.color(x, y, width, height))
+            .collect()
+    }
+}
+
+/// The color of rays that miss the fractal, a gradient from dark at the horizon to light
+/// overhead.
+fn background(direction: Vector3) -> ColorU8 {
+    color::lerp(
+        ColorU8([20, 22, 30, 255]),
+        ColorU8([70, 80, 110, 255]),
+        (direction.z * 0.5 + 0.5).clamp(0.0, 1.0),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::mandelbox::{self, Mandelbox};
+    use super::super::mandelbulb::{self, Mandelbulb};
+    use super::*;
+
+    fn mandelbulb() -> RayMarcher<Mandelbulb> {
+        let bulb = Mandelbulb::new(10, mandelbulb::DEFAULT_POWER).unwrap();
+        let camera = Camera::overview(bulb.bounding_radius());
+        RayMarcher::new(bulb, camera)
+    }
+
+    #[test]
+    fn test_camera() {
+        let origin = Vector3::new(0.0, 0.0, 0.0);
+        let up = Vector3::new(0.0, 0.0, 1.0);
+        let camera = Camera::new(Vector3::new(0.0, -5.0, 0.0), origin, up, 90.0).unwrap();
+        let center = camera.ray(1.0, 1.0, 3, 3);
+        assert_approx_eq!(center.y, 1.0, 1e-12);
+        // The right and top of the image point towards +x and +z
+        assert!(camera.ray(2.0, 1.0, 3, 3).x > 0.0);
+        assert!(camera.ray(1.0, 0.0, 3, 3).z > 0.0);
+
+        assert!(Camera::new(origin, origin, up, 45.0).is_err());
+        assert!(Camera::new(Vector3::new(0.0, 0.0, 5.0), origin, up, 45.0).is_err());
+        assert!(Camera::new(Vector3::new(0.0, -5.0, 0.0), origin, up, 180.0).is_err());
+    }
+
+    #[test]
+    fn test_march() {
+        let marcher = mandelbulb();
+        let camera = *marcher.camera();
+        let pixel_angle = camera.pixel_angle(64);
+        let hit = marcher
+            .march(
+                camera.position(),
+                camera.ray(31.5, 31.5, 64, 64),
+                pixel_angle,
+            )
+            .expect("The center of the image should hit the bulb");
+        assert!(hit.position.length() < marcher.fractal().bounding_radius());
+        assert!(hit.estimate.distance < hit.epsilon);
+
+        assert!(marcher
+            .march(camera.position(), camera.ray(0.0, 0.0, 64, 64), pixel_angle)
+            .is_none());
+        assert_eq!(
+            marcher.color(0, 0, 64, 64),
+            background(camera.ray(0.0, 0.0, 64, 64))
+        );
+        assert_ne!(
+            marcher.color(32, 32, 64, 64),
+            background(camera.ray(32.0, 32.0, 64, 64))
+        );
+    }
+
+    #[test]
+    fn test_shading() {
+        let mandelbox = Mandelbox::new(10, mandelbox::DEFAULT_SCALE).unwrap();
+        let camera = Camera::overview(mandelbox.bounding_radius());
+        for marcher in &[
+            RayMarcher::new(mandelbox, camera),
+            RayMarcher::new(Mandelbox::new(10, 2.0).unwrap(), camera)
+                .with_ambient_occlusion(0.0)
+                .with_soft_shadows(0.0),
+        ] {
+            for y in (0..32).step_by(4) {
+                for x in (0..32).step_by(4) {
+                    let direction = camera.ray(f64::from(x), f64::from(y), 32, 32);
+                    if let Some(hit) =
+                        marcher.march(camera.position(), direction, camera.pixel_angle(32))
+                    {
+                        let normal = marcher.normal(hit.position, hit.epsilon);
+                        assert_approx_eq!(normal.length(), 1.0, 1e-9);
+                        let occlusion =
+                            marcher.ambient_occlusion(hit.position, normal, hit.epsilon);
+                        assert!((0.0..=1.0).contains(&occlusion));
+                        let shadow = marcher.soft_shadow(hit.position, normal, hit.epsilon);
+                        assert!((0.0..=1.0).contains(&shadow));
+                    }
+                }
+            }
+        }
+        assert_eq!(mandelbulb().render_row(3, 20, 10).len(), 20);
+    }
+}
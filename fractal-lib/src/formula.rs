@@ -0,0 +1,719 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A small expression language for complex-valued formulas, so that fractals can be defined at
+//! runtime instead of in Rust (see `escapetime::formula`).
+//!
+//! A formula is made up of:
+//!
+//! * Numbers, such as `2`, `0.5`, or `1e-3`, and imaginary numbers, such as `2i` or `0.5i`
+//! * The constants `i`, `pi`, and `e`
+//! * The variables `z`, `c`, and the parameters `p1`, `p2`, `p3`, and `p4`
+//! * The operators `+`, `-`, `*`, `/`, and `^` (which is right associative and binds more
+//!   tightly than negation, so `-z^2` is `-(z^2)`), along with parentheses
+//! * The functions `abs`, `arg`, `conj`, `re`, `im`, `pow`, `sqrt`, `exp`, `log`, `sin`, `cos`,
+//!   `tan`, `sinh`, `cosh`, and `tanh`
+//!
+//! All values are complex numbers. The functions that produce a real number (`abs`, `arg`, `re`,
+//! and `im`) return it as a complex number with an imaginary part of 0. Multi-valued functions
+//! use their principal branch (see `geometry::cpow_general()`).
+//!
+//! ```
+//! use fractal_lib::formula::{Formula, Variables};
+//! use num::complex::Complex64;
+//!
+//! let formula = Formula::parse("(abs(re(z)) + i*abs(im(z)))^2 + c").unwrap();
+//! let variables = Variables::new(Complex64::new(-1.0, 1.0), Complex64::new(0.5, 0.0));
+//! assert_eq!(formula.evaluate(&variables), Complex64::new(0.5, 2.0));
+//! ```
+
+use super::geometry::{self, Exponent};
+use num::complex::Complex64;
+use std::f64::consts::{E, PI};
+use std::fmt;
+
+/// The values of the variables that a formula may refer to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Variables {
+    pub z: Complex64,
+    pub c: Complex64,
+    /// `p1` through `p4`.
+    pub parameters: [Complex64; 4],
+}
+
+impl Variables {
+    /// Creates the variables for `z` and `c`, with every parameter set to 0.
+    pub fn new(z: Complex64, c: Complex64) -> Variables {
+        Variables {
+            z,
+            c,
+            parameters: [Complex64::new(0.0, 0.0); 4],
+        }
+    }
+}
+
+/// A variable that a formula refers to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Variable {
+    Z,
+    C,
+    /// `p1` through `p4`, indexed from 0.
+    Parameter(usize),
+}
+
+/// The functions that a formula may call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Function {
+    Abs,
+    Arg,
+    Conj,
+    Re,
+    Im,
+    Pow,
+    Sqrt,
+    Exp,
+    Log,
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+}
+
+impl Function {
+    /// The most arguments that any function takes.
+    pub const MAX_ARITY: usize = 2;
+
+    /// Looks up a function by the name that is used in formulas.
+    pub fn from_name(name: &str) -> Option<Function> {
+        let function = match name {
+            "abs" => Function::Abs,
+            "arg" => Function::Arg,
+            "conj" => Function::Conj,
+            "re" => Function::Re,
+            "im" => Function::Im,
+            "pow" => Function::Pow,
+            "sqrt" => Function::Sqrt,
+            "exp" => Function::Exp,
+            "log" => Function::Log,
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            _ => return None,
+        };
+        Some(function)
+    }
+
+    /// The number of arguments that the function takes.
+    pub fn arity(self) -> usize {
+        match self {
+            Function::Pow => 2,
+            _ => 1,
+        }
+    }
+
+    fn apply(self, args: &[Complex64]) -> Complex64 {
+        let x = args[0];
+        match self {
+            Function::Abs => Complex64::new(x.norm(), 0.0),
+            Function::Arg => Complex64::new(x.arg(), 0.0),
+            Function::Conj => x.conj(),
+            Function::Re => Complex64::new(x.re, 0.0),
+            Function::Im => Complex64::new(x.im, 0.0),
+            Function::Pow => power(x, args[1]),
+            Function::Sqrt => x.sqrt(),
+            Function::Exp => x.exp(),
+            Function::Log => x.ln(),
+            Function::Sin => x.sin(),
+            Function::Cos => x.cos(),
+            Function::Tan => x.tan(),
+            Function::Sinh => x.sinh(),
+            Function::Cosh => x.cosh(),
+            Function::Tanh => x.tanh(),
+        }
+    }
+}
+
+/// A binary operator.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+/// A parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Constant(Complex64),
+    Variable(Variable),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+impl Expression {
+    /// Computes the value of the expression.
+    pub fn evaluate(&self, variables: &Variables) -> Complex64 {
+        match self {
+            Expression::Constant(value) => *value,
+            Expression::Variable(Variable::Z) => variables.z,
+            Expression::Variable(Variable::C) => variables.c,
+            Expression::Variable(Variable::Parameter(index)) => variables.parameters[*index],
+            Expression::Negate(operand) => -operand.evaluate(variables),
+            Expression::Binary(operator, left, right) => {
+                let left = left.evaluate(variables);
+                let right = right.evaluate(variables);
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Subtract => left - right,
+                    Operator::Multiply => left * right,
+                    Operator::Divide => left / right,
+                    Operator::Power => power(left, right),
+                }
+            }
+            Expression::Call(function, args) => {
+                // Formulas are evaluated for every iteration of every point, so the arguments
+                // are kept on the stack instead of in a new Vec
+                let mut values = [Complex64::new(0.0, 0.0); Function::MAX_ARITY];
+                for (value, arg) in values.iter_mut().zip(args) {
+                    *value = arg.evaluate(variables);
+                }
+                function.apply(&values[..args.len()])
+            }
+        }
+    }
+}
+
+/// Raises `base` to `exponent`, using exact repeated multiplication when the exponent is an
+/// integer.
+fn power(base: Complex64, exponent: Complex64) -> Complex64 {
+    if exponent.im == 0.0 && exponent.re.fract() == 0.0 && exponent.re.abs() <= 1024.0 {
+        geometry::cpow_general(base, Exponent::Integer(exponent.re as i64))
+    } else {
+        geometry::cpow_general(base, Exponent::Complex(exponent))
+    }
+}
+
+/// A formula that has been parsed from its source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    source: String,
+    expression: Expression,
+}
+
+impl Formula {
+    /// Parses a formula.
+    ///
+    /// If the formula is invalid, the error describes the problem and points out the column
+    /// (counted in characters, starting from 1) where it was found.
+    pub fn parse(source: &str) -> Result<Formula, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            source,
+            tokens: &tokens,
+            next: 0,
+            depth: 0,
+        };
+        let expression = parser.expression()?;
+        if let Some(token) = parser.peek() {
+            return Err(parser.error_at(token.position, format!("Unexpected {}", token.kind)));
+        }
+        Ok(Formula {
+            source: source.to_string(),
+            expression,
+        })
+    }
+
+    /// The text that the formula was parsed from.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    /// Computes the value of the formula.
+    pub fn evaluate(&self, variables: &Variables) -> Complex64 {
+        self.expression.evaluate(variables)
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+/// Describes a problem in `source` at the 0-based character `position`, and draws a caret under
+/// it.
+fn error_at(source: &str, position: usize, message: String) -> String {
+    format!(
+        "{} at column {}:\n{}\n{}^",
+        message,
+        position + 1,
+        source,
+        " ".repeat(position)
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Number(f64),
+    Imaginary(f64),
+    Identifier(String),
+    Symbol(char),
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Number(value) => write!(f, "number {}", value),
+            TokenKind::Imaginary(value) => write!(f, "number {}i", value),
+            TokenKind::Identifier(name) => write!(f, "'{}'", name),
+            TokenKind::Symbol(symbol) => write!(f, "'{}'", symbol),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token {
+    kind: TokenKind,
+    /// The 0-based character that the token starts at.
+    position: usize,
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars = source.chars().collect::<Vec<char>>();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let start = index;
+        let c = chars[index];
+        if c.is_whitespace() {
+            index += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.') {
+                index += 1;
+            }
+            // An `e` is only an exponent if digits follow it, since it may be the constant e
+            if index < chars.len() && (chars[index] == 'e' || chars[index] == 'E') {
+                let mut end = index + 1;
+                if end < chars.len() && (chars[end] == '+' || chars[end] == '-') {
+                    end += 1;
+                }
+                if end < chars.len() && chars[end].is_ascii_digit() {
+                    index = end;
+                    while index < chars.len() && chars[index].is_ascii_digit() {
+                        index += 1;
+                    }
+                }
+            }
+            let text = chars[start..index].iter().collect::<String>();
+            let value = text
+                .parse::<f64>()
+                .map_err(|_| error_at(source, start, format!("Invalid number '{}'", text)))?;
+            let imaginary = index < chars.len()
+                && chars[index] == 'i'
+                && (index + 1 == chars.len() || !is_identifier_char(chars[index + 1]));
+            let kind = if imaginary {
+                index += 1;
+                TokenKind::Imaginary(value)
+            } else {
+                TokenKind::Number(value)
+            };
+            tokens.push(Token {
+                kind,
+                position: start,
+            });
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while index < chars.len() && is_identifier_char(chars[index]) {
+                index += 1;
+            }
+            tokens.push(Token {
+                kind: TokenKind::Identifier(chars[start..index].iter().collect()),
+                position: start,
+            });
+        } else if "+-*/^(),".contains(c) {
+            index += 1;
+            tokens.push(Token {
+                kind: TokenKind::Symbol(c),
+                position: start,
+            });
+        } else {
+            return Err(error_at(
+                source,
+                start,
+                format!("Unexpected character '{}'", c),
+            ));
+        }
+    }
+    Ok(tokens)
+}
+
+/// How deeply parentheses, calls, signs, and operators may nest in a formula, which keeps the
+/// recursive descent parser (and the recursive evaluation of what it parses) from overflowing the
+/// stack.
+const MAX_DEPTH: usize = 256;
+
+/// A recursive descent parser for the grammar:
+///
+/// ```text
+/// expression := term (('+' | '-') term)*
+/// term       := unary (('*' | '/') unary)*
+/// unary      := ('-' | '+') unary | power
+/// power      := primary ('^' unary)?
+/// primary    := number | identifier | identifier '(' arguments ')' | '(' expression ')'
+/// arguments  := expression (',' expression)*
+/// ```
+struct Parser<'a> {
+    source: &'a str,
+    tokens: &'a [Token],
+    next: usize,
+    /// How deeply the expression being parsed is nested, see Parser::nest().
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn error_at(&self, position: usize, message: String) -> String {
+        error_at(self.source, position, message)
+    }
+
+    /// An error for a formula that ended while more was expected.
+    fn unexpected_end(&self, expected: &str) -> String {
+        self.error_at(
+            self.source.chars().count(),
+            format!("Expected {} but the formula ended", expected),
+        )
+    }
+
+    /// Consumes the next token if it is `symbol`.
+    fn accept(&mut self, symbol: char) -> bool {
+        match self.peek() {
+            Some(Token {
+                kind: TokenKind::Symbol(c),
+                ..
+            }) if *c == symbol => {
+                self.next += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Consumes the next token, which must be `symbol`.
+    fn expect(&mut self, symbol: char) -> Result<(), String> {
+        if self.accept(symbol) {
+            return Ok(());
+        }
+        match self.peek() {
+            Some(token) => Err(self.error_at(
+                token.position,
+                format!("Expected '{}' but found {}", symbol, token.kind),
+            )),
+            None => Err(self.unexpected_end(&format!("'{}'", symbol))),
+        }
+    }
+
+    /// Goes one level deeper into the expression, or fails if that is deeper than `MAX_DEPTH`.
+    ///
+    /// Every unary operator (which every parenthesis, call, and exponent passes through) goes one
+    /// level deeper until it has been parsed, and so does every binary operator in a chain, since
+    /// each one nests everything to its left.
+    fn nest(&mut self) -> Result<(), String> {
+        if self.depth == MAX_DEPTH {
+            let position = match self.peek() {
+                Some(token) => token.position,
+                None => self.source.chars().count(),
+            };
+            return Err(self.error_at(
+                position,
+                format!("The formula nests more than {} levels deep", MAX_DEPTH),
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn expression(&mut self) -> Result<Expression, String> {
+        let depth = self.depth;
+        let mut left = self.term()?;
+        loop {
+            let operator = if self.accept('+') {
+                Operator::Add
+            } else if self.accept('-') {
+                Operator::Subtract
+            } else {
+                self.depth = depth;
+                return Ok(left);
+            };
+            self.nest()?;
+            let right = self.term()?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expression, String> {
+        let depth = self.depth;
+        let mut left = self.unary()?;
+        loop {
+            let operator = if self.accept('*') {
+                Operator::Multiply
+            } else if self.accept('/') {
+                Operator::Divide
+            } else {
+                self.depth = depth;
+                return Ok(left);
+            };
+            self.nest()?;
+            let right = self.unary()?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expression, String> {
+        self.nest()?;
+        let result = if self.accept('-') {
+            Expression::Negate(Box::new(self.unary()?))
+        } else if self.accept('+') {
+            self.unary()?
+        } else {
+            self.power()?
+        };
+        self.depth -= 1;
+        Ok(result)
+    }
+
+    fn power(&mut self) -> Result<Expression, String> {
+        let base = self.primary()?;
+        if self.accept('^') {
+            let exponent = self.unary()?;
+            Ok(Expression::Binary(
+                Operator::Power,
+                Box::new(base),
+                Box::new(exponent),
+            ))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expression, String> {
+        let token = match self.peek() {
+            Some(token) => token,
+            None => return Err(self.unexpected_end("a value")),
+        };
+        self.next += 1;
+        match &token.kind {
+            TokenKind::Number(value) => Ok(Expression::Constant(Complex64::new(*value, 0.0))),
+            TokenKind::Imaginary(value) => Ok(Expression::Constant(Complex64::new(0.0, *value))),
+            TokenKind::Symbol('(') => {
+                let inner = self.expression()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            TokenKind::Identifier(name) => {
+                if let Some(function) = Function::from_name(name) {
+                    return self.call(function, token);
+                }
+                let value = match name.as_str() {
+                    "z" => Expression::Variable(Variable::Z),
+                    "c" => Expression::Variable(Variable::C),
+                    "p1" => Expression::Variable(Variable::Parameter(0)),
+                    "p2" => Expression::Variable(Variable::Parameter(1)),
+                    "p3" => Expression::Variable(Variable::Parameter(2)),
+                    "p4" => Expression::Variable(Variable::Parameter(3)),
+                    "i" => Expression::Constant(Complex64::new(0.0, 1.0)),
+                    "pi" => Expression::Constant(Complex64::new(PI, 0.0)),
+                    "e" => Expression::Constant(Complex64::new(E, 0.0)),
+                    _ => {
+                        return Err(self.error_at(
+                            token.position,
+                            format!("Unknown variable or function '{}'", name),
+                        ))
+                    }
+                };
+                Ok(value)
+            }
+            kind => Err(self.error_at(
+                token.position,
+                format!("Expected a value but found {}", kind),
+            )),
+        }
+    }
+
+    /// Parses the arguments of a call to `function`, whose name is `name_token`.
+    fn call(&mut self, function: Function, name_token: &Token) -> Result<Expression, String> {
+        self.expect('(')?;
+        let mut args = vec![self.expression()?];
+        while self.accept(',') {
+            args.push(self.expression()?);
+        }
+        self.expect(')')?;
+        if args.len() != function.arity() {
+            return Err(self.error_at(
+                name_token.position,
+                format!(
+                    "{} takes {} argument(s) but was given {}",
+                    name_token.kind,
+                    function.arity(),
+                    args.len()
+                ),
+            ));
+        }
+        Ok(Expression::Call(function, args))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn evaluate(source: &str, z: Complex64, c: Complex64) -> Complex64 {
+        Formula::parse(source)
+            .unwrap()
+            .evaluate(&Variables::new(z, c))
+    }
+
+    #[test]
+    fn test_precedence() {
+        let z = Complex64::new(3.0, 0.0);
+        let c = Complex64::new(2.0, 0.0);
+        assert_eq!(evaluate("z + c * 2", z, c), Complex64::new(7.0, 0.0));
+        assert_eq!(evaluate("(z + c) * 2", z, c), Complex64::new(10.0, 0.0));
+        assert_eq!(evaluate("z - c - 1", z, c), Complex64::new(0.0, 0.0));
+        assert_eq!(evaluate("z / c / 3", z, c), Complex64::new(0.5, 0.0));
+        assert_eq!(evaluate("-z^2", z, c), Complex64::new(-9.0, 0.0));
+        assert_eq!(evaluate("c^c^z", z, c), Complex64::new(256.0, 0.0));
+        assert_eq!(evaluate("z^-1", z, c), Complex64::new(1.0 / 3.0, 0.0));
+        assert_eq!(evaluate("2 * -z", z, c), Complex64::new(-6.0, 0.0));
+    }
+
+    #[test]
+    fn test_numbers_and_constants() {
+        let zero = Complex64::new(0.0, 0.0);
+        assert_eq!(evaluate("1.5e2", zero, zero), Complex64::new(150.0, 0.0));
+        assert_eq!(evaluate("2.5i", zero, zero), Complex64::new(0.0, 2.5));
+        assert_eq!(evaluate("i * i", zero, zero), Complex64::new(-1.0, 0.0));
+        assert_eq!(evaluate("2 * e", zero, zero), Complex64::new(2.0 * E, 0.0));
+        assert_eq!(evaluate("pi", zero, zero), Complex64::new(PI, 0.0));
+    }
+
+    #[test]
+    fn test_mandelbrot() {
+        let z = Complex64::new(0.5, -1.0);
+        let c = Complex64::new(-0.75, 0.25);
+        assert_eq!(evaluate("z^2 + c", z, c), z * z + c);
+        assert_eq!(evaluate("z*z + c", z, c), z * z + c);
+        assert_eq!(evaluate("pow(z, 3) + c", z, c), z * z * z + c);
+    }
+
+    #[test]
+    fn test_functions() {
+        let z = Complex64::new(3.0, -4.0);
+        let zero = Complex64::new(0.0, 0.0);
+        assert_eq!(evaluate("abs(z)", z, zero), Complex64::new(5.0, 0.0));
+        assert_eq!(evaluate("conj(z)", z, zero), Complex64::new(3.0, 4.0));
+        assert_eq!(evaluate("re(z)", z, zero), Complex64::new(3.0, 0.0));
+        assert_eq!(evaluate("im(z)", z, zero), Complex64::new(-4.0, 0.0));
+        assert_eq!(evaluate("exp(z)", z, zero), z.exp());
+        assert_eq!(evaluate("sin(z)", z, zero), z.sin());
+        assert_eq!(evaluate("log(z)", z, zero), z.ln());
+        let half = evaluate("pow(z, 0.5)", z, zero);
+        assert!((half * half - z).norm() < 1e-12);
+    }
+
+    #[test]
+    fn test_parameters() {
+        let formula = Formula::parse("p1 * z + p4").unwrap();
+        let mut variables = Variables::new(Complex64::new(2.0, 0.0), Complex64::new(0.0, 0.0));
+        variables.parameters[0] = Complex64::new(0.0, 1.0);
+        variables.parameters[3] = Complex64::new(1.0, 0.0);
+        assert_eq!(formula.evaluate(&variables), Complex64::new(1.0, 2.0));
+    }
+
+    fn assert_parse_error(source: &str, expected: &str) {
+        let error = Formula::parse(source).unwrap_err();
+        assert!(error.starts_with(expected), "{:?}", error);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Formula::parse("z^2 + q").unwrap_err(),
+            "Unknown variable or function 'q' at column 7:\nz^2 + q\n      ^"
+        );
+        assert_parse_error(
+            "z^2 +",
+            "Expected a value but the formula ended at column 6",
+        );
+        assert_parse_error("(z + c", "Expected ')' but the formula ended at column 7");
+        assert_parse_error("z + c)", "Unexpected ')' at column 6");
+        assert_parse_error("z $ c", "Unexpected character '$' at column 3");
+        assert_parse_error("z * * c", "Expected a value but found '*' at column 5");
+        assert_parse_error(
+            "pow(z) + c",
+            "'pow' takes 2 argument(s) but was given 1 at column 1",
+        );
+        assert_parse_error("sin z", "Expected '(' but found 'z' at column 5");
+        assert_parse_error("1.2.3", "Invalid number '1.2.3' at column 1");
+        assert_parse_error("", "Expected a value but the formula ended at column 1");
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        let nested = format!("{}z{}", "(".repeat(200), ")".repeat(200));
+        assert_eq!(
+            evaluate(&nested, Complex64::new(2.0, 1.0), Complex64::new(0.0, 0.0)),
+            Complex64::new(2.0, 1.0)
+        );
+
+        let nested = format!("{}z{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert_parse_error(
+            &nested,
+            "The formula nests more than 256 levels deep at column 257",
+        );
+        assert_parse_error(
+            &"-".repeat(100_000),
+            "The formula nests more than 256 levels deep at column 257",
+        );
+        assert_parse_error(
+            &"sin(".repeat(100_000),
+            "The formula nests more than 256 levels deep at column 1025",
+        );
+        assert_parse_error(
+            &"z^".repeat(100_000),
+            "The formula nests more than 256 levels deep at column 513",
+        );
+        assert_parse_error(
+            &format!("z{}", "+z".repeat(100_000)),
+            "The formula nests more than 256 levels deep at column 513",
+        );
+    }
+}
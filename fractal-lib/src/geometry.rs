@@ -18,6 +18,7 @@
 use num::complex::Complex64;
 use std::f64::consts::PI;
 use std::fmt;
+use std::str::FromStr;
 
 pub type Vec2d = [f64; 2];
 
@@ -145,6 +146,7 @@ impl AffineTransform<Point> for CartesianAffineTransform {
 ///   the positive direction is usually up and right.
 /// * It ensures that the view area is not stretched or squished, limiting the transforms to
 ///   zooming and shifting.
+#[derive(Copy, Clone, Debug)]
 pub struct ViewAreaTransformer {
     // view_area_size: Vec2d,
     top_left: Point,
@@ -224,6 +226,11 @@ impl ViewAreaTransformer {
         }
     }
 
+    /// The width (and height) of a single pixel, measured in the cartesian plane.
+    pub fn pixel_size(&self) -> f64 {
+        self.scale
+    }
+
     pub fn map_point_to_pixel(&self, point: Point) -> Vec2d {
         [
             (point.x - self.top_left.x + self.offset_factor_x) / self.scale,
@@ -248,6 +255,135 @@ pub fn cpow(c: Complex64, exponent: u64) -> Complex64 {
     }
 }
 
+/// An exponent for `cpow_general()`, which may be a natural number, a (possibly negative)
+/// integer, a real number, or a complex number.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Exponent {
+    Natural(u64),
+    Integer(i64),
+    Real(f64),
+    Complex(Complex64),
+}
+
+impl Exponent {
+    /// Returns the exponent as a natural number, if it is one (eg, `Real(3.0)` is 3). Natural
+    /// exponents can be computed exactly with repeated multiplication.
+    pub fn as_natural(self) -> Option<u64> {
+        let real = match self {
+            Exponent::Natural(n) => return Some(n),
+            Exponent::Integer(n) => return if n >= 0 { Some(n as u64) } else { None },
+            Exponent::Real(r) => r,
+            Exponent::Complex(w) if w.im == 0.0 => w.re,
+            Exponent::Complex(_) => return None,
+        };
+        if real >= 0.0 && real.fract() == 0.0 && real <= u64::MAX as f64 {
+            Some(real as u64)
+        } else {
+            None
+        }
+    }
+
+    /// The real part of the exponent.
+    pub fn real_part(self) -> f64 {
+        match self {
+            Exponent::Natural(n) => n as f64,
+            Exponent::Integer(n) => n as f64,
+            Exponent::Real(r) => r,
+            Exponent::Complex(w) => w.re,
+        }
+    }
+}
+
+impl From<u64> for Exponent {
+    fn from(n: u64) -> Exponent {
+        Exponent::Natural(n)
+    }
+}
+
+impl FromStr for Exponent {
+    type Err = String;
+
+    /// Parses an integer (eg, `3` or `-2`), a real number (eg, `2.5`), or a complex number
+    /// written as `re,im` (eg, `2,0.5`).
+    fn from_str(text: &str) -> Result<Exponent, String> {
+        let text = text.trim();
+        let invalid = || format!("Invalid exponent: {}", text);
+        if let Some(comma) = text.find(',') {
+            let re = text[..comma].trim().parse::<f64>().map_err(|_| invalid())?;
+            let im = text[comma + 1..]
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| invalid())?;
+            return Ok(Exponent::Complex(Complex64::new(re, im)));
+        }
+        if let Ok(n) = text.parse::<u64>() {
+            return Ok(Exponent::Natural(n));
+        }
+        if let Ok(n) = text.parse::<i64>() {
+            return Ok(Exponent::Integer(n));
+        }
+        match text.parse::<f64>() {
+            Ok(r) if r.is_finite() => Ok(Exponent::Real(r)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Exponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Exponent::Natural(n) => write!(f, "{}", n),
+            Exponent::Integer(n) => write!(f, "{}", n),
+            Exponent::Real(r) => write!(f, "{}", r),
+            Exponent::Complex(w) => write!(f, "{},{}", w.re, w.im),
+        }
+    }
+}
+
+/// Raises `c` to any `Exponent`.
+///
+/// Natural (and non-negative integer) exponents use `cpow()`, and negative integers use the
+/// reciprocal of `cpow()`, so both are single-valued. Real and complex exponents are computed with
+/// the principal branch, `c^w = exp(w * ln(c))`, where `ln(c)` uses the argument of `c` in
+/// `(-π, π]`. The branch cut is therefore along the negative real axis: points just above and
+/// just below it can map to very different values.
+///
+/// `0^w` is 0 when the real part of `w` is positive, 1 when `w` is 0, and infinite otherwise.
+pub fn cpow_general(c: Complex64, exponent: Exponent) -> Complex64 {
+    if let Some(n) = exponent.as_natural() {
+        return cpow(c, n);
+    }
+    if c == Complex64::new(0.0, 0.0) {
+        return if exponent.real_part() > 0.0 {
+            Complex64::new(0.0, 0.0)
+        } else {
+            Complex64::new(f64::INFINITY, 0.0)
+        };
+    }
+    match exponent {
+        Exponent::Integer(n) => Complex64::new(1.0, 0.0) / cpow(c, n.unsigned_abs()),
+        Exponent::Real(r) => c.powf(r),
+        Exponent::Complex(w) => c.powc(w),
+        Exponent::Natural(_) => unreachable!(),
+    }
+}
+
+/// The derivative of `cpow_general(c, exponent)` with respect to `c`, which is
+/// `exponent * c^(exponent - 1)` (using the same branch as `cpow_general()`).
+pub fn cpow_general_derivative(c: Complex64, exponent: Exponent) -> Complex64 {
+    match exponent.as_natural() {
+        Some(0) => return Complex64::new(0.0, 0.0),
+        Some(n) => return n as f64 * cpow(c, n - 1),
+        None => {}
+    }
+    match exponent {
+        Exponent::Integer(n) => n as f64 * cpow_general(c, Exponent::Integer(n - 1)),
+        Exponent::Real(r) => r * cpow_general(c, Exponent::Real(r - 1.0)),
+        Exponent::Complex(w) => w * cpow_general(c, Exponent::Complex(w - 1.0)),
+        Exponent::Natural(_) => unreachable!(),
+    }
+}
+
 #[cfg(test)]
 mod test {
 
@@ -724,4 +860,105 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn test_exponent_from_str() {
+        assert_eq!("3".parse::<Exponent>(), Ok(Exponent::Natural(3)));
+        assert_eq!("-2".parse::<Exponent>(), Ok(Exponent::Integer(-2)));
+        assert_eq!(" 2.5 ".parse::<Exponent>(), Ok(Exponent::Real(2.5)));
+        assert_eq!(
+            "2, -0.5".parse::<Exponent>(),
+            Ok(Exponent::Complex(Complex64::new(2.0, -0.5)))
+        );
+        assert!("two".parse::<Exponent>().is_err());
+        assert!("1,2,3".parse::<Exponent>().is_err());
+        assert!("inf".parse::<Exponent>().is_err());
+        assert_eq!(Exponent::Real(2.5).to_string(), "2.5");
+    }
+
+    #[test]
+    fn test_exponent_as_natural() {
+        assert_eq!(Exponent::Natural(4).as_natural(), Some(4));
+        assert_eq!(Exponent::Integer(3).as_natural(), Some(3));
+        assert_eq!(Exponent::Integer(-3).as_natural(), None);
+        assert_eq!(Exponent::Real(2.0).as_natural(), Some(2));
+        assert_eq!(Exponent::Real(2.5).as_natural(), None);
+        assert_eq!(
+            Exponent::Complex(Complex64::new(3.0, 0.0)).as_natural(),
+            Some(3)
+        );
+        assert_eq!(Exponent::Complex(Complex64::new(3.0, 1.0)).as_natural(), None);
+    }
+
+    #[test]
+    fn test_cpow_general() {
+        let c = Complex64::new(1.5, -0.5);
+        assert_eq!(cpow_general(c, Exponent::Natural(3)), cpow(c, 3));
+        assert_eq!(cpow_general(c, Exponent::Real(3.0)), cpow(c, 3));
+
+        let inverse_square = cpow_general(c, Exponent::Integer(-2));
+        assert_approx_eq!((inverse_square * c * c).re, 1.0, 1e-12);
+        assert_approx_eq!((inverse_square * c * c).im, 0.0, 1e-12);
+
+        // (c^0.5)^2 = c
+        let root = cpow_general(c, Exponent::Real(0.5));
+        assert_approx_eq!((root * root - c).norm(), 0.0, 1e-12);
+        // i^i = e^(-π/2)
+        let i = Complex64::new(0.0, 1.0);
+        let ii = cpow_general(i, Exponent::Complex(i));
+        assert_approx_eq!(ii.re, (-PI / 2.0).exp(), 1e-12);
+        assert_approx_eq!(ii.im, 0.0, 1e-12);
+    }
+
+    #[test]
+    fn test_cpow_general_branch_cut() {
+        // (-1)^0.5 is i on the principal branch, and the cut is crossed just below -1
+        let above = cpow_general(Complex64::new(-1.0, 1e-12), Exponent::Real(0.5));
+        let below = cpow_general(Complex64::new(-1.0, -1e-12), Exponent::Real(0.5));
+        let on = cpow_general(Complex64::new(-1.0, 0.0), Exponent::Real(0.5));
+        assert_approx_eq!(on.im, 1.0, 1e-12);
+        assert_approx_eq!(above.im, 1.0, 1e-9);
+        assert_approx_eq!(below.im, -1.0, 1e-9);
+    }
+
+    #[test]
+    fn test_cpow_general_zero() {
+        let zero = Complex64::new(0.0, 0.0);
+        assert_eq!(cpow_general(zero, Exponent::Real(2.5)), zero);
+        assert_eq!(cpow_general(zero, Exponent::Natural(0)), Complex64::new(1.0, 0.0));
+        assert!(cpow_general(zero, Exponent::Integer(-2)).re.is_infinite());
+        assert!(cpow_general(zero, Exponent::Real(-0.5)).re.is_infinite());
+    }
+
+    #[test]
+    fn test_cpow_general_derivative() {
+        let c = Complex64::new(1.5, -0.5);
+        assert_eq!(
+            cpow_general_derivative(c, Exponent::Natural(0)),
+            Complex64::new(0.0, 0.0)
+        );
+        assert_eq!(cpow_general_derivative(c, Exponent::Natural(3)), 3.0 * c * c);
+        // Compare each against a finite difference
+        let h = Complex64::new(1e-7, 0.0);
+        for &exponent in &[
+            Exponent::Integer(-2),
+            Exponent::Real(2.5),
+            Exponent::Complex(Complex64::new(2.0, 1.0)),
+        ] {
+            let numeric =
+                (cpow_general(c + h, exponent) - cpow_general(c - h, exponent)) / (2.0 * h);
+            let derivative = cpow_general_derivative(c, exponent);
+            assert_approx_eq!((derivative - numeric).norm(), 0.0, 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_pixel_size() {
+        let vat = ViewAreaTransformer::new(
+            [800.0, 600.0],
+            Point { x: -2.0, y: 1.0 },
+            Point { x: 2.0, y: -1.0 },
+        );
+        assert_approx_eq!(vat.pixel_size(), 0.005, 1e-12);
+    }
 }
@@ -16,10 +16,16 @@
 #[macro_use]
 pub mod macros;
 
+pub mod bigfloat;
 pub mod chaosgame;
 pub mod color;
+pub mod convergent;
 pub mod curves;
+pub mod doubledouble;
 pub mod escapetime;
+pub mod escapetime3d;
+pub mod formula;
 pub mod geometry;
 pub mod lindenmayer;
+pub mod numeric;
 pub mod turtle;
@@ -0,0 +1,397 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lets escape time fractals be computed with different kinds of real numbers, trading speed for
+//! precision.
+//!
+//! The supported types are:
+//!
+//! * `f32`, which is fast but only good for shallow views
+//! * `f64`, which is what the rest of the library uses (through `Complex64`)
+//! * `DoubleDouble`, with about 106 bits of precision
+//! * `BigFloat`, with as much precision as is asked for
+//!
+//! Some types (`BigFloat`) need to know their precision when they are created, so conversions
+//! take a `Real::Precision`, which is `()` for the fixed precision types.
+
+use super::bigfloat::BigFloat;
+use super::doubledouble::DoubleDouble;
+use super::geometry::Vec2d;
+use num::complex::Complex64;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// A real number type that escape time fractals can be computed with.
+pub trait Real:
+    Clone
+    + fmt::Debug
+    + PartialOrd
+    + Send
+    + Sync
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+{
+    /// Describes how precise a number is, for types whose precision is chosen at runtime.
+    type Precision: Copy + fmt::Debug + Send + Sync;
+
+    /// Converts an `f64`, rounding it if the type is less precise.
+    fn from_f64(value: f64, precision: Self::Precision) -> Self;
+
+    /// Parses a decimal number, keeping as many digits as the precision allows.
+    fn parse(text: &str, precision: Self::Precision) -> Result<Self, String>;
+
+    fn precision(&self) -> Self::Precision;
+
+    /// Rounds to the nearest `f64`.
+    fn to_f64(&self) -> f64;
+
+    fn abs(&self) -> Self;
+}
+
+impl Real for f32 {
+    type Precision = ();
+
+    fn from_f64(value: f64, _: ()) -> f32 {
+        value as f32
+    }
+
+    fn parse(text: &str, _: ()) -> Result<f32, String> {
+        text.trim()
+            .parse::<f32>()
+            .map_err(|e| format!("Invalid number {}: {}", text, e))
+    }
+
+    fn precision(&self) {}
+
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+
+    fn abs(&self) -> f32 {
+        f32::abs(*self)
+    }
+}
+
+impl Real for f64 {
+    type Precision = ();
+
+    fn from_f64(value: f64, _: ()) -> f64 {
+        value
+    }
+
+    fn parse(text: &str, _: ()) -> Result<f64, String> {
+        text.trim()
+            .parse::<f64>()
+            .map_err(|e| format!("Invalid number {}: {}", text, e))
+    }
+
+    fn precision(&self) {}
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn abs(&self) -> f64 {
+        f64::abs(*self)
+    }
+}
+
+impl Real for DoubleDouble {
+    type Precision = ();
+
+    fn from_f64(value: f64, _: ()) -> DoubleDouble {
+        DoubleDouble::from_f64(value)
+    }
+
+    fn parse(text: &str, _: ()) -> Result<DoubleDouble, String> {
+        DoubleDouble::parse(text)
+    }
+
+    fn precision(&self) {}
+
+    fn to_f64(&self) -> f64 {
+        DoubleDouble::to_f64(*self)
+    }
+
+    fn abs(&self) -> DoubleDouble {
+        DoubleDouble::abs(*self)
+    }
+}
+
+impl Real for BigFloat {
+    /// The number of bits in the mantissa
+    type Precision = u32;
+
+    fn from_f64(value: f64, precision: u32) -> BigFloat {
+        BigFloat::from_f64(value, precision)
+    }
+
+    fn parse(text: &str, precision: u32) -> Result<BigFloat, String> {
+        BigFloat::parse(text, precision)
+    }
+
+    fn precision(&self) -> u32 {
+        BigFloat::precision(self)
+    }
+
+    fn to_f64(&self) -> f64 {
+        BigFloat::to_f64(self)
+    }
+
+    fn abs(&self) -> BigFloat {
+        BigFloat::abs(self)
+    }
+}
+
+/// A complex number made of any `Real` type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RealComplex<T>
+where
+    T: Real,
+{
+    pub re: T,
+    pub im: T,
+}
+
+impl<T> RealComplex<T>
+where
+    T: Real,
+{
+    pub fn new(re: T, im: T) -> RealComplex<T> {
+        RealComplex { re, im }
+    }
+
+    pub fn from_complex64(c: Complex64, precision: T::Precision) -> RealComplex<T> {
+        RealComplex {
+            re: T::from_f64(c.re, precision),
+            im: T::from_f64(c.im, precision),
+        }
+    }
+
+    /// Rounds both parts to the nearest `f64`.
+    pub fn to_complex64(&self) -> Complex64 {
+        Complex64::new(self.re.to_f64(), self.im.to_f64())
+    }
+
+    /// The precision of the real part.
+    pub fn precision(&self) -> T::Precision {
+        self.re.precision()
+    }
+
+    pub fn square(&self) -> RealComplex<T> {
+        let cross = self.re.clone() * self.im.clone();
+        RealComplex {
+            re: self.re.clone() * self.re.clone() - self.im.clone() * self.im.clone(),
+            im: cross.clone() + cross,
+        }
+    }
+
+    /// Raises the value to a positive integer power, like `geometry::cpow()`.
+    pub fn powu(&self, exponent: u64) -> RealComplex<T> {
+        match exponent {
+            0 => RealComplex::from_complex64(Complex64::new(1.0, 0.0), self.precision()),
+            1 => self.clone(),
+            2 => self.square(),
+            _ => {
+                let mut accum = self.clone();
+                for _ in 1..exponent {
+                    accum = accum * self.clone();
+                }
+                accum
+            }
+        }
+    }
+}
+
+impl<T> Add for RealComplex<T>
+where
+    T: Real,
+{
+    type Output = RealComplex<T>;
+
+    fn add(self, other: RealComplex<T>) -> RealComplex<T> {
+        RealComplex {
+            re: self.re + other.re,
+            im: self.im + other.im,
+        }
+    }
+}
+
+impl<T> Sub for RealComplex<T>
+where
+    T: Real,
+{
+    type Output = RealComplex<T>;
+
+    fn sub(self, other: RealComplex<T>) -> RealComplex<T> {
+        RealComplex {
+            re: self.re - other.re,
+            im: self.im - other.im,
+        }
+    }
+}
+
+impl<T> Mul for RealComplex<T>
+where
+    T: Real,
+{
+    type Output = RealComplex<T>;
+
+    fn mul(self, other: RealComplex<T>) -> RealComplex<T> {
+        RealComplex {
+            re: self.re.clone() * other.re.clone() - self.im.clone() * other.im.clone(),
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+/// A rectangular area of the complex plane, stored with the same precision that the fractal is
+/// computed with, so that it can describe views that are too small for `f64` coordinates.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ViewArea<T>
+where
+    T: Real,
+{
+    pub top_left: RealComplex<T>,
+    pub bottom_right: RealComplex<T>,
+}
+
+impl<T> ViewArea<T>
+where
+    T: Real,
+{
+    pub fn new(top_left: RealComplex<T>, bottom_right: RealComplex<T>) -> ViewArea<T> {
+        ViewArea {
+            top_left,
+            bottom_right,
+        }
+    }
+
+    /// Converts a view area such as the one from `EscapeTime::default_view_area()`.
+    pub fn from_complex64(view_area: [Complex64; 2], precision: T::Precision) -> ViewArea<T> {
+        ViewArea::new(
+            RealComplex::from_complex64(view_area[0], precision),
+            RealComplex::from_complex64(view_area[1], precision),
+        )
+    }
+
+    /// Maps a pixel on a screen that is `screen_size` pixels large to the point on the complex
+    /// plane that it shows. Pixel `[0, 0]` is the top left corner of the view area.
+    pub fn map_pixel_to_point(&self, screen_size: Vec2d, pixel: Vec2d) -> RealComplex<T> {
+        let precision = self.top_left.precision();
+        let x_fraction = T::from_f64(pixel[0] / screen_size[0], precision);
+        let y_fraction = T::from_f64(pixel[1] / screen_size[1], precision);
+        let width = self.bottom_right.re.clone() - self.top_left.re.clone();
+        let height = self.bottom_right.im.clone() - self.top_left.im.clone();
+        RealComplex {
+            re: self.top_left.re.clone() + width * x_fraction,
+            im: self.top_left.im.clone() + height * y_fraction,
+        }
+    }
+
+    /// Computes the view area shown by the rectangle of pixels from `rect[0]` (top left) to
+    /// `rect[1]` (bottom right).
+    pub fn zoom(&self, screen_size: Vec2d, rect: [Vec2d; 2]) -> ViewArea<T> {
+        ViewArea::new(
+            self.map_pixel_to_point(screen_size, rect[0]),
+            self.map_pixel_to_point(screen_size, rect[1]),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(<f32 as Real>::parse("1.5", ()).unwrap(), 1.5f32);
+        assert_eq!(<f64 as Real>::parse(" -0.25 ", ()).unwrap(), -0.25);
+        assert_eq!(
+            <DoubleDouble as Real>::parse("2", ()).unwrap(),
+            DoubleDouble::from_f64(2.0)
+        );
+        assert_eq!(
+            <BigFloat as Real>::parse("0.75", 80).unwrap().precision(),
+            80
+        );
+        assert!(<f64 as Real>::parse("nope", ()).is_err());
+    }
+
+    #[test]
+    fn test_powu_matches_cpow() {
+        let c = Complex64::new(5.5, 1.0);
+        for exponent in 0..5 {
+            let expected = super::super::geometry::cpow(c, exponent);
+            assert_eq!(
+                RealComplex::<f64>::from_complex64(c, ())
+                    .powu(exponent)
+                    .to_complex64(),
+                expected
+            );
+            assert_eq!(
+                RealComplex::<DoubleDouble>::from_complex64(c, ())
+                    .powu(exponent)
+                    .to_complex64(),
+                expected
+            );
+            assert_eq!(
+                RealComplex::<BigFloat>::from_complex64(c, 64)
+                    .powu(exponent)
+                    .to_complex64(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_view_area_map_pixel_to_point() {
+        let view = ViewArea::<f64>::from_complex64(
+            [Complex64::new(-2.0, 1.0), Complex64::new(1.0, -1.0)],
+            (),
+        );
+        let screen = [300.0, 200.0];
+        assert_eq!(
+            view.map_pixel_to_point(screen, [0.0, 0.0]).to_complex64(),
+            Complex64::new(-2.0, 1.0)
+        );
+        assert_eq!(
+            view.map_pixel_to_point(screen, [150.0, 100.0])
+                .to_complex64(),
+            Complex64::new(-0.5, 0.0)
+        );
+        assert_eq!(
+            view.zoom(screen, [[100.0, 50.0], [200.0, 150.0]]),
+            ViewArea::from_complex64([Complex64::new(-1.0, 0.5), Complex64::new(0.0, -0.5)], ())
+        );
+    }
+
+    #[test]
+    fn test_view_area_keeps_precision() {
+        // Neighboring pixels in a view that is 1e-25 wide are indistinguishable as f64s
+        let one = DoubleDouble::from_f64(1.0);
+        let tiny = DoubleDouble::from_f64(1e-25);
+        let view = ViewArea::new(
+            RealComplex::new(one, tiny),
+            RealComplex::new(one + tiny, DoubleDouble::default()),
+        );
+        let a = view.map_pixel_to_point([100.0, 100.0], [10.0, 10.0]);
+        let b = view.map_pixel_to_point([100.0, 100.0], [11.0, 10.0]);
+        assert_eq!(a.re.to_f64(), b.re.to_f64());
+        assert!(a.re < b.re);
+        assert_approx_eq!((b.re - a.re).to_f64(), 1e-27, 1e-40);
+    }
+}
@@ -18,22 +18,52 @@
 //! configure a `WindowHandler` to handle callbacks from the event loop.
 
 use clap;
+use num::complex::Complex64;
 use std;
+use std::cmp;
+use std::path::Path;
 use std::sync::Arc;
 
 use super::pistonrendering;
+use super::pistonrendering::escapetime::{SliceFn, Surface, SweptJulia};
+use super::raymarching;
+use super::reference;
+use fractal_lib::bigfloat::{BigComplex, BigFloat};
 use fractal_lib::chaosgame::barnsleyfern;
 use fractal_lib::chaosgame::sierpinski::SierpinskiChaosGame;
 use fractal_lib::chaosgame::ChaosGameMoveIterator;
+use fractal_lib::color::{ColorScale, ColorU8};
+use fractal_lib::convergent::magnet::{MagnetTypeI, MagnetTypeII};
+use fractal_lib::convergent::newton::{NewtonFractal, NovaFractal};
+use fractal_lib::convergent::polynomial::Polynomial;
+use fractal_lib::convergent::ConvergenceTime;
 use fractal_lib::curves::cesaro::CesaroFractal;
 use fractal_lib::curves::cesarotri::CesaroTriFractal;
 use fractal_lib::curves::dragon::DragonFractal;
 use fractal_lib::curves::kochcurve::KochCurve;
 use fractal_lib::curves::levyccurve::LevyCCurve;
 use fractal_lib::curves::terdragon::TerdragonFractal;
+use fractal_lib::doubledouble::DoubleDouble;
+use fractal_lib::escapetime::averaging::Averaging;
+use fractal_lib::escapetime::buddhabrot::{Buddhabrot, OrbitSelection};
 use fractal_lib::escapetime::burningship::*;
+use fractal_lib::escapetime::distance::DistanceEstimator;
+use fractal_lib::escapetime::formula::FormulaFractal;
+use fractal_lib::escapetime::generic::{GenericEscapeTime, Real, RealComplex, ViewArea};
+use fractal_lib::escapetime::hypercomplex::{Algebra, Hypercomplex, HypercomplexJulia, Slice};
+use fractal_lib::escapetime::julia::JuliaSet;
+use fractal_lib::escapetime::lighting::Lighting;
 use fractal_lib::escapetime::mandelbrot::Mandelbrot;
-use fractal_lib::escapetime::EscapeTime;
+use fractal_lib::escapetime::orbittrap::{ImageTrap, OrbitTrap, TrapShape};
+use fractal_lib::escapetime::perturbation::{precision_for_view_size, PerturbedMandelbrot};
+use fractal_lib::escapetime::phoenix::Phoenix;
+use fractal_lib::escapetime::supersample::Supersampling;
+use fractal_lib::escapetime::{EscapeCriterion, EscapeTime};
+use fractal_lib::escapetime3d::mandelbox::Mandelbox;
+use fractal_lib::escapetime3d::mandelbulb::Mandelbulb;
+use fractal_lib::escapetime3d::raymarch::{Camera, RayMarcher};
+use fractal_lib::escapetime3d::{DistanceEstimator3D, Vector3};
+use fractal_lib::geometry::Exponent;
 use fractal_lib::lindenmayer::LindenmayerSystemTurtleProgram;
 use fractal_lib::turtle::TurtleProgram;
 
@@ -67,6 +97,135 @@ where
     }
 }
 
+/// Parses a complex number written as `re,im` (eg, `-0.8,0.156`).
+pub fn parse_complex(opt_name: &str, opt_val: &str) -> Result<Complex64, String> {
+    let parts = opt_val.split(',').collect::<Vec<&str>>();
+    if parts.len() != 2 {
+        return Err(format!(
+            "Error parsing {}: expected a complex number of the form re,im",
+            opt_name
+        ));
+    }
+    let re = parse_arg::<f64>(opt_name, parts[0].trim())?;
+    let im = parse_arg::<f64>(opt_name, parts[1].trim())?;
+    Ok(Complex64::new(re, im))
+}
+
+/// Parses a point or direction in three dimensions written as `x,y,z`.
+fn parse_vector3(opt_name: &str, opt_val: &str) -> Result<Vector3, String> {
+    let parts = opt_val.split(',').collect::<Vec<&str>>();
+    if parts.len() != 3 {
+        return Err(format!(
+            "Error parsing {}: expected a vector of the form x,y,z",
+            opt_name
+        ));
+    }
+    Ok(Vector3::new(
+        parse_arg::<f64>(opt_name, parts[0].trim())?,
+        parse_arg::<f64>(opt_name, parts[1].trim())?,
+        parse_arg::<f64>(opt_name, parts[2].trim())?,
+    ))
+}
+
+/// Parses a hypercomplex number written as `w,x,y,z` (its components along 1, i, j, and k).
+fn parse_hypercomplex(opt_name: &str, opt_val: &str) -> Result<Hypercomplex, String> {
+    let parts = opt_val.split(',').collect::<Vec<&str>>();
+    if parts.len() != 4 {
+        return Err(format!(
+            "Error parsing {}: expected a hypercomplex number of the form w,x,y,z",
+            opt_name
+        ));
+    }
+    Ok(Hypercomplex::new(
+        parse_arg::<f64>(opt_name, parts[0].trim())?,
+        parse_arg::<f64>(opt_name, parts[1].trim())?,
+        parse_arg::<f64>(opt_name, parts[2].trim())?,
+        parse_arg::<f64>(opt_name, parts[3].trim())?,
+    ))
+}
+
+/// Parses the Nebulabrot's red, green, and blue iteration limits, written as `red,green,blue`.
+fn parse_iteration_limits(opt_name: &str, opt_val: &str) -> Result<[u64; 3], String> {
+    let parts = opt_val.split(',').collect::<Vec<&str>>();
+    if parts.len() != 3 {
+        return Err(format!(
+            "Error parsing {}: expected three iteration limits of the form red,green,blue",
+            opt_name
+        ));
+    }
+    Ok([
+        parse_arg::<u64>(opt_name, parts[0].trim())?,
+        parse_arg::<u64>(opt_name, parts[1].trim())?,
+        parse_arg::<u64>(opt_name, parts[2].trim())?,
+    ])
+}
+
+/// Parses a high precision complex number written as `re,im`, keeping as many digits as
+/// `precision` allows.
+pub fn parse_big_complex(
+    opt_name: &str,
+    opt_val: &str,
+    precision: u32,
+) -> Result<BigComplex, String> {
+    let parts = opt_val.split(',').collect::<Vec<&str>>();
+    if parts.len() != 2 {
+        return Err(format!(
+            "Error parsing {}: expected a complex number of the form re,im",
+            opt_name
+        ));
+    }
+    let parse = |part: &str| {
+        BigFloat::parse(part.trim(), precision)
+            .map_err(|e| format!("Error parsing {}: {}", opt_name, e))
+    };
+    Ok(BigComplex::new(parse(parts[0])?, parse(parts[1])?))
+}
+
+/// Parses a complex number written as `re,im` into `T`s, keeping as many digits as `precision`
+/// allows.
+fn parse_real_complex<T>(
+    opt_name: &str,
+    opt_val: &str,
+    precision: T::Precision,
+) -> Result<RealComplex<T>, String>
+where
+    T: Real,
+{
+    let parts = opt_val.split(',').collect::<Vec<&str>>();
+    if parts.len() != 2 {
+        return Err(format!(
+            "Error parsing {}: expected a complex number of the form re,im",
+            opt_name
+        ));
+    }
+    let parse = |part: &str| {
+        T::parse(part.trim(), precision).map_err(|e| format!("Error parsing {}: {}", opt_name, e))
+    };
+    Ok(RealComplex::new(parse(parts[0])?, parse(parts[1])?))
+}
+
+/// Parses the polynomial given by the `root` or `coefficient` arguments, defaulting to `z^3 - 1`.
+fn parse_polynomial(matches: &clap::ArgMatches) -> Result<Polynomial, String> {
+    if let Some(roots) = matches.values_of("root") {
+        let roots = roots
+            .map(|root| parse_complex("root", root))
+            .collect::<Result<Vec<Complex64>, String>>()?;
+        Polynomial::from_roots(&roots)
+    } else if let Some(coefficients) = matches.values_of("coefficient") {
+        let coefficients = coefficients
+            .map(|coefficient| parse_complex("coefficient", coefficient))
+            .collect::<Result<Vec<Complex64>, String>>()?;
+        Polynomial::from_coefficients(&coefficients)
+    } else {
+        Polynomial::from_coefficients(&[
+            Complex64::new(1.0, 0.0),
+            Complex64::new(0.0, 0.0),
+            Complex64::new(0.0, 0.0),
+            Complex64::new(-1.0, 0.0),
+        ])
+    }
+}
+
 /// A subcommand that can configure and run a particular fractal renderer.
 pub trait FractalSubcommand {
     /// Returns a clap::App definition of this subcommand. The command line arguments it
@@ -74,131 +233,1551 @@ pub trait FractalSubcommand {
     /// should then be handled by the `run()` method.
     fn command(&self) -> clap::App<'static, 'static>;
 
-    /// Runs the command with the given command line arguments and the provided callback.
-    fn run(&self, matches: &clap::ArgMatches) -> Result<(), String>;
+    /// Runs the command with the given command line arguments and the provided callback.
+    fn run(&self, matches: &clap::ArgMatches) -> Result<(), String>;
+}
+
+pub struct ChaosGameCommand<E>
+where
+    E: ChaosGameMoveIterator,
+{
+    name: &'static str,
+    description: &'static str,
+    ctor: Box<dyn Fn() -> E>,
+}
+
+impl<E> ChaosGameCommand<E>
+where
+    E: ChaosGameMoveIterator,
+{
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        ctor: Box<dyn Fn() -> E>,
+    ) -> ChaosGameCommand<E> {
+        ChaosGameCommand {
+            name,
+            description,
+            ctor,
+        }
+    }
+}
+
+impl<E> FractalSubcommand for ChaosGameCommand<E>
+where
+    E: ChaosGameMoveIterator + 'static,
+{
+    fn command(&self) -> clap::App<'static, 'static> {
+        clap::SubCommand::with_name(self.name)
+            .about(self.description)
+            .arg(
+                clap::Arg::with_name("drawrate")
+                    .takes_value(true)
+                    .help("The number of points to draw per frame")
+                    .long("drawrate")
+                    .value_name("MPF")
+                    .default_value("1"),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches) -> Result<(), String> {
+        let drawrate = extract!(matches, "drawrate")?;
+
+        let game = Box::new((self.ctor)());
+        let mut handler = pistonrendering::chaosgame::ChaosGameWindowHandler::new(game, drawrate);
+        pistonrendering::run(&mut handler);
+
+        Ok(())
+    }
+}
+
+/// Converts a fractal (or its Julia set for the given constant) into a `DistanceEstimator`.
+type DistanceEstimatorFn<E> = fn(E, Option<Complex64>) -> Arc<dyn DistanceEstimator + Send + Sync>;
+
+pub struct EscapeTimeCommand<E>
+where
+    E: EscapeTime + Send + Sync,
+{
+    name: &'static str,
+    description: &'static str,
+    ctor: Box<dyn Fn(u64, Exponent, EscapeCriterion) -> E>,
+    /// Only set for fractals that support `--coloring distance`.
+    distance_estimator: Option<DistanceEstimatorFn<E>>,
+    /// Another name that the subcommand can be run as, for fractals that are known by two names.
+    alias: Option<&'static str>,
+}
+
+impl<E> EscapeTimeCommand<E>
+where
+    E: EscapeTime + Send + Sync,
+{
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        ctor: Box<dyn Fn(u64, Exponent, EscapeCriterion) -> E>,
+    ) -> EscapeTimeCommand<E> {
+        EscapeTimeCommand {
+            name,
+            description,
+            ctor,
+            distance_estimator: None,
+            alias: None,
+        }
+    }
+
+    /// Lets the subcommand also be run as `alias`.
+    pub fn with_alias(mut self, alias: &'static str) -> EscapeTimeCommand<E> {
+        self.alias = Some(alias);
+        self
+    }
+}
+
+impl<E> EscapeTimeCommand<E>
+where
+    E: DistanceEstimator + Send + Sync + 'static,
+{
+    /// Allows the fractal to be drawn with `--coloring distance`.
+    pub fn with_distance_estimate(mut self) -> EscapeTimeCommand<E> {
+        self.distance_estimator = Some(distance_estimator::<E>);
+        self
+    }
+}
+
+/// Converts `et` (or its Julia set, if `julia` is the constant for one) into a
+/// `DistanceEstimator`.
+fn distance_estimator<E>(
+    et: E,
+    julia: Option<Complex64>,
+) -> Arc<dyn DistanceEstimator + Send + Sync>
+where
+    E: DistanceEstimator + Send + Sync + 'static,
+{
+    match julia {
+        Some(c) => Arc::new(JuliaSet::new(et, c)),
+        None => Arc::new(et),
+    }
+}
+
+impl<E> FractalSubcommand for EscapeTimeCommand<E>
+where
+    E: EscapeTime + Send + Sync + 'static,
+{
+    fn command(&self) -> clap::App<'static, 'static> {
+        let command = clap::SubCommand::with_name(self.name)
+            .about(self.description)
+            .arg(
+                clap::Arg::with_name("MAX_ITERATIONS")
+                    .required(true)
+                    .index(1)
+                    .help(
+                        "The maximum number of iterations of the escape time function before \
+                         deciding the fracal has escaped",
+                    ),
+            )
+            .arg(
+                clap::Arg::with_name("POWER")
+                    .required(true)
+                    .index(2)
+                    .allow_hyphen_values(true)
+                    .help(
+                        "The exponent used in the escape time function: an integer (which may be \
+                         negative), a real number, or a complex number written as RE,IM",
+                    ),
+            );
+        let command = match self.alias {
+            Some(alias) => command.visible_alias(alias),
+            None => command,
+        };
+        escape_time_options(command)
+    }
+
+    fn run(&self, matches: &clap::ArgMatches) -> Result<(), String> {
+        let max_iterations = (extract!(matches, "MAX_ITERATIONS"))?;
+        // .unwrap_or_else(|| return Err("Must specify a MAX_ITERATIONS of 1 or greater!"));
+        let power = (extract!(matches, "POWER"))?;
+        // .unwrap_or_else(|| return Err("Must specify a POWER of 1 or greater!"));
+        let bailout = EscapeCriterion::from_name(
+            matches.value_of("criterion").unwrap_or("norm"),
+            (extract!(matches, "bailout"))?,
+        )?;
+
+        // The ctor callback can return a raw object that implements EscapeTime because this method
+        // is templated to E instead of handling a boxed object that implements EscapeTime.
+        //
+        // We could alternately avoid using templating, in which case the callback would have to
+        // return an Arc<EscapeTime> in order to abstract away the implementation of the trait.
+        let et = (self.ctor)(max_iterations, power, bailout);
+        // --lighting goes first, so that it can complain about being combined with --coloring
+        let distance_option = ["lighting", "coloring"]
+            .iter()
+            .find(|&&option| matches.value_of(option) == Some("distance"));
+        if let Some(&option) = distance_option {
+            let distance_estimator = self
+                .distance_estimator
+                .ok_or_else(|| format!("{} does not support --{} distance", self.name, option))?;
+            let julia = match matches.value_of("julia") {
+                Some(julia) => Some(parse_complex("julia", julia)?),
+                None => None,
+            };
+            let system = distance_estimator(et, julia);
+            let mut handler = if option == "lighting" {
+                pistonrendering::escapetime::EscapeTimeWindowHandler::with_lighting(
+                    Surface::Distance(system),
+                    color_scale(matches)?,
+                    lighting(matches)?,
+                )
+            } else {
+                check_incompatible_options(matches)?;
+                pistonrendering::escapetime::EscapeTimeWindowHandler::for_distance_estimate(
+                    system,
+                    supersampling(matches)?,
+                )
+            };
+            pistonrendering::run(&mut handler);
+            return Ok(());
+        }
+        run_escape_time(et, matches)
+    }
+}
+
+/// Draws a fractal whose escape time function is a formula given on the command line.
+pub struct FormulaCommand {
+    name: &'static str,
+    description: &'static str,
+}
+
+impl FormulaCommand {
+    pub fn new(name: &'static str, description: &'static str) -> FormulaCommand {
+        FormulaCommand { name, description }
+    }
+}
+
+impl FractalSubcommand for FormulaCommand {
+    fn command(&self) -> clap::App<'static, 'static> {
+        let command = clap::SubCommand::with_name(self.name)
+            .about(self.description)
+            .arg(
+                clap::Arg::with_name("FORMULA")
+                    .required(true)
+                    .index(1)
+                    .allow_hyphen_values(true)
+                    .help(
+                        "The escape time function, in terms of z, c, and the parameters p1 \
+                         through p4 (eg, \"z^2 + c\")",
+                    ),
+            )
+            .arg(
+                clap::Arg::with_name("MAX_ITERATIONS")
+                    .index(2)
+                    .default_value("100")
+                    .help(
+                        "The maximum number of iterations of the escape time function before \
+                         deciding the fracal has escaped",
+                    ),
+            )
+            .arg(
+                clap::Arg::with_name("parameter")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .max_values(4)
+                    .allow_hyphen_values(true)
+                    .help("The value of p1, p2, p3, and p4, in order (repeat for each one)")
+                    .long("parameter")
+                    .value_name("RE,IM"),
+            );
+        escape_time_options(command)
+    }
+
+    fn run(&self, matches: &clap::ArgMatches) -> Result<(), String> {
+        let formula = matches.value_of("FORMULA").ok_or("Missing FORMULA")?;
+        let max_iterations = (extract!(matches, "MAX_ITERATIONS"))?;
+        let mut parameters = [Complex64::new(0.0, 0.0); 4];
+        if let Some(values) = matches.values_of("parameter") {
+            for (parameter, value) in parameters.iter_mut().zip(values) {
+                *parameter = parse_complex("parameter", value)?;
+            }
+        }
+        let bailout = EscapeCriterion::from_name(
+            matches.value_of("criterion").unwrap_or("norm"),
+            (extract!(matches, "bailout"))?,
+        )?;
+
+        for option in &["coloring", "lighting"] {
+            if matches.value_of(option) == Some("distance") {
+                return Err(format!(
+                    "{} does not support --{} distance",
+                    self.name, option
+                ));
+            }
+        }
+
+        let et = FormulaFractal::with_parameters(max_iterations, formula, parameters, bailout)?;
+        run_escape_time(et, matches)
+    }
+}
+
+/// Draws a slice through the Julia set of a hypercomplex algebra.
+pub struct HypercomplexJuliaCommand {
+    name: &'static str,
+    description: &'static str,
+    algebra: Algebra,
+    /// The default value of `c`, as a `W,X,Y,Z` argument.
+    default_c: &'static str,
+}
+
+impl HypercomplexJuliaCommand {
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        algebra: Algebra,
+        default_c: &'static str,
+    ) -> HypercomplexJuliaCommand {
+        HypercomplexJuliaCommand {
+            name,
+            description,
+            algebra,
+            default_c,
+        }
+    }
+}
+
+impl FractalSubcommand for HypercomplexJuliaCommand {
+    fn command(&self) -> clap::App<'static, 'static> {
+        let command = clap::SubCommand::with_name(self.name)
+            .about(self.description)
+            .arg(
+                clap::Arg::with_name("MAX_ITERATIONS")
+                    .required(true)
+                    .index(1)
+                    .help(
+                        "The maximum number of iterations of the escape time function before \
+                         deciding the fracal has escaped",
+                    ),
+            )
+            .arg(
+                clap::Arg::with_name("c")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help("The constant added during each iteration")
+                    .long("c")
+                    .value_name("W,X,Y,Z")
+                    .default_value(self.default_c),
+            )
+            .arg(
+                clap::Arg::with_name("plane")
+                    .takes_value(true)
+                    .help(
+                        "The axes (out of 1, i, j, and k) that the real and imaginary axes of the \
+                         window show",
+                    )
+                    .long("plane")
+                    .value_name("AXIS,AXIS")
+                    .default_value("1,i"),
+            )
+            .arg(
+                clap::Arg::with_name("offset")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help(
+                        "Where the slice is along the other two axes (its components along the \
+                         plane are ignored)",
+                    )
+                    .long("offset")
+                    .value_name("W,X,Y,Z")
+                    .default_value("0,0,0,0"),
+            )
+            .arg(
+                clap::Arg::with_name("slice-step")
+                    .takes_value(true)
+                    .help(
+                        "How far each press of , or . moves the slice along the last axis that \
+                         is not in the plane",
+                    )
+                    .long("slice-step")
+                    .value_name("STEP")
+                    .default_value("0.05"),
+            );
+        escape_time_options(command)
+    }
+
+    fn run(&self, matches: &clap::ArgMatches) -> Result<(), String> {
+        let max_iterations = (extract!(matches, "MAX_ITERATIONS"))?;
+        let c = parse_hypercomplex("c", matches.value_of("c").unwrap_or(self.default_c))?;
+        let slice = Slice::from_names(
+            matches.value_of("plane").unwrap_or("1,i"),
+            parse_hypercomplex("offset", matches.value_of("offset").unwrap_or("0,0,0,0"))?,
+        )?;
+        let step: f64 = (extract!(matches, "slice-step"))?;
+        // Escaping is decided by the norm of all four components
+        let bailout = match EscapeCriterion::from_name(
+            matches.value_of("criterion").unwrap_or("norm"),
+            (extract!(matches, "bailout"))?,
+        )? {
+            EscapeCriterion::Norm(radius) => radius,
+            _ => {
+                return Err(format!(
+                    "{} only supports --escape-criterion norm",
+                    self.name
+                ))
+            }
+        };
+        for option in &["julia", "orbit-density", "nebulabrot"] {
+            if matches.is_present(option) {
+                return Err(format!("{} does not support --{}", self.name, option));
+            }
+        }
+        for option in &["coloring", "lighting"] {
+            if matches.value_of(option) == Some("distance") {
+                return Err(format!(
+                    "{} does not support --{} distance",
+                    self.name, option
+                ));
+            }
+        }
+
+        let fractal =
+            HypercomplexJulia::new(self.algebra, max_iterations, c, slice).with_bailout(bailout);
+        let swept = SweptJulia::new(fractal);
+        let slice_fn = swept.slice_fn(step);
+        draw_escape_time(swept, matches, Some(slice_fn))
+    }
+}
+
+/// Adds the options shared by every escape time fractal to `command`.
+fn escape_time_options(command: clap::App<'static, 'static>) -> clap::App<'static, 'static> {
+    command
+        .arg(
+            clap::Arg::with_name("julia")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .help(
+                    "Draw the Julia set of the escape time function for the constant c = \
+                     RE + IM*i instead",
+                )
+                .long("julia")
+                .value_name("RE,IM"),
+        )
+        .arg(
+            clap::Arg::with_name("bailout")
+                .takes_value(true)
+                .help("The bailout radius that decides when a value has escaped")
+                .long("bailout")
+                .value_name("RADIUS")
+                .default_value("3.0"),
+        )
+        .arg(
+            clap::Arg::with_name("criterion")
+                .takes_value(true)
+                .help("How to compare a value against the bailout radius")
+                .long("escape-criterion")
+                .value_name("CRITERION")
+                .possible_values(&EscapeCriterion::NAMES)
+                .default_value("norm"),
+        )
+        .arg(
+            clap::Arg::with_name("coloring")
+                .takes_value(true)
+                .help(
+                    "How to color the points outside of the set: by how many iterations they \
+                     took to escape (the default), by their estimated distance to the set \
+                     (only supported by some fractals), by how close their orbits come to an \
+                     orbit trap (which colors the points inside of the set as well), or by \
+                     the stripe, triangle inequality, or curvature average over their orbits \
+                     (which look best with a large --bailout, eg 1000)",
+                )
+                .long("coloring")
+                .value_name("COLORING")
+                .possible_values(&[
+                    "iterations",
+                    "distance",
+                    "trap",
+                    "stripe",
+                    "triangle",
+                    "curvature",
+                ])
+                .conflicts_with_all(&["orbit-density", "nebulabrot"]),
+        )
+        .arg(
+            clap::Arg::with_name("stripe-density")
+                .takes_value(true)
+                .help("The number of stripes per turn around the origin for --coloring stripe")
+                .long("stripe-density")
+                .value_name("DENSITY")
+                .default_value("5"),
+        )
+        .arg(
+            clap::Arg::with_name("trap")
+                .takes_value(true)
+                .help("The shape of the orbit trap for --coloring trap")
+                .long("trap")
+                .value_name("SHAPE")
+                .possible_values(&TrapShape::NAMES)
+                .default_value("point"),
+        )
+        .arg(
+            clap::Arg::with_name("trap-center")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .help("The center of the orbit trap (or of --trap-image)")
+                .long("trap-center")
+                .value_name("RE,IM")
+                .default_value("0,0"),
+        )
+        .arg(
+            clap::Arg::with_name("trap-radius")
+                .takes_value(true)
+                .help("The radius of a circle orbit trap (or half the width of --trap-image)")
+                .long("trap-radius")
+                .value_name("RADIUS")
+                .default_value("1"),
+        )
+        .arg(
+            clap::Arg::with_name("trap-angle")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .help(
+                    "The angle of a line orbit trap, in degrees counterclockwise from the real \
+                     axis",
+                )
+                .long("trap-angle")
+                .value_name("DEGREES")
+                .default_value("0"),
+        )
+        .arg(
+            clap::Arg::with_name("trap-image")
+                .takes_value(true)
+                .help(
+                    "Use an image as the orbit trap instead of --trap: orbits take the color of \
+                     the first opaque pixel they land on",
+                )
+                .long("trap-image")
+                .value_name("FILE"),
+        )
+        .arg(
+            clap::Arg::with_name("lighting")
+                .takes_value(true)
+                .help(
+                    "Shade the fractal as a surface that rises towards the set, either with the \
+                     smoothed iteration counts or with the distance estimate (only supported \
+                     by some fractals), and light it. Press [ and ] to turn the light, and ; \
+                     and ' to lower and raise it",
+                )
+                .long("lighting")
+                .value_name("SURFACE")
+                .possible_values(&["iterations", "distance"])
+                .conflicts_with_all(&["subdivide", "orbit-density", "nebulabrot"]),
+        )
+        .arg(
+            clap::Arg::with_name("light-azimuth")
+                .takes_value(true)
+                .allow_hyphen_values(true)
+                .help(
+                    "The direction the light comes from for --lighting, in degrees \
+                     counterclockwise from the right of the window",
+                )
+                .long("light-azimuth")
+                .value_name("DEGREES")
+                .default_value("45"),
+        )
+        .arg(
+            clap::Arg::with_name("light-elevation")
+                .takes_value(true)
+                .help("The angle of the light above the fractal for --lighting, in degrees")
+                .long("light-elevation")
+                .value_name("DEGREES")
+                .default_value("45"),
+        )
+        .arg(
+            clap::Arg::with_name("height-scale")
+                .takes_value(true)
+                .help("How much --lighting exaggerates the slopes of the surface")
+                .long("height-scale")
+                .value_name("SCALE")
+                .default_value("1"),
+        )
+        .arg(
+            clap::Arg::with_name("light-strength")
+                .takes_value(true)
+                .help(
+                    "How much of each color --lighting replaces with its lit version, from 0 \
+                     to 1",
+                )
+                .long("light-strength")
+                .value_name("STRENGTH")
+                .default_value("0.75"),
+        )
+        .arg(
+            clap::Arg::with_name("orbit-density")
+                .takes_value(true)
+                .help(
+                    "Draw how often the orbits of random points pass through each pixel \
+                     instead, using either the orbits that escape or the ones that do not",
+                )
+                .long("orbit-density")
+                .value_name("SELECTION")
+                .possible_values(&OrbitSelection::NAMES),
+        )
+        .arg(
+            clap::Arg::with_name("nebulabrot")
+                .takes_value(true)
+                .help(
+                    "Draw the orbit density with separate iteration limits for the red, \
+                     green, and blue channels instead (uses the escaping orbits unless \
+                     --orbit-density says otherwise)",
+                )
+                .long("nebulabrot")
+                .value_name("RED,GREEN,BLUE"),
+        )
+        .arg(
+            clap::Arg::with_name("samples")
+                .takes_value(true)
+                .help(
+                    "The number of random points whose orbits are drawn by --orbit-density \
+                     or --nebulabrot",
+                )
+                .long("samples")
+                .value_name("COUNT")
+                .default_value("10000000"),
+        )
+        .arg(
+            clap::Arg::with_name("subdivide")
+                .help(
+                    "Skip evaluating the pixels inside of uniform regions of the set by \
+                     subdividing the screen into rectangles (only used for fractals where \
+                     this is safe), and log how many pixels were evaluated",
+                )
+                .long("subdivide")
+                .conflicts_with_all(&["coloring", "orbit-density", "nebulabrot"]),
+        )
+        .arg(
+            clap::Arg::with_name("supersample")
+                .takes_value(true)
+                .help(
+                    "Anti-alias by averaging N by N samples for each pixel (1 turns it off, not \
+                     used for orbit density renderings)",
+                )
+                .long("supersample")
+                .value_name("N")
+                .default_value("1"),
+        )
+        .arg(
+            clap::Arg::with_name("supersample-mode")
+                .takes_value(true)
+                .help(
+                    "How to pick the samples for --supersample: a regular grid, a grid with \
+                     random offsets, or a grid only for the pixels whose colors differ from \
+                     their neighbors'",
+                )
+                .long("supersample-mode")
+                .value_name("MODE")
+                .possible_values(&Supersampling::NAMES)
+                .default_value("grid"),
+        )
+        .arg(
+            clap::Arg::with_name("color-scale")
+                .takes_value(true)
+                .help(
+                    "How to spread the palette over the iteration counts: one color per \
+                     iteration, by the histogram of the counts in the view, or over the range \
+                     of the counts in the view",
+                )
+                .long("color-scale")
+                .value_name("SCALE")
+                .possible_values(&ColorScale::NAMES)
+                .default_value("fixed"),
+        )
+}
+
+/// Reads the `--color-scale` option from `matches`.
+fn color_scale(matches: &clap::ArgMatches) -> Result<ColorScale, String> {
+    ColorScale::from_name(matches.value_of("color-scale").unwrap_or("fixed"))
+}
+
+/// Reads the `--trap` options from `matches`.
+fn orbit_trap(matches: &clap::ArgMatches) -> Result<OrbitTrap, String> {
+    let center = parse_complex(
+        "trap-center",
+        matches.value_of("trap-center").unwrap_or("0,0"),
+    )?;
+    let radius: f64 = (extract!(matches, "trap-radius"))?;
+    let shape = match matches.value_of("trap-image") {
+        Some(path) => {
+            let image = ::image::open(path)
+                .map_err(|e| format!("Error reading trap-image {}: {}", path, e))?
+                .to_rgba();
+            let (width, height) = image.dimensions();
+            let pixels = image.pixels().map(|pixel| ColorU8(pixel.0)).collect();
+            TrapShape::Image(ImageTrap::new(width, height, pixels, center, 2.0 * radius)?)
+        }
+        None => TrapShape::from_name(
+            matches.value_of("trap").unwrap_or("point"),
+            center,
+            radius,
+            (extract!(matches, "trap-angle"))?,
+        )?,
+    };
+    Ok(OrbitTrap::new(shape))
+}
+
+/// Reads the averaging coloring that `--coloring` names from `matches`, if it names one.
+fn averaging(matches: &clap::ArgMatches) -> Result<Option<Averaging>, String> {
+    match matches.value_of("coloring") {
+        Some(name) if Averaging::NAMES.contains(&name) => Ok(Some(Averaging::from_name(
+            name,
+            (extract!(matches, "stripe-density"))?,
+        )?)),
+        _ => Ok(None),
+    }
+}
+
+/// Reads the `--light-*` options from `matches`, after checking that none of the options
+/// conflict (including the ones that can not be combined with `--lighting`).
+fn lighting(matches: &clap::ArgMatches) -> Result<Lighting, String> {
+    check_incompatible_options(matches)?;
+    Ok(Lighting::new(
+        (extract!(matches, "light-azimuth"))?,
+        (extract!(matches, "light-elevation"))?,
+    )
+    .with_height_scale((extract!(matches, "height-scale"))?)
+    .with_strength((extract!(matches, "light-strength"))?))
+}
+
+/// Describes how an option is used (such as `--coloring trap`) if `matches` uses it in a way
+/// that some other options do not support.
+type OptionUseFn = fn(&clap::ArgMatches) -> Result<Option<String>, String>;
+
+/// Names `option` if `used` is true.
+fn option_use(option: &str, used: bool) -> Option<String> {
+    if used {
+        Some(option.to_string())
+    } else {
+        None
+    }
+}
+
+fn lighting_use(matches: &clap::ArgMatches) -> Result<Option<String>, String> {
+    Ok(option_use("--lighting", matches.is_present("lighting")))
+}
+
+fn coloring_use(matches: &clap::ArgMatches) -> Result<Option<String>, String> {
+    Ok(matches
+        .value_of("coloring")
+        .filter(|&coloring| coloring != "iterations")
+        .map(|coloring| format!("--coloring {}", coloring)))
+}
+
+fn supersample_use(matches: &clap::ArgMatches) -> Result<Option<String>, String> {
+    let used = supersampling(matches)? != Supersampling::None;
+    Ok(option_use("--supersample", used))
+}
+
+fn color_scale_use(matches: &clap::ArgMatches) -> Result<Option<String>, String> {
+    let used = color_scale(matches)?.is_adaptive();
+    Ok(option_use("--color-scale", used))
+}
+
+fn subdivide_use(matches: &clap::ArgMatches) -> Result<Option<String>, String> {
+    Ok(option_use("--subdivide", matches.is_present("subdivide")))
+}
+
+/// Pairs of escape time options that can not be combined with each other, beyond the ones that
+/// clap already rejects (the `conflicts_with` of each argument). The options only conflict when
+/// they are used in a way that the other one does not support, such as `--supersample` with
+/// more than one sample.
+const INCOMPATIBLE_OPTIONS: [(OptionUseFn, OptionUseFn); 6] = [
+    (lighting_use, coloring_use),
+    (supersample_use, lighting_use),
+    (color_scale_use, coloring_use),
+    (supersample_use, subdivide_use),
+    (color_scale_use, subdivide_use),
+    (supersample_use, color_scale_use),
+];
+
+/// Checks that `matches` does not combine any of the `INCOMPATIBLE_OPTIONS`.
+fn check_incompatible_options(matches: &clap::ArgMatches) -> Result<(), String> {
+    for (first, second) in INCOMPATIBLE_OPTIONS.iter() {
+        if let (Some(first), Some(second)) = (first(matches)?, second(matches)?) {
+            return Err(format!("{} can not be combined with {}", first, second));
+        }
+    }
+    Ok(())
+}
+
+/// Reads the `--supersample` options from `matches`.
+fn supersampling(matches: &clap::ArgMatches) -> Result<Supersampling, String> {
+    Supersampling::from_name(
+        matches.value_of("supersample-mode").unwrap_or("grid"),
+        (extract!(matches, "supersample"))?,
+    )
+}
+
+/// Draws `et`, or its Julia set if `--julia` was given, using the options in `matches`.
+fn run_escape_time<E>(et: E, matches: &clap::ArgMatches) -> Result<(), String>
+where
+    E: EscapeTime + Send + Sync + 'static,
+{
+    match matches.value_of("julia") {
+        Some(julia) => draw_escape_time(
+            JuliaSet::new(et, parse_complex("julia", julia)?),
+            matches,
+            None,
+        ),
+        None => draw_escape_time(et, matches, None),
+    }
+}
+
+/// Draws `et` using the escape time or orbit density options in `matches`. If `slice_fn` is
+/// given, it moves the slice that `et` is drawn through.
+fn draw_escape_time<E>(
+    et: E,
+    matches: &clap::ArgMatches,
+    slice_fn: Option<SliceFn>,
+) -> Result<(), String>
+where
+    E: EscapeTime + Send + Sync + 'static,
+{
+    let selection = match matches.value_of("orbit-density") {
+        Some(name) => Some(OrbitSelection::from_name(name)?),
+        None => None,
+    };
+    let renderer = match (matches.value_of("nebulabrot"), selection) {
+        (Some(limits), selection) => Buddhabrot::nebulabrot(
+            et,
+            selection.unwrap_or(OrbitSelection::Escaping),
+            parse_iteration_limits("nebulabrot", limits)?,
+        ),
+        (None, Some(selection)) => Buddhabrot::new(et, selection),
+        (None, None) => {
+            // TODO: `et` when passed in here wants E to be constraint by `'static`. Why?
+            if matches.is_present("lighting") {
+                let mut handler =
+                    pistonrendering::escapetime::EscapeTimeWindowHandler::with_lighting(
+                        Surface::Iterations(Arc::new(et)),
+                        color_scale(matches)?,
+                        lighting(matches)?,
+                    );
+                if let Some(slice_fn) = slice_fn {
+                    handler = handler.with_slice_sweep(slice_fn);
+                }
+                pistonrendering::run(&mut handler);
+                return Ok(());
+            }
+            check_incompatible_options(matches)?;
+            let supersampling = supersampling(matches)?;
+            let scale = color_scale(matches)?;
+            let averaging = averaging(matches)?;
+            let coloring = matches.value_of("coloring").unwrap_or("iterations");
+            let mut handler = if coloring == "trap" {
+                pistonrendering::escapetime::EscapeTimeWindowHandler::with_orbit_trap(
+                    Arc::new(et),
+                    orbit_trap(matches)?,
+                    supersampling,
+                )
+            } else if let Some(averaging) = averaging {
+                pistonrendering::escapetime::EscapeTimeWindowHandler::with_averaging(
+                    Arc::new(et),
+                    averaging,
+                    supersampling,
+                )
+            } else if matches.is_present("subdivide") {
+                pistonrendering::escapetime::EscapeTimeWindowHandler::with_subdivision(Arc::new(et))
+            } else if scale.is_adaptive() {
+                pistonrendering::escapetime::EscapeTimeWindowHandler::with_color_scale(
+                    Arc::new(et),
+                    scale,
+                )
+            } else {
+                pistonrendering::escapetime::EscapeTimeWindowHandler::new(
+                    Arc::new(et),
+                    supersampling,
+                )
+            };
+            if let Some(slice_fn) = slice_fn {
+                handler = handler.with_slice_sweep(slice_fn);
+            }
+            pistonrendering::run(&mut handler);
+            return Ok(());
+        }
+    };
+    let samples = (extract!(matches, "samples"))?;
+    let mut handler = pistonrendering::buddhabrot::BuddhabrotWindowHandler::new(renderer, samples);
+    pistonrendering::run(&mut handler);
+
+    Ok(())
+}
+
+/// Constructs a fractal from MAX_ITERATIONS and the rest of the subcommand's arguments.
+type ArgsCtorFn<T> = Box<dyn Fn(u64, &clap::ArgMatches) -> Result<T, String>>;
+
+/// The arguments a `ConvergenceTimeCommand` accepts in addition to MAX_ITERATIONS.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConvergenceTimeArgs {
+    /// No other arguments.
+    None,
+    /// A polynomial (as roots or coefficients) and a relaxation factor.
+    Polynomial,
+}
+
+pub struct ConvergenceTimeCommand<E>
+where
+    E: ConvergenceTime + Send + Sync,
+{
+    name: &'static str,
+    description: &'static str,
+    args: ConvergenceTimeArgs,
+    ctor: ArgsCtorFn<E>,
+}
+
+impl<E> ConvergenceTimeCommand<E>
+where
+    E: ConvergenceTime + Send + Sync,
+{
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        args: ConvergenceTimeArgs,
+        ctor: ArgsCtorFn<E>,
+    ) -> ConvergenceTimeCommand<E> {
+        ConvergenceTimeCommand {
+            name,
+            description,
+            args,
+            ctor,
+        }
+    }
+}
+
+impl<E> FractalSubcommand for ConvergenceTimeCommand<E>
+where
+    E: ConvergenceTime + Send + Sync + 'static,
+{
+    fn command(&self) -> clap::App<'static, 'static> {
+        let command = clap::SubCommand::with_name(self.name)
+            .about(self.description)
+            .arg(
+                clap::Arg::with_name("MAX_ITERATIONS")
+                    .required(true)
+                    .index(1)
+                    .help("The maximum number of iterations to wait for a point to converge"),
+            );
+        if self.args == ConvergenceTimeArgs::None {
+            return command;
+        }
+        command
+            .arg(
+                clap::Arg::with_name("root")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .allow_hyphen_values(true)
+                    .conflicts_with("coefficient")
+                    .help("A root of the polynomial (repeat for each root) [default: z^3 - 1]")
+                    .long("root")
+                    .value_name("RE,IM"),
+            )
+            .arg(
+                clap::Arg::with_name("coefficient")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .allow_hyphen_values(true)
+                    .help(
+                        "A coefficient of the polynomial (repeat for each term, starting with the \
+                         highest degree)",
+                    )
+                    .long("coefficient")
+                    .value_name("RE,IM"),
+            )
+            .arg(
+                clap::Arg::with_name("relaxation")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help("The factor that each step of Newton's method is scaled by")
+                    .long("relaxation")
+                    .value_name("RE,IM")
+                    .default_value("1,0"),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches) -> Result<(), String> {
+        let max_iterations = (extract!(matches, "MAX_ITERATIONS"))?;
+
+        let system = (self.ctor)(max_iterations, matches)?;
+        let mut handler =
+            pistonrendering::escapetime::EscapeTimeWindowHandler::for_convergence_time(Arc::new(
+                system,
+            ));
+        pistonrendering::run(&mut handler);
+
+        Ok(())
+    }
+}
+
+/// Draws the mandelbrot set using perturbation theory, which allows zooming far beyond the
+/// precision of an `f64`.
+pub struct DeepZoomCommand {
+    name: &'static str,
+    description: &'static str,
+}
+
+impl DeepZoomCommand {
+    pub fn new(name: &'static str, description: &'static str) -> DeepZoomCommand {
+        DeepZoomCommand { name, description }
+    }
+}
+
+impl FractalSubcommand for DeepZoomCommand {
+    fn command(&self) -> clap::App<'static, 'static> {
+        clap::SubCommand::with_name(self.name)
+            .about(self.description)
+            .arg(
+                clap::Arg::with_name("MAX_ITERATIONS")
+                    .required(true)
+                    .index(1)
+                    .help(
+                        "The maximum number of iterations of the escape time function before \
+                         deciding the fracal has escaped",
+                    ),
+            )
+            .arg(
+                clap::Arg::with_name("POWER")
+                    .required(true)
+                    .index(2)
+                    .help("The exponent used in the escape time function (positive integer)"),
+            )
+            .arg(
+                clap::Arg::with_name("center")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help("The center of the initial view, with as many digits as needed")
+                    .long("center")
+                    .value_name("RE,IM")
+                    .default_value("-0.75,0"),
+            )
+            .arg(
+                clap::Arg::with_name("view-width")
+                    .takes_value(true)
+                    .help("The width of the initial view on the complex plane")
+                    .long("view-width")
+                    .value_name("WIDTH")
+                    .default_value("3.5"),
+            )
+            .arg(
+                clap::Arg::with_name("bailout")
+                    .takes_value(true)
+                    .help("The bailout radius that decides when a value has escaped")
+                    .long("bailout")
+                    .value_name("RADIUS")
+                    .default_value("3.0"),
+            )
+            .arg(
+                clap::Arg::with_name("criterion")
+                    .takes_value(true)
+                    .help("How to compare a value against the bailout radius")
+                    .long("escape-criterion")
+                    .value_name("CRITERION")
+                    .possible_values(&EscapeCriterion::NAMES)
+                    .default_value("norm"),
+            )
+    }
+
+    fn run(&self, matches: &clap::ArgMatches) -> Result<(), String> {
+        let max_iterations = (extract!(matches, "MAX_ITERATIONS"))?;
+        let power = (extract!(matches, "POWER"))?;
+        let view_width: f64 = (extract!(matches, "view-width"))?;
+        if view_width <= 0.0 || !view_width.is_finite() {
+            return Err("view-width must be a positive number".to_string());
+        }
+        let bailout = EscapeCriterion::from_name(
+            matches.value_of("criterion").unwrap_or("norm"),
+            (extract!(matches, "bailout"))?,
+        )?;
+        // Keep every digit of the center, even if the initial view is wide, so that zooming in
+        // later does not lose them.
+        let center_text = matches.value_of("center").unwrap_or("-0.75,0");
+        let digit_bits = (center_text.len() as f64 * 10f64.log2()).ceil() as u32;
+        let precision = cmp::max(precision_for_view_size(view_width), digit_bits);
+        let center = parse_big_complex("center", center_text, precision)?;
+
+        let fractal = PerturbedMandelbrot::new(center, max_iterations, power, bailout);
+        let mut handler =
+            pistonrendering::deepzoom::DeepZoomWindowHandler::new(fractal, view_width);
+        pistonrendering::run(&mut handler);
+
+        Ok(())
+    }
 }
 
-pub struct ChaosGameCommand<E>
-where
-    E: ChaosGameMoveIterator,
-{
+/// The fractals that `ReferenceCommand` can draw, which are the ones that implement
+/// `GenericEscapeTime`.
+const REFERENCE_FRACTALS: [&str; 7] = [
+    "buffalo",
+    "burningmandel",
+    "burningship",
+    "celtic",
+    "mandelbrot",
+    "roadrunner",
+    "tricorn",
+];
+
+/// The number types that `ReferenceCommand` can compute with.
+const REFERENCE_PRECISIONS: [&str; 4] = ["f32", "f64", "double-double", "bigfloat"];
+
+/// Renders an escape time fractal with a chosen number type, and writes it to a PNG instead of
+/// opening a window. The images are slow to compute, but can be used to check the faster
+/// renderers, such as `deepmandelbrot`, against.
+pub struct ReferenceCommand {
     name: &'static str,
     description: &'static str,
-    ctor: Box<dyn Fn() -> E>,
 }
 
-impl<E> ChaosGameCommand<E>
-where
-    E: ChaosGameMoveIterator,
-{
-    pub fn new(
-        name: &'static str,
-        description: &'static str,
-        ctor: Box<dyn Fn() -> E>,
-    ) -> ChaosGameCommand<E> {
-        ChaosGameCommand {
-            name,
-            description,
-            ctor,
-        }
+impl ReferenceCommand {
+    pub fn new(name: &'static str, description: &'static str) -> ReferenceCommand {
+        ReferenceCommand { name, description }
     }
 }
 
-impl<E> FractalSubcommand for ChaosGameCommand<E>
-where
-    E: ChaosGameMoveIterator + 'static,
-{
+impl FractalSubcommand for ReferenceCommand {
     fn command(&self) -> clap::App<'static, 'static> {
         clap::SubCommand::with_name(self.name)
             .about(self.description)
             .arg(
-                clap::Arg::with_name("drawrate")
+                clap::Arg::with_name("OUTPUT")
+                    .required(true)
+                    .index(1)
+                    .help("The path of the PNG image to write"),
+            )
+            .arg(
+                clap::Arg::with_name("FRACTAL")
+                    .required(true)
+                    .index(2)
+                    .possible_values(&REFERENCE_FRACTALS)
+                    .help("The escape time fractal to draw"),
+            )
+            .arg(
+                clap::Arg::with_name("MAX_ITERATIONS")
+                    .required(true)
+                    .index(3)
+                    .help(
+                        "The maximum number of iterations of the escape time function before \
+                         deciding the fracal has escaped",
+                    ),
+            )
+            .arg(
+                clap::Arg::with_name("POWER")
+                    .required(true)
+                    .index(4)
+                    .allow_hyphen_values(true)
+                    .help(
+                        "The exponent used in the escape time function: an integer (which may be \
+                         negative), a real number, or a complex number written as RE,IM",
+                    ),
+            )
+            .arg(
+                clap::Arg::with_name("precision")
                     .takes_value(true)
-                    .help("The number of points to draw per frame")
-                    .long("drawrate")
-                    .value_name("MPF")
-                    .default_value("1"),
+                    .help("The number type that every step of the iteration is computed with")
+                    .long("precision")
+                    .value_name("TYPE")
+                    .possible_values(&REFERENCE_PRECISIONS)
+                    .default_value("double-double"),
+            )
+            .arg(
+                clap::Arg::with_name("center")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help(
+                        "The center of the view, with as many digits as needed [default: the \
+                         center of the fractal's default view]",
+                    )
+                    .long("center")
+                    .value_name("RE,IM"),
+            )
+            .arg(
+                clap::Arg::with_name("view-width")
+                    .takes_value(true)
+                    .help(
+                        "The width of the view on the complex plane [default: the width of the \
+                         fractal's default view]",
+                    )
+                    .long("view-width")
+                    .value_name("WIDTH"),
+            )
+            .arg(
+                clap::Arg::with_name("width")
+                    .takes_value(true)
+                    .help("The width of the image in pixels")
+                    .long("width")
+                    .value_name("PIXELS")
+                    .default_value("800"),
+            )
+            .arg(
+                clap::Arg::with_name("height")
+                    .takes_value(true)
+                    .help("The height of the image in pixels")
+                    .long("height")
+                    .value_name("PIXELS")
+                    .default_value("600"),
+            )
+            .arg(
+                clap::Arg::with_name("bailout")
+                    .takes_value(true)
+                    .help("The bailout radius that decides when a value has escaped")
+                    .long("bailout")
+                    .value_name("RADIUS")
+                    .default_value("3.0"),
+            )
+            .arg(
+                clap::Arg::with_name("criterion")
+                    .takes_value(true)
+                    .help("How to compare a value against the bailout radius")
+                    .long("escape-criterion")
+                    .value_name("CRITERION")
+                    .possible_values(&EscapeCriterion::NAMES)
+                    .default_value("norm"),
             )
     }
 
     fn run(&self, matches: &clap::ArgMatches) -> Result<(), String> {
-        let drawrate = extract!(matches, "drawrate")?;
+        let max_iterations = (extract!(matches, "MAX_ITERATIONS"))?;
+        let power = (extract!(matches, "POWER"))?;
+        let bailout = EscapeCriterion::from_name(
+            matches.value_of("criterion").unwrap_or("norm"),
+            (extract!(matches, "bailout"))?,
+        )?;
+        match matches.value_of("FRACTAL").unwrap_or("") {
+            "buffalo" => render_reference(
+                Buffalo::with_exponent(max_iterations, power, bailout),
+                matches,
+            ),
+            "burningmandel" => render_reference(
+                BurningMandel::with_exponent(max_iterations, power, bailout),
+                matches,
+            ),
+            "burningship" => render_reference(
+                BurningShip::with_exponent(max_iterations, power, bailout),
+                matches,
+            ),
+            "celtic" => render_reference(
+                Celtic::with_exponent(max_iterations, power, bailout),
+                matches,
+            ),
+            "mandelbrot" => render_reference(
+                Mandelbrot::with_exponent(max_iterations, power, bailout),
+                matches,
+            ),
+            "roadrunner" => render_reference(
+                RoadRunner::with_exponent(max_iterations, power, bailout),
+                matches,
+            ),
+            "tricorn" => render_reference(
+                Tricorn::with_exponent(max_iterations, power, bailout),
+                matches,
+            ),
+            name => Err(format!("Unknown fractal {}", name)),
+        }
+    }
+}
 
-        let game = Box::new((self.ctor)());
-        let mut handler = pistonrendering::chaosgame::ChaosGameWindowHandler::new(game, drawrate);
-        pistonrendering::run(&mut handler);
+/// Renders `fractal` for a `ReferenceCommand`, with the number type that `matches` asks for.
+fn render_reference<E>(fractal: E, matches: &clap::ArgMatches) -> Result<(), String>
+where
+    E: GenericEscapeTime<f32>
+        + GenericEscapeTime<f64>
+        + GenericEscapeTime<DoubleDouble>
+        + GenericEscapeTime<BigFloat>
+        + Send
+        + Sync
+        + 'static,
+{
+    let width: u32 = (extract!(matches, "width"))?;
+    let height: u32 = (extract!(matches, "height"))?;
+    if width == 0 || height == 0 {
+        return Err("The image must be at least 1 pixel wide and tall".to_string());
+    }
+    let [top_left, bottom_right] = fractal.default_view_area();
+    let view_width = match matches.value_of("view-width") {
+        Some(_) => (extract!(matches, "view-width"))?,
+        None => bottom_right.re - top_left.re,
+    };
+    if view_width <= 0.0 || !view_width.is_finite() {
+        return Err("view-width must be a positive number".to_string());
+    }
+    let view_size = [
+        view_width,
+        view_width * f64::from(height) / f64::from(width),
+    ];
+    let center_text = matches.value_of("center");
+    let default_center = (top_left + bottom_right) / 2.0;
 
-        Ok(())
+    let fractal = Arc::new(fractal);
+    let path = Path::new(matches.value_of("OUTPUT").unwrap());
+    match matches.value_of("precision").unwrap_or("double-double") {
+        "f32" => {
+            let view = reference_view::<f32>(center_text, default_center, view_size, ())?;
+            reference::render_png(fractal, view, width, height, path)
+        }
+        "f64" => {
+            let view = reference_view::<f64>(center_text, default_center, view_size, ())?;
+            reference::render_png(fractal, view, width, height, path)
+        }
+        "double-double" => {
+            let view = reference_view::<DoubleDouble>(center_text, default_center, view_size, ())?;
+            reference::render_png(fractal, view, width, height, path)
+        }
+        "bigfloat" => {
+            // Like deepmandelbrot, keep every digit of the center
+            let digit_bits = center_text
+                .map(|text| (text.len() as f64 * 10f64.log2()).ceil() as u32)
+                .unwrap_or(0);
+            let precision = cmp::max(precision_for_view_size(view_width), digit_bits);
+            let view =
+                reference_view::<BigFloat>(center_text, default_center, view_size, precision)?;
+            reference::render_png(fractal, view, width, height, path)
+        }
+        name => Err(format!("Unknown precision {}", name)),
     }
 }
 
-pub struct EscapeTimeCommand<E>
+/// Computes the view area of `view_size` (its width and height) around the center given by
+/// `center_text`, or `default_center` if there is none.
+fn reference_view<T>(
+    center_text: Option<&str>,
+    default_center: Complex64,
+    view_size: [f64; 2],
+    precision: T::Precision,
+) -> Result<ViewArea<T>, String>
 where
-    E: EscapeTime + Send + Sync,
+    T: Real,
+{
+    let center = match center_text {
+        Some(text) => parse_real_complex::<T>("center", text, precision)?,
+        None => RealComplex::from_complex64(default_center, precision),
+    };
+    let half_width = T::from_f64(view_size[0] / 2.0, precision);
+    let half_height = T::from_f64(view_size[1] / 2.0, precision);
+    Ok(ViewArea::new(
+        RealComplex::new(
+            center.re.clone() - half_width.clone(),
+            center.im.clone() + half_height.clone(),
+        ),
+        RealComplex::new(center.re + half_width, center.im - half_height),
+    ))
+}
+
+/// The arguments a `RayMarchCommand` accepts to shape its fractal.
+#[derive(Clone, Copy, PartialEq)]
+pub enum RayMarchArgs {
+    /// The power of a Mandelbulb.
+    Power,
+    /// The scale of a Mandelbox.
+    Scale,
+}
+
+/// Renders a three dimensional fractal by ray marching, and writes it to a PNG instead of opening
+/// a window.
+pub struct RayMarchCommand<F>
+where
+    F: DistanceEstimator3D + Send + Sync,
 {
     name: &'static str,
     description: &'static str,
-    ctor: Box<dyn Fn(u64, u64) -> E>,
+    args: RayMarchArgs,
+    ctor: ArgsCtorFn<F>,
 }
 
-impl<E> EscapeTimeCommand<E>
+impl<F> RayMarchCommand<F>
 where
-    E: EscapeTime + Send + Sync,
+    F: DistanceEstimator3D + Send + Sync,
 {
     pub fn new(
         name: &'static str,
         description: &'static str,
-        ctor: Box<dyn Fn(u64, u64) -> E>,
-    ) -> EscapeTimeCommand<E> {
-        EscapeTimeCommand {
+        args: RayMarchArgs,
+        ctor: ArgsCtorFn<F>,
+    ) -> RayMarchCommand<F> {
+        RayMarchCommand {
             name,
             description,
+            args,
             ctor,
         }
     }
 }
 
-impl<E> FractalSubcommand for EscapeTimeCommand<E>
+impl<F> FractalSubcommand for RayMarchCommand<F>
 where
-    E: EscapeTime + Send + Sync + 'static,
+    F: DistanceEstimator3D + Send + Sync + 'static,
 {
     fn command(&self) -> clap::App<'static, 'static> {
-        clap::SubCommand::with_name(self.name)
+        let command = clap::SubCommand::with_name(self.name)
             .about(self.description)
             .arg(
-                clap::Arg::with_name("MAX_ITERATIONS")
+                clap::Arg::with_name("OUTPUT")
                     .required(true)
                     .index(1)
+                    .help("The path of the PNG image to write"),
+            )
+            .arg(
+                clap::Arg::with_name("MAX_ITERATIONS")
+                    .required(true)
+                    .index(2)
                     .help(
                         "The maximum number of iterations of the escape time function before \
-                         deciding the fracal has escaped",
+                         deciding a point is inside of the set",
                     ),
             )
             .arg(
-                clap::Arg::with_name("POWER")
-                    .required(true)
-                    .index(2)
-                    .help("The exponent used in the escape time function (positive integer)"),
+                clap::Arg::with_name("width")
+                    .takes_value(true)
+                    .help("The width of the image in pixels")
+                    .long("width")
+                    .value_name("PIXELS")
+                    .default_value("800"),
+            )
+            .arg(
+                clap::Arg::with_name("height")
+                    .takes_value(true)
+                    .help("The height of the image in pixels")
+                    .long("height")
+                    .value_name("PIXELS")
+                    .default_value("600"),
+            )
+            .arg(
+                clap::Arg::with_name("camera")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help(
+                        "The position of the camera [default: in front of and above the \
+                         fractal, far enough away to see all of it]",
+                    )
+                    .long("camera")
+                    .value_name("X,Y,Z"),
+            )
+            .arg(
+                clap::Arg::with_name("look-at")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help("The point that the camera looks at")
+                    .long("look-at")
+                    .value_name("X,Y,Z")
+                    .default_value("0,0,0"),
+            )
+            .arg(
+                clap::Arg::with_name("up")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help("The direction that points towards the top of the image")
+                    .long("up")
+                    .value_name("X,Y,Z")
+                    .default_value("0,0,1"),
+            )
+            .arg(
+                clap::Arg::with_name("fov")
+                    .takes_value(true)
+                    .help("The vertical field of view of the camera, in degrees")
+                    .long("fov")
+                    .value_name("DEGREES")
+                    .default_value("45"),
+            )
+            .arg(
+                clap::Arg::with_name("light")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help("The direction that the light comes from")
+                    .long("light")
+                    .value_name("X,Y,Z")
+                    .default_value("1,-1,2"),
+            )
+            .arg(
+                clap::Arg::with_name("ambient-occlusion")
+                    .takes_value(true)
+                    .help("How much creases are darkened, from 0 (not at all) to 1")
+                    .long("ambient-occlusion")
+                    .value_name("STRENGTH")
+                    .default_value("1"),
+            )
+            .arg(
+                clap::Arg::with_name("soft-shadows")
+                    .takes_value(true)
+                    .help("How hard the edges of shadows are (0 turns off shadows)")
+                    .long("soft-shadows")
+                    .value_name("HARDNESS")
+                    .default_value("16"),
+            )
+            .arg(
+                clap::Arg::with_name("max-steps")
+                    .takes_value(true)
+                    .help("The maximum number of steps a ray takes before it counts as a hit")
+                    .long("max-steps")
+                    .value_name("STEPS")
+                    .default_value("200"),
             )
+            .arg(
+                clap::Arg::with_name("detail")
+                    .takes_value(true)
+                    .help("How finely the surface is resolved, as a multiple of the pixel size")
+                    .long("detail")
+                    .value_name("DETAIL")
+                    .default_value("1"),
+            );
+        match self.args {
+            RayMarchArgs::Power => command.arg(
+                clap::Arg::with_name("power")
+                    .takes_value(true)
+                    .help("The power of the triplex formula (greater than 1)")
+                    .long("power")
+                    .value_name("POWER")
+                    .default_value("8"),
+            ),
+            RayMarchArgs::Scale => command.arg(
+                clap::Arg::with_name("scale")
+                    .takes_value(true)
+                    .allow_hyphen_values(true)
+                    .help("The scale of each iteration (greater than 1 or less than -1)")
+                    .long("scale")
+                    .value_name("SCALE")
+                    .default_value("2"),
+            ),
+        }
     }
 
     fn run(&self, matches: &clap::ArgMatches) -> Result<(), String> {
         let max_iterations = (extract!(matches, "MAX_ITERATIONS"))?;
-        // .unwrap_or_else(|| return Err("Must specify a MAX_ITERATIONS of 1 or greater!"));
-        let power = (extract!(matches, "POWER"))?;
-        // .unwrap_or_else(|| return Err("Must specify a POWER of 1 or greater!"));
+        let width: u32 = (extract!(matches, "width"))?;
+        let height: u32 = (extract!(matches, "height"))?;
+        if width == 0 || height == 0 {
+            return Err("The image must be at least 1 pixel wide and tall".to_string());
+        }
 
-        // The ctor callback can return a raw object that implements EscapeTime because this method
-        // is templated to E instead of handling a boxed object that implements EscapeTime.
-        //
-        // We could alternately avoid using templating, in which case the callback would have to
-        // return an Arc<EscapeTime> in order to abstract away the implementation of the trait.
-        let et = Arc::new((self.ctor)(max_iterations, power));
-        // TODO: `et` when passed in here wants E to be constraint by `'static`. Why?
-        let mut handler = pistonrendering::escapetime::EscapeTimeWindowHandler::new(et);
-        pistonrendering::run(&mut handler);
+        let fractal = (self.ctor)(max_iterations, matches)?;
+        let position = match matches.value_of("camera") {
+            Some(camera) => parse_vector3("camera", camera)?,
+            None => Camera::overview(fractal.bounding_radius()).position(),
+        };
+        let camera = Camera::new(
+            position,
+            parse_vector3("look-at", matches.value_of("look-at").unwrap_or("0,0,0"))?,
+            parse_vector3("up", matches.value_of("up").unwrap_or("0,0,1"))?,
+            (extract!(matches, "fov"))?,
+        )?;
+        let marcher = RayMarcher::new(fractal, camera)
+            .with_light(parse_vector3(
+                "light",
+                matches.value_of("light").unwrap_or("1,-1,2"),
+            )?)?
+            .with_ambient_occlusion((extract!(matches, "ambient-occlusion"))?)
+            .with_soft_shadows((extract!(matches, "soft-shadows"))?)
+            .with_max_steps((extract!(matches, "max-steps"))?)
+            .with_detail((extract!(matches, "detail"))?)?;
 
-        Ok(())
+        raymarching::render_png(
+            Arc::new(marcher),
+            width,
+            height,
+            Path::new(matches.value_of("OUTPUT").unwrap()),
+        )
     }
 }
 
@@ -294,12 +1873,31 @@ define_subcommands! {
         )
     },
 
+    bicomplex: {
+        HypercomplexJuliaCommand::new(
+            "bicomplex",
+            "Draws a slice through a bicomplex Julia set",
+            Algebra::Bicomplex,
+            "-0.6,0.2,0.2,0"
+        )
+    },
+
+    buffalo: {
+        EscapeTimeCommand::new(
+            "buffalo",
+            "Draws the Buffalo fractal",
+            Box::new(|max_iterations, power, bailout| {
+                Buffalo::with_exponent(max_iterations, power, bailout)
+            })
+        )
+    },
+
     burningship: {
         EscapeTimeCommand::new(
             "burningship",
             "Draws the burning ship fractal",
-            Box::new(|max_iterations, power| {
-                BurningShip::new(max_iterations, power)
+            Box::new(|max_iterations, power, bailout| {
+                BurningShip::with_exponent(max_iterations, power, bailout)
             })
         )
     },
@@ -308,8 +1906,18 @@ define_subcommands! {
         EscapeTimeCommand::new(
             "burningmandel",
             "Draws a variation of the burning ship fractal",
-            Box::new(|max_iterations, power| {
-                BurningMandel::new(max_iterations, power)
+            Box::new(|max_iterations, power, bailout| {
+                BurningMandel::with_exponent(max_iterations, power, bailout)
+            })
+        )
+    },
+
+    celtic: {
+        EscapeTimeCommand::new(
+            "celtic",
+            "Draws the Celtic fractal",
+            Box::new(|max_iterations, power, bailout| {
+                Celtic::with_exponent(max_iterations, power, bailout)
             })
         )
     },
@@ -334,6 +1942,13 @@ define_subcommands! {
         )
     },
 
+    deepmandelbrot: {
+        DeepZoomCommand::new(
+            "deepmandelbrot",
+            "Draws the mandelbrot fractal with perturbation theory for deep zooms",
+        )
+    },
+
     dragon: {
         TurtleCommand::new(
             "dragon",
@@ -342,6 +1957,13 @@ define_subcommands! {
         )
     },
 
+    formula: {
+        FormulaCommand::new(
+            "formula",
+            "Draws an escape time fractal defined by a formula, such as \"z^2 + c\"",
+        )
+    },
+
     kochcurve: {
         TurtleCommand::new(
             "kochcurve",
@@ -362,24 +1984,124 @@ define_subcommands! {
         )
     },
 
+    magnet1: {
+        ConvergenceTimeCommand::new(
+            "magnet1",
+            "Draws the type I magnet fractal",
+            ConvergenceTimeArgs::None,
+            Box::new(|max_iterations, _: &clap::ArgMatches| Ok(MagnetTypeI::new(max_iterations)))
+        )
+    },
+
+    magnet2: {
+        ConvergenceTimeCommand::new(
+            "magnet2",
+            "Draws the type II magnet fractal",
+            ConvergenceTimeArgs::None,
+            Box::new(|max_iterations, _: &clap::ArgMatches| Ok(MagnetTypeII::new(max_iterations)))
+        )
+    },
+
+    mandelbox: {
+        RayMarchCommand::new(
+            "mandelbox",
+            "Renders the Mandelbox, a three dimensional fractal of folds, to a PNG",
+            RayMarchArgs::Scale,
+            Box::new(|max_iterations, matches: &clap::ArgMatches| {
+                Mandelbox::new(max_iterations, (extract!(matches, "scale"))?)
+            })
+        )
+    },
+
     mandelbrot: {
         EscapeTimeCommand::new(
             "mandelbrot",
             "Draws the mandelbrot fractal",
-            Box::new(|max_iterations, power| {
-                Mandelbrot::new(max_iterations, power)
+            Box::new(|max_iterations, power, bailout| {
+                Mandelbrot::with_exponent(max_iterations, power, bailout)
+            })
+        )
+        .with_distance_estimate()
+    },
+
+    mandelbulb: {
+        RayMarchCommand::new(
+            "mandelbulb",
+            "Renders the Mandelbulb, a three dimensional mandelbrot set, to a PNG",
+            RayMarchArgs::Power,
+            Box::new(|max_iterations, matches: &clap::ArgMatches| {
+                Mandelbulb::new(max_iterations, (extract!(matches, "power"))?)
+            })
+        )
+    },
+
+    newton: {
+        ConvergenceTimeCommand::new(
+            "newton",
+            "Draws the Newton fractal for a polynomial",
+            ConvergenceTimeArgs::Polynomial,
+            Box::new(|max_iterations, matches: &clap::ArgMatches| {
+                Ok(NewtonFractal::with_relaxation(
+                    max_iterations,
+                    parse_polynomial(matches)?,
+                    parse_complex("relaxation", matches.value_of("relaxation").unwrap_or("1,0"))?,
+                ))
+            })
+        )
+    },
+
+    nova: {
+        ConvergenceTimeCommand::new(
+            "nova",
+            "Draws the Nova fractal for a polynomial",
+            ConvergenceTimeArgs::Polynomial,
+            Box::new(|max_iterations, matches: &clap::ArgMatches| {
+                Ok(NovaFractal::new(
+                    max_iterations,
+                    parse_polynomial(matches)?,
+                    parse_complex("relaxation", matches.value_of("relaxation").unwrap_or("1,0"))?,
+                ))
+            })
+        )
+    },
+
+    phoenix: {
+        EscapeTimeCommand::new(
+            "phoenix",
+            "Draws the Phoenix fractal, whose iterations also depend on the previous value of z",
+            Box::new(|max_iterations, power, bailout| {
+                Phoenix::with_exponent(max_iterations, power, bailout)
             })
         )
     },
 
+    quaternion: {
+        HypercomplexJuliaCommand::new(
+            "quaternion",
+            "Draws a slice through a quaternion Julia set",
+            Algebra::Quaternion,
+            "-0.4,0.3,0.3,0.2"
+        )
+    },
+
+    reference: {
+        ReferenceCommand::new(
+            "reference",
+            "Renders an escape time fractal with a chosen number type to a PNG, to check the \
+            faster renderers against",
+        )
+    },
+
     roadrunner: {
         EscapeTimeCommand::new(
             "roadrunner",
-            "Draws a variation of the burning ship fractal",
-            Box::new(|max_iterations, power| {
-                RoadRunner::new(max_iterations, power)
+            "Draws a variation of the burning ship fractal, also known as the perpendicular \
+            burning ship",
+            Box::new(|max_iterations, power, bailout| {
+                RoadRunner::with_exponent(max_iterations, power, bailout)
             })
         )
+        .with_alias("perpendicular")
     },
 
     sierpinski: {
@@ -400,6 +2122,16 @@ define_subcommands! {
                 LindenmayerSystemTurtleProgram::new(TerdragonFractal::new(iteration))
             })
         )
+    },
+
+    tricorn: {
+        EscapeTimeCommand::new(
+            "tricorn",
+            "Draws the Tricorn (Mandelbar) fractal",
+            Box::new(|max_iterations, power, bailout| {
+                Tricorn::with_exponent(max_iterations, power, bailout)
+            })
+        )
     }
 
 }
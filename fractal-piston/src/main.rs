@@ -14,6 +14,8 @@
 
 pub mod fractaldata;
 pub mod pistonrendering;
+pub mod raymarching;
+pub mod reference;
 pub mod work_multiplexer;
 
 fn main() {
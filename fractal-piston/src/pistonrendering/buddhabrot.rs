@@ -0,0 +1,226 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::work_multiplexer::{
+    ThreadedWorkMultiplexerBuilder, ThreadedWorkMultiplexerHandles,
+};
+use super::escapetime::FractalImageBuffer;
+use super::{RenderContext, WindowHandler};
+use ::image::Rgba;
+use fractal_lib::color;
+use fractal_lib::escapetime::buddhabrot::{Buddhabrot, DensityMap};
+use fractal_lib::escapetime::EscapeTime;
+use fractal_lib::geometry::{Point, ViewAreaTransformer};
+use graphics::math::Vec2d;
+use log;
+use num::complex::Complex64;
+use piston_window;
+use rand;
+use std::cmp;
+use std::sync::{Arc, RwLock};
+
+/// How many points each thread samples before merging its orbits into the shared density map.
+const SAMPLES_PER_BATCH: u64 = 100_000;
+
+/// How many frames to wait between rebuilding the image from the density map.
+const FRAMES_PER_REFRESH: u32 = 30;
+
+/// Draws the orbit density of an escape time fractal (the Buddhabrot, anti-Buddhabrot, or
+/// Nebulabrot), which gradually fills in as more points are sampled.
+pub struct BuddhabrotWindowHandler<E>
+where
+    E: EscapeTime + Send + Sync + 'static,
+{
+    renderer: Arc<Buddhabrot<E>>,
+    samples: u64,
+    screen_size: Vec2d,
+    view_area: [Point; 2],
+    vat: Arc<ViewAreaTransformer>,
+    density: Arc<RwLock<DensityMap>>,
+    canvas: FractalImageBuffer,
+    threads: Option<ThreadedWorkMultiplexerHandles>,
+    /// Main thread only
+    texture_context: Option<piston_window::G2dTextureContext>,
+}
+
+impl<E> BuddhabrotWindowHandler<E>
+where
+    E: EscapeTime + Send + Sync + 'static,
+{
+    /// Creates a handler that samples `samples` random points from the fractal's default view
+    /// area every time the view changes.
+    pub fn new(renderer: Buddhabrot<E>, samples: u64) -> BuddhabrotWindowHandler<E> {
+        let default_view_area = renderer.etsystem().default_view_area();
+        let view_area = [
+            Point::from(default_view_area[0]),
+            Point::from(default_view_area[1]),
+        ];
+        let density = renderer.density_map(800, 600, default_view_area);
+
+        BuddhabrotWindowHandler {
+            renderer: Arc::new(renderer),
+            samples,
+            screen_size: [800.0, 600.0],
+            view_area,
+            vat: Arc::new(ViewAreaTransformer::new(
+                [800.0, 600.0],
+                view_area[0],
+                view_area[1],
+            )),
+            density: Arc::new(RwLock::new(density)),
+            canvas: FractalImageBuffer::new(800, 600),
+            threads: None,
+            texture_context: None,
+        }
+    }
+
+    /// Throws away the current density map and starts sampling orbits for the current view area
+    /// and screen size.
+    fn redraw(&mut self) {
+        self.vat = Arc::new(ViewAreaTransformer::new(
+            self.screen_size,
+            self.view_area[0],
+            self.view_area[1],
+        ));
+        log::debug!("view area: {:?}", self.view_area);
+        let width = self.screen_size[0] as u32;
+        let height = self.screen_size[1] as u32;
+        let view_area = [self.view_area[0].into(), self.view_area[1].into()];
+        self.density = Arc::new(RwLock::new(
+            self.renderer.density_map(width, height, view_area),
+        ));
+        self.canvas = FractalImageBuffer::new(width, height);
+
+        self.threads = Some(spawn_sampling(
+            Arc::clone(&self.renderer),
+            Arc::clone(&self.density),
+            self.samples,
+            view_area,
+        ));
+    }
+
+    /// Rebuilds the image from the current state of the density map.
+    fn refresh_canvas(&mut self) {
+        let density = self.density.read().unwrap();
+        let maxima = density.maxima();
+        for (x, y, pixel) in self.canvas.enumerate_pixels_mut() {
+            *pixel = Rgba(density.color(x, y, &maxima).0);
+        }
+    }
+}
+
+/// Starts sampling `samples` points in total across a pool of threads, merging their orbits into
+/// `density`, and returns the handles for those threads.
+///
+/// Points are sampled from the fractal's default view area rather than from `view_area`, because
+/// orbits that start outside of the visible area can still pass through it.
+fn spawn_sampling<E>(
+    renderer: Arc<Buddhabrot<E>>,
+    density: Arc<RwLock<DensityMap>>,
+    samples: u64,
+    view_area: [Complex64; 2],
+) -> ThreadedWorkMultiplexerHandles
+where
+    E: EscapeTime + Send + Sync + 'static,
+{
+    let sample_area = renderer.etsystem().default_view_area();
+    let (width, height) = {
+        let density = density.read().unwrap();
+        (density.width(), density.height())
+    };
+
+    ThreadedWorkMultiplexerBuilder::new()
+        .base_name("buddhabrot_render")
+        .split_work(move |thread_id, total_threads, notifier, name| {
+            let total_threads = total_threads as u64;
+            let thread_id = thread_id as u64;
+            // Spread the remainder across the first few threads
+            let mut remaining = samples / total_threads
+                + if thread_id < samples % total_threads {
+                    1
+                } else {
+                    0
+                };
+            let mut rng = rand::thread_rng();
+            let mut batch = renderer.density_map(width, height, view_area);
+            while remaining > 0 {
+                if notifier.should_i_stop() {
+                    log::debug!("{}: Remote side disconnected", name);
+                    break;
+                }
+                let batch_samples = cmp::min(remaining, SAMPLES_PER_BATCH);
+                renderer.sample(&mut rng, batch_samples, sample_area, &mut batch);
+                remaining -= batch_samples;
+                // only lock the density map while merging into it
+                {
+                    let mut density = density.write().unwrap();
+                    if let Err(e) = density.merge(&batch) {
+                        log::error!("{}: {}", name, e);
+                        break;
+                    }
+                }
+                batch.clear();
+            }
+        })
+}
+
+impl<E> WindowHandler for BuddhabrotWindowHandler<E>
+where
+    E: EscapeTime + Send + Sync + 'static,
+{
+    fn window_resized(&mut self, new_size: Vec2d, window: &mut piston_window::PistonWindow) {
+        self.screen_size = new_size;
+        self.redraw();
+        self.texture_context = Some(window.create_texture_context());
+    }
+
+    fn render_frame(&mut self, render_context: &mut RenderContext, frame_num: u32) {
+        // Coloring every pixel is too slow to do every frame, and the density map only changes
+        // gradually anyway.
+        if frame_num % FRAMES_PER_REFRESH == 1 {
+            self.refresh_canvas();
+        }
+        let texture = piston_window::Texture::from_image(
+            self.texture_context.as_mut().unwrap(),
+            &self.canvas,
+            &piston_window::TextureSettings::new(),
+        )
+        .unwrap();
+
+        piston_window::clear(color::BLACK_F32.0, render_context.gfx);
+        piston_window::image(
+            &texture,
+            render_context.context.transform,
+            render_context.gfx,
+        );
+    }
+
+    /// Change the view area to the newly selected area, and then start sampling again.
+    fn zoom(&mut self, rect: [Vec2d; 2]) {
+        let tlp = self.vat.map_pixel_to_point(rect[0]);
+        let brp = self.vat.map_pixel_to_point(rect[1]);
+
+        self.view_area = [tlp, brp];
+        self.redraw();
+    }
+
+    fn reset_view(&mut self) {
+        let default_view_area = self.renderer.etsystem().default_view_area();
+        self.view_area = [
+            Point::from(default_view_area[0]),
+            Point::from(default_view_area[1]),
+        ];
+        self.redraw();
+    }
+}
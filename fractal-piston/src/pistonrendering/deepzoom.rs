@@ -0,0 +1,238 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::super::work_multiplexer::ThreadedWorkMultiplexerHandles;
+use super::escapetime::{escape_time_column_color, spawn_render, FractalImageBuffer};
+use super::{RenderContext, WindowHandler};
+use fractal_lib::color;
+use fractal_lib::escapetime::perturbation::PerturbedMandelbrot;
+use fractal_lib::escapetime::raster::PaletteColorizer;
+use fractal_lib::escapetime::supersample::Supersampling;
+use fractal_lib::escapetime::viewcache::Region;
+use fractal_lib::escapetime::EscapeResult;
+use fractal_lib::geometry::{Point, ViewAreaTransformer};
+use graphics::math::Vec2d;
+use log;
+use num::complex::Complex64;
+use piston_window;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+/// Draws the Mandelbrot set at zoom levels beyond what `f64` can resolve, using perturbation
+/// theory.
+///
+/// The view area is stored relative to the center of the `PerturbedMandelbrot`, and every zoom
+/// recenters the fractal on the middle of the newly selected area. The reference orbit for the
+/// new center is computed on a background thread, and the current view stays on screen until it
+/// is ready.
+pub struct DeepZoomWindowHandler {
+    initial: Arc<PerturbedMandelbrot>,
+    initial_width: f64,
+    fractal: Arc<PerturbedMandelbrot>,
+    screen_size: Vec2d,
+    /// Relative to the center of `fractal`
+    view_area: [Point; 2],
+    vat: Arc<ViewAreaTransformer>,
+    canvas: Arc<RwLock<FractalImageBuffer>>,
+    threads: Option<ThreadedWorkMultiplexerHandles>,
+    /// The recentered fractal and its view area (relative to its center), once its reference
+    /// orbit has been computed
+    pending: Option<Receiver<(PerturbedMandelbrot, [Point; 2])>>,
+    /// Main thread only
+    texture_context: Option<piston_window::G2dTextureContext>,
+}
+
+/// Computes a view area centered on 0 that is `width` wide and matches the aspect ratio of
+/// `screen_size`.
+fn centered_view_area(width: f64, screen_size: Vec2d) -> [Point; 2] {
+    let height = width * screen_size[1] / screen_size[0];
+    [
+        Point {
+            x: -width / 2.0,
+            y: height / 2.0,
+        },
+        Point {
+            x: width / 2.0,
+            y: -height / 2.0,
+        },
+    ]
+}
+
+impl DeepZoomWindowHandler {
+    /// `view_width` is the width of the initial view area, centered on `fractal.center()`.
+    pub fn new(fractal: PerturbedMandelbrot, view_width: f64) -> DeepZoomWindowHandler {
+        let screen_size = [800.0, 600.0];
+        let view_area = centered_view_area(view_width, screen_size);
+        let fractal = Arc::new(fractal);
+
+        DeepZoomWindowHandler {
+            initial: Arc::clone(&fractal),
+            initial_width: view_width,
+            fractal,
+            screen_size,
+            view_area,
+            vat: Arc::new(ViewAreaTransformer::new(
+                screen_size,
+                view_area[0],
+                view_area[1],
+            )),
+            canvas: Arc::new(RwLock::new(FractalImageBuffer::new(800, 600))),
+            threads: None,
+            pending: None,
+            texture_context: None,
+        }
+    }
+
+    /// Switches to the recentered fractal if its reference orbit is ready, and redraws it.
+    fn receive_pending(&mut self) {
+        let received = match self.pending.as_ref().map(Receiver::try_recv) {
+            Some(Ok(received)) => received,
+            Some(Err(TryRecvError::Empty)) | None => return,
+            Some(Err(TryRecvError::Disconnected)) => {
+                log::error!("Failed to compute the reference orbit");
+                self.pending = None;
+                return;
+            }
+        };
+        self.pending = None;
+        let (fractal, view_area) = received;
+        self.fractal = Arc::new(fractal);
+        self.view_area = view_area;
+        self.redraw();
+    }
+
+    /// Recomputes the fractal for the screen. This should usually be called after the
+    /// screen/window is resized, or after a new area is selected for viewing.
+    fn redraw(&mut self) {
+        self.vat = Arc::new(ViewAreaTransformer::new(
+            self.screen_size,
+            self.view_area[0],
+            self.view_area[1],
+        ));
+        log::debug!(
+            "center: {}, {}",
+            self.fractal.center().re,
+            self.fractal.center().im
+        );
+        log::debug!("relative view area: {:?}", self.view_area);
+        self.canvas = Arc::new(RwLock::new(FractalImageBuffer::new(
+            self.screen_size[0] as u32,
+            self.screen_size[1] as u32,
+        )));
+
+        let fractal = Arc::clone(&self.fractal);
+        self.threads = Some(spawn_render(
+            Arc::clone(&self.canvas),
+            Arc::clone(&self.vat),
+            vec![Region::new(
+                0,
+                0,
+                self.screen_size[0] as u32,
+                self.screen_size[1] as u32,
+            )],
+            escape_time_column_color(
+                PaletteColorizer::new(self.fractal.max_iterations()),
+                Arc::new(move |points: &[Complex64], results: &mut [EscapeResult]| {
+                    for (delta_c, result) in points.iter().zip(results.iter_mut()) {
+                        *result = fractal.evaluate(*delta_c);
+                    }
+                }),
+            ),
+            Supersampling::None,
+            Arc::new(AtomicBool::new(false)),
+        ));
+    }
+}
+
+impl WindowHandler for DeepZoomWindowHandler {
+    fn window_resized(&mut self, new_size: Vec2d, window: &mut piston_window::PistonWindow) {
+        self.screen_size = new_size;
+        self.redraw();
+        self.texture_context = Some(window.create_texture_context());
+    }
+
+    fn render_frame(&mut self, render_context: &mut RenderContext, _: u32) {
+        self.receive_pending();
+        let texture = {
+            let canvas = self.canvas.read().unwrap();
+            piston_window::Texture::from_image(
+                self.texture_context.as_mut().unwrap(),
+                &canvas,
+                &piston_window::TextureSettings::new(),
+            )
+            .unwrap()
+        };
+
+        piston_window::clear(color::WHITE_F32.0, render_context.gfx);
+        piston_window::image(
+            &texture,
+            render_context.context.transform,
+            render_context.gfx,
+        );
+    }
+
+    /// Recenter the fractal on the newly selected area, and then redraw once the new reference
+    /// orbit has been computed. Zooming again before then replaces the pending zoom.
+    fn zoom(&mut self, rect: [Vec2d; 2]) {
+        let tlp = self.vat.map_pixel_to_point(rect[0]);
+        let brp = self.vat.map_pixel_to_point(rect[1]);
+        let middle = Point {
+            x: (tlp.x + brp.x) / 2.0,
+            y: (tlp.y + brp.y) / 2.0,
+        };
+        let half_width = (brp.x - tlp.x) / 2.0;
+        let half_height = (tlp.y - brp.y) / 2.0;
+
+        // Computing the new reference orbit can take a moment, so stop rendering the old one
+        // first to leave it the CPU.
+        self.threads = None;
+        let view_size = (2.0 * half_width).abs().min((2.0 * half_height).abs());
+        let view_area = [
+            Point {
+                x: -half_width,
+                y: half_height,
+            },
+            Point {
+                x: half_width,
+                y: -half_height,
+            },
+        ];
+        let fractal = Arc::clone(&self.fractal);
+        let (sender, receiver) = channel();
+        let res = thread::Builder::new()
+            .name("deepzoom_reference".to_string())
+            .spawn(move || {
+                let recentered = fractal.recenter(middle.into(), view_size);
+                // The zoom may have been replaced or reset in the meantime, in which case nobody
+                // is listening anymore
+                let _ = sender.send((recentered, view_area));
+            });
+        if res.is_err() {
+            log::error!("Failed to spawn the thread for the reference orbit");
+            return;
+        }
+        log::info!("Computing the reference orbit for the new view");
+        self.pending = Some(receiver);
+    }
+
+    fn reset_view(&mut self) {
+        self.threads = None;
+        self.pending = None;
+        self.fractal = Arc::clone(&self.initial);
+        self.view_area = centered_view_area(self.initial_width, self.screen_size);
+        self.redraw();
+    }
+}
@@ -17,40 +17,451 @@ use super::super::work_multiplexer::{
 };
 use super::{RenderContext, WindowHandler};
 use ::image::{ImageBuffer, Rgba};
-use fractal_lib::color;
-use fractal_lib::escapetime::EscapeTime;
+use fractal_lib::color::{self, ColorScale, ColorU8};
+use fractal_lib::convergent::ConvergenceTime;
+use fractal_lib::escapetime::averaging::Averaging;
+use fractal_lib::escapetime::distance::DistanceEstimator;
+use fractal_lib::escapetime::hypercomplex::{HypercomplexJulia, Slice, AXIS_NAMES};
+use fractal_lib::escapetime::lighting::{Lighting, ReliefRaster};
+use fractal_lib::escapetime::orbit::{Orbit, OrbitObserver};
+use fractal_lib::escapetime::orbittrap::OrbitTrap;
+use fractal_lib::escapetime::progressive::{Coverage, Pass, DEFAULT_INITIAL_BLOCK_SIZE};
+use fractal_lib::escapetime::raster::{Colorizer, EscapeTimeRaster, PaletteColorizer};
+use fractal_lib::escapetime::supersample::{self, Supersampling};
+use fractal_lib::escapetime::viewcache::{self, Region, ViewCache};
+use fractal_lib::escapetime::{EscapeCriterion, EscapeResult, EscapeTime};
 use fractal_lib::geometry::{Point, ViewAreaTransformer};
+use graphics;
 use graphics::math::Vec2d;
 use log;
 use num::complex::Complex64;
 use piston_window;
 use std::cmp;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier, Mutex, RwLock};
 
-type FractalImageBuffer = ImageBuffer<Rgba<u8>, Vec<u8>>;
+pub type FractalImageBuffer = ImageBuffer<Rgba<u8>, Vec<u8>>;
 
-/// Draws escape time fractals by testing the point that each pixel corresponds to on the complex
-/// plane.
+/// A function that picks the color of the pixel that shows a point on the complex plane, given
+/// the width of a pixel on the complex plane.
+pub type PixelColorFn = Arc<dyn Fn(Complex64, f64) -> Rgba<u8> + Send + Sync>;
+
+/// A function that picks the colors of the pixels that show a column of points on the complex
+/// plane, given the width of a pixel on the complex plane and the supersampling that picks the
+/// samples each pixel's color is averaged from. Coloring a column at a time lets escape time
+/// fractals evaluate all of its points in one batch.
+pub type ColumnColorFn =
+    Arc<dyn Fn(&[Complex64], f64, Supersampling) -> Vec<Rgba<u8>> + Send + Sync>;
+
+/// A function that tests each of a batch of points on the complex plane, storing the results in
+/// the same positions of a slice of results.
+pub type BatchEvaluateFn = Arc<dyn Fn(&[Complex64], &mut [EscapeResult]) + Send + Sync>;
+
+/// A function that follows the orbit of a point on the complex plane.
+type OrbitFn = Box<dyn Fn(Complex64) -> Orbit>;
+
+/// A function that moves the slice that a fractal is drawn through by a number of steps along
+/// its depth, and returns the new slice.
+pub type SliceFn = Box<dyn Fn(i32) -> Slice>;
+
+/// The width of the strips of the screen that are each rendered with subdivision by one thread.
+const SUBDIVISION_TILE_WIDTH: u32 = 64;
+
+/// The width (and height) of the dots that mark each value of an orbit overlay, in pixels.
+const ORBIT_DOT_SIZE: f64 = 3.0;
+
+/// The surface that a lit escape time fractal is shaded as (see `escapetime::lighting`).
+#[derive(Clone)]
+pub enum Surface {
+    /// Rises with the smoothed iteration count of an escape time fractal.
+    Iterations(Arc<dyn EscapeTime + Send + Sync>),
+    /// Rises with the potential that a fractal's distance estimate is derived from.
+    Distance(Arc<dyn DistanceEstimator + Send + Sync>),
+}
+
+impl Surface {
+    /// Tests each of `points` into the same position of `results`, and for distance estimates,
+    /// returns the derivatives of their final values.
+    fn evaluate(&self, points: &[Complex64], results: &mut [EscapeResult]) -> Vec<Complex64> {
+        match *self {
+            Surface::Iterations(ref etsystem) => {
+                etsystem.evaluate_batch(points, results);
+                Vec::new()
+            }
+            Surface::Distance(ref system) => points
+                .iter()
+                .zip(results.iter_mut())
+                .map(|(&c, result)| {
+                    let estimate = system.estimate_distance(c);
+                    *result = estimate.result;
+                    estimate.derivative
+                })
+                .collect(),
+        }
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        match *self {
+            Surface::Iterations(ref etsystem) => etsystem.default_view_area(),
+            Surface::Distance(ref system) => system.default_view_area(),
+        }
+    }
+
+    fn max_iterations(&self) -> u64 {
+        match *self {
+            Surface::Iterations(ref etsystem) => etsystem.max_iterations(),
+            Surface::Distance(ref system) => system.max_iterations(),
+        }
+    }
+
+    fn orbit(&self, c: Complex64) -> Orbit {
+        match *self {
+            Surface::Iterations(ref etsystem) => etsystem.orbit(c),
+            Surface::Distance(ref system) => system.orbit(c),
+        }
+    }
+}
+
+/// A `HypercomplexJulia` whose slice can be moved while it is drawn. Every clone shares the same
+/// fractal, so the handler can move the slice of the fractal that its renderer draws.
+#[derive(Clone)]
+pub struct SweptJulia(Arc<RwLock<HypercomplexJulia>>);
+
+impl SweptJulia {
+    pub fn new(fractal: HypercomplexJulia) -> SweptJulia {
+        SweptJulia(Arc::new(RwLock::new(fractal)))
+    }
+
+    /// Builds a `SliceFn` that moves the slice by `step` for each step.
+    pub fn slice_fn(&self, step: f64) -> SliceFn {
+        let fractal = Arc::clone(&self.0);
+        Box::new(move |steps| {
+            let mut fractal = fractal.write().unwrap();
+            let slice = fractal.slice();
+            let slice = slice.with_depth(slice.depth() + step * f64::from(steps));
+            *fractal = fractal.clone().with_slice(slice);
+            slice
+        })
+    }
+}
+
+impl EscapeTime for SweptJulia {
+    fn max_iterations(&self) -> u64 {
+        self.0.read().unwrap().max_iterations()
+    }
+
+    fn default_view_area(&self) -> [Complex64; 2] {
+        self.0.read().unwrap().default_view_area()
+    }
+
+    fn iterate(&self, c: Complex64, z: Complex64) -> Complex64 {
+        self.0.read().unwrap().iterate(c, z)
+    }
+
+    fn bailout(&self) -> EscapeCriterion {
+        self.0.read().unwrap().bailout()
+    }
+
+    fn initial_values(&self, point: Complex64) -> (Complex64, Complex64) {
+        self.0.read().unwrap().initial_values(point)
+    }
+
+    fn evaluate(&self, point: Complex64) -> EscapeResult {
+        self.0.read().unwrap().evaluate(point)
+    }
+
+    fn evaluate_observed(
+        &self,
+        point: Complex64,
+        observer: &mut dyn OrbitObserver,
+    ) -> EscapeResult {
+        self.0.read().unwrap().evaluate_observed(point, observer)
+    }
+
+    fn evaluate_batch(&self, points: &[Complex64], results: &mut [EscapeResult]) {
+        self.0.read().unwrap().evaluate_batch(points, results)
+    }
+}
+
+/// How `EscapeTimeWindowHandler` computes its pixels.
+enum Renderer {
+    /// Colors each column of pixels with a `ColumnColorFn`, averaging the samples that a
+    /// `Supersampling` picks.
+    Columns(ColumnColorFn, Supersampling),
+    /// Evaluates an escape time fractal with Mariani–Silver subdivision, skipping the pixels that
+    /// can be filled in, and colors the results with a `PaletteColorizer`.
+    Subdivided(Arc<dyn EscapeTime + Send + Sync>, Arc<PaletteColorizer>),
+    /// Evaluates an escape time fractal, and once the whole frame is known, colors it with a
+    /// `PaletteColorizer` spread over the frame's iteration counts by an adaptive `ColorScale`.
+    Scaled(Arc<dyn EscapeTime + Send + Sync>, ColorScale),
+    /// Evaluates a `Surface`, and once the whole frame is known, lights it with `Lighting` (which
+    /// is shared so that the light can be moved while the frame renders).
+    Lit(Surface, ColorScale, Arc<RwLock<Lighting>>),
+}
+
+impl Renderer {
+    /// Whether the color of each pixel only depends on its own point, so that the pixels of one
+    /// view can be reused in another.
+    fn colors_independently(&self) -> bool {
+        !matches!(self, Renderer::Scaled(..) | Renderer::Lit(..))
+    }
+}
+
+/// Draws escape time fractals (and other fractals on the complex plane, such as convergent
+/// fractals) by testing the point that each pixel corresponds to on the complex plane.
+///
+/// Finished renders are kept around, so that when the view is panned (or returns to an earlier
+/// view) at the same scale, the pixels that are already known are copied instead of computed
+/// again.
+///
+/// For escape time fractals, shift-clicking on a point overlays its orbit on top of the fractal.
+/// Lit fractals keep the slopes of the last finished frame, so that moving the light only shades
+/// it again. Sliced fractals can have their slice moved, which draws them from scratch.
 pub struct EscapeTimeWindowHandler {
-    etsystem: Arc<dyn EscapeTime + Send + Sync>,
+    default_view_area: [Complex64; 2],
+    renderer: Renderer,
     screen_size: Vec2d,
     view_area: [Point; 2],
     vat: Arc<ViewAreaTransformer>,
     /// Must be a u8 to work with Texture::from_image?
     canvas: Arc<RwLock<FractalImageBuffer>>,
+    /// Set once every pixel of `canvas` has been rendered.
+    render_complete: Arc<AtomicBool>,
+    /// For lit fractals, the colors and slopes of `canvas` once every pixel has been rendered.
+    relief: Arc<Mutex<Option<ReliefRaster>>>,
+    /// Earlier finished renders, to reuse pixels from.
+    views: ViewCache<FractalImageBuffer>,
     threads: Option<ThreadedWorkMultiplexerHandles>,
     /// Main thread only
     texture_context: Option<piston_window::G2dTextureContext>,
+    /// Follows the orbits of inspected points, for fractals that have them.
+    orbit_fn: Option<OrbitFn>,
+    /// The orbit of the most recently inspected point, drawn on top of the fractal.
+    orbit: Option<Orbit>,
+    /// Moves the slice of four dimensional fractals.
+    slice_fn: Option<SliceFn>,
 }
 
 impl EscapeTimeWindowHandler {
-    pub fn new(etsystem: Arc<dyn EscapeTime + Send + Sync>) -> EscapeTimeWindowHandler {
+    /// Creates a handler for an escape time fractal, where each pixel is colored by averaging the
+    /// samples that `supersampling` picks.
+    pub fn new(
+        etsystem: Arc<dyn EscapeTime + Send + Sync>,
+        supersampling: Supersampling,
+    ) -> EscapeTimeWindowHandler {
+        let default_view_area = etsystem.default_view_area();
+        let orbits = Arc::clone(&etsystem);
+        let column_color = escape_time_column_color(
+            PaletteColorizer::new(etsystem.max_iterations()),
+            Arc::new(move |points: &[Complex64], results: &mut [EscapeResult]| {
+                etsystem.evaluate_batch(points, results)
+            }),
+        );
+        EscapeTimeWindowHandler::with_column_color(default_view_area, column_color, supersampling)
+            .with_orbits(Box::new(move |c| orbits.orbit(c)))
+    }
+
+    /// Creates a handler for an escape time fractal that uses Mariani–Silver subdivision (see
+    /// `EscapeTimeRaster::evaluate_subdivided()`) to skip evaluating the pixels inside of
+    /// uniform regions of the set, and logs how many pixels it evaluated after each redraw.
+    ///
+    /// Fractals that do not allow subdivision have every pixel evaluated.
+    pub fn with_subdivision(
+        etsystem: Arc<dyn EscapeTime + Send + Sync>,
+    ) -> EscapeTimeWindowHandler {
+        let default_view_area = etsystem.default_view_area();
+        let colorizer = Arc::new(PaletteColorizer::new(etsystem.max_iterations()));
+        let orbits = Arc::clone(&etsystem);
+        EscapeTimeWindowHandler::with_renderer(
+            default_view_area,
+            Renderer::Subdivided(etsystem, colorizer),
+        )
+        .with_orbits(Box::new(move |c| orbits.orbit(c)))
+    }
+
+    /// Creates a handler for an escape time fractal whose palette is spread over the iteration
+    /// counts of each frame by `scale` (see `color::ColorScale`). A rough version of each frame
+    /// is drawn with the default palette until all of its pixels have been evaluated.
+    pub fn with_color_scale(
+        etsystem: Arc<dyn EscapeTime + Send + Sync>,
+        scale: ColorScale,
+    ) -> EscapeTimeWindowHandler {
+        let default_view_area = etsystem.default_view_area();
+        let orbits = Arc::clone(&etsystem);
+        EscapeTimeWindowHandler::with_renderer(default_view_area, Renderer::Scaled(etsystem, scale))
+            .with_orbits(Box::new(move |c| orbits.orbit(c)))
+    }
+
+    /// Creates a handler for an escape time fractal that is shaded as a `surface` lit by
+    /// `lighting`, where the palette is spread over the iteration counts of each frame by `scale`.
+    /// An unlit version of each frame is drawn with the default palette until all of its pixels
+    /// have been evaluated.
+    pub fn with_lighting(
+        surface: Surface,
+        scale: ColorScale,
+        lighting: Lighting,
+    ) -> EscapeTimeWindowHandler {
+        let default_view_area = surface.default_view_area();
+        let orbits = surface.clone();
+        EscapeTimeWindowHandler::with_renderer(
+            default_view_area,
+            Renderer::Lit(surface, scale, Arc::new(RwLock::new(lighting))),
+        )
+        .with_orbits(Box::new(move |c| orbits.orbit(c)))
+    }
+
+    /// Creates a handler for an escape time fractal that colors each point by how close its
+    /// orbit comes to `trap`, where each pixel is colored by averaging the samples that
+    /// `supersampling` picks.
+    pub fn with_orbit_trap(
+        etsystem: Arc<dyn EscapeTime + Send + Sync>,
+        trap: OrbitTrap,
+        supersampling: Supersampling,
+    ) -> EscapeTimeWindowHandler {
+        EscapeTimeWindowHandler::with_sample_color(
+            Arc::clone(&etsystem),
+            supersampling,
+            Arc::new(move |c| trap.color(&trap.evaluate(&*etsystem, c))),
+        )
+    }
+
+    /// Creates a handler for an escape time fractal that colors the points outside of the set by
+    /// the average of a statistic over their orbits (see `escapetime::averaging`), where each
+    /// pixel is colored by averaging the samples that `supersampling` picks.
+    pub fn with_averaging(
+        etsystem: Arc<dyn EscapeTime + Send + Sync>,
+        averaging: Averaging,
+        supersampling: Supersampling,
+    ) -> EscapeTimeWindowHandler {
+        EscapeTimeWindowHandler::with_sample_color(
+            Arc::clone(&etsystem),
+            supersampling,
+            Arc::new(move |c| averaging.color(&averaging.evaluate(&*etsystem, c))),
+        )
+    }
+
+    /// Creates a handler for an escape time fractal that colors each of the samples that
+    /// `supersampling` picks with `sample_color`.
+    fn with_sample_color(
+        etsystem: Arc<dyn EscapeTime + Send + Sync>,
+        supersampling: Supersampling,
+        sample_color: Arc<dyn Fn(Complex64) -> ColorU8 + Send + Sync>,
+    ) -> EscapeTimeWindowHandler {
+        let default_view_area = etsystem.default_view_area();
+        EscapeTimeWindowHandler::with_column_color(
+            default_view_area,
+            Arc::new(
+                move |points: &[Complex64], pixel_size: f64, supersampling: Supersampling| {
+                    let colors = supersampling.colors(points, pixel_size, |samples| {
+                        samples.iter().map(|&c| sample_color(c)).collect()
+                    });
+                    colors
+                        .into_iter()
+                        .map(|color| Rgba(color.0))
+                        .collect::<Vec<Rgba<u8>>>()
+                },
+            ),
+            supersampling,
+        )
+        .with_orbits(Box::new(move |c| etsystem.orbit(c)))
+    }
+
+    /// Creates a handler for a convergent fractal, where each attractor gets its own hue.
+    pub fn for_convergence_time(
+        system: Arc<dyn ConvergenceTime + Send + Sync>,
+    ) -> EscapeTimeWindowHandler {
+        let default_view_area = system.default_view_area();
+        let shading_iterations = cmp::min(system.max_iterations(), 50) as f64;
+        EscapeTimeWindowHandler::with_pixel_color(
+            default_view_area,
+            Arc::new(move |c, _| {
+                let result = system.evaluate(c);
+                let color = result.color(system.attractors().len(), shading_iterations);
+                Rgba(color.0)
+            }),
+        )
+    }
+
+    /// Creates a handler for an escape time fractal that shades the points outside of the set by
+    /// their estimated distance to it, relative to the size of a pixel, where each pixel is
+    /// colored by averaging the samples that `supersampling` picks.
+    pub fn for_distance_estimate(
+        system: Arc<dyn DistanceEstimator + Send + Sync>,
+        supersampling: Supersampling,
+    ) -> EscapeTimeWindowHandler {
+        let default_view_area = system.default_view_area();
+        let orbits = Arc::clone(&system);
+        EscapeTimeWindowHandler::with_column_color(
+            default_view_area,
+            Arc::new(
+                move |points: &[Complex64], pixel_size: f64, supersampling: Supersampling| {
+                    let colors = supersampling.colors(points, pixel_size, |samples| {
+                        samples
+                            .iter()
+                            .map(|&c| {
+                                let estimate = system.estimate_distance(c);
+                                match estimate.distance {
+                                    Some(distance) => color::distance_color(distance, pixel_size),
+                                    None => color::interior_color(estimate.result.period),
+                                }
+                            })
+                            .collect()
+                    });
+                    colors
+                        .into_iter()
+                        .map(|color| Rgba(color.0))
+                        .collect::<Vec<Rgba<u8>>>()
+                },
+            ),
+            supersampling,
+        )
+        .with_orbits(Box::new(move |c| orbits.orbit(c)))
+    }
+
+    /// Creates a handler that colors each pixel with `pixel_color`, starting with
+    /// `default_view_area`.
+    pub fn with_pixel_color(
+        default_view_area: [Complex64; 2],
+        pixel_color: PixelColorFn,
+    ) -> EscapeTimeWindowHandler {
+        EscapeTimeWindowHandler::with_column_color(
+            default_view_area,
+            Arc::new(move |points: &[Complex64], pixel_size: f64, _| {
+                points
+                    .iter()
+                    .map(|&c| pixel_color(c, pixel_size))
+                    .collect::<Vec<Rgba<u8>>>()
+            }),
+            Supersampling::None,
+        )
+    }
+
+    /// Creates a handler that colors each column of pixels with `column_color`, averaging the
+    /// samples that `supersampling` picks, starting with `default_view_area`.
+    pub fn with_column_color(
+        default_view_area: [Complex64; 2],
+        column_color: ColumnColorFn,
+        supersampling: Supersampling,
+    ) -> EscapeTimeWindowHandler {
+        EscapeTimeWindowHandler::with_renderer(
+            default_view_area,
+            Renderer::Columns(column_color, supersampling),
+        )
+    }
+
+    fn with_renderer(
+        default_view_area: [Complex64; 2],
+        renderer: Renderer,
+    ) -> EscapeTimeWindowHandler {
         let canvas = Arc::new(RwLock::new(FractalImageBuffer::new(800, 600)));
-        let view_area_c = etsystem.default_view_area();
-        let view_area = [Point::from(view_area_c[0]), Point::from(view_area_c[1])];
+        let view_area = [
+            Point::from(default_view_area[0]),
+            Point::from(default_view_area[1]),
+        ];
 
         EscapeTimeWindowHandler {
-            etsystem,
+            default_view_area,
+            renderer,
             screen_size: [800.0, 600.0],
             view_area,
             vat: Arc::new(ViewAreaTransformer::new(
@@ -59,14 +470,88 @@ impl EscapeTimeWindowHandler {
                 view_area[1],
             )),
             canvas,
+            render_complete: Arc::new(AtomicBool::new(false)),
+            relief: Arc::new(Mutex::new(None)),
+            views: ViewCache::new(viewcache::DEFAULT_CAPACITY),
             threads: None,
             texture_context: None,
+            orbit_fn: None,
+            orbit: None,
+            slice_fn: None,
+        }
+    }
+
+    /// Lets the slice that the fractal is drawn through be moved with `slice_fn`, which must
+    /// change the fractal that the handler renders.
+    pub fn with_slice_sweep(mut self, slice_fn: SliceFn) -> EscapeTimeWindowHandler {
+        self.slice_fn = Some(slice_fn);
+        self
+    }
+
+    /// Lets inspected points show their orbits, as followed by `orbit_fn`.
+    fn with_orbits(mut self, orbit_fn: OrbitFn) -> EscapeTimeWindowHandler {
+        self.orbit_fn = Some(orbit_fn);
+        self
+    }
+
+    /// Draws `self.orbit` on top of the fractal, as dots connected by lines.
+    fn draw_orbit(&self, render_context: &mut RenderContext) {
+        let orbit = match self.orbit {
+            Some(ref orbit) => orbit,
+            None => return,
+        };
+        // Values that are far outside of the window (such as the last value of an orbit that
+        // escaped) are only drawn as part of the line that leads to them.
+        let limit = 4.0 * self.screen_size[0].max(self.screen_size[1]);
+        let pixels: Vec<Vec2d> = orbit
+            .values
+            .iter()
+            .map(|&z| self.vat.map_point_to_pixel(Point::from(z)))
+            .filter(|pixel| pixel.iter().all(|v| v.is_finite() && v.abs() < limit))
+            .collect();
+
+        let draw_state = graphics::draw_state::DrawState::default();
+        let line = piston_window::Line::new(color::RED_F32.0, 0.5);
+        for pair in pixels.windows(2) {
+            line.draw(
+                [pair[0][0], pair[0][1], pair[1][0], pair[1][1]],
+                &draw_state,
+                render_context.context.transform,
+                render_context.gfx,
+            );
+        }
+        let dot = piston_window::Rectangle::new(color::RED_F32.0);
+        for pixel in &pixels {
+            dot.draw(
+                [
+                    pixel[0] - ORBIT_DOT_SIZE / 2.0,
+                    pixel[1] - ORBIT_DOT_SIZE / 2.0,
+                    ORBIT_DOT_SIZE,
+                    ORBIT_DOT_SIZE,
+                ],
+                &draw_state,
+                render_context.context.transform,
+                render_context.gfx,
+            );
         }
     }
 
     /// Recomputes the fractal for the screen. This should usually be called after the
     /// screen/window is resized, or after a new area is selected for viewing.
+    ///
+    /// Only the pixels that no earlier finished render lines up with are computed.
     fn redraw(&mut self) {
+        // Stop the current render before deciding whether it can be reused
+        if let Some(mut threads) = self.threads.take() {
+            threads.stop();
+        }
+        let reuse = self.renderer.colors_independently();
+        if reuse && self.render_complete.load(Ordering::SeqCst) {
+            let canvas = self.canvas.read().unwrap();
+            self.views
+                .insert(*self.vat, [canvas.width(), canvas.height()], canvas.clone());
+        }
+
         self.vat = Arc::new(ViewAreaTransformer::new(
             self.screen_size,
             self.view_area[0],
@@ -83,69 +568,491 @@ impl EscapeTimeWindowHandler {
             self.screen_size[1] as u32,
             self.vat.map_pixel_to_point(self.screen_size)
         );
-        let colors = Arc::new(color::color_range_linear(
-            color::BLACK_U8,
-            color::WHITE_U8,
-            cmp::min(self.etsystem.max_iterations(), 50) as usize,
-        ));
+        let width = self.screen_size[0] as u32;
+        let height = self.screen_size[1] as u32;
+        let mut canvas = FractalImageBuffer::new(width, height);
+        let earlier = if reuse {
+            self.views.best_match(&self.vat, [width, height])
+        } else {
+            None
+        };
+        let regions = match earlier {
+            Some((earlier, alignment)) => {
+                let overlap = alignment.overlap();
+                for y in overlap.top..overlap.top + overlap.height {
+                    for x in overlap.left..overlap.left + overlap.width {
+                        let (earlier_x, earlier_y) = alignment.earlier_pixel(x, y);
+                        canvas.put_pixel(x, y, *earlier.get_pixel(earlier_x, earlier_y));
+                    }
+                }
+                log::debug!(
+                    "Reused {} of {} pixels",
+                    overlap.area(),
+                    u64::from(width) * u64::from(height)
+                );
+                alignment.exposed()
+            }
+            None => vec![Region::new(0, 0, width, height)],
+        };
+        self.canvas = Arc::new(RwLock::new(canvas));
+        self.render_complete = Arc::new(AtomicBool::new(false));
+        self.relief = Arc::new(Mutex::new(None));
 
-        self.canvas = Arc::new(RwLock::new(FractalImageBuffer::new(
-            self.screen_size[0] as u32,
-            self.screen_size[1] as u32,
-        )));
-
-        {
-            let shared_canvas = (&self.canvas).clone();
-            let vat = Arc::clone(&self.vat);
-            let etsystem = Arc::clone(&self.etsystem);
-            let colors = Arc::clone(&colors);
-            let tl = [0.0, 0.0];
-            let br = self.screen_size;
-
-            let work_muxer = ThreadedWorkMultiplexerBuilder::new()
-                .base_name("escapetime_render")
-                .split_work(move |thread_id, total_threads, notifier, name| {
-                    // Each thread will process x values, sharded by the number of
-                    // threads.
-                    //
-                    // no `step_by` in stable yet.
-                    let sequence = ((tl[0] as u32)..(br[0] as u32))
-                        .enumerate()
-                        .filter(|&(index, _)| (index + thread_id) % total_threads == 0)
-                        .map(|(_, val)| val);
-                    for x in sequence {
-                        if notifier.should_i_stop() {
-                            log::debug!("{}: Remote side disconnected", name);
-                            break;
+        self.threads = Some(match self.renderer {
+            Renderer::Columns(ref column_color, supersampling) => spawn_render(
+                Arc::clone(&self.canvas),
+                Arc::clone(&self.vat),
+                regions,
+                Arc::clone(column_color),
+                supersampling,
+                Arc::clone(&self.render_complete),
+            ),
+            Renderer::Subdivided(ref etsystem, ref colorizer) => spawn_subdivided_render(
+                Arc::clone(&self.canvas),
+                Arc::clone(&self.vat),
+                regions,
+                Arc::clone(etsystem),
+                Arc::clone(colorizer),
+                Arc::clone(&self.render_complete),
+            ),
+            Renderer::Scaled(ref etsystem, scale) => spawn_scaled_render(
+                Arc::clone(&self.canvas),
+                Arc::clone(&self.vat),
+                Arc::clone(etsystem),
+                scale,
+                Arc::clone(&self.render_complete),
+            ),
+            Renderer::Lit(ref surface, scale, ref lighting) => spawn_lit_render(
+                Arc::clone(&self.canvas),
+                Arc::clone(&self.vat),
+                surface.clone(),
+                scale,
+                Arc::clone(lighting),
+                Arc::clone(&self.relief),
+                Arc::clone(&self.render_complete),
+            ),
+        });
+    }
+}
+
+/// Builds a `ColumnColorFn` for an escape time fractal, where `evaluate` tests each of a column
+/// of points into the same position of a slice of results, and `colorizer` picks their colors.
+pub fn escape_time_column_color<C>(colorizer: C, evaluate: BatchEvaluateFn) -> ColumnColorFn
+where
+    C: Colorizer + Send + Sync + 'static,
+{
+    Arc::new(
+        move |points: &[Complex64], pixel_size: f64, supersampling: Supersampling| {
+            let colors = supersampling.colors(points, pixel_size, |samples| {
+                let mut results =
+                    vec![EscapeResult::attracted(Complex64::new(0.0, 0.0)); samples.len()];
+                evaluate(samples, &mut results);
+                results
+                    .iter()
+                    .map(|result| colorizer.color(result))
+                    .collect()
+            });
+            colors
+                .into_iter()
+                .map(|color| Rgba(color.0))
+                .collect::<Vec<Rgba<u8>>>()
+        },
+    )
+}
+
+/// Starts rendering `regions` of `canvas` using a pool of threads, and returns the handles for
+/// those threads. Once every thread finishes, it sets `complete`.
+///
+/// Each column of pixels is colored by `column_color`, using the complex numbers that `vat` maps
+/// them to. Each region is rendered progressively: the first pass draws a coarse version of it in
+/// blocks of pixels, and each later pass only computes the pixels needed to refine those blocks
+/// (see `fractal_lib::escapetime::progressive`).
+///
+/// Each pixel's color is averaged from the samples that `supersampling` picks. For
+/// `Supersampling::Adaptive`, the passes take one sample per pixel, and once all of them are
+/// done, the pixels whose colors differ from their neighbors' are colored again with more
+/// samples. That includes the pixels of `canvas` that border `regions`, since their neighbors
+/// were just drawn.
+pub fn spawn_render(
+    canvas: Arc<RwLock<FractalImageBuffer>>,
+    vat: Arc<ViewAreaTransformer>,
+    regions: Vec<Region>,
+    column_color: ColumnColorFn,
+    supersampling: Supersampling,
+    complete: Arc<AtomicBool>,
+) -> ThreadedWorkMultiplexerHandles {
+    let pixel_size = vat.pixel_size();
+    // The blocks of each region's passes start at the region's top-left corner
+    let coverages = regions
+        .iter()
+        .map(|region| Mutex::new(Coverage::new(region.width, region.height)))
+        .collect::<Vec<Mutex<Coverage>>>();
+    let finished_threads = Arc::new(AtomicUsize::new(0));
+
+    let builder = ThreadedWorkMultiplexerBuilder::new();
+    // For adaptive supersampling, the threads wait for each other to finish their passes, and
+    // then for one of them to find the edges in the finished canvas
+    let passes_done = Barrier::new(builder.thread_count);
+    let edges = Mutex::new(Arc::new(Vec::new()));
+
+    builder.base_name("escapetime_render").split_work(
+        move |thread_id, total_threads, notifier, name| {
+            let mut stopped = false;
+            'passes: for pass in Pass::passes(DEFAULT_INITIAL_BLOCK_SIZE) {
+                // Each thread will process the pass's columns of every region, sharded by the
+                // number of threads.
+                let sequence = regions
+                    .iter()
+                    .zip(&coverages)
+                    .flat_map(|(region, coverage)| {
+                        pass.columns(region.width)
+                            .map(move |x| (region, coverage, x))
+                    })
+                    .enumerate()
+                    .filter(|&(index, _)| (index + thread_id) % total_threads == 0)
+                    .map(|(_, val)| val);
+                for (region, coverage, x) in sequence {
+                    if notifier.should_i_stop() {
+                        log::debug!("{}: Remote side disconnected", name);
+                        stopped = true;
+                        break 'passes;
+                    }
+                    let rows = pass.rows(x, region.height);
+                    let points = rows
+                        .iter()
+                        .map(|&y| {
+                            vat.map_pixel_to_point([
+                                f64::from(region.left + x),
+                                f64::from(region.top + y),
+                            ])
+                            .into()
+                        })
+                        .collect::<Vec<Complex64>>();
+                    let y_colors = column_color(&points, pixel_size, supersampling);
+                    // only lock the canvas while writing to it
+                    {
+                        // Write a column at a time to improve performance. Locking for every
+                        // pixel actually winds up harming performance, but a column at a time
+                        // seems to work much better. Haven't tested trying to do multiple
+                        // columns at once yet.
+                        let mut canvas = canvas.write().unwrap();
+                        let mut coverage = coverage.lock().unwrap();
+                        for (&y, color) in rows.iter().zip(y_colors) {
+                            coverage.draw_sample(&pass, x, y, |px, py| {
+                                canvas.put_pixel(region.left + px, region.top + py, color)
+                            });
                         }
-                        let y_colors = ((tl[1] as u32)..(br[1] as u32))
-                            .map(|y| {
-                                let c: Complex64 =
-                                    vat.map_pixel_to_point([f64::from(x), f64::from(y)]).into();
-                                let (attracted, time) = etsystem.test_point(c);
-                                if attracted {
-                                    Rgba(color::AEBLUE_U8.0)
-                                } else {
-                                    Rgba(colors[cmp::min(time, 50 - 1) as usize].0)
-                                }
-                            })
-                            .collect::<Vec<Rgba<u8>>>();
-                        // only lock the canvas while writing to it
-                        {
-                            // Write a column at a time to improve performance. Locking for every
-                            // pixel actually winds up harming performance, but a column at a time
-                            // seems to work much better. Haven't tested trying to do multiple
-                            // columns at once yet.
-                            let mut canvas = shared_canvas.write().unwrap();
-                            for (y, color) in y_colors.into_iter().enumerate() {
-                                canvas.put_pixel(x, y as u32, color);
-                            }
+                    }
+                }
+            }
+            if let Some(edge_sampling) = supersampling.edge_sampling() {
+                // Every thread has to reach both barriers, even the ones that were stopped, so
+                // that the others do not wait for them forever
+                if passes_done.wait().is_leader() && !stopped {
+                    // Every pixel has been drawn, so the neighbors of each one are known
+                    *edges.lock().unwrap() =
+                        Arc::new(edge_columns(&canvas.read().unwrap(), &regions));
+                }
+                passes_done.wait();
+                if stopped {
+                    return;
+                }
+                let columns = Arc::clone(&edges.lock().unwrap());
+                let sequence = columns
+                    .iter()
+                    .enumerate()
+                    .filter(|&(index, _)| (index + thread_id) % total_threads == 0)
+                    .map(|(_, val)| val);
+                for (x, rows) in sequence {
+                    if notifier.should_i_stop() {
+                        log::debug!("{}: Remote side disconnected", name);
+                        return;
+                    }
+                    let points = rows
+                        .iter()
+                        .map(|&y| vat.map_pixel_to_point([f64::from(*x), f64::from(y)]).into())
+                        .collect::<Vec<Complex64>>();
+                    let y_colors = column_color(&points, pixel_size, edge_sampling);
+                    let mut canvas = canvas.write().unwrap();
+                    for (&y, color) in rows.iter().zip(y_colors) {
+                        canvas.put_pixel(*x, y, color);
+                    }
+                }
+            }
+            if stopped {
+                return;
+            }
+            if finished_threads.fetch_add(1, Ordering::SeqCst) + 1 == total_threads {
+                complete.store(true, Ordering::SeqCst);
+            }
+        },
+    )
+}
+
+/// Finds the pixels of `canvas` that `Supersampling::Adaptive` supersamples (see
+/// `supersample::edges()`) in `regions`, or in the ring of pixels just outside of them.
+///
+/// The pixels outside of `regions` were reused from an earlier view, so they have already been
+/// supersampled, but the ring still has to be compared to the new pixels that it borders.
+fn edge_columns(canvas: &FractalImageBuffer, regions: &[Region]) -> Vec<(u32, Vec<u32>)> {
+    let near_regions = |x: u32, y: u32| {
+        regions.iter().any(|region| {
+            x + 1 >= region.left
+                && x <= region.left + region.width
+                && y + 1 >= region.top
+                && y <= region.top + region.height
+        })
+    };
+    let color_at = |x, y| ColorU8(canvas.get_pixel(x, y).0);
+    supersample::edges(canvas.width(), canvas.height(), color_at)
+        .into_iter()
+        .map(|(x, rows)| {
+            let rows = rows.into_iter().filter(|&y| near_regions(x, y));
+            (x, rows.collect::<Vec<u32>>())
+        })
+        .filter(|(_, rows)| !rows.is_empty())
+        .collect()
+}
+
+/// Starts rendering `regions` of `canvas` for an escape time fractal with subdivision, using a
+/// pool of threads that each take strips of the regions, and returns the handles for those
+/// threads.
+///
+/// Once every thread finishes, it logs how many of the pixels were actually evaluated, and sets
+/// `complete`.
+pub fn spawn_subdivided_render(
+    canvas: Arc<RwLock<FractalImageBuffer>>,
+    vat: Arc<ViewAreaTransformer>,
+    regions: Vec<Region>,
+    etsystem: Arc<dyn EscapeTime + Send + Sync>,
+    colorizer: Arc<PaletteColorizer>,
+    complete: Arc<AtomicBool>,
+) -> ThreadedWorkMultiplexerHandles {
+    let total: u64 = regions.iter().map(Region::area).sum();
+    let evaluated = Arc::new(AtomicU64::new(0));
+    let finished_threads = Arc::new(AtomicUsize::new(0));
+
+    ThreadedWorkMultiplexerBuilder::new()
+        .base_name("escapetime_subdivide")
+        .split_work(move |thread_id, total_threads, notifier, name| {
+            let strips = regions
+                .iter()
+                .flat_map(|region| {
+                    (0..region.width)
+                        .step_by(SUBDIVISION_TILE_WIDTH as usize)
+                        .map(move |left| (region, left))
+                })
+                .enumerate()
+                .filter(|&(index, _)| (index + thread_id) % total_threads == 0)
+                .map(|(_, val)| val);
+            for (region, left) in strips {
+                if notifier.should_i_stop() {
+                    log::debug!("{}: Remote side disconnected", name);
+                    return;
+                }
+                let strip_left = region.left + left;
+                let strip_width = cmp::min(SUBDIVISION_TILE_WIDTH, region.width - left);
+                let (raster, count) = EscapeTimeRaster::evaluate_tile_subdivided(
+                    &*etsystem,
+                    &vat,
+                    [strip_left, region.top],
+                    strip_width,
+                    region.height,
+                );
+                evaluated.fetch_add(count, Ordering::SeqCst);
+                // only lock the canvas while writing to it
+                let mut canvas = canvas.write().unwrap();
+                for y in 0..region.height {
+                    for x in 0..strip_width {
+                        canvas.put_pixel(
+                            strip_left + x,
+                            region.top + y,
+                            Rgba(colorizer.color(raster.get(x, y)).0),
+                        );
+                    }
+                }
+            }
+            if finished_threads.fetch_add(1, Ordering::SeqCst) + 1 == total_threads {
+                log::info!(
+                    "Evaluated {} of {} pixels",
+                    evaluated.load(Ordering::SeqCst),
+                    total
+                );
+                complete.store(true, Ordering::SeqCst);
+            }
+        })
+}
+
+/// Starts rendering an escape time fractal onto `canvas` using a pool of threads, and returns the
+/// handles for those threads.
+///
+/// The pixels are evaluated progressively like `spawn_render()` does, and drawn with the default
+/// palette in the meantime. Once every thread finishes, the whole canvas is colored again with
+/// the palette spread over the frame's iteration counts by `scale`, and `complete` is set.
+pub fn spawn_scaled_render(
+    canvas: Arc<RwLock<FractalImageBuffer>>,
+    vat: Arc<ViewAreaTransformer>,
+    etsystem: Arc<dyn EscapeTime + Send + Sync>,
+    scale: ColorScale,
+    complete: Arc<AtomicBool>,
+) -> ThreadedWorkMultiplexerHandles {
+    let colorizer = PaletteColorizer::new(etsystem.max_iterations());
+    let scaled_canvas = Arc::clone(&canvas);
+    spawn_raster_render(
+        "escapetime_scaled",
+        canvas,
+        vat,
+        colorizer.clone(),
+        move |_, _, points, results| etsystem.evaluate_batch(points, results),
+        move |raster| {
+            let scaled = colorizer.clone().scaled_to(scale, raster);
+            let mut canvas = scaled_canvas.write().unwrap();
+            for y in 0..raster.height() {
+                for x in 0..raster.width() {
+                    canvas.put_pixel(x, y, Rgba(scaled.color(raster.get(x, y)).0));
+                }
+            }
+        },
+        complete,
+    )
+}
+
+/// Starts rendering a lit escape time fractal onto `canvas` using a pool of threads, and returns
+/// the handles for those threads.
+///
+/// The pixels are evaluated progressively like `spawn_render()` does, and drawn unlit with the
+/// default palette in the meantime. Once every thread finishes, the colors (with the palette
+/// spread over the frame's iteration counts by `scale`) and the slopes of `surface` are stored in
+/// `relief`, the canvas is shaded with the current `lighting`, and `complete` is set.
+pub fn spawn_lit_render(
+    canvas: Arc<RwLock<FractalImageBuffer>>,
+    vat: Arc<ViewAreaTransformer>,
+    surface: Surface,
+    scale: ColorScale,
+    lighting: Arc<RwLock<Lighting>>,
+    relief: Arc<Mutex<Option<ReliefRaster>>>,
+    complete: Arc<AtomicBool>,
+) -> ThreadedWorkMultiplexerHandles {
+    let (width, height) = canvas.read().unwrap().dimensions();
+    let colorizer = PaletteColorizer::new(surface.max_iterations());
+    let derivatives = Arc::new(Mutex::new(match surface {
+        Surface::Iterations(_) => Vec::new(),
+        Surface::Distance(_) => vec![Complex64::new(0.0, 0.0); width as usize * height as usize],
+    }));
+    let lit_canvas = Arc::clone(&canvas);
+    let lit_surface = surface.clone();
+    let lit_derivatives = Arc::clone(&derivatives);
+    spawn_raster_render(
+        "escapetime_lit",
+        canvas,
+        vat,
+        colorizer.clone(),
+        move |x, rows, points, results| {
+            let column = surface.evaluate(points, results);
+            if !column.is_empty() {
+                let mut derivatives = derivatives.lock().unwrap();
+                for (&y, derivative) in rows.iter().zip(column) {
+                    derivatives[y as usize * width as usize + x as usize] = derivative;
+                }
+            }
+        },
+        move |raster| {
+            let scaled = colorizer.clone().scaled_to(scale, raster);
+            let lit = match lit_surface {
+                Surface::Iterations(_) => ReliefRaster::from_smooth_iterations(raster, &scaled),
+                Surface::Distance(_) => ReliefRaster::from_derivatives(
+                    raster,
+                    &lit_derivatives.lock().unwrap(),
+                    &scaled,
+                ),
+            };
+            // Hold onto the relief while shading it, so that a light that moves in the
+            // meantime shades it again afterwards
+            let mut relief = relief.lock().unwrap();
+            draw_relief(&lit_canvas, &lit, &lighting.read().unwrap());
+            *relief = Some(lit);
+        },
+        complete,
+    )
+}
+
+/// Replaces the contents of `canvas` with `relief`, shaded by `lighting`.
+fn draw_relief(canvas: &RwLock<FractalImageBuffer>, relief: &ReliefRaster, lighting: &Lighting) {
+    let shaded =
+        FractalImageBuffer::from_raw(relief.width(), relief.height(), relief.shade(lighting))
+            .expect("a relief has 4 components for every pixel");
+    *canvas.write().unwrap() = shaded;
+}
+
+/// Starts evaluating every pixel of `canvas` into an `EscapeTimeRaster` using a pool of threads
+/// named after `base_name`, and returns the handles for those threads.
+///
+/// The pixels are evaluated progressively like `spawn_render()` does, a column of a pass at a
+/// time: `evaluate` is given the column, the rows of the column in the pass, their points, and
+/// the results to fill in. Each result is drawn with `colorizer` as soon as it is known. Once
+/// every thread finishes, the last one calls `finish` with the complete raster, and sets
+/// `complete`.
+fn spawn_raster_render<C, E, F>(
+    base_name: &str,
+    canvas: Arc<RwLock<FractalImageBuffer>>,
+    vat: Arc<ViewAreaTransformer>,
+    colorizer: C,
+    evaluate: E,
+    finish: F,
+    complete: Arc<AtomicBool>,
+) -> ThreadedWorkMultiplexerHandles
+where
+    C: Colorizer + Send + Sync + 'static,
+    E: Fn(u32, &[u32], &[Complex64], &mut [EscapeResult]) + Send + Sync + 'static,
+    F: Fn(&EscapeTimeRaster) + Send + Sync + 'static,
+{
+    let (width, height) = canvas.read().unwrap().dimensions();
+    let coverage = Mutex::new(Coverage::new(width, height));
+    let raster = Mutex::new(EscapeTimeRaster::new(width, height));
+    let finished_threads = Arc::new(AtomicUsize::new(0));
+
+    ThreadedWorkMultiplexerBuilder::new()
+        .base_name(base_name)
+        .split_work(move |thread_id, total_threads, notifier, name| {
+            for pass in Pass::passes(DEFAULT_INITIAL_BLOCK_SIZE) {
+                let sequence = pass
+                    .columns(width)
+                    .enumerate()
+                    .filter(|&(index, _)| (index + thread_id) % total_threads == 0)
+                    .map(|(_, val)| val);
+                for x in sequence {
+                    if notifier.should_i_stop() {
+                        log::debug!("{}: Remote side disconnected", name);
+                        return;
+                    }
+                    let rows = pass.rows(x, height);
+                    let points = rows
+                        .iter()
+                        .map(|&y| vat.map_pixel_to_point([f64::from(x), f64::from(y)]).into())
+                        .collect::<Vec<Complex64>>();
+                    let mut results =
+                        vec![EscapeResult::attracted(Complex64::new(0.0, 0.0)); points.len()];
+                    evaluate(x, &rows, &points, &mut results);
+                    {
+                        let mut raster = raster.lock().unwrap();
+                        for (&y, result) in rows.iter().zip(&results) {
+                            raster.set(x, y, *result);
                         }
                     }
-                });
-            self.threads = Some(work_muxer);
-        }
-    }
+                    let mut canvas = canvas.write().unwrap();
+                    let mut coverage = coverage.lock().unwrap();
+                    for (&y, result) in rows.iter().zip(&results) {
+                        let color = Rgba(colorizer.color(result).0);
+                        coverage.draw_sample(&pass, x, y, |px, py| canvas.put_pixel(px, py, color));
+                    }
+                }
+            }
+            if finished_threads.fetch_add(1, Ordering::SeqCst) + 1 == total_threads {
+                // Every pixel has been sampled exactly once, so the raster is complete
+                finish(&raster.lock().unwrap());
+                complete.store(true, Ordering::SeqCst);
+            }
+        })
 }
 
 impl WindowHandler for EscapeTimeWindowHandler {
@@ -182,6 +1089,7 @@ impl WindowHandler for EscapeTimeWindowHandler {
             render_context.context.transform,
             render_context.gfx,
         );
+        self.draw_orbit(render_context);
     }
 
     /// Change the view area to the newly selected area, and then redraw.
@@ -193,9 +1101,88 @@ impl WindowHandler for EscapeTimeWindowHandler {
         self.redraw();
     }
 
+    /// Overlay the orbit of the inspected point, and log how it ended.
+    fn inspect(&mut self, pos: Vec2d) {
+        let orbit_fn = match self.orbit_fn {
+            Some(ref orbit_fn) => orbit_fn,
+            None => {
+                log::info!("This fractal has no orbits to inspect");
+                return;
+            }
+        };
+        let c: Complex64 = self.vat.map_pixel_to_point(pos).into();
+        let orbit = orbit_fn(c);
+        if orbit.result.escaped {
+            log::info!(
+                "Orbit of {}: escaped during iteration {}",
+                c,
+                orbit.result.iterations
+            );
+        } else if let Some(period) = orbit.result.period {
+            log::info!("Orbit of {}: settled into a cycle of period {}", c, period);
+        } else {
+            log::info!(
+                "Orbit of {}: did not escape within {} iterations",
+                c,
+                orbit.values.len() - 1
+            );
+        }
+        self.orbit = Some(orbit);
+    }
+
+    /// Shade the last finished frame again, if it is lit.
+    fn move_light(&mut self, azimuth: f64, elevation: f64) {
+        let lighting = match self.renderer {
+            Renderer::Lit(_, _, ref lighting) => lighting,
+            _ => {
+                log::info!("This fractal is not lit");
+                return;
+            }
+        };
+        let moved = {
+            let mut lighting = lighting.write().unwrap();
+            lighting.move_by(azimuth, elevation);
+            *lighting
+        };
+        log::info!(
+            "Light at {} degrees, {} degrees above the fractal",
+            moved.azimuth,
+            moved.elevation
+        );
+        if let Some(ref relief) = *self.relief.lock().unwrap() {
+            draw_relief(&self.canvas, relief, &moved);
+        }
+    }
+
+    /// Forgets every earlier render, since they show the old slice, and redraws the fractal.
+    fn move_slice(&mut self, steps: i32) {
+        if self.slice_fn.is_none() {
+            log::info!("This fractal is not sliced");
+            return;
+        }
+        // Stop drawing the old slice before moving it, so that no pixel mixes the two
+        if let Some(mut threads) = self.threads.take() {
+            threads.stop();
+        }
+        self.render_complete.store(false, Ordering::SeqCst);
+        self.views = ViewCache::new(viewcache::DEFAULT_CAPACITY);
+        self.orbit = None;
+        let slice = (self.slice_fn.as_ref().unwrap())(steps);
+        log::info!(
+            "Slice at {} along {}",
+            slice.depth(),
+            AXIS_NAMES[slice.depth_axis()]
+        );
+        self.redraw();
+    }
+
+    /// Also removes the orbit overlay.
     fn reset_view(&mut self) {
-        let view_area_c = self.etsystem.default_view_area();
-        self.view_area = [Point::from(view_area_c[0]), Point::from(view_area_c[1])];
+        self.orbit = None;
+        self.view_area = [
+            Point::from(self.default_view_area[0]),
+            Point::from(self.default_view_area[1]),
+        ];
         self.redraw();
     }
 }
@@ -14,7 +14,9 @@
 
 //! A piston window-based renderer and event loop.
 
+pub mod buddhabrot;
 pub mod chaosgame;
+pub mod deepzoom;
 pub mod escapetime;
 pub mod turtle;
 
@@ -25,6 +27,9 @@ use piston_window::{
     RenderEvent, WindowSettings,
 };
 
+/// How many degrees each key press moves the light source by.
+const LIGHT_STEP: f64 = 15.0;
+
 /// State machine for `WindowHandlers` that want to animate across the double buffered frames.
 #[derive(Debug, PartialEq)]
 pub enum WhichFrame {
@@ -59,6 +64,23 @@ pub trait WindowHandler {
         log::info!("Selected: {:?}, {:?}", rect[0], rect[1]);
     }
 
+    /// Optional: used to indicate that the user shift-clicked on a pixel to inspect it.
+    fn inspect(&mut self, pos: Vec2d) {
+        log::info!("Inspected: {:?}", pos);
+    }
+
+    /// Optional: used to indicate that the user wants to turn the light source by `azimuth`
+    /// degrees counterclockwise, and raise it by `elevation` degrees.
+    fn move_light(&mut self, azimuth: f64, elevation: f64) {
+        log::info!("Move light: {}, {}", azimuth, elevation);
+    }
+
+    /// Optional: used to indicate that the user wants to move the slice that a four dimensional
+    /// fractal is drawn through by `steps` steps along its depth.
+    fn move_slice(&mut self, steps: i32) {
+        log::info!("Move slice: {}", steps);
+    }
+
     /// Optional: used to indicate that the user wants to revert to the default view.
     fn reset_view(&mut self) {
         log::info!("Reset zoom");
@@ -68,7 +90,10 @@ pub trait WindowHandler {
 /// Runs a `WindowHandler` in a `PistonWindow`.
 pub fn run(window_handler: &mut dyn WindowHandler) {
     log::info!("Use the mouse to select an area to zoom in on");
+    log::info!("Shift-click on a point to inspect it (eg, to show its orbit)");
     log::info!("Press backspace to reset the view back to the initial view");
+    log::info!("Press [ and ] to turn the light, and ; and ' to lower and raise it (if it is lit)");
+    log::info!("Press , and . to sweep the slice through the fourth dimension (if it is sliced)");
     log::info!("Press esc to exit");
 
     let mut window: PistonWindow = WindowSettings::new("Fractal", [800, 600])
@@ -81,6 +106,7 @@ pub fn run(window_handler: &mut dyn WindowHandler) {
 
     let mut mouse_pos: Vec2d = [0.0, 0.0];
     let mut mouse_down_pos = None;
+    let mut shift_held = false;
 
     while let Some(e) = window.next() {
         if let Some(args) = e.render_args() {
@@ -105,15 +131,27 @@ pub fn run(window_handler: &mut dyn WindowHandler) {
         });
         e.press(|button| {
             match button {
-                Button::Mouse(mbutton) => {
-                    if let MouseButton::Left = mbutton {
-                        // mouse down
-                        mouse_down_pos = Some(mouse_pos);
-                        log::debug!("Pressed mouse left: {:?}", mouse_down_pos.as_ref().unwrap());
+                Button::Mouse(MouseButton::Left) => {
+                    if shift_held {
+                        log::debug!("Shift-clicked mouse left: {:?}", mouse_pos);
+                        window_handler.inspect(mouse_pos);
+                        return;
                     }
+                    // mouse down
+                    mouse_down_pos = Some(mouse_pos);
+                    log::debug!("Pressed mouse left: {:?}", mouse_down_pos.as_ref().unwrap());
                 }
                 Button::Keyboard(key) => {
                     match key {
+                        Key::LShift | Key::RShift => {
+                            shift_held = true;
+                        }
+                        Key::LeftBracket => window_handler.move_light(-LIGHT_STEP, 0.0),
+                        Key::RightBracket => window_handler.move_light(LIGHT_STEP, 0.0),
+                        Key::Semicolon => window_handler.move_light(0.0, -LIGHT_STEP),
+                        Key::Quote => window_handler.move_light(0.0, LIGHT_STEP),
+                        Key::Comma => window_handler.move_slice(-1),
+                        Key::Period => window_handler.move_slice(1),
                         Key::Backspace => {
                             // "backspace" key down
                             log::debug!("reset zoom");
@@ -178,6 +216,9 @@ pub fn run(window_handler: &mut dyn WindowHandler) {
             }
         });
         e.release(|button| {
+            if let Button::Keyboard(Key::LShift) | Button::Keyboard(Key::RShift) = button {
+                shift_held = false;
+            }
             // mouse up
             if button == Button::Mouse(MouseButton::Left) {
                 let p2 = mouse_pos;
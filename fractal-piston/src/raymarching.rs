@@ -0,0 +1,85 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Renders three dimensional fractals to images without opening a window.
+//!
+//! Ray marching a single image can take a while, so the rows of the image are shared between a
+//! pool of threads, and the finished image is written out as a PNG.
+
+use super::pistonrendering::escapetime::FractalImageBuffer;
+use super::work_multiplexer::ThreadedWorkMultiplexerBuilder;
+use ::image::{ImageFormat, Rgba};
+use fractal_lib::escapetime3d::raymarch::RayMarcher;
+use fractal_lib::escapetime3d::DistanceEstimator3D;
+use log;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Ray marches every pixel of a `width` by `height` image, using a thread per CPU.
+pub fn render<F>(marcher: Arc<RayMarcher<F>>, width: u32, height: u32) -> FractalImageBuffer
+where
+    F: DistanceEstimator3D + Send + Sync + 'static,
+{
+    let canvas = Arc::new(RwLock::new(FractalImageBuffer::new(width, height)));
+    let finished_rows = Arc::new(AtomicU32::new(0));
+
+    let thread_canvas = Arc::clone(&canvas);
+    ThreadedWorkMultiplexerBuilder::new()
+        .base_name("raymarch_render")
+        .split_work(move |thread_id, total_threads, notifier, name| {
+            // Interleave the rows, since some parts of the image take much longer than others
+            for y in (thread_id as u32..height).step_by(total_threads) {
+                if notifier.should_i_stop() {
+                    log::debug!("{}: Remote side disconnected", name);
+                    return;
+                }
+                let row = marcher.render_row(y, width, height);
+                // only lock the canvas while writing to it
+                {
+                    let mut canvas = thread_canvas.write().unwrap();
+                    for (x, color) in row.into_iter().enumerate() {
+                        canvas.put_pixel(x as u32, y, Rgba(color.0));
+                    }
+                }
+                let finished = finished_rows.fetch_add(1, Ordering::SeqCst) + 1;
+                if finished.is_multiple_of(50) || finished == height {
+                    log::info!("Rendered {} of {} rows", finished, height);
+                }
+            }
+        })
+        .wait();
+
+    // Every thread has finished, so the canvas is complete
+    let image = canvas.read().unwrap();
+    image.clone()
+}
+
+/// Renders an image like `render()`, and saves it as a PNG at `path`.
+pub fn render_png<F>(
+    marcher: Arc<RayMarcher<F>>,
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> Result<(), String>
+where
+    F: DistanceEstimator3D + Send + Sync + 'static,
+{
+    let image = render(marcher, width, height);
+    image
+        .save_with_format(path, ImageFormat::Png)
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    log::info!("Wrote {}", path.display());
+    Ok(())
+}
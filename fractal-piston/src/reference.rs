@@ -0,0 +1,103 @@
+// Copyright (c) 2015-2019 William (B.J.) Snow Orvis
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Renders escape time fractals with a chosen number type to images without opening a window.
+//!
+//! Every pixel is evaluated with `GenericEscapeTime::evaluate_generic()`, which is far too slow
+//! to explore with, but gives reference images that faster approaches, such as the perturbation
+//! used by `deepmandelbrot`, can be checked against.
+
+use super::pistonrendering::escapetime::FractalImageBuffer;
+use super::work_multiplexer::ThreadedWorkMultiplexerBuilder;
+use ::image::{ImageFormat, Rgba};
+use fractal_lib::escapetime::generic::{GenericEscapeTime, Real, ViewArea};
+use fractal_lib::escapetime::raster::{Colorizer, PaletteColorizer};
+use log;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Evaluates every pixel of a `width` by `height` image of `view` with `T`s, using a thread per
+/// CPU.
+pub fn render<E, T>(
+    fractal: Arc<E>,
+    view: ViewArea<T>,
+    width: u32,
+    height: u32,
+) -> FractalImageBuffer
+where
+    E: GenericEscapeTime<T> + Send + Sync + 'static,
+    T: Real + 'static,
+{
+    let canvas = Arc::new(RwLock::new(FractalImageBuffer::new(width, height)));
+    let finished_rows = Arc::new(AtomicU32::new(0));
+    let colorizer = PaletteColorizer::new(fractal.max_iterations());
+    let screen_size = [f64::from(width), f64::from(height)];
+
+    let thread_canvas = Arc::clone(&canvas);
+    ThreadedWorkMultiplexerBuilder::new()
+        .base_name("reference_render")
+        .split_work(move |thread_id, total_threads, notifier, name| {
+            // Interleave the rows, since some parts of the image take much longer than others
+            for y in (thread_id as u32..height).step_by(total_threads) {
+                if notifier.should_i_stop() {
+                    log::debug!("{}: Remote side disconnected", name);
+                    return;
+                }
+                let row = (0..width)
+                    .map(|x| {
+                        let point =
+                            view.map_pixel_to_point(screen_size, [f64::from(x), f64::from(y)]);
+                        colorizer.color(&fractal.evaluate_generic(&point))
+                    })
+                    .collect::<Vec<_>>();
+                // only lock the canvas while writing to it
+                {
+                    let mut canvas = thread_canvas.write().unwrap();
+                    for (x, color) in row.into_iter().enumerate() {
+                        canvas.put_pixel(x as u32, y, Rgba(color.0));
+                    }
+                }
+                let finished = finished_rows.fetch_add(1, Ordering::SeqCst) + 1;
+                if finished.is_multiple_of(50) || finished == height {
+                    log::info!("Rendered {} of {} rows", finished, height);
+                }
+            }
+        })
+        .wait();
+
+    // Every thread has finished, so the canvas is complete
+    let image = canvas.read().unwrap();
+    image.clone()
+}
+
+/// Renders an image like `render()`, and saves it as a PNG at `path`.
+pub fn render_png<E, T>(
+    fractal: Arc<E>,
+    view: ViewArea<T>,
+    width: u32,
+    height: u32,
+    path: &Path,
+) -> Result<(), String>
+where
+    E: GenericEscapeTime<T> + Send + Sync + 'static,
+    T: Real + 'static,
+{
+    let image = render(fractal, view, width, height);
+    image
+        .save_with_format(path, ImageFormat::Png)
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    log::info!("Wrote {}", path.display());
+    Ok(())
+}
@@ -181,19 +181,27 @@ impl ThreadedWorkMultiplexerHandles {
 
     /// Signals each thread to stop, then blocks until they have stopped.
     ///
-    /// Threads have to check to see if they have been signaled using their notifier.
+    /// Threads have to check to see if they have been signaled using their notifier. Every
+    /// thread is signaled before any of them are joined, so that threads that wait for each
+    /// other (eg, at a `Barrier`) all get to stop.
     pub fn stop(&mut self) {
-        for thread_info in &mut self.thread_sync {
-            if let Some((tx, handle)) = thread_info.take() {
+        let handles = self
+            .thread_sync
+            .iter_mut()
+            .filter_map(|thread_info| thread_info.take())
+            .map(|(tx, handle)| {
                 drop(tx);
-                let thread_name = handle.thread().name().unwrap_or("UNKNOWN").to_string();
-                match handle.join() {
-                    Ok(_) => {
-                        log::debug!("Joined {}", thread_name);
-                    }
-                    Err(_) => {
-                        log::error!("{} panicked while it ran", thread_name);
-                    }
+                handle
+            })
+            .collect::<Vec<thread::JoinHandle<()>>>();
+        for handle in handles {
+            let thread_name = handle.thread().name().unwrap_or("UNKNOWN").to_string();
+            match handle.join() {
+                Ok(_) => {
+                    log::debug!("Joined {}", thread_name);
+                }
+                Err(_) => {
+                    log::error!("{} panicked while it ran", thread_name);
                 }
             }
         }
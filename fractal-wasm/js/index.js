@@ -12,6 +12,126 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+/**
+ * Optional configuration shared by the escape time fractals, in the order that
+ * the `animated_*` escape time functions expect them after the max iterations
+ * and power:
+ *
+ * - If either part of the Julia constant is filled in, the Julia set for that
+ *   constant is rendered instead.
+ * - The bailout radius and escape criterion decide when a value has escaped.
+ * - Supersampling averages that many samples squared for each pixel, picked
+ *   in the given mode.
+ * - The color scale spreads the palette over the iteration counts, either one
+ *   color per iteration, or adapted to the counts in the view (which can not
+ *   be combined with supersampling).
+ * - An orbit trap colors each point by how close its orbit comes to the
+ *   trap's shape instead, which is centered on the trap center. The radius is
+ *   only used by circles, and the angle (in degrees) only by lines.
+ * - The coloring either colors each point by its escape time, or by the
+ *   average of a statistic over its orbit (which looks best with a large
+ *   bailout radius). The stripe density is only used by the stripe average.
+ */
+const escape_time_config = [
+  { name: "Julia c (real)", id: "julia-re", optional: true, step: "any" },
+  { name: "Julia c (imaginary)", id: "julia-im", optional: true, step: "any" },
+  { name: "Bailout radius", id: "bailout", default: 3, min: 0, step: "any" },
+  {
+    name: "Escape criterion",
+    id: "criterion",
+    choices: ["norm", "real", "imaginary", "manhattan"]
+  },
+  { name: "Supersample", id: "supersample", default: 1, min: 1 },
+  {
+    name: "Supersample mode",
+    id: "supersample-mode",
+    choices: ["grid", "jittered", "adaptive"]
+  },
+  {
+    name: "Color scale",
+    id: "color-scale",
+    choices: ["fixed", "histogram", "range"]
+  },
+  {
+    name: "Orbit trap",
+    id: "trap",
+    choices: ["none", "point", "line", "cross", "circle"]
+  },
+  { name: "Trap center (real)", id: "trap-re", default: 0, step: "any" },
+  { name: "Trap center (imaginary)", id: "trap-im", default: 0, step: "any" },
+  { name: "Trap radius", id: "trap-radius", default: 1, min: 0, step: "any" },
+  { name: "Trap angle", id: "trap-angle", default: 0, step: "any" },
+  {
+    name: "Coloring",
+    id: "coloring",
+    choices: ["iterations", "stripe", "triangle", "curvature"]
+  },
+  { name: "Stripe density", id: "stripe-density", default: 5, step: "any" }
+];
+
+/**
+ * Configuration shared by the Newton and Nova fractals, in the order that the
+ * `animated_*` functions expect them after the max iterations:
+ *
+ * - The polynomial is a list of `re,im` pairs separated by semicolons, which
+ *   are either its roots or its coefficients (highest degree first).
+ * - The relaxation factor scales each step of Newton's method.
+ */
+const polynomial_config = [
+  {
+    name: "Polynomial",
+    id: "polynomial",
+    type: "text",
+    default: "1,0; -0.5,0.866025403784; -0.5,-0.866025403784"
+  },
+  {
+    name: "Polynomial given as",
+    id: "polynomial-kind",
+    choices: ["roots", "coefficients"]
+  },
+  { name: "Relaxation (real)", id: "relaxation-re", default: 1, step: "any" },
+  {
+    name: "Relaxation (imaginary)",
+    id: "relaxation-im",
+    default: 0,
+    step: "any"
+  }
+];
+
+/**
+ * Reads the values of the `polynomial_config` options for the given fractal.
+ */
+function get_polynomial_options(id) {
+  return [
+    document.querySelector(`#${id}-polynomial`).value,
+    document.querySelector(`#${id}-polynomial-kind`).value,
+    get_optional_float(`#${id}-relaxation-re`),
+    get_optional_float(`#${id}-relaxation-im`)
+  ];
+}
+
+/**
+ * Reads the values of the `escape_time_config` options for the given fractal.
+ */
+function get_escape_time_options(id) {
+  return [
+    get_optional_float(`#${id}-julia-re`),
+    get_optional_float(`#${id}-julia-im`),
+    get_optional_float(`#${id}-bailout`),
+    document.querySelector(`#${id}-criterion`).value,
+    get_int(`#${id}-supersample`),
+    document.querySelector(`#${id}-supersample-mode`).value,
+    document.querySelector(`#${id}-color-scale`).value,
+    document.querySelector(`#${id}-trap`).value,
+    get_optional_float(`#${id}-trap-re`),
+    get_optional_float(`#${id}-trap-im`),
+    get_optional_float(`#${id}-trap-radius`),
+    get_optional_float(`#${id}-trap-angle`),
+    document.querySelector(`#${id}-coloring`).value,
+    get_optional_float(`#${id}-stripe-density`)
+  ];
+}
+
 const fractal_descriptions = [
   {
     id: "barnsleyfern",
@@ -22,18 +142,44 @@ const fractal_descriptions = [
       return fractal_mod.animated_barnsleyfern(canvas);
     }
   },
+  {
+    id: "buffalo",
+    name: "Buffalo",
+    category: "Escape Time Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
+      { name: "Power", id: "power", default: 2, min: 1 },
+      ...escape_time_config
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#buffalo-max-iterations");
+      let power = get_int("#buffalo-power");
+      return fractal_mod.animated_buffalo(
+        canvas,
+        max_iterations,
+        power,
+        ...get_escape_time_options("buffalo")
+      );
+    }
+  },
   {
     id: "burningmandel",
     name: "Burning Mandel",
     category: "Escape Time Fractals",
     config: [
       { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
-      { name: "Power", id: "power", default: 2, min: 1 }
+      { name: "Power", id: "power", default: 2, min: 1 },
+      ...escape_time_config
     ],
     get_animation: (canvas, fractal_mod) => event => {
       let max_iterations = get_int("#burningmandel-max-iterations");
       let power = get_int("#burningmandel-power");
-      return fractal_mod.animated_burningmandel(canvas, max_iterations, power);
+      return fractal_mod.animated_burningmandel(
+        canvas,
+        max_iterations,
+        power,
+        ...get_escape_time_options("burningmandel")
+      );
     }
   },
   {
@@ -42,12 +188,38 @@ const fractal_descriptions = [
     category: "Escape Time Fractals",
     config: [
       { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
-      { name: "Power", id: "power", default: 2, min: 1 }
+      { name: "Power", id: "power", default: 2, min: 1 },
+      ...escape_time_config
     ],
     get_animation: (canvas, fractal_mod) => event => {
       let max_iterations = get_int("#burningship-max-iterations");
       let power = get_int("#burningship-power");
-      return fractal_mod.animated_burningship(canvas, max_iterations, power);
+      return fractal_mod.animated_burningship(
+        canvas,
+        max_iterations,
+        power,
+        ...get_escape_time_options("burningship")
+      );
+    }
+  },
+  {
+    id: "celtic",
+    name: "Celtic",
+    category: "Escape Time Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
+      { name: "Power", id: "power", default: 2, min: 1 },
+      ...escape_time_config
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#celtic-max-iterations");
+      let power = get_int("#celtic-power");
+      return fractal_mod.animated_celtic(
+        canvas,
+        max_iterations,
+        power,
+        ...get_escape_time_options("celtic")
+      );
     }
   },
   {
@@ -80,6 +252,32 @@ const fractal_descriptions = [
       return fractal_mod.animated_dragon(canvas, iterations);
     }
   },
+  {
+    id: "formula",
+    name: "Formula",
+    category: "Escape Time Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
+      { name: "Formula", id: "formula", type: "text", default: "z^2 + c" },
+      {
+        name: "Parameters (p1; p2; ...)",
+        id: "parameters",
+        type: "text",
+        optional: true
+      },
+      ...escape_time_config
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#formula-max-iterations");
+      return fractal_mod.animated_formula(
+        canvas,
+        max_iterations,
+        document.querySelector("#formula-formula").value,
+        document.querySelector("#formula-parameters").value,
+        ...get_escape_time_options("formula")
+      );
+    }
+  },
   {
     id: "kochcurve",
     name: "Koch Curve",
@@ -100,32 +298,129 @@ const fractal_descriptions = [
       return fractal_mod.animated_levyccurve(canvas, iterations);
     }
   },
+  {
+    id: "magnet1",
+    name: "Magnet (type I)",
+    category: "Convergent Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 }
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#magnet1-max-iterations");
+      return fractal_mod.animated_magnet1(canvas, max_iterations);
+    }
+  },
+  {
+    id: "magnet2",
+    name: "Magnet (type II)",
+    category: "Convergent Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 }
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#magnet2-max-iterations");
+      return fractal_mod.animated_magnet2(canvas, max_iterations);
+    }
+  },
   {
     id: "mandelbrot",
     name: "Mandelbrot",
     category: "Escape Time Fractals",
     config: [
       { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
-      { name: "Power", id: "power", default: 2, min: 1 }
+      { name: "Power", id: "power", default: 2, min: 1 },
+      // The Mandelbrot set can also be shaded by its distance estimate.
+      ...escape_time_config.map(option =>
+        option.id === "coloring"
+          ? Object.assign({}, option, {
+              choices: [...option.choices, "distance"]
+            })
+          : option
+      )
     ],
     get_animation: (canvas, fractal_mod) => event => {
       let max_iterations = get_int("#mandelbrot-max-iterations");
       let power = get_int("#mandelbrot-power");
-      return fractal_mod.animated_mandelbrot(canvas, max_iterations, power);
+      return fractal_mod.animated_mandelbrot(
+        canvas,
+        max_iterations,
+        power,
+        ...get_escape_time_options("mandelbrot")
+      );
+    }
+  },
+  {
+    id: "newton",
+    name: "Newton",
+    category: "Convergent Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 50, min: 1 },
+      ...polynomial_config
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#newton-max-iterations");
+      return fractal_mod.animated_newton(
+        canvas,
+        max_iterations,
+        ...get_polynomial_options("newton")
+      );
+    }
+  },
+  {
+    id: "nova",
+    name: "Nova",
+    category: "Convergent Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
+      ...polynomial_config
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#nova-max-iterations");
+      return fractal_mod.animated_nova(
+        canvas,
+        max_iterations,
+        ...get_polynomial_options("nova")
+      );
+    }
+  },
+  {
+    id: "phoenix",
+    name: "Phoenix",
+    category: "Escape Time Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
+      { name: "Power", id: "power", default: 2, min: 1 },
+      ...escape_time_config
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#phoenix-max-iterations");
+      let power = get_int("#phoenix-power");
+      return fractal_mod.animated_phoenix(
+        canvas,
+        max_iterations,
+        power,
+        ...get_escape_time_options("phoenix")
+      );
     }
   },
   {
     id: "roadrunner",
-    name: "Roadrunner",
+    name: "Roadrunner (Perpendicular Burning Ship)",
     category: "Escape Time Fractals",
     config: [
       { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
-      { name: "Power", id: "power", default: 2, min: 1 }
+      { name: "Power", id: "power", default: 2, min: 1 },
+      ...escape_time_config
     ],
     get_animation: (canvas, fractal_mod) => event => {
       let max_iterations = get_int("#roadrunner-max-iterations");
       let power = get_int("#roadrunner-power");
-      return fractal_mod.animated_roadrunner(canvas, max_iterations, power);
+      return fractal_mod.animated_roadrunner(
+        canvas,
+        max_iterations,
+        power,
+        ...get_escape_time_options("roadrunner")
+      );
     }
   },
   {
@@ -146,6 +441,26 @@ const fractal_descriptions = [
       let iterations = get_int("#terdragon-iterations");
       return fractal_mod.animated_terdragon(canvas, iterations);
     }
+  },
+  {
+    id: "tricorn",
+    name: "Tricorn",
+    category: "Escape Time Fractals",
+    config: [
+      { name: "Max Iterations", id: "max-iterations", default: 100, min: 1 },
+      { name: "Power", id: "power", default: 2, min: 1 },
+      ...escape_time_config
+    ],
+    get_animation: (canvas, fractal_mod) => event => {
+      let max_iterations = get_int("#tricorn-max-iterations");
+      let power = get_int("#tricorn-power");
+      return fractal_mod.animated_tricorn(
+        canvas,
+        max_iterations,
+        power,
+        ...get_escape_time_options("tricorn")
+      );
+    }
   }
 ];
 
@@ -162,6 +477,20 @@ function get_int(selector) {
   }
 }
 
+/**
+ * Returns the float in the input, or undefined if the input was left blank.
+ */
+function get_optional_float(selector) {
+  const input = document.querySelector(selector);
+  if (!input.checkValidity()) {
+    throw `${selector} isn't valid`;
+  }
+  if (input.value === "") {
+    return undefined;
+  }
+  return parseFloat(input.value);
+}
+
 /**
  * Upddates which configuration element is shown --- assumes that the
  * configuration elements for each fractals have already been created.
@@ -199,10 +528,52 @@ const update_coords = event => {
   }
 };
 
+/**
+ * Draws the orbit of the point under the pixel at x, y on top of the canvas,
+ * as dots connected by lines, and logs how it ended.
+ */
+const show_orbit = (canvas, x, y) => {
+  let orbit = window.current_animation.orbit(x, y);
+  if (!orbit) {
+    console.log("This fractal has no orbits to inspect");
+    return;
+  }
+  if (orbit.escaped()) {
+    console.log(`Orbit escaped during iteration ${orbit.iterations()}`);
+  } else if (orbit.period() !== undefined) {
+    console.log(`Orbit settled into a cycle of period ${orbit.period()}`);
+  } else {
+    console.log("Orbit did not escape");
+  }
+
+  const pixels = orbit.pixels();
+  orbit.free();
+  const ctx = canvas.getContext("2d");
+  ctx.strokeStyle = "red";
+  ctx.fillStyle = "red";
+  ctx.lineWidth = 0.5;
+  ctx.beginPath();
+  for (const [px, py] of pixels) {
+    ctx.lineTo(px, py);
+  }
+  ctx.stroke();
+  for (const [px, py] of pixels) {
+    ctx.fillRect(px - 1.5, py - 1.5, 3, 3);
+  }
+};
+
 const start_selection = event => {
   let down_x = event.clientX - event.target.offsetLeft;
   let down_y = event.clientY - event.target.offsetTop;
 
+  // Shift-clicking inspects the point's orbit instead of selecting an area
+  if (event.shiftKey) {
+    if (window.current_animation && window.current_animation.orbit) {
+      show_orbit(event.target, down_x, down_y);
+    }
+    return;
+  }
+
   let finish_selection = event => {
     let up_x = event.clientX - event.target.offsetLeft;
     let up_y = event.clientY - event.target.offsetTop;
@@ -212,6 +583,10 @@ const start_selection = event => {
     if (window.current_animation) {
       let redraw = window.current_animation.zoom(down_x, down_y, up_x, up_y);
       if (redraw) {
+        // Stop refining the old view
+        if (window.current_frame) {
+          window.cancelAnimationFrame(window.current_frame);
+        }
         let draw = ts => {
           if (window.current_animation.draw_one_frame()) {
             window.current_frame = window.requestAnimationFrame(draw);
@@ -292,11 +667,29 @@ function setup_configs(canvas, fractal) {
       config_label.appendChild(document.createTextNode(config_option.name));
       config_div.appendChild(config_label);
 
+      // Options with a list of choices get a dropdown instead of an input
+      if (config_option.choices !== undefined) {
+        let config_select = document.createElement("select");
+        config_select.id = desc.id + "-" + config_option.id;
+        for (const choice of config_option.choices) {
+          let option = document.createElement("option");
+          option.value = choice;
+          option.appendChild(document.createTextNode(choice));
+          config_select.appendChild(option);
+        }
+        config_div.appendChild(config_select);
+        fractal_config.appendChild(config_div);
+        continue;
+      }
+
       // Add an Input
       let config_input = document.createElement("input");
       config_input.id = desc.id + "-" + config_option.id;
-      config_input.type = "number";
-      config_input.required = true;
+      config_input.type = config_option.type || "number";
+      config_input.required = !config_option.optional;
+      if (config_option.step !== undefined) {
+        config_input.step = config_option.step;
+      }
       if (config_option.default !== undefined) {
         config_input.value = config_option.default;
       }
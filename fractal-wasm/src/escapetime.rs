@@ -12,47 +12,386 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use fractal_lib::color;
-use fractal_lib::escapetime::EscapeTime;
+use fractal_lib::color::{self, ColorScale, ColorU8};
+use fractal_lib::convergent::ConvergenceTime;
+use fractal_lib::escapetime::averaging::Averaging;
+use fractal_lib::escapetime::distance::DistanceEstimator;
+use fractal_lib::escapetime::orbit::Orbit;
+use fractal_lib::escapetime::orbittrap::OrbitTrap;
+use fractal_lib::escapetime::progressive::{Coverage, Pass, DEFAULT_INITIAL_BLOCK_SIZE};
+use fractal_lib::escapetime::raster::{Colorizer, EscapeTimeRaster, PaletteColorizer};
+use fractal_lib::escapetime::supersample::{self, Supersampling};
+use fractal_lib::escapetime::{EscapeResult, EscapeTime};
 use fractal_lib::geometry;
-use js_sys::Array;
+use js_sys::{Array, Date};
 use log;
 use num::complex::Complex64;
 use std::cmp;
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::Clamped;
 use web_sys::{CanvasRenderingContext2d, ImageData};
 
+/// How long each call to `EscapeTimeAnimation::draw_one_frame()` may spend computing pixels
+/// before it shows what it has so far.
+const FRAME_BUDGET_MILLIS: f64 = 30.0;
+
+/// The state of a progressive render that is spread over several frames.
+struct Progress {
+    width: u32,
+    height: u32,
+    vat: geometry::ViewAreaTransformer,
+    passes: Vec<Pass>,
+    /// The index of the pass being rendered.
+    pass: usize,
+    /// The next column to render in the current pass.
+    x: u32,
+    coverage: Coverage,
+    /// The RGBA components of every pixel, row by row.
+    image_pixels: Vec<u8>,
+    /// The result of every pixel, for colorings that need the whole frame.
+    raster: Option<EscapeTimeRaster>,
+    /// How to color the edge pixels again once the passes are done, for
+    /// `Supersampling::Adaptive`.
+    edge_sampling: Option<Supersampling>,
+    /// The columns of edge pixels (see `supersample::edges()`), once they have been found.
+    edges: Option<Vec<(u32, Vec<u32>)>>,
+    /// The next column of edge pixels to color again.
+    edge: usize,
+}
+
+impl Progress {
+    fn is_complete(&self) -> bool {
+        let edges_done = match (self.edge_sampling, &self.edges) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(_), Some(edges)) => self.edge >= edges.len(),
+        };
+        self.pass >= self.passes.len() && edges_done
+    }
+}
+
+type PointColorFn = Box<dyn Fn(&[Complex64], f64, Supersampling) -> Vec<ColorU8>>;
+
+/// How `EscapeTimeAnimation` colors its pixels.
+enum Coloring {
+    /// Picks the colors of the pixels for a list of points, given the width of a pixel and the
+    /// samples to take for each one, using the EscapeTime (or ConvergenceTime) system that is
+    /// being animated. Boxed to encapsulate/avoid generics. The passes sample the pixels with
+    /// the `Supersampling`, and for `Supersampling::Adaptive`, the edge pixels are colored again
+    /// once the passes are done.
+    Points(PointColorFn, Supersampling),
+    /// Evaluates a list of points of an escape time fractal. They are drawn with `colorizer`
+    /// until the whole frame is known, and then colored again with its palette spread over the
+    /// frame's iteration counts by `scale`.
+    Scaled {
+        evaluate: Box<dyn Fn(&[Complex64]) -> Vec<EscapeResult>>,
+        colorizer: PaletteColorizer,
+        scale: ColorScale,
+    },
+}
+
+/// The orbit of a point, as returned by `EscapeTimeAnimation::orbit()`.
+#[wasm_bindgen]
+pub struct OrbitInfo {
+    orbit: Orbit,
+    /// The view that the orbit was inspected in, for mapping its values to pixels.
+    vat: geometry::ViewAreaTransformer,
+}
+
+#[wasm_bindgen]
+impl OrbitInfo {
+    /// Whether the point escaped (and is not a member of the fractal's set).
+    pub fn escaped(&self) -> bool {
+        self.orbit.result.escaped
+    }
+
+    /// The iteration during which the point escaped, or 0 if it did not escape.
+    pub fn iterations(&self) -> f64 {
+        self.orbit.result.iterations as f64
+    }
+
+    /// The length of the cycle that the orbit settled into, if one was detected.
+    pub fn period(&self) -> Option<u32> {
+        self.orbit.result.period.map(|period| period as u32)
+    }
+
+    /// Every value of `z` along the orbit, as `[re, im]` arrays.
+    pub fn values(&self) -> Array {
+        let values = Array::new();
+        for z in &self.orbit.values {
+            values.push(&Array::of2(&z.re.into(), &z.im.into()));
+        }
+        values
+    }
+
+    /// Where every value of `z` along the orbit appears on the canvas, as `[x, y]` arrays.
+    pub fn pixels(&self) -> Array {
+        let pixels = Array::new();
+        for &z in &self.orbit.values {
+            let pixel = self.vat.map_point_to_pixel(geometry::Point::from(z));
+            pixels.push(&Array::of2(&pixel[0].into(), &pixel[1].into()));
+        }
+        pixels
+    }
+}
+
 #[wasm_bindgen]
 pub struct EscapeTimeAnimation {
     /// The rendering context.
     ctx: CanvasRenderingContext2d,
 
-    /// Which EscapeTime system is being animated. Boxed to encapsulate/avoid generics.
-    etsystem: Box<dyn EscapeTime>,
+    /// How the pixels are colored.
+    coloring: Coloring,
 
     /// The current part of the fractal we're viewing.
     view_area: [geometry::Point; 2],
+
+    /// The render of the current view area, if one has been started.
+    progress: Option<Progress>,
+
+    /// Follows the orbits of inspected points, for fractals that have them.
+    orbit_fn: Option<Box<dyn Fn(Complex64) -> Orbit>>,
 }
 
 impl EscapeTimeAnimation {
+    /// Animates an escape time fractal, where each pixel is colored by averaging the samples
+    /// that `supersampling` picks.
     pub fn new(
         ctx: CanvasRenderingContext2d,
         etsystem: Box<dyn EscapeTime>,
+        supersampling: Supersampling,
+    ) -> EscapeTimeAnimation {
+        let etsystem: Rc<dyn EscapeTime> = Rc::from(etsystem);
+        let orbits = Rc::clone(&etsystem);
+        let view_area_c = etsystem.default_view_area();
+        let colorizer = PaletteColorizer::new(etsystem.max_iterations());
+        // Evaluates all of the points at once, which is faster than one at a time (see
+        // `escapetime::batch`)
+        let point_colors = move |points: &[Complex64], pixel_size: f64, sampling: Supersampling| {
+            sampling.colors(points, pixel_size, |samples| {
+                let mut results =
+                    vec![EscapeResult::attracted(Complex64::new(0.0, 0.0)); samples.len()];
+                etsystem.evaluate_batch(samples, &mut results);
+                results
+                    .iter()
+                    .map(|result| colorizer.color(result))
+                    .collect()
+            })
+        };
+        EscapeTimeAnimation::with_point_colors(
+            ctx,
+            view_area_c,
+            Box::new(point_colors),
+            supersampling,
+        )
+        .with_orbits(Box::new(move |c| orbits.orbit(c)))
+    }
+
+    /// Animates an escape time fractal whose palette is spread over the iteration counts of each
+    /// view by `scale` (see `ColorScale`), once all of its pixels are known.
+    pub fn with_color_scale(
+        ctx: CanvasRenderingContext2d,
+        etsystem: Box<dyn EscapeTime>,
+        scale: ColorScale,
+    ) -> EscapeTimeAnimation {
+        let etsystem: Rc<dyn EscapeTime> = Rc::from(etsystem);
+        let orbits = Rc::clone(&etsystem);
+        let view_area_c = etsystem.default_view_area();
+        let colorizer = PaletteColorizer::new(etsystem.max_iterations());
+        let evaluate = move |points: &[Complex64]| {
+            let mut results = vec![EscapeResult::attracted(Complex64::new(0.0, 0.0)); points.len()];
+            etsystem.evaluate_batch(points, &mut results);
+            results
+        };
+        EscapeTimeAnimation::with_coloring(
+            ctx,
+            view_area_c,
+            Coloring::Scaled {
+                evaluate: Box::new(evaluate),
+                colorizer,
+                scale,
+            },
+        )
+        .with_orbits(Box::new(move |c| orbits.orbit(c)))
+    }
+
+    /// Animates an escape time fractal that colors each point by how close its orbit comes to
+    /// `trap`, where each pixel is colored by averaging the samples that `supersampling` picks.
+    pub fn with_orbit_trap(
+        ctx: CanvasRenderingContext2d,
+        etsystem: Box<dyn EscapeTime>,
+        trap: OrbitTrap,
+        supersampling: Supersampling,
+    ) -> EscapeTimeAnimation {
+        let etsystem: Rc<dyn EscapeTime> = Rc::from(etsystem);
+        EscapeTimeAnimation::with_sample_color(
+            ctx,
+            Rc::clone(&etsystem),
+            supersampling,
+            Box::new(move |c| trap.color(&trap.evaluate(&*etsystem, c))),
+        )
+    }
+
+    /// Animates an escape time fractal that colors the points outside of the set by the average
+    /// of a statistic over their orbits (see `escapetime::averaging`), where each pixel is
+    /// colored by averaging the samples that `supersampling` picks.
+    pub fn with_averaging(
+        ctx: CanvasRenderingContext2d,
+        etsystem: Box<dyn EscapeTime>,
+        averaging: Averaging,
+        supersampling: Supersampling,
+    ) -> EscapeTimeAnimation {
+        let etsystem: Rc<dyn EscapeTime> = Rc::from(etsystem);
+        EscapeTimeAnimation::with_sample_color(
+            ctx,
+            Rc::clone(&etsystem),
+            supersampling,
+            Box::new(move |c| averaging.color(&averaging.evaluate(&*etsystem, c))),
+        )
+    }
+
+    /// Animates an escape time fractal that colors each of the samples that `supersampling`
+    /// picks with `sample_color`.
+    fn with_sample_color(
+        ctx: CanvasRenderingContext2d,
+        etsystem: Rc<dyn EscapeTime>,
+        supersampling: Supersampling,
+        sample_color: Box<dyn Fn(Complex64) -> ColorU8>,
     ) -> EscapeTimeAnimation {
         let view_area_c = etsystem.default_view_area();
+        let point_colors = move |points: &[Complex64], pixel_size: f64, sampling: Supersampling| {
+            sampling.colors(points, pixel_size, |samples| {
+                samples.iter().map(|&c| sample_color(c)).collect()
+            })
+        };
+        EscapeTimeAnimation::with_point_colors(
+            ctx,
+            view_area_c,
+            Box::new(point_colors),
+            supersampling,
+        )
+        .with_orbits(Box::new(move |c| etsystem.orbit(c)))
+    }
+
+    /// Animates a convergent fractal, where each attractor gets its own hue.
+    pub fn for_convergence_time(
+        ctx: CanvasRenderingContext2d,
+        system: Box<dyn ConvergenceTime>,
+    ) -> EscapeTimeAnimation {
+        let view_area_c = system.default_view_area();
+        let shading_iterations = cmp::min(system.max_iterations(), 50) as f64;
+        let pixel_color = move |c, _| {
+            system
+                .evaluate(c)
+                .color(system.attractors().len(), shading_iterations)
+        };
+        EscapeTimeAnimation::with_pixel_color(ctx, view_area_c, Box::new(pixel_color))
+    }
+
+    /// Animates an escape time fractal that shades the points outside of the set by their
+    /// estimated distance to it, relative to the size of a pixel, where each pixel is colored by
+    /// averaging the samples that `supersampling` picks.
+    pub fn for_distance_estimate(
+        ctx: CanvasRenderingContext2d,
+        system: Box<dyn DistanceEstimator>,
+        supersampling: Supersampling,
+    ) -> EscapeTimeAnimation {
+        let system: Rc<dyn DistanceEstimator> = Rc::from(system);
+        let orbits = Rc::clone(&system);
+        let view_area_c = system.default_view_area();
+        let point_colors = move |points: &[Complex64], pixel_size: f64, sampling: Supersampling| {
+            sampling.colors(points, pixel_size, |samples| {
+                samples
+                    .iter()
+                    .map(|&c| {
+                        let estimate = system.estimate_distance(c);
+                        match estimate.distance {
+                            Some(distance) => color::distance_color(distance, pixel_size),
+                            None => color::interior_color(estimate.result.period),
+                        }
+                    })
+                    .collect()
+            })
+        };
+        EscapeTimeAnimation::with_point_colors(
+            ctx,
+            view_area_c,
+            Box::new(point_colors),
+            supersampling,
+        )
+        .with_orbits(Box::new(move |c| orbits.orbit(c)))
+    }
+
+    fn with_pixel_color(
+        ctx: CanvasRenderingContext2d,
+        view_area_c: [Complex64; 2],
+        pixel_color: Box<dyn Fn(Complex64, f64) -> ColorU8>,
+    ) -> EscapeTimeAnimation {
+        let point_colors = move |points: &[Complex64], pixel_size: f64, _: Supersampling| {
+            points
+                .iter()
+                .map(|&c| pixel_color(c, pixel_size))
+                .collect::<Vec<ColorU8>>()
+        };
+        EscapeTimeAnimation::with_point_colors(
+            ctx,
+            view_area_c,
+            Box::new(point_colors),
+            Supersampling::None,
+        )
+    }
+
+    fn with_point_colors(
+        ctx: CanvasRenderingContext2d,
+        view_area_c: [Complex64; 2],
+        point_colors: PointColorFn,
+        supersampling: Supersampling,
+    ) -> EscapeTimeAnimation {
+        EscapeTimeAnimation::with_coloring(
+            ctx,
+            view_area_c,
+            Coloring::Points(point_colors, supersampling),
+        )
+    }
+
+    fn with_coloring(
+        ctx: CanvasRenderingContext2d,
+        view_area_c: [Complex64; 2],
+        coloring: Coloring,
+    ) -> EscapeTimeAnimation {
         let view_area = [
             geometry::Point::from(view_area_c[0]),
             geometry::Point::from(view_area_c[1]),
         ];
         EscapeTimeAnimation {
             ctx,
-            etsystem,
+            coloring,
             view_area,
+            progress: None,
+            orbit_fn: None,
         }
     }
 
-    fn render(&self) {
+    /// Lets inspected points show their orbits, as followed by `orbit_fn`.
+    fn with_orbits(mut self, orbit_fn: Box<dyn Fn(Complex64) -> Orbit>) -> EscapeTimeAnimation {
+        self.orbit_fn = Some(orbit_fn);
+        self
+    }
+
+    /// The transformer for the current view area at the size of the canvas.
+    fn current_vat(&self) -> geometry::ViewAreaTransformer {
+        let screen_width = self.ctx.canvas().unwrap().width();
+        let screen_height = self.ctx.canvas().unwrap().height();
+        geometry::ViewAreaTransformer::new(
+            [screen_width.into(), screen_height.into()],
+            self.view_area[0],
+            self.view_area[1],
+        )
+    }
+
+    /// Starts a new progressive render of the current view area at the size of the canvas.
+    fn start_render(&self) -> Progress {
         let screen_width = self.ctx.canvas().unwrap().width();
         let screen_height = self.ctx.canvas().unwrap().height();
         let vat = geometry::ViewAreaTransformer::new(
@@ -68,45 +407,157 @@ impl EscapeTimeAnimation {
             screen_height as u32,
             vat.map_pixel_to_point([screen_width.into(), screen_height.into()])
         );
+        Progress {
+            width: screen_width,
+            height: screen_height,
+            vat,
+            passes: Pass::passes(DEFAULT_INITIAL_BLOCK_SIZE),
+            pass: 0,
+            x: 0,
+            coverage: Coverage::new(screen_width, screen_height),
+            image_pixels: vec![0; screen_width as usize * screen_height as usize * 4],
+            raster: match self.coloring {
+                Coloring::Points(..) => None,
+                Coloring::Scaled { .. } => Some(EscapeTimeRaster::new(screen_width, screen_height)),
+            },
+            edge_sampling: match self.coloring {
+                Coloring::Points(_, supersampling) => supersampling.edge_sampling(),
+                Coloring::Scaled { .. } => None,
+            },
+            edges: None,
+            edge: 0,
+        }
+    }
 
-        log::debug!("build color range");
-        let colors = color::color_range_linear(
-            color::BLACK_U8,
-            color::WHITE_U8,
-            cmp::min(self.etsystem.max_iterations(), 50) as usize,
-        );
+    /// Computes the pixels of the next column of `progress`: one of the current pass, or, once
+    /// the passes are done, one of the edge pixels to color again.
+    fn render_column(&self, progress: &mut Progress) {
+        if progress.pass < progress.passes.len() {
+            self.render_pass_column(progress);
+        } else if let Some(edge_sampling) = progress.edge_sampling {
+            self.render_edge_column(progress, edge_sampling);
+        }
+    }
 
-        log::debug!("build image pixels");
-        let mut image_pixels = (0..screen_height)
-            .map(|y| {
-                (0..screen_width)
-                    .map(|x| {
-                        let c: Complex64 =
-                            vat.map_pixel_to_point([f64::from(x), f64::from(y)]).into();
-                        let (attracted, time) = self.etsystem.test_point(c);
-                        if attracted {
-                            color::AEBLUE_U8.0.iter()
-                        } else {
-                            colors[cmp::min(time, 50 - 1) as usize].0.iter()
+    /// Computes the pixels of one column of the current pass of `progress`, and moves on to the
+    /// next column.
+    fn render_pass_column(&self, progress: &mut Progress) {
+        let pass = progress.passes[progress.pass];
+        let x = progress.x;
+        if x < progress.width {
+            let rows = pass.rows(x, progress.height);
+            let points = rows
+                .iter()
+                .map(|&y| {
+                    progress
+                        .vat
+                        .map_pixel_to_point([f64::from(x), f64::from(y)])
+                        .into()
+                })
+                .collect::<Vec<Complex64>>();
+            let colors = match self.coloring {
+                Coloring::Points(ref point_colors, supersampling) => {
+                    point_colors(&points, progress.vat.pixel_size(), supersampling)
+                }
+                Coloring::Scaled {
+                    ref evaluate,
+                    ref colorizer,
+                    ..
+                } => {
+                    let results = evaluate(&points);
+                    if let Some(raster) = progress.raster.as_mut() {
+                        for (&y, result) in rows.iter().zip(&results) {
+                            raster.set(x, y, *result);
                         }
-                    })
-                    .flatten()
-                    .collect::<Vec<&u8>>()
+                    }
+                    results
+                        .iter()
+                        .map(|result| colorizer.color(result))
+                        .collect::<Vec<ColorU8>>()
+                }
+            };
+            let width = progress.width as usize;
+            let image_pixels = &mut progress.image_pixels;
+            for (&y, color) in rows.iter().zip(colors) {
+                progress.coverage.draw_sample(&pass, x, y, |px, py| {
+                    let offset = (py as usize * width + px as usize) * 4;
+                    image_pixels[offset..offset + 4].copy_from_slice(&color.0);
+                });
+            }
+            progress.x += pass.block_size();
+        } else {
+            progress.pass += 1;
+            progress.x = 0;
+        }
+    }
+
+    /// Finds the edge pixels of `progress` once every pixel has been drawn, and then colors one
+    /// column of them again with `edge_sampling` on each call.
+    fn render_edge_column(&self, progress: &mut Progress, edge_sampling: Supersampling) {
+        let width = progress.width as usize;
+        if progress.edges.is_none() {
+            let image_pixels = &progress.image_pixels;
+            let color_at = |x: u32, y: u32| {
+                let offset = (y as usize * width + x as usize) * 4;
+                let mut color = [0; 4];
+                color.copy_from_slice(&image_pixels[offset..offset + 4]);
+                ColorU8(color)
+            };
+            progress.edges = Some(supersample::edges(
+                progress.width,
+                progress.height,
+                color_at,
+            ));
+            return;
+        }
+        let point_colors = match self.coloring {
+            Coloring::Points(ref point_colors, _) => point_colors,
+            Coloring::Scaled { .. } => return,
+        };
+        let (x, rows) = &progress.edges.as_ref().unwrap()[progress.edge];
+        let points = rows
+            .iter()
+            .map(|&y| {
+                progress
+                    .vat
+                    .map_pixel_to_point([f64::from(*x), f64::from(y)])
+                    .into()
             })
-            .flatten()
-            .cloned()
-            .collect::<Vec<u8>>();
+            .collect::<Vec<Complex64>>();
+        let colors = point_colors(&points, progress.vat.pixel_size(), edge_sampling);
+        for (&y, color) in rows.iter().zip(colors) {
+            let offset = (y as usize * width + *x as usize) * 4;
+            progress.image_pixels[offset..offset + 4].copy_from_slice(&color.0);
+        }
+        progress.edge += 1;
+    }
+
+    /// Once every pixel of `progress` is known, colors them again if the coloring adapts to the
+    /// whole frame.
+    fn finish_render(&self, progress: &mut Progress) {
+        if let Coloring::Scaled {
+            colorizer, scale, ..
+        } = &self.coloring
+        {
+            if let Some(raster) = progress.raster.as_ref() {
+                let scaled = colorizer.clone().scaled_to(*scale, raster);
+                progress.image_pixels = scaled.colorize(raster);
+            }
+        }
+    }
 
+    /// Draws the pixels that `progress` has computed so far onto the canvas.
+    fn put_image(&self, progress: &mut Progress) {
         // Construct a Clamped Uint8 Array
         log::debug!("build clamped image array");
-        let clamped_image_array = Clamped(image_pixels.as_mut_slice());
+        let clamped_image_array = Clamped(progress.image_pixels.as_mut_slice());
 
         // Create an ImageData from the array
         log::debug!("Create Image Data");
         let image = ImageData::new_with_u8_clamped_array_and_sh(
             clamped_image_array,
-            screen_width,
-            screen_height,
+            progress.width,
+            progress.height,
         )
         .unwrap();
 
@@ -117,41 +568,56 @@ impl EscapeTimeAnimation {
 
 #[wasm_bindgen]
 impl EscapeTimeAnimation {
+    /// Renders the current view area progressively, spending about `FRAME_BUDGET_MILLIS` on
+    /// each frame. Returns whether there is more to render.
     pub fn draw_one_frame(&mut self) -> bool {
-        self.render();
-        false
+        let start = Date::now();
+        let mut progress = match self.progress.take() {
+            Some(progress) => progress,
+            None => self.start_render(),
+        };
+        if progress.is_complete() {
+            self.progress = Some(progress);
+            return false;
+        }
+        while !progress.is_complete() && Date::now() - start < FRAME_BUDGET_MILLIS {
+            self.render_column(&mut progress);
+        }
+        if progress.is_complete() {
+            self.finish_render(&mut progress);
+        }
+        self.put_image(&mut progress);
+        let more = !progress.is_complete();
+        self.progress = Some(progress);
+        more
     }
 
     pub fn pixel_to_coordinate(&self, x: f64, y: f64) -> Array {
-        let screen_width = self.ctx.canvas().unwrap().width();
-        let screen_height = self.ctx.canvas().unwrap().height();
-
-        let vat = geometry::ViewAreaTransformer::new(
-            [screen_width.into(), screen_height.into()],
-            self.view_area[0],
-            self.view_area[1],
-        );
+        let vat = self.current_vat();
         let pos_point = vat.map_pixel_to_point([x, y]);
         Array::of2(&pos_point.x.into(), &pos_point.y.into())
     }
 
-    pub fn zoom(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> bool {
-        let screen_width = self.ctx.canvas().unwrap().width();
-        let screen_height = self.ctx.canvas().unwrap().height();
+    /// Follows the orbit of the point under the pixel at `x`, `y`, or returns `undefined` if the
+    /// fractal does not have orbits (eg, convergent fractals).
+    pub fn orbit(&self, x: f64, y: f64) -> Option<OrbitInfo> {
+        let orbit_fn = self.orbit_fn.as_ref()?;
+        let vat = self.current_vat();
+        let orbit = orbit_fn(vat.map_pixel_to_point([x, y]).into());
+        Some(OrbitInfo { orbit, vat })
+    }
 
+    pub fn zoom(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> bool {
         // get the vat for the current view area
-        let vat = geometry::ViewAreaTransformer::new(
-            [screen_width.into(), screen_height.into()],
-            self.view_area[0],
-            self.view_area[1],
-        );
+        let vat = self.current_vat();
 
         // compute the new view area in the fractal's coordinate system
         let tlp = vat.map_pixel_to_point([x1, y1]);
         let brp = vat.map_pixel_to_point([x2, y2]);
 
-        // update
+        // update, and start over with a new render
         self.view_area = [tlp, brp];
+        self.progress = None;
 
         true
     }
@@ -30,16 +30,31 @@ use console_error_panic_hook;
 use console_log;
 use fractal_lib::chaosgame::barnsleyfern;
 use fractal_lib::chaosgame::sierpinski;
+use fractal_lib::color::ColorScale;
+use fractal_lib::convergent::magnet::{MagnetTypeI, MagnetTypeII};
+use fractal_lib::convergent::newton::{NewtonFractal, NovaFractal};
+use fractal_lib::convergent::polynomial::Polynomial;
 use fractal_lib::curves::cesaro;
 use fractal_lib::curves::cesarotri;
 use fractal_lib::curves::dragon;
 use fractal_lib::curves::kochcurve;
 use fractal_lib::curves::levyccurve;
 use fractal_lib::curves::terdragon;
-use fractal_lib::escapetime::burningship::{BurningMandel, BurningShip, RoadRunner};
+use fractal_lib::escapetime::averaging::{Averaging, DEFAULT_STRIPE_DENSITY};
+use fractal_lib::escapetime::burningship::{
+    Buffalo, BurningMandel, BurningShip, Celtic, RoadRunner, Tricorn,
+};
+use fractal_lib::escapetime::distance::DistanceEstimator;
+use fractal_lib::escapetime::formula::FormulaFractal;
+use fractal_lib::escapetime::julia::JuliaSet;
 use fractal_lib::escapetime::mandelbrot::Mandelbrot;
+use fractal_lib::escapetime::orbittrap::{OrbitTrap, TrapShape};
+use fractal_lib::escapetime::phoenix::Phoenix;
+use fractal_lib::escapetime::supersample::Supersampling;
+use fractal_lib::escapetime::{EscapeCriterion, EscapeTime, DEFAULT_BAILOUT};
 use fractal_lib::lindenmayer::LindenmayerSystemTurtleProgram;
 use log;
+use num::complex::Complex64;
 use paste;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
@@ -180,6 +195,77 @@ animated_chaos_game!(
 
 animated_chaos_game!(sierpinski: sierpinski::SierpinskiChaosGame::new());
 
+/// Macro that generates a function for constructing an EscapeTimeAnimation for a particular kind
+/// of escape time fractal.
+///
+/// It takes a name identifier, a colon, and then expression that should evaluate to an
+/// EscapeTime. The expression may use `max_iterations`, `power`, and `bailout` (an
+/// `EscapeCriterion`) to configure the EscapeTime.
+///
+/// For example:
+/// ```rust,ignore
+/// animated_escape_time!(
+///     mandelbrot:
+///         Mandelbrot::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+/// );
+/// ```
+///
+/// Will create a function with signature:
+///
+/// ```rust,ignore
+/// #[wasm_bindgen]
+/// pub fn animated_mandelbrot(
+///     canvas: &HtmlCanvasElement,
+///     max_iterations: u32,
+///     power: u32,
+///     julia_re: Option<f64>,
+///     julia_im: Option<f64>,
+///     bailout_radius: Option<f64>,
+///     escape_criterion: Option<String>,
+///     supersample: Option<u32>,
+///     supersample_mode: Option<String>,
+///     color_scale: Option<String>,
+///     trap: Option<String>,
+///     trap_re: Option<f64>,
+///     trap_im: Option<f64>,
+///     trap_radius: Option<f64>,
+///     trap_angle: Option<f64>,
+///     coloring: Option<String>,
+///     stripe_density: Option<f64>,
+/// ) -> Result<EscapeTimeAnimation, JsValue>;
+/// ```
+///
+/// If either `julia_re` or `julia_im` is specified, then it renders the Julia set of the escape
+/// time fractal for the constant `c = julia_re + julia_im*i` instead (a missing component is
+/// treated as 0).
+///
+/// `escape_criterion` is one of `EscapeCriterion::NAMES`, and defaults to "norm".
+/// `bailout_radius` defaults to `DEFAULT_BAILOUT`. An invalid criterion or radius results in an
+/// error.
+///
+/// Each pixel is anti-aliased by averaging `supersample` by `supersample` samples, picked in one
+/// of the ways in `Supersampling::NAMES` by `supersample_mode` (defaulting to "grid"). A missing
+/// `supersample` or a `supersample` of 1 turns it off, and 0 results in an error.
+///
+/// `color_scale` is one of `ColorScale::NAMES`, and defaults to "fixed". The adaptive scales
+/// recolor each view once all of its pixels are known, and can not be combined with
+/// supersampling.
+///
+/// `trap` is one of `TrapShape::NAMES` to color every point by how close its orbit comes to an
+/// orbit trap of that shape (which can not be combined with an adaptive color scale), or "none"
+/// (the default) to color by escape time. The trap is centered on `trap_re + trap_im*i`
+/// (defaulting to 0), circles have a radius of `trap_radius` (defaulting to 1), and lines are
+/// `trap_angle` degrees counterclockwise from the real axis (defaulting to 0).
+///
+/// `coloring` is either "iterations" (the default), which colors the points outside of the set by
+/// their escape time (or by the orbit trap) as usual, or one of `Averaging::NAMES`, which colors
+/// them by the average of a statistic over their orbits (which can not be combined with an orbit
+/// trap or an adaptive color scale). `stripe_density` is the number of stripes per turn for the
+/// "stripe" average, and defaults to `DEFAULT_STRIPE_DENSITY`.
+///
+/// Alternately, if the name is followed by `(distance)`, the expression must evaluate to a
+/// `DistanceEstimator`, and `coloring` may also be "distance", which shades the points outside
+/// of the set by their estimated distance to it.
 macro_rules! animated_escape_time {
     ($name:ident: $expr:expr) => {
         // Paste is needed to concatenate render_ and the name of the fractal. Rust's own macros
@@ -187,7 +273,472 @@ macro_rules! animated_escape_time {
         paste::item! {
             #[wasm_bindgen]
             pub fn [<animated_ $name>] (
-                canvas: &HtmlCanvasElement, max_iterations: u32, power: u32
+                canvas: &HtmlCanvasElement,
+                max_iterations: u32,
+                power: u32,
+                julia_re: Option<f64>,
+                julia_im: Option<f64>,
+                bailout_radius: Option<f64>,
+                escape_criterion: Option<String>,
+                supersample: Option<u32>,
+                supersample_mode: Option<String>,
+                color_scale: Option<String>,
+                trap: Option<String>,
+                trap_re: Option<f64>,
+                trap_im: Option<f64>,
+                trap_radius: Option<f64>,
+                trap_angle: Option<f64>,
+                coloring: Option<String>,
+                stripe_density: Option<f64>
+            ) -> Result<escapetime::EscapeTimeAnimation, JsValue> {
+                log::debug!("Starting animation {}", stringify!($name));
+                let options = EscapeTimeOptions::parse(
+                    julia_re,
+                    julia_im,
+                    bailout_radius,
+                    escape_criterion,
+                    supersample,
+                    supersample_mode,
+                    color_scale,
+                    trap,
+                    trap_re,
+                    trap_im,
+                    trap_radius,
+                    trap_angle,
+                    coloring,
+                    stripe_density,
+                )?;
+                let bailout = options.bailout;
+                let ctx = blank_canvas(canvas);
+                let etsystem: Box<dyn EscapeTime> = match options.julia {
+                    None => Box::new($expr),
+                    Some(c) => Box::new(JuliaSet::new($expr, c)),
+                };
+                options.animate(ctx, etsystem)
+            }
+        }
+    };
+    ($name:ident(distance): $expr:expr) => {
+        paste::item! {
+            #[wasm_bindgen]
+            pub fn [<animated_ $name>] (
+                canvas: &HtmlCanvasElement,
+                max_iterations: u32,
+                power: u32,
+                julia_re: Option<f64>,
+                julia_im: Option<f64>,
+                bailout_radius: Option<f64>,
+                escape_criterion: Option<String>,
+                supersample: Option<u32>,
+                supersample_mode: Option<String>,
+                color_scale: Option<String>,
+                trap: Option<String>,
+                trap_re: Option<f64>,
+                trap_im: Option<f64>,
+                trap_radius: Option<f64>,
+                trap_angle: Option<f64>,
+                coloring: Option<String>,
+                stripe_density: Option<f64>
+            ) -> Result<escapetime::EscapeTimeAnimation, JsValue> {
+                log::debug!("Starting animation {}", stringify!($name));
+                let options = EscapeTimeOptions::parse(
+                    julia_re,
+                    julia_im,
+                    bailout_radius,
+                    escape_criterion,
+                    supersample,
+                    supersample_mode,
+                    color_scale,
+                    trap,
+                    trap_re,
+                    trap_im,
+                    trap_radius,
+                    trap_angle,
+                    coloring,
+                    stripe_density,
+                )?;
+                let bailout = options.bailout;
+                let ctx = blank_canvas(canvas);
+                if options.coloring != "distance" {
+                    let etsystem: Box<dyn EscapeTime> = match options.julia {
+                        None => Box::new($expr),
+                        Some(c) => Box::new(JuliaSet::new($expr, c)),
+                    };
+                    return options.animate(ctx, etsystem);
+                }
+                if options.scale.is_adaptive() {
+                    return Err(JsValue::from_str(
+                        "The distance coloring can not be combined with an adaptive color scale",
+                    ));
+                }
+                if options.trap.is_some() {
+                    return Err(JsValue::from_str(
+                        "The distance coloring can not be combined with an orbit trap",
+                    ));
+                }
+                let system: Box<dyn DistanceEstimator> = match options.julia {
+                    None => Box::new($expr),
+                    Some(c) => Box::new(JuliaSet::new($expr, c)),
+                };
+                Ok(escapetime::EscapeTimeAnimation::for_distance_estimate(
+                    ctx,
+                    system,
+                    options.supersampling,
+                ))
+            }
+        }
+    };
+}
+
+animated_escape_time!(
+    buffalo: Buffalo::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+);
+animated_escape_time!(
+    burningmandel:
+        BurningMandel::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+);
+animated_escape_time!(
+    burningship: BurningShip::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+);
+animated_escape_time!(
+    celtic: Celtic::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+);
+animated_escape_time!(
+    mandelbrot(distance):
+        Mandelbrot::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+);
+animated_escape_time!(
+    phoenix: Phoenix::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+);
+animated_escape_time!(
+    roadrunner: RoadRunner::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+);
+animated_escape_time!(
+    tricorn: Tricorn::with_bailout(u64::from(max_iterations), u64::from(power), bailout)
+);
+
+/// Parses a list of complex numbers written as `re,im` pairs that are separated by semicolons, eg
+/// `1,0; -0.5,0.866`.
+fn parse_complex_list(text: &str) -> Result<Vec<Complex64>, String> {
+    text.split(';')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| {
+            let parts = pair.split(',').map(str::trim).collect::<Vec<&str>>();
+            match parts.as_slice() {
+                [re, im] => match (re.parse::<f64>(), im.parse::<f64>()) {
+                    (Ok(re), Ok(im)) => Ok(Complex64::new(re, im)),
+                    _ => Err(format!("Invalid complex number: {}", pair)),
+                },
+                _ => Err(format!(
+                    "Expected a complex number of the form re,im: {}",
+                    pair
+                )),
+            }
+        })
+        .collect()
+}
+
+/// Constructs an EscapeTimeAnimation for a fractal whose escape time function is `formula` (see
+/// `fractal_lib::formula` for the language), eg `z^2 + c`.
+///
+/// `parameters` gives the values of `p1` through `p4` in order, as `re,im` pairs separated by
+/// semicolons. Missing parameters are 0. The remaining arguments are the same as the ones that
+/// `animated_escape_time!` generates. An invalid formula results in an error that points out
+/// where the problem is.
+#[wasm_bindgen]
+pub fn animated_formula(
+    canvas: &HtmlCanvasElement,
+    max_iterations: u32,
+    formula: String,
+    parameters: Option<String>,
+    julia_re: Option<f64>,
+    julia_im: Option<f64>,
+    bailout_radius: Option<f64>,
+    escape_criterion: Option<String>,
+    supersample: Option<u32>,
+    supersample_mode: Option<String>,
+    color_scale: Option<String>,
+    trap: Option<String>,
+    trap_re: Option<f64>,
+    trap_im: Option<f64>,
+    trap_radius: Option<f64>,
+    trap_angle: Option<f64>,
+    coloring: Option<String>,
+    stripe_density: Option<f64>,
+) -> Result<escapetime::EscapeTimeAnimation, JsValue> {
+    log::debug!("Starting animation formula: {}", formula);
+    let options = EscapeTimeOptions::parse(
+        julia_re,
+        julia_im,
+        bailout_radius,
+        escape_criterion,
+        supersample,
+        supersample_mode,
+        color_scale,
+        trap,
+        trap_re,
+        trap_im,
+        trap_radius,
+        trap_angle,
+        coloring,
+        stripe_density,
+    )?;
+    let values = parse_complex_list(parameters.as_ref().map_or("", String::as_str))
+        .map_err(|e| JsValue::from_str(&e))?;
+    if values.len() > 4 {
+        return Err(JsValue::from_str("At most 4 parameters may be given"));
+    }
+    let mut parameters = [Complex64::new(0.0, 0.0); 4];
+    parameters[..values.len()].copy_from_slice(&values);
+    let fractal = FormulaFractal::with_parameters(
+        u64::from(max_iterations),
+        &formula,
+        parameters,
+        options.bailout,
+    )
+    .map_err(|e| JsValue::from_str(&e))?;
+
+    let ctx = blank_canvas(canvas);
+    let etsystem: Box<dyn EscapeTime> = match options.julia {
+        None => Box::new(fractal),
+        Some(c) => Box::new(JuliaSet::new(fractal, c)),
+    };
+    options.animate(ctx, etsystem)
+}
+
+/// The options that `animated_escape_time!` and `animated_formula()` share, parsed from their
+/// arguments (see `animated_escape_time!` for what each of them means).
+struct EscapeTimeOptions {
+    /// The constant of the Julia set to draw instead, if any.
+    julia: Option<Complex64>,
+    bailout: EscapeCriterion,
+    supersampling: Supersampling,
+    scale: ColorScale,
+    trap: Option<OrbitTrap>,
+    coloring: String,
+    stripe_density: Option<f64>,
+}
+
+impl EscapeTimeOptions {
+    #[allow(clippy::too_many_arguments)]
+    fn parse(
+        julia_re: Option<f64>,
+        julia_im: Option<f64>,
+        bailout_radius: Option<f64>,
+        escape_criterion: Option<String>,
+        supersample: Option<u32>,
+        supersample_mode: Option<String>,
+        color_scale: Option<String>,
+        trap: Option<String>,
+        trap_re: Option<f64>,
+        trap_im: Option<f64>,
+        trap_radius: Option<f64>,
+        trap_angle: Option<f64>,
+        coloring: Option<String>,
+        stripe_density: Option<f64>,
+    ) -> Result<EscapeTimeOptions, JsValue> {
+        let julia = match (julia_re, julia_im) {
+            (None, None) => None,
+            (re, im) => Some(Complex64::new(re.unwrap_or(0.0), im.unwrap_or(0.0))),
+        };
+        let bailout = EscapeCriterion::from_name(
+            escape_criterion.as_ref().map_or("norm", String::as_str),
+            bailout_radius.unwrap_or(DEFAULT_BAILOUT),
+        )
+        .map_err(|e| JsValue::from_str(&e))?;
+        let supersampling = Supersampling::from_name(
+            supersample_mode.as_ref().map_or("grid", String::as_str),
+            supersample.unwrap_or(1),
+        )
+        .map_err(|e| JsValue::from_str(&e))?;
+        let scale = ColorScale::from_name(color_scale.as_ref().map_or("fixed", String::as_str))
+            .map_err(|e| JsValue::from_str(&e))?;
+        Ok(EscapeTimeOptions {
+            julia,
+            bailout,
+            supersampling,
+            scale,
+            trap: orbit_trap(trap, trap_re, trap_im, trap_radius, trap_angle)?,
+            coloring: coloring.unwrap_or_else(|| "iterations".to_string()),
+            stripe_density,
+        })
+    }
+
+    /// Animates `etsystem` on `ctx` with the options, where `coloring` must be "iterations" or
+    /// one of `Averaging::NAMES`.
+    fn animate(
+        self,
+        ctx: CanvasRenderingContext2d,
+        etsystem: Box<dyn EscapeTime>,
+    ) -> Result<escapetime::EscapeTimeAnimation, JsValue> {
+        let averaging = averaging(&self.coloring, self.stripe_density)?;
+        animate_escape_time(
+            ctx,
+            etsystem,
+            self.supersampling,
+            self.scale,
+            self.trap,
+            averaging,
+        )
+    }
+}
+
+/// Blanks `canvas`, and returns its 2D context to draw on.
+fn blank_canvas(canvas: &HtmlCanvasElement) -> CanvasRenderingContext2d {
+    let ctx = JsValue::from(canvas.get_context("2d").unwrap().unwrap())
+        .dyn_into::<CanvasRenderingContext2d>()
+        .unwrap();
+
+    ctx.clear_rect(0.0, 0.0, canvas.width().into(), canvas.height().into());
+    ctx
+}
+
+/// Builds the orbit trap for the `trap` options of `animated_escape_time!`, or None if `trap` is
+/// missing or "none".
+fn orbit_trap(
+    trap: Option<String>,
+    trap_re: Option<f64>,
+    trap_im: Option<f64>,
+    trap_radius: Option<f64>,
+    trap_angle: Option<f64>,
+) -> Result<Option<OrbitTrap>, JsValue> {
+    match trap.as_ref().map_or("none", String::as_str) {
+        "none" => Ok(None),
+        name => {
+            let shape = TrapShape::from_name(
+                name,
+                Complex64::new(trap_re.unwrap_or(0.0), trap_im.unwrap_or(0.0)),
+                trap_radius.unwrap_or(1.0),
+                trap_angle.unwrap_or(0.0),
+            )
+            .map_err(|e| JsValue::from_str(&e))?;
+            Ok(Some(OrbitTrap::new(shape)))
+        }
+    }
+}
+
+/// Builds the averaging coloring that `coloring` names (one of `Averaging::NAMES`), or None if
+/// it is "iterations".
+fn averaging(coloring: &str, stripe_density: Option<f64>) -> Result<Option<Averaging>, JsValue> {
+    if coloring == "iterations" {
+        return Ok(None);
+    }
+    if !Averaging::NAMES.contains(&coloring) {
+        return Err(JsValue::from_str(&format!(
+            "Unknown coloring: {}",
+            coloring
+        )));
+    }
+    Averaging::from_name(coloring, stripe_density.unwrap_or(DEFAULT_STRIPE_DENSITY))
+        .map(Some)
+        .map_err(|e| JsValue::from_str(&e))
+}
+
+/// Animates `etsystem` with `supersampling` and at most one of an orbit `trap`, an `averaging`
+/// coloring, or an adaptive color `scale`. The adaptive scales can not be combined with
+/// supersampling either.
+fn animate_escape_time(
+    ctx: CanvasRenderingContext2d,
+    etsystem: Box<dyn EscapeTime>,
+    supersampling: Supersampling,
+    scale: ColorScale,
+    trap: Option<OrbitTrap>,
+    averaging: Option<Averaging>,
+) -> Result<escapetime::EscapeTimeAnimation, JsValue> {
+    if (trap.is_some() || averaging.is_some()) && scale.is_adaptive() {
+        return Err(JsValue::from_str(
+            "Orbit traps and averaging colorings can not be combined with an adaptive color scale",
+        ));
+    }
+    match (trap, averaging) {
+        (Some(_), Some(_)) => {
+            return Err(JsValue::from_str(
+                "An orbit trap can not be combined with an averaging coloring",
+            ));
+        }
+        (Some(trap), None) => {
+            return Ok(escapetime::EscapeTimeAnimation::with_orbit_trap(
+                ctx,
+                etsystem,
+                trap,
+                supersampling,
+            ));
+        }
+        (None, Some(averaging)) => {
+            return Ok(escapetime::EscapeTimeAnimation::with_averaging(
+                ctx,
+                etsystem,
+                averaging,
+                supersampling,
+            ));
+        }
+        (None, None) => {}
+    }
+    if !scale.is_adaptive() {
+        return Ok(escapetime::EscapeTimeAnimation::new(
+            ctx,
+            etsystem,
+            supersampling,
+        ));
+    }
+    if supersampling != Supersampling::None {
+        return Err(JsValue::from_str(
+            "Supersampling can not be combined with an adaptive color scale",
+        ));
+    }
+    Ok(escapetime::EscapeTimeAnimation::with_color_scale(
+        ctx, etsystem, scale,
+    ))
+}
+
+/// Macro that generates a function for constructing an EscapeTimeAnimation for a particular kind
+/// of convergent fractal.
+///
+/// It takes a name identifier, a colon, and then expression that should evaluate to a
+/// ConvergenceTime. The expression may use `max_iterations` to configure the ConvergenceTime.
+///
+/// For example:
+/// ```rust,ignore
+/// animated_convergence_time!(magnet1: MagnetTypeI::new(u64::from(max_iterations)));
+/// ```
+///
+/// Will create a function with signature:
+///
+/// ```rust,ignore
+/// #[wasm_bindgen]
+/// pub fn animated_magnet1(
+///     canvas: &HtmlCanvasElement,
+///     max_iterations: u32,
+/// ) -> EscapeTimeAnimation;
+/// ```
+///
+/// Alternately, if the name is followed by `(polynomial)`, the function also takes a polynomial
+/// and a relaxation factor, and the expression may use `polynomial` (a `Polynomial`) and
+/// `relaxation` (a `Complex64`):
+///
+/// ```rust,ignore
+/// #[wasm_bindgen]
+/// pub fn animated_newton(
+///     canvas: &HtmlCanvasElement,
+///     max_iterations: u32,
+///     polynomial: String,
+///     polynomial_kind: Option<String>,
+///     relaxation_re: Option<f64>,
+///     relaxation_im: Option<f64>,
+/// ) -> Result<EscapeTimeAnimation, JsValue>;
+/// ```
+///
+/// `polynomial` is a list of `re,im` pairs separated by semicolons, which are either the roots of
+/// the polynomial (if `polynomial_kind` is "roots", the default), or its coefficients starting
+/// with the highest degree term (if `polynomial_kind` is "coefficients"). The relaxation factor
+/// defaults to 1 (a missing component is treated as 0).
+macro_rules! animated_convergence_time {
+    ($name:ident: $expr:expr) => {
+        paste::item! {
+            #[wasm_bindgen]
+            pub fn [<animated_ $name>] (
+                canvas: &HtmlCanvasElement,
+                max_iterations: u32
             ) -> escapetime::EscapeTimeAnimation {
                 log::debug!("Starting animation {}", stringify!($name));
                 let ctx = JsValue::from(canvas.get_context("2d").unwrap().unwrap())
@@ -196,15 +747,52 @@ macro_rules! animated_escape_time {
 
                 ctx.clear_rect(0.0, 0.0, canvas.width().into(), canvas.height().into());
 
-                escapetime::EscapeTimeAnimation::new(ctx, Box::new($expr))
+                escapetime::EscapeTimeAnimation::for_convergence_time(ctx, Box::new($expr))
+            }
+        }
+    };
+    ($name:ident(polynomial): $expr:expr) => {
+        paste::item! {
+            #[wasm_bindgen]
+            pub fn [<animated_ $name>] (
+                canvas: &HtmlCanvasElement,
+                max_iterations: u32,
+                polynomial: String,
+                polynomial_kind: Option<String>,
+                relaxation_re: Option<f64>,
+                relaxation_im: Option<f64>
+            ) -> Result<escapetime::EscapeTimeAnimation, JsValue> {
+                log::debug!("Starting animation {}", stringify!($name));
+                let values = parse_complex_list(&polynomial).map_err(|e| JsValue::from_str(&e))?;
+                let polynomial = match polynomial_kind.as_ref().map_or("roots", String::as_str) {
+                    "roots" => Polynomial::from_roots(&values),
+                    "coefficients" => Polynomial::from_coefficients(&values),
+                    kind => Err(format!("Unknown kind of polynomial: {}", kind)),
+                }
+                .map_err(|e| JsValue::from_str(&e))?;
+                let relaxation = match (relaxation_re, relaxation_im) {
+                    (None, None) => Complex64::new(1.0, 0.0),
+                    (re, im) => Complex64::new(re.unwrap_or(0.0), im.unwrap_or(0.0)),
+                };
+
+                let ctx = JsValue::from(canvas.get_context("2d").unwrap().unwrap())
+                    .dyn_into::<CanvasRenderingContext2d>()
+                    .unwrap();
+
+                ctx.clear_rect(0.0, 0.0, canvas.width().into(), canvas.height().into());
+
+                Ok(escapetime::EscapeTimeAnimation::for_convergence_time(ctx, Box::new($expr)))
             }
         }
     };
 }
 
-animated_escape_time!(
-    burningmandel: BurningMandel::new(u64::from(max_iterations), u64::from(power))
+animated_convergence_time!(magnet1: MagnetTypeI::new(u64::from(max_iterations)));
+animated_convergence_time!(magnet2: MagnetTypeII::new(u64::from(max_iterations)));
+animated_convergence_time!(
+    newton(polynomial):
+        NewtonFractal::with_relaxation(u64::from(max_iterations), polynomial, relaxation)
+);
+animated_convergence_time!(
+    nova(polynomial): NovaFractal::new(u64::from(max_iterations), polynomial, relaxation)
 );
-animated_escape_time!(burningship: BurningShip::new(u64::from(max_iterations), u64::from(power)));
-animated_escape_time!(mandelbrot: Mandelbrot::new(u64::from(max_iterations), u64::from(power)));
-animated_escape_time!(roadrunner: RoadRunner::new(u64::from(max_iterations), u64::from(power)));